@@ -6,25 +6,51 @@ use crate::{serialize::func_emission, utils::*};
 mod serialize;
 mod utils;
 
-pub fn code_emission(program: RiscvProgram, file_name: String) -> String {
+/// The assembly of a program. If it is `exported`, the functions and
+/// variables it defines are visible to the files it is linked with, otherwise
+/// only `main` is. Strings are always local.
+pub fn code_emission(
+	program: RiscvProgram,
+	file_name: String,
+	exported: bool,
+) -> String {
 	let mut map = LabelMapper::default();
 	let funcs = program
 		.funcs
 		.into_iter()
 		.map(func_emission)
-		.map(|(name, instrs)| format_func(name, map_label(instrs, &mut map)))
+		.map(|(name, instrs)| {
+			let is_global = exported || name == "main";
+			format_func(name, map_label(instrs, &mut map), is_global)
+		})
 		.collect::<Vec<_>>()
 		.join("\n");
 	let (bss, data): (Vec<_>, Vec<_>) =
 		program.global_vars.into_iter().partition(|v| v.is_bss());
-	let data = data.into_iter().map(format_data).collect::<Vec<_>>().join("\n");
-	let bss = bss.into_iter().map(format_bss).collect::<Vec<_>>().join("\n");
+	let (rodata, data): (Vec<_>, Vec<_>) =
+		data.into_iter().partition(|v| v.is_const);
+	let data = data
+		.into_iter()
+		.map(|v| format_data(v, exported))
+		.collect::<Vec<_>>()
+		.join("\n");
+	let rodata = rodata
+		.into_iter()
+		.map(|v| format_data(v, exported))
+		.collect::<Vec<_>>()
+		.join("\n");
+	let bss = bss
+		.into_iter()
+		.map(|v| format_bss(v, exported))
+		.collect::<Vec<_>>()
+		.join("\n");
 
 	format!(
-		"{}\n{}{}  .text\n  .global main\n{}\n  .ident {}\n {}",
+		"{}\n{}{}{}  .text\n{}\n  .ident {}\n {}",
 		program_head(file_name),
 		set_section("  .section	.sbss, \"aw\", @nobits", bss),
 		set_section("  .section	.sdata, \"aw\"", data),
+		set_section("  .section	.rodata", rodata),
 		funcs,
 		PROGRAM_IDENT,
 		RUNTIME_FUNCTION
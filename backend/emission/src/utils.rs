@@ -6,27 +6,38 @@ pub const PROGRAM_IDENT: &str = "\"SYSYC: (made by RRVM) 1.0.0\"";
 pub const RUNTIME_FUNCTION: &str = r#"
 
 .text
-.global __create_threads
-.global __join_threads
 
 	SYS_clone = 220
 	CLONE_VM = 256
 	SIGCHLD = 17
 	__create_threads:
-		li a0, 3   # addi a0, a0, -1
-		ble a0, zero, .ret_0
-		mv a6, a0
+		addi a6, a0, -1
+		ble a6, zero, .ret_0
+		# 调用者的栈帧在 sp 与 fp 之间，新线程的栈在它下面再留出 a1 字节
+		sub t2, fp, sp
+		add t0, a1, t2
 		li a5, 0
-		mv a1, sp
+	.L0_builtin:
+		sub a1, sp, t0
+		# 新线程复制调用者的整个栈帧
+		li t1, 0
+	.L10_builtin:
+		add t3, sp, t1
+		ld t4, 0(t3)
+		add t3, a1, t1
+		sd t4, 0(t3)
+		addi t1, t1, 8
+		blt t1, t2, .L10_builtin
 		li a2, 0
 		li a3, 0
 		li a4, 0
-	.L0_builtin:
 		li a0, (CLONE_VM | SIGCHLD)
 		li a7, SYS_clone
 		ecall
 		blt a0, zero, .try_again
 		bne a0, zero, .ret_i
+		# 溢出的变量用 fp 寻址，新线程使用自己的栈帧
+		add fp, sp, t2
 		addi a5, a5, 1
 	.try_again:
 		blt a5, a6, .L0_builtin
@@ -46,9 +57,6 @@ pub const RUNTIME_FUNCTION: &str = r#"
 	P_ALL = 0
 	WEXITED = 4
 	__join_threads:
-		li a1, 3
-		sub a0, a1, a0
-		li a1, 4 # new
 		mv a4, a0
 		addi a5, a1, -1
 		beq a4, a5, .L2_builtin
@@ -112,18 +120,34 @@ pub fn program_head(file_name: String) -> String {
 	)
 }
 
-pub fn format_func(name: String, instrs: String) -> String {
+// the `.global` directive of a symbol other files may use, none for a local
+// one
+fn visibility(name: &str, is_global: bool) -> String {
+	if is_global {
+		format!("  .global {name}\n")
+	} else {
+		String::new()
+	}
+}
+
+pub fn format_func(name: String, instrs: String, is_global: bool) -> String {
 	format!(
-		"  .align 1
+		"{}  .align 1
   .type {name}, @function\n{name}:\n{instrs}
-  .size {name}, .-{name}"
+  .size {name}, .-{name}",
+		visibility(&name, is_global)
 	)
 }
 
-pub fn format_data(var: GlobalVar) -> String {
+// strings are named `.str.N`, which no variable of SysY can be
+fn is_string(var: &GlobalVar) -> bool {
+	var.ident.starts_with('.')
+}
+
+pub fn format_data(var: GlobalVar, exported: bool) -> String {
 	format!(
-		"  .global {}\n  .align 2\n  .type {}, @object\n  .size {}, {}\n{}",
-		var.ident,
+		"{}  .align 2\n  .type {}, @object\n  .size {}, {}\n{}",
+		visibility(&var.ident, exported && !is_string(&var)),
 		var.ident,
 		var.ident,
 		var.size(),
@@ -131,10 +155,10 @@ pub fn format_data(var: GlobalVar) -> String {
 	)
 }
 
-pub fn format_bss(var: GlobalVar) -> String {
+pub fn format_bss(var: GlobalVar, exported: bool) -> String {
 	format!(
-		"  .global {}\n  .align 2\n  .type {}, @object\n  .size {}, {}\n{}:\n  .zero {}",
-		var.ident,
+		"{}  .align 2\n  .type {}, @object\n  .size {}, {}\n{}:\n  .zero {}",
+		visibility(&var.ident, exported && !is_string(&var)),
 		var.ident,
 		var.ident,
 		var.size(),
@@ -144,11 +168,10 @@ pub fn format_bss(var: GlobalVar) -> String {
 }
 
 pub fn set_section(header: &str, str: String) -> String {
-	if let Some(pos) = str.find('\n') {
-		let (before, after) = str.split_at(pos + 1);
-		format!("{}{}\n{}\n", before, header, after)
-	} else {
+	if str.is_empty() {
 		"".to_string()
+	} else {
+		format!("{}\n{}\n", header, str)
 	}
 }
 
@@ -85,8 +85,8 @@ pub fn la_reduce_block(
 					}
 				}
 
-				if let LD | LW | LWU | FLD | FLW | FSD | FSW | SB | SD | SH | SW =
-					ibin.op
+				if let LD | LW | LWU | LB | LBU | FLD | FLW | FSD | FSW | SB | SD | SH
+				| SW = ibin.op
 				{
 					if let RiscvImm::OffsetReg(_offset, basereg) = &ibin.rs1 {
 						if la_writes.contains(basereg) {
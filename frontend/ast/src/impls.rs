@@ -1,20 +1,22 @@
-use std::collections::HashMap;
+use utils::Span;
 
 use crate::tree::{LiteralFloat, LiteralInt, Node};
 
 impl LiteralInt {
-	pub fn node(value: i32) -> Node {
+	pub fn node(value: i32, span: Span) -> Node {
 		Box::new(Self {
-			_attrs: HashMap::new(),
+			_attrs: Default::default(),
+			_span: span,
 			value,
 		})
 	}
 }
 
 impl LiteralFloat {
-	pub fn node(value: f32) -> Node {
+	pub fn node(value: f32, span: Span) -> Node {
 		Box::new(Self {
-			_attrs: HashMap::new(),
+			_attrs: Default::default(),
+			_span: span,
 			value,
 		})
 	}
@@ -1,28 +1,25 @@
 pub mod impls;
+pub mod printer;
 pub mod tree;
 pub mod visitor;
 
 pub use tree::*;
+use utils::Span;
 use value::Value;
 pub use visitor::*;
 
-pub fn val_2_node(value: &Value) -> Node {
+pub fn val_2_node(value: &Value, span: Span) -> Node {
 	match value {
-		Value::Int(v) => LiteralInt::node(*v),
-		Value::Float(v) => LiteralFloat::node(*v),
+		Value::Int(v) => LiteralInt::node(*v, span),
+		Value::Float(v) => LiteralFloat::node(*v, span),
 		_ => unreachable!(),
 	}
 }
 
 pub fn shirink(node: &mut Node) {
-	if let Some(value) = node.get_attr("value") {
-		match value {
-			attr::Attr::Value(Value::Float(_)) | attr::Attr::Value(Value::Int(_)) => {
-				let v = value.clone();
-				*node = val_2_node(&value.into());
-				node.set_attr("value", v);
-			}
-			_ => {}
-		}
+	if let Some(value @ (Value::Float(_) | Value::Int(_))) = node.get_value() {
+		let value = value.clone();
+		*node = val_2_node(&value, node.span());
+		node.set_value(value);
 	}
 }
@@ -0,0 +1,436 @@
+use crate::{tree::*, Visitor};
+use utils::errors::Result;
+use value::{BType, BinaryOp, FuncRetType, UnaryOp};
+
+// binding strength of operators, an operand binding weaker than its operator
+// is put in parentheses
+const ASSIGN: u8 = 1;
+const CONDITIONAL: u8 = 2;
+const ADD: u8 = 11;
+const UNARY: u8 = 13;
+const POSTFIX: u8 = 14;
+const PRIMARY: u8 = 15;
+
+fn binary(op: BinaryOp) -> (&'static str, u8) {
+	use BinaryOp::*;
+	match op {
+		Assign => ("=", ASSIGN),
+		AddAssign => ("+=", ASSIGN),
+		SubAssign => ("-=", ASSIGN),
+		MulAssign => ("*=", ASSIGN),
+		DivAssign => ("/=", ASSIGN),
+		ModAssign => ("%=", ASSIGN),
+		BitAndAssign => ("&=", ASSIGN),
+		BitOrAssign => ("|=", ASSIGN),
+		BitXorAssign => ("^=", ASSIGN),
+		ShlAssign => ("<<=", ASSIGN),
+		ShrAssign => (">>=", ASSIGN),
+		LOr => ("||", 3),
+		LAnd => ("&&", 4),
+		BitOr => ("|", 5),
+		BitXor => ("^", 6),
+		BitAnd => ("&", 7),
+		EQ => ("==", 8),
+		NE => ("!=", 8),
+		LT => ("<", 9),
+		LE => ("<=", 9),
+		GT => (">", 9),
+		GE => (">=", 9),
+		Shl => ("<<", 10),
+		Shr => (">>", 10),
+		Add => ("+", ADD),
+		Sub => ("-", ADD),
+		Mul => ("*", 12),
+		Div => ("/", 12),
+		Mod => ("%", 12),
+		IDX => ("[]", POSTFIX),
+	}
+}
+
+fn unary(op: UnaryOp) -> &'static str {
+	match op {
+		UnaryOp::Plus => "+",
+		UnaryOp::Neg => "-",
+		UnaryOp::Not => "!",
+		UnaryOp::BitNot => "~",
+		UnaryOp::PreInc | UnaryOp::PostInc => "++",
+		UnaryOp::PreDec | UnaryOp::PostDec => "--",
+	}
+}
+
+fn btype(t: &BType) -> String {
+	match t {
+		BType::Char => "char".to_string(),
+		BType::Int => "int".to_string(),
+		BType::Float => "float".to_string(),
+		BType::Struct(record) => format!("struct {}", record.ident),
+	}
+}
+
+// the text of a string literal, with the bytes that are not printable in
+// octal escapes
+fn escape(bytes: &[u8]) -> String {
+	let mut out = String::from("\"");
+	for v in bytes {
+		match v {
+			b'"' => out.push_str("\\\""),
+			b'\\' => out.push_str("\\\\"),
+			b'\n' => out.push_str("\\n"),
+			b'\t' => out.push_str("\\t"),
+			b' '..=b'~' => out.push(*v as char),
+			_ => out.push_str(&format!("\\{v:03o}")),
+		}
+	}
+	out.push('"');
+	out
+}
+
+fn ret_type(t: FuncRetType) -> &'static str {
+	match t {
+		FuncRetType::Int => "int",
+		FuncRetType::Float => "float",
+		FuncRetType::Void => "void",
+	}
+}
+
+/// Prints a `Program` back as SysY source, one statement a line with tab
+/// indents and only the parentheses needed
+#[derive(Default)]
+pub struct SourcePrinter {
+	out: String,
+	indent: usize,
+	// binding strength of the expression printed last
+	prec: u8,
+	// print the next block without braces, its owner prints them
+	bare: bool,
+	// the next block is made by the parser around a statement, which is
+	// printed without braces too if it is a block
+	wrapper: bool,
+}
+
+impl SourcePrinter {
+	pub fn print(program: &mut Program) -> Result<String> {
+		let mut printer = Self::default();
+		printer.visit_program(program)?;
+		Ok(printer.out)
+	}
+	fn line(&mut self, text: &str) {
+		self.out.push_str(&"\t".repeat(self.indent));
+		self.out.push_str(text);
+		self.out.push('\n');
+	}
+	// print an expression on its own, wrapped if it binds weaker than `prec`
+	fn expr(&mut self, node: &mut Node, prec: u8) -> Result<String> {
+		let out = std::mem::take(&mut self.out);
+		node.accept(self)?;
+		let text = std::mem::replace(&mut self.out, out);
+		Ok(if self.prec < prec {
+			format!("({text})")
+		} else {
+			text
+		})
+	}
+	fn list(&mut self, nodes: &mut NodeList) -> Result<String> {
+		let mut items = Vec::new();
+		for node in nodes.iter_mut() {
+			items.push(self.expr(node, ASSIGN + 1)?);
+		}
+		Ok(items.join(", "))
+	}
+	fn dims(&mut self, dims: &mut [Node]) -> Result<String> {
+		let mut text = String::new();
+		for dim in dims.iter_mut() {
+			text += &format!("[{}]", self.expr(dim, 0)?);
+		}
+		Ok(text)
+	}
+	// print a block after `head`, which opens it on the same line
+	fn body(
+		&mut self,
+		head: String,
+		node: &mut Node,
+		wrapper: bool,
+	) -> Result<()> {
+		self.line(&format!("{head} {{"));
+		self.indent += 1;
+		self.bare = true;
+		self.wrapper = wrapper;
+		node.accept(self)?;
+		self.bare = false;
+		self.indent -= 1;
+		Ok(())
+	}
+}
+
+impl Visitor for SourcePrinter {
+	fn visit_program(&mut self, node: &mut Program) -> Result<()> {
+		for decl in node.global_vars.iter_mut() {
+			decl.accept(self)?;
+		}
+		for func in node.functions.iter_mut() {
+			if !self.out.is_empty() {
+				self.out.push('\n');
+			}
+			func.accept(self)?;
+		}
+		Ok(())
+	}
+	fn visit_var_def(&mut self, node: &mut VarDef) -> Result<()> {
+		let mut text = format!("{}{}", node.ident, self.dims(&mut node.dim_list)?);
+		if let Some(init) = node.init.as_mut() {
+			text += &format!(" = {}", self.expr(init, ASSIGN + 1)?);
+		}
+		self.out.push_str(&text);
+		Ok(())
+	}
+	fn visit_var_decl(&mut self, node: &mut VarDecl) -> Result<()> {
+		let mut defs = Vec::new();
+		for def in node.defs.iter_mut() {
+			defs.push(self.expr(def, 0)?);
+		}
+		let head = match (node.is_const, node.is_extern) {
+			(true, _) => "const ",
+			(_, true) => "extern ",
+			_ => "",
+		};
+		self.line(&format!(
+			"{head}{} {};",
+			btype(&node.type_t),
+			defs.join(", ")
+		));
+		Ok(())
+	}
+	fn visit_struct_decl(&mut self, node: &mut StructDecl) -> Result<()> {
+		self.line(&format!("struct {} {{", node.ident));
+		self.indent += 1;
+		for field in node.fields.iter_mut() {
+			field.accept(self)?;
+		}
+		self.indent -= 1;
+		self.line("};");
+		Ok(())
+	}
+	fn visit_field_decl(&mut self, node: &mut FieldDecl) -> Result<()> {
+		let dims = self.dims(&mut node.dim_list)?;
+		self.line(&format!("{} {}{dims};", btype(&node.type_t), node.ident));
+		Ok(())
+	}
+	fn visit_func_decl(&mut self, node: &mut FuncDecl) -> Result<()> {
+		let mut params = Vec::new();
+		for param in node.formal_params.iter_mut() {
+			params.push(self.expr(param, 0)?);
+		}
+		let head = format!(
+			"{} {}({})",
+			ret_type(node.ret_type),
+			node.ident,
+			params.join(", ")
+		);
+		if let Some(block) = node.block.as_mut() {
+			self.body(head, block, false)?;
+			self.line("}");
+		} else {
+			self.line(&format!("extern {head};"));
+		}
+		Ok(())
+	}
+	fn visit_init_val_list(&mut self, node: &mut InitValList) -> Result<()> {
+		let text = format!("{{{}}}", self.list(&mut node.val_list)?);
+		self.out.push_str(&text);
+		self.prec = PRIMARY;
+		Ok(())
+	}
+	fn visit_literal_int(&mut self, node: &mut LiteralInt) -> Result<()> {
+		// `2147483648` is not a valid literal
+		let text = match node.value {
+			i32::MIN => "-2147483647 - 1".to_string(),
+			v => v.to_string(),
+		};
+		self.out.push_str(&text);
+		self.prec = match node.value {
+			i32::MIN => ADD,
+			v if v < 0 => UNARY,
+			_ => PRIMARY,
+		};
+		Ok(())
+	}
+	fn visit_literal_float(&mut self, node: &mut LiteralFloat) -> Result<()> {
+		// the shortest text that reads back as the same `f32`
+		let text = format!("{:?}", node.value);
+		self.prec = if text.starts_with('-') {
+			UNARY
+		} else {
+			PRIMARY
+		};
+		self.out.push_str(&text);
+		Ok(())
+	}
+	fn visit_literal_string(&mut self, node: &mut LiteralString) -> Result<()> {
+		self.out.push_str(&escape(&node.value));
+		self.prec = PRIMARY;
+		Ok(())
+	}
+	fn visit_field_access(&mut self, node: &mut FieldAccess) -> Result<()> {
+		let base = self.expr(&mut node.base, POSTFIX)?;
+		self.out.push_str(&format!("{base}.{}", node.ident));
+		self.prec = POSTFIX;
+		Ok(())
+	}
+	fn visit_binary_expr(&mut self, node: &mut BinaryExpr) -> Result<()> {
+		let (op, prec) = binary(node.op);
+		let text = if node.op == BinaryOp::IDX {
+			let lhs = self.expr(&mut node.lhs, POSTFIX)?;
+			format!("{lhs}[{}]", self.expr(&mut node.rhs, 0)?)
+		} else if node.op.is_assign() {
+			let lhs = self.expr(&mut node.lhs, prec + 1)?;
+			format!("{lhs} {op} {}", self.expr(&mut node.rhs, prec)?)
+		} else {
+			let lhs = self.expr(&mut node.lhs, prec)?;
+			format!("{lhs} {op} {}", self.expr(&mut node.rhs, prec + 1)?)
+		};
+		self.out.push_str(&text);
+		self.prec = prec;
+		Ok(())
+	}
+	fn visit_unary_expr(&mut self, node: &mut UnaryExpr) -> Result<()> {
+		let op = unary(node.op);
+		if node.op.is_postfix() {
+			let text = format!("{}{op}", self.expr(&mut node.rhs, POSTFIX)?);
+			self.out.push_str(&text);
+			self.prec = POSTFIX;
+			return Ok(());
+		}
+		let rhs = self.expr(&mut node.rhs, UNARY)?;
+		// `- -a` is not `--a`, nor is `- --a` `---a`
+		let space = if rhs.starts_with(&op[op.len() - 1..]) {
+			" "
+		} else {
+			""
+		};
+		let text = format!("{op}{space}{rhs}");
+		self.out.push_str(&text);
+		self.prec = UNARY;
+		Ok(())
+	}
+	fn visit_conditional(&mut self, node: &mut Conditional) -> Result<()> {
+		let cond = self.expr(&mut node.cond, CONDITIONAL + 1)?;
+		let on_true = self.expr(&mut node.on_true, 0)?;
+		let on_false = self.expr(&mut node.on_false, CONDITIONAL)?;
+		self.out.push_str(&format!("{cond} ? {on_true} : {on_false}"));
+		self.prec = CONDITIONAL;
+		Ok(())
+	}
+	fn visit_func_call(&mut self, node: &mut FuncCall) -> Result<()> {
+		// the parser passes the line number to the timing functions
+		let text = match node.ident.as_str() {
+			"_sysy_starttime" => "starttime()".to_string(),
+			"_sysy_stoptime" => "stoptime()".to_string(),
+			_ => format!("{}({})", node.ident, self.list(&mut node.params)?),
+		};
+		self.out.push_str(&text);
+		self.prec = PRIMARY;
+		Ok(())
+	}
+	fn visit_formal_param(&mut self, node: &mut FormalParam) -> Result<()> {
+		// the first dimension of an array parameter is always `[]`
+		let dims = match node.dim_list.split_first_mut() {
+			Some((_, rest)) => format!("[]{}", self.dims(rest)?),
+			None => String::new(),
+		};
+		self.out.push_str(&format!("{} {}{dims}", btype(&node.type_t), node.ident));
+		Ok(())
+	}
+	fn visit_variable(&mut self, node: &mut Variable) -> Result<()> {
+		self.out.push_str(&node.ident);
+		self.prec = PRIMARY;
+		Ok(())
+	}
+	fn visit_block(&mut self, node: &mut Block) -> Result<()> {
+		let bare = std::mem::take(&mut self.bare);
+		let wrapper = std::mem::take(&mut self.wrapper);
+		if !bare {
+			self.line("{");
+			self.indent += 1;
+		}
+		let single = node.stmts.len() == 1;
+		for stmt in node.stmts.iter_mut() {
+			self.bare = wrapper && single;
+			// declarations and statements print their own lines
+			let out = std::mem::take(&mut self.out);
+			stmt.accept(self)?;
+			let text = std::mem::replace(&mut self.out, out);
+			self.bare = false;
+			// an empty statement is an empty block, which prints nothing
+			if text.is_empty() || text.ends_with('\n') {
+				self.out.push_str(&text);
+			} else {
+				self.line(&format!("{text};"));
+			}
+		}
+		if !bare {
+			self.indent -= 1;
+			self.line("}");
+		}
+		Ok(())
+	}
+	fn visit_if(&mut self, node: &mut If) -> Result<()> {
+		let head = format!("if ({})", self.expr(&mut node.cond, 0)?);
+		self.body(head, &mut node.body, true)?;
+		if let Some(then) = node.then.as_mut() {
+			self.body("} else".to_string(), then, true)?;
+		}
+		self.line("}");
+		Ok(())
+	}
+	fn visit_while(&mut self, node: &mut While) -> Result<()> {
+		let head = format!("while ({})", self.expr(&mut node.cond, 0)?);
+		self.body(head, &mut node.body, true)?;
+		self.line("}");
+		Ok(())
+	}
+	fn visit_for(&mut self, node: &mut For) -> Result<()> {
+		// a declaration prints a line of its own, which is put in the head
+		let init = match node.init.as_mut() {
+			Some(init) => {
+				self.expr(init, 0)?.trim().trim_end_matches(';').to_string()
+			}
+			None => String::new(),
+		};
+		let cond = match node.cond.as_mut() {
+			Some(cond) => format!(" {}", self.expr(cond, 0)?),
+			None => String::new(),
+		};
+		let step = match node.step.as_mut() {
+			Some(step) => format!(" {}", self.expr(step, 0)?),
+			None => String::new(),
+		};
+		let head = format!("for ({init};{cond};{step})");
+		self.body(head, &mut node.body, true)?;
+		self.line("}");
+		Ok(())
+	}
+	fn visit_do_while(&mut self, node: &mut DoWhile) -> Result<()> {
+		self.body("do".to_string(), &mut node.body, true)?;
+		let cond = self.expr(&mut node.cond, 0)?;
+		self.line(&format!("}} while ({cond});"));
+		Ok(())
+	}
+	fn visit_continue(&mut self, _node: &mut Continue) -> Result<()> {
+		self.line("continue;");
+		Ok(())
+	}
+	fn visit_break(&mut self, _node: &mut Break) -> Result<()> {
+		self.line("break;");
+		Ok(())
+	}
+	fn visit_return(&mut self, node: &mut Return) -> Result<()> {
+		match node.value.as_mut() {
+			Some(value) => {
+				let text = format!("return {};", self.expr(value, 0)?);
+				self.line(&text);
+			}
+			None => self.line("return;"),
+		}
+		Ok(())
+	}
+}
@@ -1,13 +1,14 @@
-use attr::{Attr, Attrs};
+use attr::Attrs;
 use std::fmt::Debug;
 use sysyc_derive::{has_attrs, AstNode};
-use utils::errors::Result;
+use utils::{errors::Result, Span};
 use value::{BType, BinaryOp, FuncRetType, UnaryOp};
 
 use crate::visitor::Visitor;
 
 pub trait AstNode: Debug + Attrs {
 	fn accept(&mut self, visitor: &mut dyn Visitor) -> Result<()>;
+	fn span(&self) -> Span;
 	fn is_end(&self) -> bool {
 		false
 	}
@@ -35,10 +36,26 @@ pub struct VarDef {
 #[has_attrs]
 pub struct VarDecl {
 	pub is_const: bool,
+	pub is_extern: bool,
 	pub type_t: BType,
 	pub defs: NodeList,
 }
 
+#[derive(Debug, AstNode)]
+#[has_attrs]
+pub struct StructDecl {
+	pub ident: String,
+	pub fields: NodeList,
+}
+
+#[derive(Debug, AstNode)]
+#[has_attrs]
+pub struct FieldDecl {
+	pub type_t: BType,
+	pub ident: String,
+	pub dim_list: NodeList,
+}
+
 #[derive(Debug, AstNode)]
 #[has_attrs]
 pub struct InitValList {
@@ -57,12 +74,27 @@ pub struct LiteralFloat {
 	pub value: f32,
 }
 
+/// The bytes of a string literal, without the ending `\0`
+#[derive(Debug, AstNode)]
+#[has_attrs]
+pub struct LiteralString {
+	pub value: Vec<u8>,
+}
+
 #[derive(Debug, AstNode)]
 #[has_attrs]
 pub struct Variable {
 	pub ident: String,
 }
 
+/// `base.ident`
+#[derive(Debug, AstNode)]
+#[has_attrs]
+pub struct FieldAccess {
+	pub base: Node,
+	pub ident: String,
+}
+
 #[derive(Debug, AstNode)]
 #[has_attrs]
 pub struct BinaryExpr {
@@ -78,6 +110,14 @@ pub struct UnaryExpr {
 	pub rhs: Node,
 }
 
+#[derive(Debug, AstNode)]
+#[has_attrs]
+pub struct Conditional {
+	pub cond: Node,
+	pub on_true: Node,
+	pub on_false: Node,
+}
+
 #[derive(Debug, AstNode)]
 #[has_attrs]
 pub struct FuncCall {
@@ -91,7 +131,8 @@ pub struct FuncDecl {
 	pub ret_type: FuncRetType,
 	pub ident: String,
 	pub formal_params: NodeList,
-	pub block: Node,
+	/// `None` for a function declared `extern`
+	pub block: Option<Node>,
 }
 
 #[derive(Debug, AstNode)]
@@ -123,6 +164,23 @@ pub struct While {
 	pub body: Node,
 }
 
+#[derive(Debug, AstNode)]
+#[has_attrs]
+pub struct For {
+	/// a declaration or an expression
+	pub init: Option<Node>,
+	pub cond: Option<Node>,
+	pub step: Option<Node>,
+	pub body: Node,
+}
+
+#[derive(Debug, AstNode)]
+#[has_attrs]
+pub struct DoWhile {
+	pub body: Node,
+	pub cond: Node,
+}
+
 #[derive(Debug, AstNode)]
 #[has_attrs]
 #[derive(Default)]
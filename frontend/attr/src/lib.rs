@@ -1,17 +1,43 @@
-mod impls;
+pub use rrvm_symbol::{FuncSymbol, VarSymbol};
+use utils::errors::Result;
+pub use value::{Value, VarType};
 
-use rrvm_symbol::{FuncSymbol, VarSymbol};
-use value::{Value, VarType};
-
-#[derive(Clone, Debug)]
-pub enum Attr {
-	FuncSymbol(FuncSymbol),
-	VarSymbol(VarSymbol),
-	VarType(VarType),
-	Value(Value),
+/// What the stages of the frontend find out about a node, a slot for each
+/// kind of it
+#[derive(Clone, Debug, Default)]
+pub struct AttrSlots {
+	pub symbol: Option<VarSymbol>,
+	pub func_symbol: Option<FuncSymbol>,
+	pub var_type: Option<VarType>,
+	/// the value of a constant expression
+	pub value: Option<Value>,
+	/// where an initializer list starts in the scalars of the whole one
+	pub slot: Option<usize>,
 }
 
+/// Accessors of the slots of a node, generated by `#[has_attrs]`. Reading a
+/// slot by its own name fails if no stage before has set it, while `get_`
+/// tells whether it is set.
 pub trait Attrs {
-	fn set_attr(&mut self, name: &str, attr: Attr);
-	fn get_attr(&self, name: &str) -> Option<&Attr>;
+	fn attrs(&self) -> &AttrSlots;
+
+	fn symbol(&self) -> Result<&VarSymbol>;
+	fn get_symbol(&self) -> Option<&VarSymbol>;
+	fn set_symbol(&mut self, value: VarSymbol);
+
+	fn func_symbol(&self) -> Result<&FuncSymbol>;
+	fn get_func_symbol(&self) -> Option<&FuncSymbol>;
+	fn set_func_symbol(&mut self, value: FuncSymbol);
+
+	fn var_type(&self) -> Result<&VarType>;
+	fn get_var_type(&self) -> Option<&VarType>;
+	fn set_var_type(&mut self, value: VarType);
+
+	fn value(&self) -> Result<&Value>;
+	fn get_value(&self) -> Option<&Value>;
+	fn set_value(&mut self, value: Value);
+
+	fn slot(&self) -> Result<&usize>;
+	fn get_slot(&self) -> Option<&usize>;
+	fn set_slot(&mut self, value: usize);
 }
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use interpreter::{Evaluator, Val};
+use llvm::{ArithInstr, LlvmInstrVariant, LlvmTemp, Value, VarType};
+use rrvm::LlvmCFG;
+use utils::{errors::Result, GlobalVar, Span, SysycError::SemanticError};
+use value::utils::to_data;
+
+use crate::{utils::type_convert, IRGenerator};
+
+// the steps all the calls in `const` initializers may run
+const FUEL: u64 = 1 << 24;
+
+/// A call in the initializer of a `const` array, its arguments are constants
+pub struct ConstCall {
+	ident: String,
+	span: Span,
+	target: LlvmTemp,
+	func: String,
+	params: Vec<Value>,
+}
+
+/// A global `const` array with calls in its initializer, the value of each
+/// scalar is a constant or the target of a call
+pub struct ConstGlobal {
+	pub ident: String,
+	pub var_type: value::VarType,
+	pub values: Vec<Value>,
+}
+
+fn to_val(value: &Value) -> Option<Val> {
+	match value {
+		Value::Int(v) => Some(Val::from(*v)),
+		Value::Float(v) => Some(Val::Float(*v)),
+		Value::Temp(_) => None,
+	}
+}
+
+impl IRGenerator {
+	/// Keep the calls in `cfg`, which initializes a scalar of `const` array
+	/// `ident`, to be evaluated once all the functions are generated
+	pub fn record_const_calls(&mut self, ident: &str, span: Span, cfg: &LlvmCFG) {
+		for block in cfg.blocks.iter() {
+			for instr in block.borrow().instrs.iter() {
+				if let LlvmInstrVariant::CallInstr(v) = instr.get_variant() {
+					self.const_calls.push(ConstCall {
+						ident: ident.to_string(),
+						span,
+						target: v.target.clone(),
+						func: v.func.name.clone(),
+						params: v.params.iter().map(|(_, v)| v.clone()).collect(),
+					});
+				}
+			}
+		}
+	}
+
+	/// Run the calls in the initializers of `const` arrays, each of them is
+	/// replaced by what it returns, and the global arrays get their data
+	pub fn eval_const_calls(&mut self) -> Result<()> {
+		let mut results = HashMap::new();
+		if !self.const_calls.is_empty() {
+			let evaluator = Evaluator::new(&self.program)?;
+			let mut fuel = FUEL;
+			for call in self.const_calls.iter() {
+				let value = call
+					.params
+					.iter()
+					.map(to_val)
+					.collect::<Option<Vec<_>>>()
+					.and_then(|args| evaluator.call(&call.func, args, &mut fuel).ok())
+					.ok_or_else(|| {
+						SemanticError(format!(
+							"'const {}' calls `{}`, which can not be evaluated at compile time",
+							call.ident, call.func
+						))
+						.at(call.span)
+					})?;
+				let value = match call.target.var_type {
+					VarType::F32 => Value::Float(value.as_float()),
+					_ => Value::Int(value.as_int() as i32),
+				};
+				results.insert(call.target.clone(), value);
+			}
+		}
+		for func in self.program.funcs.iter() {
+			for block in func.cfg.blocks.iter() {
+				for instr in block.borrow_mut().instrs.iter_mut() {
+					let Some(target) = instr.get_write() else {
+						continue;
+					};
+					let Some(value) = results.get(&target) else {
+						continue;
+					};
+					*instr = ArithInstr::constant(target, value.clone());
+				}
+			}
+		}
+		for global in std::mem::take(&mut self.const_globals) {
+			let var_type = &global.var_type;
+			let mut array = Vec::new();
+			for (slot, value) in global.values.iter().enumerate() {
+				let value = match value {
+					Value::Temp(temp) => &results[temp],
+					value => value,
+				};
+				let value: value::Value = match value {
+					Value::Float(v) => (*v).into(),
+					Value::Int(v) => (*v).into(),
+					Value::Temp(_) => unreachable!(),
+				};
+				array.push(value.to_type(var_type.scalar_at(slot).1.type_t)?);
+			}
+			let value = (var_type.dims.clone(), array).into();
+			self.program.global_vars.push(GlobalVar::new(
+				global.ident,
+				to_data(value, &var_type.type_t),
+				type_convert(var_type).is_float(),
+				false,
+			));
+		}
+		Ok(())
+	}
+}
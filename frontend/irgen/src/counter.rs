@@ -1,7 +1,5 @@
 use ast::*;
-use rrvm_symbol::VarSymbol;
 use utils::errors::Result;
-use value::BinaryOp;
 
 pub struct Counter {
 	pub symbols: Vec<i32>,
@@ -13,6 +11,13 @@ impl Counter {
 			symbols: Vec::new(),
 		}
 	}
+	fn assign(&mut self, node: &Node) {
+		if let Some(symbol) = node.get_symbol() {
+			if !symbol.is_global {
+				self.symbols.push(symbol.id);
+			}
+		}
+	}
 }
 
 impl Visitor for Counter {
@@ -37,27 +42,39 @@ impl Visitor for Counter {
 	fn visit_variable(&mut self, _node: &mut Variable) -> Result<()> {
 		Ok(())
 	}
+	fn visit_struct_decl(&mut self, _node: &mut StructDecl) -> Result<()> {
+		Ok(())
+	}
+	fn visit_field_access(&mut self, node: &mut FieldAccess) -> Result<()> {
+		node.base.accept(self)
+	}
 	fn visit_literal_int(&mut self, _node: &mut LiteralInt) -> Result<()> {
 		Ok(())
 	}
 	fn visit_literal_float(&mut self, _node: &mut LiteralFloat) -> Result<()> {
 		Ok(())
 	}
+	fn visit_literal_string(&mut self, _node: &mut LiteralString) -> Result<()> {
+		Ok(())
+	}
 	fn visit_binary_expr(&mut self, node: &mut BinaryExpr) -> Result<()> {
-		if node.op == BinaryOp::Assign {
-			if let Some(symbol) = node.lhs.get_attr("symbol") {
-				let symbol: VarSymbol = symbol.into();
-				if !symbol.is_global {
-					self.symbols.push(symbol.id);
-				}
-			}
+		if node.op.is_assign() {
+			self.assign(&node.lhs);
 		}
 		node.lhs.accept(self)?;
 		node.rhs.accept(self)
 	}
 	fn visit_unary_expr(&mut self, node: &mut UnaryExpr) -> Result<()> {
+		if node.op.step().is_some() {
+			self.assign(&node.rhs);
+		}
 		node.rhs.accept(self)
 	}
+	fn visit_conditional(&mut self, node: &mut Conditional) -> Result<()> {
+		node.cond.accept(self)?;
+		node.on_true.accept(self)?;
+		node.on_false.accept(self)
+	}
 	fn visit_func_call(&mut self, node: &mut FuncCall) -> Result<()> {
 		for param in node.params.iter_mut() {
 			param.accept(self)?;
@@ -82,6 +99,17 @@ impl Visitor for Counter {
 		node.cond.accept(self)?;
 		node.body.accept(self)
 	}
+	fn visit_for(&mut self, node: &mut For) -> Result<()> {
+		let parts = [&mut node.init, &mut node.cond, &mut node.step];
+		for part in parts.into_iter().flatten() {
+			part.accept(self)?;
+		}
+		node.body.accept(self)
+	}
+	fn visit_do_while(&mut self, node: &mut DoWhile) -> Result<()> {
+		node.body.accept(self)?;
+		node.cond.accept(self)
+	}
 	fn visit_return(&mut self, node: &mut Return) -> Result<()> {
 		if let Some(val) = &mut node.value {
 			val.accept(self)?;
@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+	collections::{HashMap, HashSet},
+	rc::Rc,
+};
 
 use ast::tree::*;
 
@@ -9,12 +12,14 @@ use rrvm::{
 	LlvmCFG, LlvmNode,
 };
 
+use rrvm_symbol::VarSymbol;
 use utils::errors::Result;
-use value::FuncRetType;
+use value::{BType, FuncRetType};
 
 use crate::{
 	loop_state::LoopState,
 	symbol_table::{SymbolTable, Table},
+	utils::type_convert,
 	visitor::Item,
 	IRGenerator,
 };
@@ -30,7 +35,10 @@ impl IRGenerator {
 			states: Vec::new(),
 			weights: Vec::new(),
 			is_global: false,
+			is_extern: false,
 			init_state: None,
+			const_calls: Vec::new(),
+			const_globals: Vec::new(),
 		}
 	}
 	pub fn to_rrvm(mut self, mut program: Program) -> Result<LlvmProgram> {
@@ -72,6 +80,32 @@ impl IRGenerator {
 			_ => unreachable!(),
 		}
 	}
+	/// Convert `value` to be stored in a variable of `type_t`, a `char` is
+	/// an `i32` sign-extended from its lowest byte
+	pub fn conv_to(
+		&mut self,
+		value: Value,
+		type_t: &value::VarType,
+		cfg: &LlvmCFG,
+	) -> Value {
+		let value = self.type_conv(value, type_convert(type_t), cfg);
+		if type_t.type_t != BType::Char || type_t.is_array() {
+			return value;
+		}
+		match value {
+			Value::Int(v) => Value::Int(v as i8 as i32),
+			value => {
+				let temp = self.new_temp(I32, false);
+				let instr = ArithInstr::new(temp.clone(), value, ArithOp::Shl, 24, I32);
+				cfg.get_exit().borrow_mut().push(instr);
+				let target = self.new_temp(I32, false);
+				let instr =
+					ArithInstr::new(target.clone(), temp, ArithOp::Ashr, 24, I32);
+				cfg.get_exit().borrow_mut().push(instr);
+				target.into()
+			}
+		}
+	}
 	pub fn solve(
 		&mut self,
 		val: Option<Value>,
@@ -157,6 +191,26 @@ impl IRGenerator {
 		cond.append(exit);
 		cond
 	}
+	// a local variable takes the value in the symbol table, anything else is
+	// stored to its address
+	pub fn assign(
+		&mut self,
+		symbol: Option<VarSymbol>,
+		addr: &Option<Value>,
+		value: Value,
+		cfg: &LlvmCFG,
+	) {
+		if let Some(addr) = addr {
+			let instr = Box::new(StoreInstr {
+				value: value.clone(),
+				addr: addr.clone(),
+			});
+			cfg.get_exit().borrow_mut().push(instr);
+		}
+		if let Some(symbol) = symbol.filter(|v| !v.is_global) {
+			self.symbol_table.set(symbol.id, value);
+		}
+	}
 	pub fn copy_symbols(
 		&mut self,
 		symbols: Vec<i32>,
@@ -200,8 +254,18 @@ impl IRGenerator {
 			})
 			.collect();
 
+		// `break` and `continue` leave the block they end in, so the blocks
+		// it used to fall through to lose a predecessor
 		prev.iter().for_each(|(node, _)| {
-			node.borrow_mut().succ.clear();
+			let label = node.borrow().label();
+			let succ = std::mem::take(&mut node.borrow_mut().succ);
+			for v in succ.iter() {
+				let mut v = v.borrow_mut();
+				v.prev.retain(|u| !Rc::ptr_eq(u, node));
+				v.phi_instrs.iter_mut().for_each(|phi| {
+					phi.source.retain(|(_, l)| *l != label);
+				});
+			}
 			link_node(node, &target)
 		});
 		let init: Vec<_> =
@@ -235,11 +299,11 @@ impl IRGenerator {
 	pub fn enter_branch(&mut self) {
 		self.weights.push(*self.weights.last().unwrap() * 0.5);
 	}
-	pub fn cur_size(&self) -> usize {
-		self.init_state.as_ref().unwrap().cur_size()
+	pub fn cur_slots(&self) -> usize {
+		self.init_state.as_ref().unwrap().cur_slots()
 	}
-	pub fn push(&mut self) {
-		self.init_state.as_mut().unwrap().push()
+	pub fn push(&mut self, slot: usize) {
+		self.init_state.as_mut().unwrap().push(slot)
 	}
 	pub fn pop(&mut self) -> Vec<Item> {
 		self.init_state.as_mut().unwrap().pop()
@@ -247,12 +311,18 @@ impl IRGenerator {
 	pub fn store(&mut self, item: Item) {
 		self.init_state.as_mut().unwrap().store(item)
 	}
-	pub fn default_init_val(&mut self) -> Value {
-		self.init_state.as_mut().unwrap().default_init_val()
-	}
 	pub fn top_len(&mut self) -> usize {
 		self.init_state.as_mut().unwrap().top_len()
 	}
+	/// Fill the slots of the list entered last with zeros up to `len`
+	pub fn fill(&mut self, len: usize) {
+		while self.top_len() < len {
+			let slot = self.top_len();
+			let value = self.init_state.as_ref().unwrap().default_init_val(slot);
+			let item = (self.new_cfg(), Some(value), None);
+			self.store(item);
+		}
+	}
 }
 
 impl Default for IRGenerator {
@@ -1,41 +1,44 @@
-use llvm::{Value, VarType};
+use llvm::Value;
 
-use crate::visitor::Item;
+use crate::{utils::type_convert, visitor::Item};
 
 pub struct InitlistState {
-	pub var_type: VarType,
-	pub decl_dims: Vec<usize>,
-	pub depth: usize,
-	pub values: Vec<Vec<Item>>,
+	pub type_t: value::VarType,
+	// each list entered, with the values of its slots so far
+	pub values: Vec<(value::VarType, Vec<Item>)>,
 }
 
 impl InitlistState {
-	pub fn new(var_type: VarType, decl_dims: Vec<usize>) -> Self {
+	pub fn new(type_t: value::VarType) -> Self {
 		Self {
-			var_type,
-			decl_dims,
-			depth: 0,
+			type_t,
 			values: Vec::new(),
 		}
 	}
-	pub fn cur_size(&self) -> usize {
-		self.decl_dims.iter().skip(self.depth).product()
-	}
-	pub fn push(&mut self) {
-		self.depth += 1;
-		self.values.push(Vec::new());
+	/// Enter a list, which initializes the member starting at `slot` of the
+	/// list it is in
+	pub fn push(&mut self, slot: usize) {
+		let type_t = match self.values.last() {
+			Some((type_t, _)) => type_t.member_at(slot).unwrap().1,
+			None => self.type_t.clone(),
+		};
+		self.values.push((type_t, Vec::new()));
 	}
 	pub fn pop(&mut self) -> Vec<Item> {
-		self.depth -= 1;
-		self.values.pop().unwrap()
+		self.values.pop().unwrap().1
 	}
 	pub fn store(&mut self, item: Item) {
-		self.values.last_mut().unwrap().push(item)
+		self.values.last_mut().unwrap().1.push(item)
+	}
+	pub fn cur_slots(&self) -> usize {
+		self.values.last().unwrap().0.slots()
 	}
-	pub fn default_init_val(&mut self) -> Value {
-		self.var_type.default_value()
+	/// The zero of the scalar in `slot` of the list entered last
+	pub fn default_init_val(&self, slot: usize) -> Value {
+		let (_, type_t) = self.values.last().unwrap().0.scalar_at(slot);
+		type_convert(&type_t).default_value()
 	}
 	pub fn top_len(&self) -> usize {
-		self.values.last().unwrap().len()
+		self.values.last().unwrap().1.len()
 	}
 }
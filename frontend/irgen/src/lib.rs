@@ -1,3 +1,4 @@
+mod const_calls;
 mod counter;
 pub mod impls;
 mod initlist_state;
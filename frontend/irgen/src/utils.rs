@@ -12,6 +12,11 @@ pub fn to_arith(op: BinaryOp, type_t: VarType) -> ArithOp {
 		(BinaryOp::Div, VarType::I32) => ArithOp::Div,
 		(BinaryOp::Div, VarType::F32) => ArithOp::Fdiv,
 		(BinaryOp::Mod, VarType::I32) => ArithOp::Rem,
+		(BinaryOp::BitAnd, VarType::I32) => ArithOp::And,
+		(BinaryOp::BitOr, VarType::I32) => ArithOp::Or,
+		(BinaryOp::BitXor, VarType::I32) => ArithOp::Xor,
+		(BinaryOp::Shl, VarType::I32) => ArithOp::Shl,
+		(BinaryOp::Shr, VarType::I32) => ArithOp::Ashr,
 		_ => unreachable!(),
 	}
 }
@@ -43,14 +48,26 @@ pub fn get_comp_kind(var_type: VarType) -> CompKind {
 }
 
 pub fn type_convert(from: &value::VarType) -> VarType {
-	match (from.type_t, from.dims.is_empty()) {
-		(BType::Int, true) => VarType::I32,
+	match (&from.type_t, from.dims.is_empty()) {
+		// a `struct` is known by its address
+		(BType::Struct(_), _) => VarType::I8Ptr,
+		(BType::Char | BType::Int, true) => VarType::I32,
 		(BType::Float, true) => VarType::F32,
+		(BType::Char, false) => VarType::I8Ptr,
 		(BType::Int, false) => VarType::I32Ptr,
 		(BType::Float, false) => VarType::F32Ptr,
 	}
 }
 
+/// The type of the address of a variable of type `from`
+pub fn ptr_convert(from: &value::VarType) -> VarType {
+	match from.type_t {
+		BType::Char | BType::Struct(_) => VarType::I8Ptr,
+		BType::Int => VarType::I32Ptr,
+		BType::Float => VarType::F32Ptr,
+	}
+}
+
 pub fn func_type_convert(from: &value::FuncRetType) -> VarType {
 	match from {
 		FuncRetType::Int => VarType::I32,
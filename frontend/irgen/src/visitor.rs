@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+	collections::{HashMap, HashSet},
+	iter::once,
+};
 
 use ast::{tree::*, Visitor};
 use attr::Attrs;
@@ -8,16 +11,24 @@ use rrvm::{
 	program::{LlvmFunc, LlvmProgram},
 	LlvmCFG,
 };
-use rrvm_symbol::{FuncSymbol, VarSymbol};
 use utils::{
-	errors::Result, math::align16, GlobalVar, Label, SysycError::TypeError,
-	ValueItem::Zero,
+	errors::Result,
+	math::align16,
+	GlobalVar, Label,
+	SysycError::TypeError,
+	ValueItem::{Byte, Zero},
+};
+use value::{
+	calc_type::to_rval, utils::to_data, BinaryOp, FuncRetType, UnaryOp,
 };
-use value::{utils::to_data, BinaryOp, FuncRetType, UnaryOp};
 
 use crate::{
-	counter::Counter, initlist_state::InitlistState, loop_state::LoopState,
-	symbol_table::SymbolTable, utils::*,
+	const_calls::{ConstCall, ConstGlobal},
+	counter::Counter,
+	initlist_state::InitlistState,
+	loop_state::LoopState,
+	symbol_table::SymbolTable,
+	utils::*,
 };
 
 pub type Item = (LlvmCFG, Option<Value>, Option<Value>);
@@ -31,7 +42,10 @@ pub struct IRGenerator {
 	pub states: Vec<LoopState>,
 	pub weights: Vec<f64>,
 	pub is_global: bool,
+	pub is_extern: bool,
 	pub init_state: Option<InitlistState>,
+	pub const_calls: Vec<ConstCall>,
+	pub const_globals: Vec<ConstGlobal>,
 }
 
 impl Visitor for IRGenerator {
@@ -47,11 +61,25 @@ impl Visitor for IRGenerator {
 			v.accept(self)?;
 			self.total = 0;
 		}
+		self.eval_const_calls()?;
 		self.symbol_table.pop();
 		Ok(())
 	}
 
 	fn visit_func_decl(&mut self, node: &mut FuncDecl) -> Result<()> {
+		let Some(block) = node.block.as_mut() else {
+			let params = node
+				.formal_params
+				.iter()
+				.map(|param| Ok(type_convert(&param.symbol()?.var_type)))
+				.collect::<Result<_>>()?;
+			self.program.externs.push(LlvmExtern::Func {
+				name: node.ident.clone(),
+				ret_type: func_type_convert(&node.ret_type),
+				params,
+			});
+			return Ok(());
+		};
 		self.symbol_table.push();
 		self.ret_type = node.ret_type;
 		let mut params = Vec::new();
@@ -59,10 +87,10 @@ impl Visitor for IRGenerator {
 			param.accept(self)?;
 		}
 		for param in node.formal_params.iter() {
-			let symbol: VarSymbol = param.get_attr("symbol").unwrap().into();
+			let symbol = param.symbol()?.clone();
 			params.push(self.symbol_table.get(&symbol.id));
 		}
-		node.block.accept(self)?;
+		block.accept(self)?;
 		let (mut cfg, _, _) = self.stack.pop().unwrap();
 		let var_type = func_type_convert(&node.ret_type);
 		cfg.blocks.iter().for_each(|v| v.borrow_mut().gen_jump(var_type));
@@ -80,34 +108,57 @@ impl Visitor for IRGenerator {
 	}
 
 	fn visit_var_def(&mut self, node: &mut VarDef) -> Result<()> {
-		let symbol: VarSymbol = node.get_attr("symbol").unwrap().into();
+		let symbol = node.symbol()?.clone();
+		let span = node.span();
 		let var_type = type_convert(&symbol.var_type);
 		if self.is_global {
 			let temp = LlvmTemp::new(&node.ident, var_type, true);
 			self.symbol_table.set(symbol.id, temp.into());
-			let data = if let Some(init) = node.init.as_ref() {
-				let value: value::Value = init.get_attr("value").unwrap().into();
-				to_data(value.conv_type(symbol.var_type.type_t))
+			// named like any global variable, but with no data here
+			if self.is_extern {
+				self.program.externs.push(LlvmExtern::Var {
+					name: node.ident.clone(),
+					var_type,
+				});
+				return Ok(());
+			}
+			let data = if let Some(init) = node.init.as_mut() {
+				let Some(value) = init.get_value().cloned() else {
+					// a `const` array calling functions has its data once they are
+					// generated
+					self.init_state = Some(InitlistState::new(symbol.var_type.clone()));
+					init.accept(self)?;
+					let _ = self.stack.pop().unwrap();
+					let mut values = Vec::new();
+					for (cfg, value, addr) in self.pop() {
+						self.record_const_calls(&node.ident, span, &cfg);
+						values.push(self.solve(value, addr, &cfg));
+					}
+					self.const_globals.push(ConstGlobal {
+						ident: node.ident.clone(),
+						var_type: symbol.var_type.clone(),
+						values,
+					});
+					return Ok(());
+				};
+				to_data(value, &symbol.var_type.type_t)
 			} else {
-				let length = symbol.var_type.dims.iter().product::<usize>();
-				vec![Zero(length * var_type.to_ptr().deref_type().get_size())]
+				vec![Zero(symbol.var_type.size() as usize)]
 			};
 			self.program.global_vars.push(GlobalVar::new(
 				node.ident.clone(),
 				data,
 				var_type.is_float(),
+				false,
 			));
 			return Ok(());
 		}
 		if let Some(init) = node.init.as_mut() {
 			self.symbol_table.set(symbol.id, var_type.default_value());
-			if symbol.var_type.is_array() {
-				let mut temp = self.new_temp(var_type, false);
-				let length = symbol.var_type.dims.iter().product::<usize>();
-				let length =
-					align16((length * var_type.deref_type().get_size()) as i32);
-				self.init_state =
-					Some(InitlistState::new(var_type, symbol.var_type.dims));
+			if symbol.var_type.is_aggregate() {
+				let temp = self.new_temp(var_type, false);
+				let length = align16(symbol.var_type.size());
+				self.init_state = Some(InitlistState::new(symbol.var_type.clone()));
 				self.symbol_table.set(symbol.id, temp.clone().into());
 				let mut now = self.new_cfg();
 				let instr = Box::new(AllocInstr {
@@ -118,39 +169,42 @@ impl Visitor for IRGenerator {
 				now.get_entry().borrow_mut().push(instr);
 				init.accept(self)?;
 				let _ = self.stack.pop().unwrap();
-				for (cfg, value, addr) in self.pop() {
+				// each scalar is stored at its offset from the start
+				for (slot, (cfg, value, addr)) in self.pop().into_iter().enumerate() {
+					if !symbol.var_type.is_lval {
+						self.record_const_calls(&node.ident, span, &cfg);
+					}
+					let (offset, scalar) = symbol.var_type.scalar_at(slot);
 					let value = self.solve(value, addr, &cfg);
-					let value = self.type_conv(value, var_type.deref_type(), &cfg);
-					let new_temp = self.new_temp(var_type, false);
-					cfg.get_exit().borrow_mut().push(Box::new(StoreInstr {
-						value,
-						addr: temp.clone().into(),
-					}));
+					let value = self.conv_to(value, &scalar, &cfg);
+					let var_type = ptr_convert(&scalar);
+					let addr = self.new_temp(var_type, false);
 					cfg.get_exit().borrow_mut().push(Box::new(GEPInstr {
-						target: new_temp.clone(),
+						target: addr.clone(),
 						var_type,
-						addr: temp.into(),
-						offset: (var_type.deref_type().get_size() as i32).into(),
+						addr: temp.clone().into(),
+						offset: offset.into(),
+					}));
+					cfg.get_exit().borrow_mut().push(Box::new(StoreInstr {
+						value,
+						addr: addr.into(),
 					}));
 					link_cfg(&now, &cfg);
 					now.append(cfg);
-					temp = new_temp;
 				}
 				self.stack.push((now, None, None));
 			} else {
 				init.accept(self)?;
 				let (cfg, value, addr) = self.stack.pop().unwrap();
 				let value = self.solve(value, addr, &cfg);
-				let value = self.type_conv(value, var_type, &cfg);
+				let value = self.conv_to(value, &symbol.var_type, &cfg);
 				self.symbol_table.set(symbol.id, value);
 				self.stack.push((cfg, None, None));
 			};
 		} else {
 			let cfg: LlvmCFG = self.new_cfg();
-			if symbol.var_type.is_array() {
-				let length: usize = symbol.var_type.dims.iter().product();
-				let length =
-					align16((length * var_type.deref_type().get_size()) as i32);
+			if symbol.var_type.is_aggregate() {
+				let length = align16(symbol.var_type.size());
 				let temp = self.new_temp(var_type, false);
 				self.symbol_table.set(symbol.id, temp.clone().into());
 				let instr = Box::new(AllocInstr {
@@ -169,9 +223,11 @@ impl Visitor for IRGenerator {
 
 	fn visit_var_decl(&mut self, node: &mut VarDecl) -> Result<()> {
 		if self.is_global {
+			self.is_extern = node.is_extern;
 			for var_def in node.defs.iter_mut() {
 				var_def.accept(self)?;
 			}
+			self.is_extern = false;
 			return Ok(());
 		}
 		let mut cfgs = Vec::new();
@@ -185,20 +241,15 @@ impl Visitor for IRGenerator {
 	}
 
 	fn visit_init_val_list(&mut self, node: &mut InitValList) -> Result<()> {
-		let cur_len = self.cur_size();
-		self.push();
-		let len = self.cur_size();
+		// the slot a list starts at in the list it is in
+		self.push(*node.slot()?);
 		for val in node.val_list.iter_mut() {
 			val.accept(self)?;
 			let (cfg, value, addr) = self.stack.pop().unwrap();
 			match (&value, &addr) {
 				(None, None) => {
 					let array = self.pop();
-					let size = (len - array.len() % len) % len;
-					for _ in 0..size {
-						let item = (self.new_cfg(), Some(self.default_init_val()), None);
-						self.store(item);
-					}
+					self.fill(*val.slot()?);
 					array.into_iter().for_each(|item| self.store(item));
 				}
 				_ => {
@@ -207,16 +258,8 @@ impl Visitor for IRGenerator {
 				}
 			}
 		}
-		let top_len = self.top_len();
-		let size = if top_len == 0 {
-			cur_len
-		} else {
-			(cur_len - top_len % cur_len) % cur_len
-		};
-		for _ in 0..size {
-			let item = (self.new_cfg(), Some(self.default_init_val()), None);
-			self.store(item);
-		}
+		let len = self.cur_slots();
+		self.fill(len);
 		let item = (self.new_cfg(), None, None);
 		self.stack.push(item);
 		Ok(())
@@ -224,10 +267,10 @@ impl Visitor for IRGenerator {
 
 	fn visit_variable(&mut self, node: &mut Variable) -> Result<()> {
 		let cfg: LlvmCFG = self.new_cfg();
-		let symbol: VarSymbol = node.get_attr("symbol").unwrap().into();
+		let symbol = node.symbol()?.clone();
 		let temp = self.symbol_table.get(&symbol.id);
 		if temp.is_global() {
-			let var_type = type_convert(&symbol.var_type).to_ptr();
+			let var_type = ptr_convert(&symbol.var_type);
 			let target = self.new_temp(var_type, false);
 			let instr = Box::new(LoadInstr {
 				target: target.clone(),
@@ -236,7 +279,7 @@ impl Visitor for IRGenerator {
 			});
 			cfg.get_exit().borrow_mut().push(instr);
 			self.stack.push((cfg, None, Some(target.into())));
-		} else if symbol.var_type.is_array() {
+		} else if symbol.var_type.is_aggregate() {
 			self.stack.push((cfg, None, Some(temp)));
 		} else {
 			self.stack.push((cfg, Some(temp), None));
@@ -244,6 +287,36 @@ impl Visitor for IRGenerator {
 		Ok(())
 	}
 
+	fn visit_struct_decl(&mut self, _node: &mut StructDecl) -> Result<()> {
+		if !self.is_global {
+			let cfg = self.new_cfg();
+			self.stack.push((cfg, None, None));
+		}
+		Ok(())
+	}
+
+	fn visit_field_access(&mut self, node: &mut FieldAccess) -> Result<()> {
+		let type_t = node.var_type()?.clone();
+		let base_t = node.base.var_type()?.clone();
+		let value::BType::Struct(record) = &base_t.type_t else {
+			unreachable!()
+		};
+		let offset = record.get_field(&node.ident).unwrap().offset;
+		node.base.accept(self)?;
+		let (cfg, _, addr) = self.stack.pop().unwrap();
+		let var_type = ptr_convert(&type_t);
+		let temp = self.new_temp(var_type, false);
+		let instr = Box::new(GEPInstr {
+			target: temp.clone(),
+			var_type,
+			addr: addr.unwrap(),
+			offset: offset.into(),
+		});
+		cfg.get_exit().borrow_mut().push(instr);
+		self.stack.push((cfg, None, Some(temp.into())));
+		Ok(())
+	}
+
 	fn visit_literal_int(&mut self, node: &mut LiteralInt) -> Result<()> {
 		let now: LlvmCFG = self.new_cfg();
 		self.stack.push((now, Some(node.value.into()), None));
@@ -256,6 +329,23 @@ impl Visitor for IRGenerator {
 		Ok(())
 	}
 
+	fn visit_literal_string(&mut self, node: &mut LiteralString) -> Result<()> {
+		let cfg: LlvmCFG = self.new_cfg();
+		let ident = format!(".str.{}", self.program.global_vars.len());
+		let data = node.value.iter().chain(once(&0)).map(|v| Byte(*v)).collect();
+		let global = LlvmTemp::new(&ident, I8Ptr, true);
+		self.program.global_vars.push(GlobalVar::new(ident, data, false, true));
+		let target = self.new_temp(I8Ptr, false);
+		let instr = Box::new(LoadInstr {
+			target: target.clone(),
+			var_type: I8Ptr,
+			addr: global.into(),
+		});
+		cfg.get_exit().borrow_mut().push(instr);
+		self.stack.push((cfg, None, Some(target.into())));
+		Ok(())
+	}
+
 	fn visit_binary_expr(&mut self, node: &mut BinaryExpr) -> Result<()> {
 		use BinaryOp::*;
 		node.lhs.accept(self)?;
@@ -263,25 +353,27 @@ impl Visitor for IRGenerator {
 		self.symbol_table.push();
 		node.rhs.accept(self)?;
 		let (rcfg, rhs_val, rhs_addr) = self.stack.pop().unwrap();
-		let type_t = node.get_attr("type").unwrap().into();
+		let type_t = node.var_type()?.clone();
 		let var_type = type_convert(&type_t);
 		let (cfg, ret_val, ret_addr) = match node.op {
-			Assign => {
-				let rhs_val = self.solve(rhs_val, rhs_addr, &rcfg);
-				let val = self.type_conv(rhs_val, var_type, &rcfg);
-				if let Some(addr) = &lhs_addr {
-					let instr = Box::new(StoreInstr {
-						value: val.clone(),
-						addr: addr.clone(),
-					});
+			op if op.is_assign() => {
+				let mut rhs_val = self.solve(rhs_val, rhs_addr, &rcfg);
+				if let Some(op) = op.compound() {
+					// loaded through the address the value is stored to
+					let lhs_val = self.solve(lhs_val, lhs_addr.clone(), &lcfg);
+					let op_type =
+						upgrade(lhs_val.get_type(), rhs_val.get_type()).unwrap();
+					let lhs = self.type_conv(lhs_val, op_type, &rcfg);
+					let rhs = self.type_conv(rhs_val, op_type, &rcfg);
+					let temp = self.new_temp(op_type, false);
+					let op = to_arith(op, op_type);
+					let instr = ArithInstr::new(temp.clone(), lhs, op, rhs, op_type);
 					rcfg.get_exit().borrow_mut().push(instr);
+					rhs_val = temp.into();
 				}
-				if let Some(symbol) = node.lhs.get_attr("symbol") {
-					let symbol: VarSymbol = symbol.into();
-					if !symbol.is_global {
-						self.symbol_table.set(symbol.id, val.clone());
-					}
-				}
+				let val = self.conv_to(rhs_val, &type_t, &rcfg);
+				let symbol = node.lhs.get_symbol().cloned();
+				self.assign(symbol, &lhs_addr, val.clone(), &rcfg);
 				link_cfg(&lcfg, &rcfg);
 				lcfg.append(rcfg);
 				self.symbol_table.pop();
@@ -317,7 +409,7 @@ impl Visitor for IRGenerator {
 				let lhs_val = self.solve(lhs_val, lhs_addr, &lcfg);
 				let rhs_val = self.solve(rhs_val, rhs_addr, &rcfg);
 				match node.op {
-					Add | Sub | Mul | Div | Mod => {
+					Add | Sub | Mul | Div | Mod | BitAnd | BitOr | BitXor | Shl | Shr => {
 						let lhs = self.type_conv(lhs_val, var_type, &lcfg);
 						let rhs = self.type_conv(rhs_val, var_type, &rcfg);
 						let op = to_arith(node.op, var_type);
@@ -389,11 +481,28 @@ impl Visitor for IRGenerator {
 	}
 
 	fn visit_unary_expr(&mut self, node: &mut UnaryExpr) -> Result<()> {
-		let var_type = type_convert(&node.get_attr("type").unwrap().into());
+		let type_t = node.var_type()?.clone();
+		let var_type = type_convert(&type_t);
 		node.rhs.accept(self)?;
 		let (cfg, val, addr) = self.stack.pop().unwrap();
-		let temp = self.solve(val, addr, &cfg);
+		let temp = self.solve(val, addr.clone(), &cfg);
 		match node.op {
+			UnaryOp::PreInc
+			| UnaryOp::PreDec
+			| UnaryOp::PostInc
+			| UnaryOp::PostDec => {
+				let op = to_arith(node.op.step().unwrap(), var_type);
+				let one = self.type_conv(1.into(), var_type, &cfg);
+				let target = self.new_temp(var_type, false);
+				let instr =
+					ArithInstr::new(target.clone(), temp.clone(), op, one, var_type);
+				cfg.get_exit().borrow_mut().push(instr);
+				let target = self.conv_to(target.into(), &type_t, &cfg);
+				let symbol = node.rhs.get_symbol().cloned();
+				self.assign(symbol, &addr, target.clone(), &cfg);
+				let val = if node.op.is_postfix() { temp } else { target };
+				self.stack.push((cfg, Some(val), None));
+			}
 			UnaryOp::Plus => self.stack.push((cfg, Some(temp), None)),
 			UnaryOp::Neg => {
 				let op = to_arith(BinaryOp::Sub, var_type);
@@ -432,21 +541,58 @@ impl Visitor for IRGenerator {
 		Ok(())
 	}
 
+	fn visit_conditional(&mut self, node: &mut Conditional) -> Result<()> {
+		let var_type = type_convert(node.var_type()?);
+		node.cond.accept(self)?;
+		let (cond, cond_val, cond_addr) = self.stack.pop().unwrap();
+		let cond_val = self.solve(cond_val, cond_addr, &cond);
+		self.enter_branch();
+		let mut branches = Vec::new();
+		for v in [&mut node.on_true, &mut node.on_false] {
+			self.symbol_table.push();
+			v.accept(self)?;
+			let (cfg, val, addr) = self.stack.pop().unwrap();
+			let val = self.solve(val, addr, &cfg);
+			let val = self.type_conv(val, var_type, &cfg);
+			branches.push((cfg, val, self.symbol_table.drop()));
+		}
+		let _ = self.weights.pop();
+		let (cfg2, val2, diff2) = branches.pop().unwrap();
+		let (cfg1, val1, diff1) = branches.pop().unwrap();
+		let source = vec![(val1, cfg1.exit_label()), (val2, cfg2.exit_label())];
+		let cfg = self.if_then_else(cond, cond_val, cfg1, diff1, cfg2, diff2);
+		let temp = self.new_temp(var_type, false);
+		let instr = PhiInstr {
+			target: temp.clone(),
+			var_type,
+			source,
+		};
+		cfg.get_exit().borrow_mut().push_phi(instr);
+		self.stack.push((cfg, Some(temp.into()), None));
+		Ok(())
+	}
+
 	fn visit_func_call(&mut self, node: &mut FuncCall) -> Result<()> {
-		let symbol: FuncSymbol = node.get_attr("func_symbol").unwrap().into();
+		let symbol = node.func_symbol()?.clone();
 		let mut cfgs = Vec::new();
 		let mut params = Vec::new();
 		let (ret_type, params_type) = symbol.var_type;
-		for (param, type_t) in node.params.iter_mut().zip(params_type.iter()) {
+		for (index, param) in node.params.iter_mut().enumerate() {
+			// the arguments of a variadic function after the declared ones keep
+			// their own types
+			let type_t = match params_type.get(index) {
+				Some(type_t) => type_t.clone(),
+				None => to_rval(param.var_type()?),
+			};
 			param.accept(self)?;
 			let (cfg, val, addr) = self.stack.pop().unwrap();
-			let var_type = type_convert(type_t);
+			let var_type = type_convert(&type_t);
 			let val = if var_type.is_ptr() {
 				addr.unwrap()
 			} else {
-				self.solve(val, addr, &cfg)
+				let val = self.solve(val, addr, &cfg);
+				self.conv_to(val, &type_t, &cfg)
 			};
-			let val = self.type_conv(val, var_type, &cfg);
 			cfgs.push(cfg);
 			params.push((var_type, val));
 		}
@@ -465,7 +611,7 @@ impl Visitor for IRGenerator {
 	}
 
 	fn visit_formal_param(&mut self, node: &mut FormalParam) -> Result<()> {
-		let symbol: VarSymbol = node.get_attr("symbol").unwrap().into();
+		let symbol = node.symbol()?.clone();
 		let temp = self.new_temp(type_convert(&symbol.var_type), false);
 		self.symbol_table.set(symbol.id, temp.into());
 		Ok(())
@@ -560,6 +706,148 @@ impl Visitor for IRGenerator {
 		Ok(())
 	}
 
+	fn visit_for(&mut self, node: &mut For) -> Result<()> {
+		let mut start = match node.init.as_mut() {
+			Some(init) => {
+				init.accept(self)?;
+				self.stack.pop().unwrap().0
+			}
+			None => self.new_cfg(),
+		};
+		let mut counter = Counter::new();
+		for part in [&mut node.cond, &mut node.step].into_iter().flatten() {
+			part.accept(&mut counter)?;
+		}
+		node.body.accept(&mut counter)?;
+		let (init, init_diff, need_phi) = self.copy_symbols(counter.symbols);
+
+		self.enter_loop();
+		let (cond, cond_val) = match node.cond.as_mut() {
+			Some(cond) => {
+				cond.accept(self)?;
+				let (cond, cond_val, cond_addr) = self.stack.pop().unwrap();
+				let cond_val = self.solve(cond_val, cond_addr, &cond);
+				(cond, cond_val)
+			}
+			None => (self.new_cfg(), 1.into()),
+		};
+
+		self.symbol_table.push();
+		node.body.accept(self)?;
+		let (body, _, _) = self.stack.pop().unwrap();
+		let body_diff = self.symbol_table.drop();
+
+		// `continue` goes on to the step, which then goes back to `cond`
+		let mut next = std::mem::take(&mut self.top_state().entry);
+		if body.get_exit().borrow().jump_instr.is_none() {
+			next.push((body.get_exit(), body_diff));
+		}
+		let step = if next.is_empty() {
+			None
+		} else {
+			self.symbol_table.push();
+			let mut step = self.new_cfg();
+			self.link_into(step.get_entry(), next, None);
+			if let Some(v) = node.step.as_mut() {
+				v.accept(self)?;
+				let (cfg, _, _) = self.stack.pop().unwrap();
+				link_cfg(&step, &cfg);
+				step.append(cfg);
+			}
+			Some((step, self.symbol_table.drop()))
+		};
+		let mut loop_state = self.states.pop().unwrap();
+
+		let _ = self.weights.pop();
+		let exit = self.new_cfg();
+		let before_exit = self.new_cfg();
+		loop_state.push_entry(init.get_exit(), init_diff);
+		loop_state.push_exit(before_exit.get_exit(), HashMap::new());
+		if let Some((step, step_diff)) = &step {
+			loop_state.push_entry(step.get_exit(), step_diff.clone());
+		}
+
+		link_cfg(&cond, &body);
+		link_cfg(&cond, &before_exit);
+		let instr = Box::new(JumpCondInstr {
+			var_type: cond_val.get_type(),
+			cond: cond_val,
+			target_true: body.entry_label(),
+			target_false: before_exit.entry_label(),
+		});
+		cond.get_exit().borrow_mut().set_jump(Some(instr));
+
+		self.link_into(cond.get_entry(), loop_state.entry, Some(need_phi));
+		self.link_into(exit.get_entry(), loop_state.exit, None);
+		link_cfg(&start, &init);
+		start.append(init);
+		start.append(cond);
+		start.append(body);
+		if let Some((step, _)) = step {
+			start.append(step);
+		}
+		start.append(before_exit);
+		start.append(exit);
+		self.stack.push((start, None, None));
+		Ok(())
+	}
+
+	fn visit_do_while(&mut self, node: &mut DoWhile) -> Result<()> {
+		let mut counter = Counter::new();
+		node.body.accept(&mut counter)?;
+		node.cond.accept(&mut counter)?;
+		let (mut init, init_diff, need_phi) = self.copy_symbols(counter.symbols);
+
+		self.enter_loop();
+		self.symbol_table.push();
+		node.body.accept(self)?;
+		let (body, _, _) = self.stack.pop().unwrap();
+		let body_diff = self.symbol_table.drop();
+
+		// `continue` goes on to `cond`, which then goes back to the body
+		let mut next = std::mem::take(&mut self.top_state().entry);
+		if body.get_exit().borrow().jump_instr.is_none() {
+			next.push((body.get_exit(), body_diff));
+		}
+		self.symbol_table.push();
+		let mut cond = self.new_cfg();
+		self.link_into(cond.get_entry(), next, None);
+		node.cond.accept(self)?;
+		let (cfg, cond_val, cond_addr) = self.stack.pop().unwrap();
+		let cond_val = self.solve(cond_val, cond_addr, &cfg);
+		link_cfg(&cond, &cfg);
+		cond.append(cfg);
+		let cond_diff = self.symbol_table.drop();
+		let mut loop_state = self.states.pop().unwrap();
+
+		let _ = self.weights.pop();
+		let exit = self.new_cfg();
+		let before_exit = self.new_cfg();
+		loop_state.push_entry(init.get_exit(), init_diff);
+		loop_state.push_entry(cond.get_exit(), cond_diff.clone());
+		loop_state.push_exit(before_exit.get_exit(), cond_diff);
+
+		// the body is linked back to first, as linking into it takes away the
+		// edges `cond` has
+		self.link_into(body.get_entry(), loop_state.entry, Some(need_phi));
+		link_cfg(&cond, &before_exit);
+		let instr = Box::new(JumpCondInstr {
+			var_type: cond_val.get_type(),
+			cond: cond_val,
+			target_true: body.entry_label(),
+			target_false: before_exit.entry_label(),
+		});
+		cond.get_exit().borrow_mut().set_jump(Some(instr));
+
+		self.link_into(exit.get_entry(), loop_state.exit, None);
+		init.append(body);
+		init.append(cond);
+		init.append(before_exit);
+		init.append(exit);
+		self.stack.push((init, None, None));
+		Ok(())
+	}
+
 	fn visit_continue(&mut self, _node: &mut Continue) -> Result<()> {
 		let cfg = self.new_cfg();
 		let diff = self.symbol_table.top(self.states.last().unwrap().size);
@@ -0,0 +1,122 @@
+pub mod visitor;
+
+use std::collections::HashSet;
+
+use utils::{render_diagnostic, render_warning, warning, SourceMap, Span};
+
+/// A kind of warning, turned on by `-W<name>` and off by `-Wno-<name>`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Lint {
+	Unused,
+	Uninitialized,
+	Unreachable,
+	ReturnType,
+	DivByZero,
+}
+
+impl Lint {
+	pub const ALL: [Lint; 5] = [
+		Lint::Unused,
+		Lint::Uninitialized,
+		Lint::Unreachable,
+		Lint::ReturnType,
+		Lint::DivByZero,
+	];
+	pub fn name(&self) -> &'static str {
+		match self {
+			Lint::Unused => "unused",
+			Lint::Uninitialized => "uninitialized",
+			Lint::Unreachable => "unreachable",
+			Lint::ReturnType => "return-type",
+			Lint::DivByZero => "div-by-zero",
+		}
+	}
+	pub fn from_name(name: &str) -> Option<Lint> {
+		Lint::ALL.into_iter().find(|v| v.name() == name)
+	}
+	/// Whether it is reported without any `-W` flag, like gcc does
+	pub fn is_default(&self) -> bool {
+		matches!(self, Lint::ReturnType | Lint::DivByZero)
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct Warning {
+	pub lint: Lint,
+	pub message: String,
+	pub span: Span,
+}
+
+impl Warning {
+	/// Render it with the flag that turns it on, as an error under `-Werror`
+	pub fn render(&self, file_name: &str, code: &str, is_error: bool) -> String {
+		self.render_in(&SourceMap::single(file_name, code), is_error)
+	}
+	/// Render it like [`Warning::render`], with the line of the file it comes
+	/// from
+	pub fn render_in(&self, sources: &SourceMap, is_error: bool) -> String {
+		let (file_name, code, span) = sources.locate(self.span);
+		let span = Some(span);
+		if is_error {
+			let message = format!("{} [-Werror={}]", self.message, self.lint.name());
+			render_diagnostic(message, span, file_name, code)
+		} else {
+			let message = format!("{} [-W{}]", self.message, self.lint.name());
+			render_warning(message, span, file_name, code)
+		}
+	}
+}
+
+/// The lints chosen by the `-W` flags, applied in order: `-Wall` turns all
+/// of them on, and `-Werror` makes them errors
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+	enabled: HashSet<Lint>,
+	pub werror: bool,
+}
+
+impl Default for LintConfig {
+	fn default() -> Self {
+		Self {
+			enabled: Lint::ALL.into_iter().filter(|v| v.is_default()).collect(),
+			werror: false,
+		}
+	}
+}
+
+impl LintConfig {
+	pub fn new(flags: &[String]) -> Self {
+		let mut config = Self::default();
+		for flag in flags {
+			let (name, on) = match flag.strip_prefix("no-") {
+				Some(name) => (name, false),
+				None => (flag.as_str(), true),
+			};
+			let lints = match name {
+				"error" => {
+					config.werror = on;
+					continue;
+				}
+				"all" => Lint::ALL.to_vec(),
+				_ => match Lint::from_name(name) {
+					Some(lint) => vec![lint],
+					None => {
+						warning(format!("unknown warning option '-W{flag}'"));
+						continue;
+					}
+				},
+			};
+			for lint in lints {
+				if on {
+					config.enabled.insert(lint);
+				} else {
+					config.enabled.remove(&lint);
+				}
+			}
+		}
+		config
+	}
+	pub fn is_enabled(&self, lint: Lint) -> bool {
+		self.enabled.contains(&lint)
+	}
+}
@@ -0,0 +1,356 @@
+use std::collections::HashSet;
+
+use ast::{tree::*, Visitor};
+use rrvm_symbol::VarSymbol;
+use utils::{errors::Result, Span};
+use value::{BType, BinaryOp, FuncRetType, Value};
+
+use crate::{Lint, Warning};
+
+// the local variables surely assigned on every path to a point, `None` if no
+// path reaches it
+type Flow = Option<HashSet<i32>>;
+
+fn meet(x: Flow, y: Flow) -> Flow {
+	match (x, y) {
+		(Some(x), Some(y)) => Some(x.intersection(&y).copied().collect()),
+		(x, None) => x,
+		(None, y) => y,
+	}
+}
+
+// the flows leaving a loop other than by its condition
+#[derive(Default)]
+struct Exits {
+	breaks: Flow,
+	continues: Flow,
+}
+
+struct Local {
+	id: i32,
+	ident: String,
+	span: Span,
+	is_param: bool,
+}
+
+fn always_true(cond: &Node) -> bool {
+	cond.get_value().is_some_and(|v| v.to_float().is_ok_and(|v| v != 0.0))
+}
+
+fn symbol(node: &dyn AstNode) -> Option<VarSymbol> {
+	node.get_symbol().cloned()
+}
+
+/// Looks for code that is valid but likely wrong, on a program the typer has
+/// checked
+#[derive(Default)]
+pub struct Linter {
+	warnings: Vec<Warning>,
+	flow: Flow,
+	loops: Vec<Exits>,
+	// scalars declared without a value, until one is read unassigned
+	tracked: HashSet<i32>,
+	locals: Vec<Local>,
+	used: HashSet<i32>,
+	is_const: bool,
+}
+
+impl Linter {
+	/// All the warnings of the program, in the order of the source
+	pub fn transform(&mut self, program: &mut Program) -> Vec<Warning> {
+		// the linter itself never fails
+		let _ = program.accept(self);
+		let mut warnings = std::mem::take(&mut self.warnings);
+		warnings.sort_by_key(|v| v.span.start);
+		warnings
+	}
+	fn warn(&mut self, lint: Lint, message: String, span: Span) {
+		self.warnings.push(Warning {
+			lint,
+			message,
+			span,
+		});
+	}
+	fn assign(&mut self, id: i32) {
+		if let Some(flow) = self.flow.as_mut() {
+			flow.insert(id);
+		}
+	}
+	fn declare(&mut self, node: &dyn AstNode, ident: &str, is_param: bool) {
+		let symbol = symbol(node).unwrap();
+		self.locals.push(Local {
+			id: symbol.id,
+			ident: ident.to_string(),
+			span: node.span(),
+			is_param,
+		});
+	}
+	// visit a loop body, returning where it breaks and continues
+	fn visit_body(&mut self, body: &mut Node) -> Result<Exits> {
+		self.loops.push(Exits::default());
+		body.accept(self)?;
+		Ok(self.loops.pop().unwrap())
+	}
+}
+
+impl Visitor for Linter {
+	fn visit_program(&mut self, node: &mut Program) -> Result<()> {
+		for v in node.functions.iter_mut() {
+			v.accept(self)?;
+		}
+		Ok(())
+	}
+
+	fn visit_func_decl(&mut self, node: &mut FuncDecl) -> Result<()> {
+		let Some(block) = node.block.as_mut() else {
+			return Ok(());
+		};
+		self.flow = Some(HashSet::new());
+		self.tracked.clear();
+		for param in node.formal_params.iter_mut() {
+			param.accept(self)?;
+		}
+		block.accept(self)?;
+		if self.flow.is_some()
+			&& node.ret_type != FuncRetType::Void
+			&& node.ident != "main"
+		{
+			let end = block.span().end;
+			self.warn(
+				Lint::ReturnType,
+				format!("control reaches end of non-void function `{}`", node.ident),
+				Span::new(end.saturating_sub(1), end),
+			);
+		}
+		for local in std::mem::take(&mut self.locals) {
+			if !self.used.contains(&local.id) {
+				let kind = if local.is_param {
+					"parameter"
+				} else {
+					"variable"
+				};
+				let message = format!("unused {} `{}`", kind, local.ident);
+				self.warn(Lint::Unused, message, local.span);
+			}
+		}
+		Ok(())
+	}
+
+	fn visit_var_def(&mut self, node: &mut VarDef) -> Result<()> {
+		if !self.is_const {
+			self.declare(node, &node.ident, false);
+		}
+		let symbol = symbol(node).unwrap();
+		match node.init.as_mut() {
+			Some(init) => {
+				init.accept(self)?;
+				self.assign(symbol.id);
+			}
+			None if !symbol.var_type.is_aggregate() => {
+				self.tracked.insert(symbol.id);
+			}
+			None => {}
+		}
+		Ok(())
+	}
+
+	fn visit_var_decl(&mut self, node: &mut VarDecl) -> Result<()> {
+		self.is_const = node.is_const;
+		for var_def in node.defs.iter_mut() {
+			var_def.accept(self)?;
+		}
+		self.is_const = false;
+		Ok(())
+	}
+
+	fn visit_struct_decl(&mut self, _node: &mut StructDecl) -> Result<()> {
+		Ok(())
+	}
+
+	fn visit_init_val_list(&mut self, node: &mut InitValList) -> Result<()> {
+		for val in node.val_list.iter_mut() {
+			val.accept(self)?;
+		}
+		Ok(())
+	}
+
+	fn visit_literal_int(&mut self, _node: &mut LiteralInt) -> Result<()> {
+		Ok(())
+	}
+
+	fn visit_literal_float(&mut self, _node: &mut LiteralFloat) -> Result<()> {
+		Ok(())
+	}
+
+	fn visit_literal_string(&mut self, _node: &mut LiteralString) -> Result<()> {
+		Ok(())
+	}
+
+	fn visit_binary_expr(&mut self, node: &mut BinaryExpr) -> Result<()> {
+		let is_div = matches!(
+			node.op,
+			BinaryOp::Div | BinaryOp::Mod | BinaryOp::DivAssign | BinaryOp::ModAssign
+		);
+		let lhs = node.lhs.var_type()?.clone();
+		let rhs = node.rhs.get_value().cloned();
+		if is_div
+			&& lhs.type_t != BType::Float
+			&& matches!(rhs, Some(Value::Int(0)))
+		{
+			self.warn(Lint::DivByZero, "division by zero".to_string(), node.span());
+		}
+		match node.op {
+			// storing to a scalar neither reads nor uses it
+			BinaryOp::Assign if !lhs.is_aggregate() => {
+				node.rhs.accept(self)?;
+				match symbol(node.lhs.as_ref()) {
+					Some(symbol) => self.assign(symbol.id),
+					None => node.lhs.accept(self)?,
+				}
+			}
+			// the right side may not run
+			BinaryOp::LAnd | BinaryOp::LOr => {
+				node.lhs.accept(self)?;
+				let flow = self.flow.clone();
+				node.rhs.accept(self)?;
+				self.flow = flow;
+			}
+			_ => {
+				node.lhs.accept(self)?;
+				node.rhs.accept(self)?;
+			}
+		}
+		Ok(())
+	}
+
+	fn visit_unary_expr(&mut self, node: &mut UnaryExpr) -> Result<()> {
+		node.rhs.accept(self)
+	}
+
+	fn visit_conditional(&mut self, node: &mut Conditional) -> Result<()> {
+		node.cond.accept(self)?;
+		let flow = self.flow.clone();
+		node.on_true.accept(self)?;
+		let on_true = std::mem::replace(&mut self.flow, flow);
+		node.on_false.accept(self)?;
+		self.flow = meet(on_true, self.flow.take());
+		Ok(())
+	}
+
+	fn visit_func_call(&mut self, node: &mut FuncCall) -> Result<()> {
+		for param in node.params.iter_mut() {
+			param.accept(self)?;
+		}
+		Ok(())
+	}
+
+	fn visit_formal_param(&mut self, node: &mut FormalParam) -> Result<()> {
+		self.declare(node, &node.ident, true);
+		Ok(())
+	}
+
+	fn visit_variable(&mut self, node: &mut Variable) -> Result<()> {
+		let symbol = symbol(node).unwrap();
+		self.used.insert(symbol.id);
+		let assigned = match &self.flow {
+			Some(flow) => flow.contains(&symbol.id),
+			None => true,
+		};
+		if !assigned && self.tracked.remove(&symbol.id) {
+			self.warn(
+				Lint::Uninitialized,
+				format!("`{}` may be used uninitialized", node.ident),
+				node.span(),
+			);
+		}
+		Ok(())
+	}
+
+	fn visit_field_access(&mut self, node: &mut FieldAccess) -> Result<()> {
+		node.base.accept(self)
+	}
+
+	fn visit_block(&mut self, node: &mut Block) -> Result<()> {
+		let mut reported = self.flow.is_none();
+		for stmt in node.stmts.iter_mut() {
+			if self.flow.is_none() && !reported {
+				reported = true;
+				let message = "unreachable statement".to_string();
+				self.warn(Lint::Unreachable, message, stmt.span());
+			}
+			stmt.accept(self)?;
+		}
+		Ok(())
+	}
+
+	fn visit_if(&mut self, node: &mut If) -> Result<()> {
+		node.cond.accept(self)?;
+		let flow = self.flow.clone();
+		node.body.accept(self)?;
+		let body = std::mem::replace(&mut self.flow, flow);
+		if let Some(then) = &mut node.then {
+			then.accept(self)?;
+		}
+		self.flow = meet(body, self.flow.take());
+		Ok(())
+	}
+
+	fn visit_while(&mut self, node: &mut While) -> Result<()> {
+		node.cond.accept(self)?;
+		let exit = (!always_true(&node.cond)).then(|| self.flow.clone());
+		let exits = self.visit_body(&mut node.body)?;
+		self.flow = meet(exit.flatten(), exits.breaks);
+		Ok(())
+	}
+
+	fn visit_for(&mut self, node: &mut For) -> Result<()> {
+		if let Some(init) = &mut node.init {
+			init.accept(self)?;
+		}
+		let mut exit = None;
+		if let Some(cond) = &mut node.cond {
+			cond.accept(self)?;
+			if !always_true(cond) {
+				exit.clone_from(&self.flow);
+			}
+		}
+		let exits = self.visit_body(&mut node.body)?;
+		if let Some(step) = &mut node.step {
+			self.flow = meet(self.flow.take(), exits.continues);
+			step.accept(self)?;
+		}
+		self.flow = meet(exit, exits.breaks);
+		Ok(())
+	}
+
+	fn visit_do_while(&mut self, node: &mut DoWhile) -> Result<()> {
+		let exits = self.visit_body(&mut node.body)?;
+		self.flow = meet(self.flow.take(), exits.continues);
+		node.cond.accept(self)?;
+		let exit = (!always_true(&node.cond)).then(|| self.flow.clone());
+		self.flow = meet(exit.flatten(), exits.breaks);
+		Ok(())
+	}
+
+	fn visit_continue(&mut self, _node: &mut Continue) -> Result<()> {
+		let flow = self.flow.take();
+		let exits = self.loops.last_mut().unwrap();
+		exits.continues = meet(exits.continues.take(), flow);
+		Ok(())
+	}
+
+	fn visit_break(&mut self, _node: &mut Break) -> Result<()> {
+		let flow = self.flow.take();
+		let exits = self.loops.last_mut().unwrap();
+		exits.breaks = meet(exits.breaks.take(), flow);
+		Ok(())
+	}
+
+	fn visit_return(&mut self, node: &mut Return) -> Result<()> {
+		if let Some(value) = &mut node.value {
+			value.accept(self)?;
+		}
+		self.flow = None;
+		Ok(())
+	}
+}
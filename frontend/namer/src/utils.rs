@@ -18,3 +18,15 @@ pub fn initialize_by_none(ident: &str) -> SysycError {
 		ident
 	))
 }
+
+pub fn excess_elements() -> SysycError {
+	SemanticError("excess elements in initializer list".to_string())
+}
+
+pub fn duplicate_field(ident: &str) -> SysycError {
+	SemanticError(format!("duplicate member '{}'", ident))
+}
+
+pub fn empty_struct(ident: &str) -> SysycError {
+	SemanticError(format!("struct {} has no members", ident))
+}
@@ -1,12 +1,17 @@
+use std::{cmp::max, collections::HashSet, iter::once, rc::Rc};
+
 use crate::utils::*;
-use ast::{shirink, tree::*, Visitor};
+use ast::{shirink, tree::*, val_2_node, Visitor};
 use attr::Attrs;
 use rrvm_symbol::manager::SymbolManager;
 use scope::stack::ScopeStack;
-use utils::errors::Result;
+use utils::{
+	errors::{check_errors, Result},
+	SysycError,
+};
 use value::{
 	calc::{exec_binaryop, exec_unaryop},
-	BType, BinaryOp, FuncType, Value, VarType,
+	BType, BinaryOp, FuncType, Record, Value, VarType,
 };
 
 #[derive(Default)]
@@ -15,19 +20,109 @@ pub struct Namer {
 	ctx: ScopeStack,
 	is_global: bool,
 	decl_type: Option<(bool, BType)>,
-	decl_dims: Vec<usize>,
-	depth: usize,
+	// the type of the variable being initialized, and of each list entered
+	// with the slot its next item takes
+	init_type: Option<VarType>,
+	lists: Vec<(VarType, usize)>,
+	fields: Vec<(String, VarType)>,
+	// functions declared before the global variables, and whether the call
+	// named last gives a value and has constant arguments
+	declaring: bool,
+	declared: HashSet<String>,
+	const_call: bool,
+	errors: Vec<SysycError>,
 }
 
 impl Namer {
 	pub fn transform(&mut self, program: &mut Program) -> Result<()> {
-		program.accept(self)
+		program.accept(self)?;
+		check_errors(std::mem::take(&mut self.errors))
+	}
+	// record the error and go on with the next declaration or statement
+	fn collect(&mut self, result: Result<()>) {
+		if let Err(err) = result {
+			self.errors.push(err);
+		}
 	}
-	fn cur_size(&self) -> usize {
-		self.decl_dims.iter().skip(self.depth).product()
+	fn visit_func_type(&mut self, node: &mut FuncDecl) -> Result<FuncType> {
+		let mut func_type = Vec::new();
+		for param in node.formal_params.iter_mut() {
+			param.accept(self)?;
+			func_type.push(param.var_type()?.clone());
+		}
+		Ok((node.ret_type, func_type))
+	}
+	fn visit_func_body(&mut self, node: &mut FuncDecl) -> Result<()> {
+		let func_type = self.visit_func_type(node)?;
+		if self.declaring {
+			let symbol = self.mgr.new_func_symbol(&node.ident, func_type);
+			self.ctx.set_func(&node.ident, symbol)?;
+			self.declared.insert(node.ident.clone());
+			return Ok(());
+		}
+		if !self.declared.remove(&node.ident) {
+			let symbol = self.mgr.new_func_symbol(&node.ident, func_type);
+			self.ctx.set_func(&node.ident, symbol)?;
+		}
+		match node.block.as_mut() {
+			Some(block) => block.accept(self),
+			None => Ok(()),
+		}
 	}
-	fn cur_dims(&self) -> Vec<usize> {
-		self.decl_dims.iter().skip(self.depth).cloned().collect()
+	// the fields of a `struct` named in a type are those it is declared with
+	fn resolve(&self, type_t: &BType) -> Result<BType> {
+		match type_t {
+			BType::Struct(record) => {
+				Ok(BType::Struct(self.ctx.get_record(&record.ident)?.clone()))
+			}
+			_ => Ok(type_t.clone()),
+		}
+	}
+	// a string initializing a `char` array is the list of its characters, the
+	// list is named again to be padded like any other
+	fn visit_init_val(&mut self, node: &mut Node) -> Result<()> {
+		let target = match self.lists.last() {
+			Some((type_t, cursor)) => type_t.member_at(*cursor).map(|v| v.1),
+			None => self.init_type.clone(),
+		};
+		node.accept(self)?;
+		// a `const` initializer may call a function with constant arguments,
+		// the call is evaluated when the IR is generated
+		let is_const = self.decl_type.as_ref().is_some_and(|v| v.0);
+		if is_const && node.get_value().is_none() && node.get_slot().is_none() {
+			let is_call = node.get_func_symbol().is_some() && self.const_call;
+			if !is_call {
+				return Err(initialize_by_none("array"));
+			}
+		}
+		let is_chars =
+			target.is_some_and(|v| v.type_t == BType::Char && v.dims.len() == 1);
+		if is_chars && node.get_slot().is_none() {
+			if let Some(Value::Array((dims, values))) = node.get_value().cloned() {
+				if dims.len() == 1 {
+					*node = Box::new(InitValList {
+						_attrs: Default::default(),
+						_span: node.span(),
+						val_list: values
+							.iter()
+							.map(|v| val_2_node(v, node.span()))
+							.collect(),
+					});
+					node.accept(self)?;
+				}
+			}
+		}
+		// a list moves on past what it initializes itself
+		if node.get_slot().is_none() {
+			if let Some((type_t, cursor)) = self.lists.last_mut() {
+				if *cursor >= type_t.slots() {
+					return Err(excess_elements());
+				}
+				*cursor += 1;
+			}
+		}
+		shirink(node);
+		Ok(())
 	}
 }
 
@@ -37,7 +132,7 @@ impl Namer {
 		for dim in node_list.iter_mut() {
 			dim.accept(self)?;
 			let value: Value =
-				dim.get_attr("value").ok_or_else(array_dims_error)?.into();
+				dim.get_value().cloned().ok_or_else(array_dims_error)?;
 			shirink(dim);
 			if let Value::Int(v) = value {
 				if v < 0 {
@@ -56,13 +151,22 @@ impl Visitor for Namer {
 	fn visit_program(&mut self, node: &mut Program) -> Result<()> {
 		self.ctx.push();
 		self.ctx.extern_init(&mut self.mgr)?;
+		// the initializer of a global `const` array may call the functions, a
+		// function whose type can not be told yet is declared where it is
+		self.declaring = true;
+		for v in node.functions.iter_mut() {
+			let _ = v.accept(self);
+		}
+		self.declaring = false;
 		self.is_global = true;
 		for v in node.global_vars.iter_mut() {
-			v.accept(self)?;
+			let result = v.accept(self);
+			self.collect(result);
 		}
 		self.is_global = false;
 		for v in node.functions.iter_mut() {
-			v.accept(self)?;
+			let result = v.accept(self);
+			self.collect(result);
 		}
 		self.ctx.pop()?;
 		Ok(())
@@ -70,47 +174,46 @@ impl Visitor for Namer {
 
 	fn visit_func_decl(&mut self, node: &mut FuncDecl) -> Result<()> {
 		self.ctx.push();
-		let mut func_type = Vec::new();
-		for param in node.formal_params.iter_mut() {
-			param.accept(self)?;
-			func_type.push(param.get_attr("type").unwrap().into());
-		}
-		let func_type: FuncType = (node.ret_type, func_type);
-		let symbol = self.mgr.new_func_symbol(&node.ident, func_type);
-		self.ctx.set_func(&node.ident, symbol)?;
-		node.block.accept(self)?;
+		let result = self.visit_func_body(node);
 		self.ctx.pop()?;
-		Ok(())
+		result
 	}
 
 	fn visit_var_def(&mut self, node: &mut VarDef) -> Result<()> {
 		let dim_list = self.visit_dim_list(&mut node.dim_list)?;
-		let (is_const, btype) = self.decl_type.unwrap();
-		let var_type: VarType = (!is_const, btype, &dim_list).into();
-		node.set_attr("type", var_type.clone().into());
-		let symbol = self.mgr.new_var_symbol(&node.ident, var_type, self.is_global);
-		node.set_attr("symbol", symbol.clone().into());
+		let (is_const, btype) = self.decl_type.clone().unwrap();
+		let var_type: VarType = (!is_const, btype.clone(), &dim_list).into();
+		node.set_var_type(var_type.clone());
+		let symbol =
+			self.mgr.new_var_symbol(&node.ident, var_type.clone(), self.is_global);
+		node.set_symbol(symbol.clone());
 		self.ctx.set_val(&node.ident, symbol.clone())?;
 		if let Some(init) = node.init.as_mut() {
-			self.decl_dims.clone_from(&dim_list);
-			init.accept(self)?;
-			shirink(init);
+			self.init_type = Some(var_type.clone());
+			self.lists.clear();
+			self.visit_init_val(init)?;
 		}
 		if is_const {
-			let value = node
-				.init
-				.as_ref()
-				.ok_or_else(|| uninitialized(&node.ident))?
-				.get_attr("value")
-				.ok_or_else(|| initialize_by_none(&node.ident))?;
-			let value: Value = value.into();
-			self.ctx.set_constant(symbol.id, value.conv_type(btype))?;
+			let init =
+				node.init.as_ref().ok_or_else(|| uninitialized(&node.ident))?;
+			let Some(value) = init.get_value().cloned() else {
+				// the calls in an array are evaluated when the IR is generated
+				if !var_type.is_aggregate() {
+					return Err(initialize_by_none(&node.ident));
+				}
+				return Ok(());
+			};
+			// the fields of a `struct` are not folded
+			if !matches!(btype, BType::Struct(_)) {
+				self.ctx.set_constant(symbol.id, value.conv_type(&btype))?;
+			}
 		}
 		Ok(())
 	}
 
 	fn visit_var_decl(&mut self, node: &mut VarDecl) -> Result<()> {
-		self.decl_type = Some((node.is_const, node.type_t));
+		node.type_t = self.resolve(&node.type_t)?;
+		self.decl_type = Some((node.is_const, node.type_t.clone()));
 		for var_def in node.defs.iter_mut() {
 			var_def.accept(self)?;
 		}
@@ -118,53 +221,102 @@ impl Visitor for Namer {
 		Ok(())
 	}
 
+	fn visit_struct_decl(&mut self, node: &mut StructDecl) -> Result<()> {
+		self.fields.clear();
+		for field in node.fields.iter_mut() {
+			field.accept(self)?;
+		}
+		let fields = std::mem::take(&mut self.fields);
+		for (i, (ident, _)) in fields.iter().enumerate() {
+			if fields[..i].iter().any(|v| v.0 == *ident) {
+				return Err(duplicate_field(ident));
+			}
+		}
+		if fields.is_empty() {
+			return Err(empty_struct(&node.ident));
+		}
+		self.ctx.set_record(Rc::new(Record::new(&node.ident, fields)))
+	}
+
+	fn visit_field_decl(&mut self, node: &mut FieldDecl) -> Result<()> {
+		let dim_list = self.visit_dim_list(&mut node.dim_list)?;
+		node.type_t = self.resolve(&node.type_t)?;
+		let var_type: VarType = (true, node.type_t.clone(), &dim_list).into();
+		self.fields.push((node.ident.clone(), var_type));
+		Ok(())
+	}
+
 	fn visit_init_val_list(&mut self, node: &mut InitValList) -> Result<()> {
-		self.depth += 1;
+		let (start, type_t) = match self.lists.last() {
+			Some((type_t, cursor)) => {
+				type_t.member_at(*cursor).ok_or_else(excess_elements)?
+			}
+			None => (0, self.init_type.clone().unwrap()),
+		};
+		node.set_slot(start);
+		self.lists.push((type_t.clone(), 0));
 		for val in node.val_list.iter_mut() {
-			val.accept(self)?;
-			shirink(val);
+			self.visit_init_val(val)?;
+		}
+		self.lists.pop();
+		if let Some((_, cursor)) = self.lists.last_mut() {
+			*cursor = start + type_t.slots();
 		}
-		let len = self.cur_size();
-		self.depth -= 1;
 
-		let (is_const, btype) = self.decl_type.unwrap();
-		if is_const || self.ctx.is_global() {
+		let is_const = self.decl_type.as_ref().unwrap().0;
+		let is_global = self.ctx.is_global();
+		// the calls in a `const` array are evaluated when the IR is generated
+		if is_const && node.val_list.iter().any(|v| v.get_value().is_none()) {
+			return Ok(());
+		}
+		if is_const || is_global {
+			// the scalars of the list in turn, a list nested in it starts
+			// at its slot and the rest is zero
+			let pad = |array: &mut Vec<Value>, len: usize| {
+				let size = len.saturating_sub(array.len());
+				let slots = array.len()..array.len() + size;
+				array.extend(slots.map(|i| type_t.scalar_at(i).1.type_t.to_value()));
+			};
 			let mut array: Vec<Value> = Vec::new();
 			for val in node.val_list.iter_mut() {
-				let value: Value = val
-					.get_attr("value")
-					.ok_or_else(|| initialize_by_none("array"))?
-					.into();
-				if let Value::Array((_, val_array)) = value {
-					let size = (len - array.len() % len) % len;
-					array.extend((0..size).map(|_| btype.to_value()));
-					array.extend(val_array);
+				let value = val
+					.get_value()
+					.cloned()
+					.ok_or_else(|| initialize_by_none("array"))?;
+				if let Some(slot) = val.get_slot() {
+					pad(&mut array, *slot);
+					if let Value::Array((_, val_array)) = value {
+						array.extend(val_array);
+					}
 				} else {
-					array.push(value.to_type(btype)?);
+					let scalar = type_t.scalar_at(array.len()).1;
+					array.push(value.to_type(scalar.type_t)?);
 				};
 			}
-			let size = if array.is_empty() {
-				self.cur_size()
-			} else {
-				let len = self.cur_size();
-				(len - array.len() % len) % len
-			};
-			array.extend((0..size).map(|_| btype.to_value()));
-			let value: Value = (self.cur_dims(), array).into();
-			node.set_attr("value", value.into());
+			pad(&mut array, type_t.slots());
+			let value: Value = (type_t.dims.clone(), array).into();
+			node.set_value(value);
 		}
 		Ok(())
 	}
 
 	fn visit_literal_int(&mut self, node: &mut LiteralInt) -> Result<()> {
 		let value: Value = node.value.into();
-		node.set_attr("value", value.into());
+		node.set_value(value);
 		Ok(())
 	}
 
 	fn visit_literal_float(&mut self, node: &mut LiteralFloat) -> Result<()> {
 		let value: Value = node.value.into();
-		node.set_attr("value", value.into());
+		node.set_value(value);
+		Ok(())
+	}
+
+	fn visit_literal_string(&mut self, node: &mut LiteralString) -> Result<()> {
+		let values = node.value.iter().chain(once(&0));
+		let values = values.map(|v| Value::Int(*v as i8 as i32)).collect();
+		let value: Value = (vec![node.value.len() + 1], values).into();
+		node.set_value(value);
 		Ok(())
 	}
 
@@ -173,15 +325,21 @@ impl Visitor for Namer {
 		node.rhs.accept(self)?;
 		shirink(&mut node.lhs);
 		shirink(&mut node.rhs);
-		if node.op != BinaryOp::Assign {
-			let lhs = node.lhs.get_attr("value");
-			let rhs = node.rhs.get_attr("value");
+		if !node.op.is_assign() {
+			let lhs = node.lhs.get_value().cloned();
+			let rhs = node.rhs.get_value().cloned();
 			if let (Some(lhs), Some(rhs)) = (lhs, rhs) {
-				let value = exec_binaryop(&lhs.into(), node.op, &rhs.into())?;
-				node.set_attr("value", value.into());
+				// an integer division by zero is left for the linter to warn about
+				if matches!(node.op, BinaryOp::Div | BinaryOp::Mod)
+					&& matches!((&lhs, &rhs), (Value::Int(_), Value::Int(0)))
+				{
+					return Ok(());
+				}
+				let value = exec_binaryop(&lhs, node.op, &rhs)?;
+				node.set_value(value);
 			}
-		} else if let Some(symbol) = node.lhs.get_attr("symbol") {
-			node.set_attr("symbol", symbol.clone());
+		} else if let Some(symbol) = node.lhs.get_symbol().cloned() {
+			node.set_symbol(symbol);
 		}
 		Ok(())
 	}
@@ -189,9 +347,33 @@ impl Visitor for Namer {
 	fn visit_unary_expr(&mut self, node: &mut UnaryExpr) -> Result<()> {
 		node.rhs.accept(self)?;
 		shirink(&mut node.rhs);
-		if let Some(rhs) = node.rhs.get_attr("value") {
-			let value = exec_unaryop(node.op, &rhs.into())?;
-			node.set_attr("value", value.into());
+		if node.op.step().is_some() {
+			return Ok(());
+		}
+		if let Some(rhs) = node.rhs.get_value().cloned() {
+			let value = exec_unaryop(node.op, &rhs)?;
+			node.set_value(value);
+		}
+		Ok(())
+	}
+
+	fn visit_conditional(&mut self, node: &mut Conditional) -> Result<()> {
+		for v in [&mut node.cond, &mut node.on_true, &mut node.on_false] {
+			v.accept(self)?;
+			shirink(v);
+		}
+		// a string is left to the typer to complain about
+		let values = [&node.cond, &node.on_true, &node.on_false].map(|v| {
+			v.get_value().cloned().filter(|v| !matches!(v, Value::Array(_)))
+		});
+		if let [Some(cond), Some(on_true), Some(on_false)] = values {
+			let type_t = max(on_true.get_type(), on_false.get_type());
+			let value = if cond.to_float()? != 0.0 {
+				on_true
+			} else {
+				on_false
+			};
+			node.set_value(value.conv_type(&type_t));
 		}
 		Ok(())
 	}
@@ -200,40 +382,48 @@ impl Visitor for Namer {
 		let symbol = self.ctx.get_func(&node.ident)?.clone();
 		let v: Option<VarType> = symbol.var_type.0.into();
 		if let Some(v) = v {
-			node.set_attr("type", v.into());
+			node.set_var_type(v);
 		}
-		node.set_attr("func_symbol", symbol.into());
+		node.set_func_symbol(symbol);
 		for param in node.params.iter_mut() {
 			param.accept(self)?;
 			shirink(param);
 		}
+		self.const_call = node.get_var_type().is_some()
+			&& node.params.iter().all(|v| v.get_value().is_some());
 		Ok(())
 	}
 
 	fn visit_formal_param(&mut self, node: &mut FormalParam) -> Result<()> {
 		let dim_list = self.visit_dim_list(&mut node.dim_list)?;
-		let var_type: VarType = (true, node.type_t, &dim_list).into();
+		node.type_t = self.resolve(&node.type_t)?;
+		let var_type: VarType = (true, node.type_t.clone(), &dim_list).into();
 		let symbol = self.mgr.new_var_symbol(&node.ident, var_type.clone(), false);
-		node.set_attr("symbol", symbol.clone().into());
+		node.set_symbol(symbol.clone());
 		self.ctx.set_val(&node.ident, symbol)?;
-		node.set_attr("type", var_type.into());
+		node.set_var_type(var_type);
 		Ok(())
 	}
 
 	fn visit_variable(&mut self, node: &mut Variable) -> Result<()> {
 		let symbol = self.ctx.get_val(&node.ident)?.clone();
-		node.set_attr("symbol", symbol.clone().into());
-		node.set_attr("type", symbol.var_type.into());
+		node.set_symbol(symbol.clone());
+		node.set_var_type(symbol.var_type);
 		if let Some(value) = self.ctx.get_constant(symbol.id) {
-			node.set_attr("value", value.clone().into())
+			node.set_value(value.clone())
 		}
 		Ok(())
 	}
 
+	fn visit_field_access(&mut self, node: &mut FieldAccess) -> Result<()> {
+		node.base.accept(self)
+	}
+
 	fn visit_block(&mut self, node: &mut Block) -> Result<()> {
 		self.ctx.push();
 		for stmt in node.stmts.iter_mut() {
-			stmt.accept(self)?;
+			let result = stmt.accept(self);
+			self.collect(result);
 		}
 		self.ctx.pop()?;
 		Ok(())
@@ -255,6 +445,31 @@ impl Visitor for Namer {
 		node.body.accept(self)
 	}
 
+	fn visit_for(&mut self, node: &mut For) -> Result<()> {
+		// the declaration in `init` is seen in the rest of the loop only
+		self.ctx.push();
+		if let Some(init) = &mut node.init {
+			init.accept(self)?;
+		}
+		if let Some(cond) = &mut node.cond {
+			cond.accept(self)?;
+			shirink(cond);
+		}
+		if let Some(step) = &mut node.step {
+			step.accept(self)?;
+		}
+		node.body.accept(self)?;
+		self.ctx.pop()?;
+		Ok(())
+	}
+
+	fn visit_do_while(&mut self, node: &mut DoWhile) -> Result<()> {
+		node.body.accept(self)?;
+		node.cond.accept(self)?;
+		shirink(&mut node.cond);
+		Ok(())
+	}
+
 	fn visit_continue(&mut self, _node: &mut Continue) -> Result<()> {
 		Ok(())
 	}
@@ -1,12 +1,18 @@
 #![allow(clippy::empty_docs)]
 
-use std::{collections::HashMap, hash::Hash, iter::once};
+use std::{
+	hash::Hash,
+	iter::{once, Peekable},
+	str::Bytes,
+};
 
 use ast::tree::*;
-use pest::{iterators::Pair, pratt_parser::PrattParser, Parser};
+use pest::{
+	error::InputLocation, iterators::Pair, pratt_parser::PrattParser, Parser,
+};
 use pest_derive::Parser;
-use utils::{errors::Result, SysycError::LexError};
-use value::{BType, BinaryOp, FuncRetType, UnaryOp};
+use utils::{errors::Result, Span, SysycError::LexError};
+use value::{BType, BinaryOp, FuncRetType, Record, UnaryOp};
 
 #[derive(Parser)]
 #[grammar = "sysy2022.pest"]
@@ -15,11 +21,26 @@ struct SysycParser;
 fn map_binary_op(pair: &Pair<Rule>) -> BinaryOp {
 	match pair.as_rule() {
 		Rule::Assign => BinaryOp::Assign,
+		Rule::AddAssign => BinaryOp::AddAssign,
+		Rule::SubAssign => BinaryOp::SubAssign,
+		Rule::MulAssign => BinaryOp::MulAssign,
+		Rule::DivAssign => BinaryOp::DivAssign,
+		Rule::ModAssign => BinaryOp::ModAssign,
+		Rule::BitAndAssign => BinaryOp::BitAndAssign,
+		Rule::BitOrAssign => BinaryOp::BitOrAssign,
+		Rule::BitXorAssign => BinaryOp::BitXorAssign,
+		Rule::ShlAssign => BinaryOp::ShlAssign,
+		Rule::ShrAssign => BinaryOp::ShrAssign,
 		Rule::Add => BinaryOp::Add,
 		Rule::Sub => BinaryOp::Sub,
 		Rule::Mul => BinaryOp::Mul,
 		Rule::Div => BinaryOp::Div,
 		Rule::Mod => BinaryOp::Mod,
+		Rule::BitAnd => BinaryOp::BitAnd,
+		Rule::BitOr => BinaryOp::BitOr,
+		Rule::BitXor => BinaryOp::BitXor,
+		Rule::Shl => BinaryOp::Shl,
+		Rule::Shr => BinaryOp::Shr,
 		Rule::LT => BinaryOp::LT,
 		Rule::LE => BinaryOp::LE,
 		Rule::GE => BinaryOp::GE,
@@ -38,10 +59,19 @@ fn map_unary_op(pair: &Pair<Rule>) -> UnaryOp {
 		Rule::UnarySub => UnaryOp::Neg,
 		Rule::UnaryNot => UnaryOp::Not,
 		Rule::UnaryBitNot => UnaryOp::BitNot,
+		Rule::PreInc => UnaryOp::PreInc,
+		Rule::PreDec => UnaryOp::PreDec,
+		Rule::PostInc => UnaryOp::PostInc,
+		Rule::PostDec => UnaryOp::PostDec,
 		_ => unreachable!(),
 	}
 }
 
+fn span(pair: &Pair<Rule>) -> Span {
+	let span = pair.as_span();
+	Span::new(span.start(), span.end())
+}
+
 fn parse_identifier(pair: Pair<Rule>) -> String {
 	match pair.as_rule() {
 		Rule::Identifier => String::from(pair.as_str()),
@@ -53,6 +83,11 @@ fn parse_var_type(pair: Pair<Rule>) -> BType {
 	match pair.as_rule() {
 		Rule::int_t => BType::Int,
 		Rule::float_t => BType::Float,
+		Rule::char_t => BType::Char,
+		// the fields are filled in by the namer
+		Rule::struct_t => BType::Struct(Record::named(&parse_identifier(
+			pair.into_inner().next().unwrap(),
+		))),
 		_ => unreachable!(),
 	}
 }
@@ -72,7 +107,8 @@ fn parse_dim_list(pair: Pair<Rule>) -> NodeList {
 
 fn to_block(node: Node) -> Node {
 	Box::new(Block {
-		_attrs: HashMap::new(),
+		_attrs: Default::default(),
+		_span: node.span(),
 		stmts: vec![node],
 	})
 }
@@ -81,36 +117,53 @@ lazy_static::lazy_static! {
 	static ref PRATT_PARSER: PrattParser<Rule> = {
 		use pest::pratt_parser::{Assoc::*, Op};
 		PrattParser::new()
-			.op(Op::infix(Rule::Assign, Right))
+			.op(Op::infix(Rule::Assign, Right) | Op::infix(Rule::AddAssign, Right)
+				| Op::infix(Rule::SubAssign, Right) | Op::infix(Rule::MulAssign, Right)
+				| Op::infix(Rule::DivAssign, Right) | Op::infix(Rule::ModAssign, Right)
+				| Op::infix(Rule::BitAndAssign, Right) | Op::infix(Rule::BitOrAssign, Right)
+				| Op::infix(Rule::BitXorAssign, Right) | Op::infix(Rule::ShlAssign, Right)
+				| Op::infix(Rule::ShrAssign, Right))
+			.op(Op::infix(Rule::Conditional, Right))
 			.op(Op::infix(Rule::LOr, Left))
 			.op(Op::infix(Rule::LAnd, Left))
+			.op(Op::infix(Rule::BitOr, Left))
+			.op(Op::infix(Rule::BitXor, Left))
+			.op(Op::infix(Rule::BitAnd, Left))
 			.op(Op::infix(Rule::EQ, Left) | Op::infix(Rule::NE, Left))
 			.op(Op::infix(Rule::LE, Left) | Op::infix(Rule::LT, Left)
 				| Op::infix(Rule::GE, Left) | Op::infix(Rule::GT, Left))
+			.op(Op::infix(Rule::Shl, Left) | Op::infix(Rule::Shr, Left))
 			.op(Op::infix(Rule::Add, Left) | Op::infix(Rule::Sub, Left))
 			.op(Op::infix(Rule::Mul, Left) | Op::infix(Rule::Div, Left) | Op::infix(Rule::Mod, Left))
 			.op(Op::prefix(Rule::UnaryAdd) | Op::prefix(Rule::UnarySub)
-				| Op::prefix(Rule::UnaryNot) | Op::prefix(Rule::UnaryBitNot))
+				| Op::prefix(Rule::UnaryNot) | Op::prefix(Rule::UnaryBitNot)
+				| Op::prefix(Rule::PreInc) | Op::prefix(Rule::PreDec))
+			.op(Op::postfix(Rule::PostInc) | Op::postfix(Rule::PostDec))
 	};
 }
 
 fn parse_func_call(pair: Pair<Rule>) -> Node {
-	let mut pairs = pair.clone().into_inner();
+	let span = span(&pair);
+	let line = pair.line_col().0 as i32;
+	let mut pairs = pair.into_inner();
 	let ident = parse_identifier(pairs.next().unwrap());
 	let params: Vec<_> = pairs.map(parse_expr).collect();
 	let func_call = match (ident.as_str(), params.len()) {
 		("starttime", 0) => FuncCall {
-			_attrs: HashMap::new(),
+			_attrs: Default::default(),
+			_span: span,
 			ident: "_sysy_starttime".to_string(),
-			params: vec![LiteralInt::node(pair.line_col().0 as i32)],
+			params: vec![LiteralInt::node(line, span)],
 		},
 		("stoptime", 0) => FuncCall {
-			_attrs: HashMap::new(),
+			_attrs: Default::default(),
+			_span: span,
 			ident: "_sysy_stoptime".to_string(),
-			params: vec![LiteralInt::node(pair.line_col().0 as i32)],
+			params: vec![LiteralInt::node(line, span)],
 		},
 		_ => FuncCall {
-			_attrs: HashMap::new(),
+			_attrs: Default::default(),
+			_span: span,
 			ident,
 			params,
 		},
@@ -120,14 +173,28 @@ fn parse_func_call(pair: Pair<Rule>) -> Node {
 
 fn parse_lval(pair: Pair<Rule>) -> Node {
 	let mut pairs = pair.into_inner();
+	let ident = pairs.next().unwrap();
 	let mut var: Node = Box::new(Variable {
-		_attrs: HashMap::new(),
-		ident: parse_identifier(pairs.next().unwrap()),
+		_attrs: Default::default(),
+		_span: span(&ident),
+		ident: parse_identifier(ident),
 	});
-	let pairs = pairs.next().unwrap().into_inner();
 	for v in pairs {
+		if v.as_rule() == Rule::Member {
+			let end = v.as_span().end();
+			var = Box::new(FieldAccess {
+				_attrs: Default::default(),
+				_span: Span::new(var.span().start, end),
+				base: var,
+				ident: parse_identifier(v.into_inner().next().unwrap()),
+			});
+			continue;
+		}
+		// `a[i]` ends after `]`, which is right after the index
+		let end = v.as_span().end() + 1;
 		var = Box::new(BinaryExpr {
-			_attrs: HashMap::new(),
+			_attrs: Default::default(),
+			_span: Span::new(var.span().start, end),
 			lhs: var,
 			op: BinaryOp::IDX,
 			rhs: parse_expr(v),
@@ -185,19 +252,64 @@ fn parse_hex_float_lit(pair: Pair<Rule>) -> f32 {
 	(int_part as f32 + frac_part) * 2f32.powi(exp)
 }
 
+// the bytes a string or character literal stands for, with escapes decoded
+fn parse_chars(s: &str) -> Vec<u8> {
+	fn digits(bytes: &mut Peekable<Bytes>, radix: u32, len: usize, v: u32) -> u8 {
+		let mut v = v;
+		for _ in 0..len {
+			match bytes.peek().and_then(|c| (*c as char).to_digit(radix)) {
+				Some(d) => v = v * radix + d,
+				None => break,
+			}
+			bytes.next();
+		}
+		v as u8
+	}
+	let mut bytes = s.bytes().peekable();
+	let mut out = Vec::new();
+	while let Some(c) = bytes.next() {
+		if c != b'\\' {
+			out.push(c);
+			continue;
+		}
+		let v = match bytes.next().unwrap() {
+			b'n' => b'\n',
+			b't' => b'\t',
+			b'r' => b'\r',
+			b'a' => 0x07,
+			b'b' => 0x08,
+			b'f' => 0x0c,
+			b'v' => 0x0b,
+			b'x' => digits(&mut bytes, 16, 2, 0),
+			c @ b'0'..=b'7' => digits(&mut bytes, 8, 2, (c - b'0') as u32),
+			c => c,
+		};
+		out.push(v);
+	}
+	out
+}
+
 fn parse_primary_expr(pair: Pair<Rule>) -> Node {
 	match pair.as_rule() {
-		Rule::Integer => Box::new(LiteralInt {
-			_attrs: HashMap::new(),
-			value: parse_int_lit(pair.as_str()),
-		}),
-		Rule::Float => Box::new(LiteralFloat {
-			_attrs: HashMap::new(),
-			value: parse_float_lit(pair.as_str()),
-		}),
-		Rule::HexFloat => Box::new(LiteralFloat {
-			_attrs: HashMap::new(),
-			value: parse_hex_float_lit(pair),
+		Rule::Integer => {
+			LiteralInt::node(parse_int_lit(pair.as_str()), span(&pair))
+		}
+		Rule::Float => {
+			LiteralFloat::node(parse_float_lit(pair.as_str()), span(&pair))
+		}
+		Rule::HexFloat => {
+			let span = span(&pair);
+			LiteralFloat::node(parse_hex_float_lit(pair), span)
+		}
+		Rule::Char => {
+			let span = span(&pair);
+			let bytes = parse_chars(pair.into_inner().as_str());
+			LiteralInt::node(bytes[0] as i8 as i32, span)
+		}
+		Rule::String => Box::new(LiteralString {
+			_attrs: Default::default(),
+			_span: span(&pair),
+			value: parse_chars(pair.into_inner().as_str()),
 		}),
 		Rule::FuncCall => parse_func_call(pair),
 		Rule::Lval => parse_lval(pair),
@@ -208,16 +320,37 @@ fn parse_primary_expr(pair: Pair<Rule>) -> Node {
 
 fn parse_binary_expr(lhs: Node, op: Pair<Rule>, rhs: Node) -> Node {
 	Box::new(BinaryExpr {
-		_attrs: HashMap::new(),
+		_attrs: Default::default(),
+		_span: lhs.span().merge(rhs.span()),
 		lhs,
 		op: map_binary_op(&op),
 		rhs,
 	})
 }
 
+fn parse_conditional(cond: Node, op: Pair<Rule>, on_false: Node) -> Node {
+	Box::new(Conditional {
+		_attrs: Default::default(),
+		_span: cond.span().merge(on_false.span()),
+		cond,
+		on_true: parse_expr(op.into_inner().next().unwrap()),
+		on_false,
+	})
+}
+
+fn parse_postfix_expr(lhs: Node, op: Pair<Rule>) -> Node {
+	Box::new(UnaryExpr {
+		_attrs: Default::default(),
+		_span: lhs.span().merge(span(&op)),
+		op: map_unary_op(&op),
+		rhs: lhs,
+	})
+}
+
 fn parse_unary_expr(op: Pair<Rule>, rhs: Node) -> Node {
 	Box::new(UnaryExpr {
-		_attrs: HashMap::new(),
+		_attrs: Default::default(),
+		_span: span(&op).merge(rhs.span()),
 		op: map_unary_op(&op),
 		rhs,
 	})
@@ -226,8 +359,12 @@ fn parse_unary_expr(op: Pair<Rule>, rhs: Node) -> Node {
 fn parse_expr(pair: Pair<Rule>) -> Node {
 	PRATT_PARSER
 		.map_primary(parse_primary_expr)
-		.map_infix(|lhs, op, rhs| parse_binary_expr(lhs, op, rhs))
+		.map_infix(|lhs, op, rhs| match op.as_rule() {
+			Rule::Conditional => parse_conditional(lhs, op, rhs),
+			_ => parse_binary_expr(lhs, op, rhs),
+		})
 		.map_prefix(|op, rhs| parse_unary_expr(op, rhs))
+		.map_postfix(|lhs, op| parse_postfix_expr(lhs, op))
 		.parse(pair.into_inner())
 }
 
@@ -241,16 +378,19 @@ fn parse_init_val(pair: Pair<Rule>) -> Node {
 
 fn parse_init_val_list(pair: Pair<Rule>) -> Node {
 	let init_val_list = InitValList {
-		_attrs: HashMap::new(),
+		_attrs: Default::default(),
+		_span: span(&pair),
 		val_list: pair.into_inner().map(parse_init_val).collect(),
 	};
 	Box::new(init_val_list)
 }
 
 fn parse_var_def(pair: Pair<Rule>) -> Node {
+	let span = span(&pair);
 	let mut pairs = pair.into_inner();
 	let mut var_def = VarDef {
-		_attrs: HashMap::new(),
+		_attrs: Default::default(),
+		_span: span,
 		ident: parse_identifier(pairs.next().unwrap()),
 		dim_list: Vec::new(),
 		init: None,
@@ -266,34 +406,79 @@ fn parse_var_def(pair: Pair<Rule>) -> Node {
 	Box::new(var_def)
 }
 
+fn parse_var_decl(pair: Pair<Rule>, is_const: bool, is_extern: bool) -> Node {
+	let span = span(&pair);
+	let mut pairs = pair.into_inner();
+	let var_decl = VarDecl {
+		_attrs: Default::default(),
+		_span: span,
+		is_const,
+		is_extern,
+		type_t: parse_var_type(pairs.next().unwrap()),
+		defs: pairs.map(parse_var_def).collect(),
+	};
+	Box::new(var_decl)
+}
+
 fn parse_decl(pair: Pair<Rule>) -> Node {
 	let pair = pair.into_inner().next().unwrap();
-	fn _parse(pair: Pair<Rule>, is_const: bool) -> VarDecl {
-		let mut pairs = pair.into_inner();
-		VarDecl {
-			_attrs: HashMap::new(),
-			is_const,
-			type_t: parse_var_type(pairs.next().unwrap()),
-			defs: pairs.map(parse_var_def).collect(),
-		}
-	}
 	match pair.as_rule() {
-		Rule::ConstDecl => Box::new(_parse(pair, true)),
-		Rule::VarDecl => Box::new(_parse(pair, false)),
+		Rule::ConstDecl => parse_var_decl(pair, true, false),
+		Rule::VarDecl => parse_var_decl(pair, false, false),
 		_ => unreachable!(),
 	}
 }
 
+fn parse_struct_decl(pair: Pair<Rule>) -> Node {
+	fn parse_field_def(pair: Pair<Rule>, type_t: &BType) -> Node {
+		let span = span(&pair);
+		let mut pairs = pair.into_inner();
+		let field_decl = FieldDecl {
+			_attrs: Default::default(),
+			_span: span,
+			type_t: type_t.clone(),
+			ident: parse_identifier(pairs.next().unwrap()),
+			dim_list: parse_dim_list(pairs.next().unwrap()),
+		};
+		Box::new(field_decl)
+	}
+	let span = span(&pair);
+	let mut pairs = pair.into_inner();
+	let ident = pairs.next().unwrap().into_inner().next().unwrap();
+	let mut fields = Vec::new();
+	// `int x, y[2];` declares a field for each name
+	for pair in pairs {
+		let mut pairs = pair.into_inner();
+		let type_t = parse_var_type(pairs.next().unwrap());
+		fields.extend(pairs.map(|v| parse_field_def(v, &type_t)));
+	}
+	let struct_decl = StructDecl {
+		_attrs: Default::default(),
+		_span: span,
+		ident: parse_identifier(ident),
+		fields,
+	};
+	Box::new(struct_decl)
+}
+
 fn parse_formal_params(pair: Pair<Rule>) -> NodeList {
 	fn parse_formal_param(pair: Pair<Rule>) -> Node {
+		let span = span(&pair);
 		let mut pairs = pair.into_inner();
 		let formal_param = FormalParam {
-			_attrs: HashMap::new(),
+			_attrs: Default::default(),
+			_span: span,
 			type_t: parse_var_type(pairs.next().unwrap()),
 			ident: parse_identifier(pairs.next().unwrap()),
 			dim_list: pairs
 				.next()
-				.map(|v| once(LiteralInt::node(0)).chain(parse_dim_list(v)).collect())
+				.map(|v| {
+					let first = LiteralInt::node(
+						0,
+						Span::new(v.as_span().start(), v.as_span().start()),
+					);
+					once(first).chain(parse_dim_list(v)).collect()
+				})
 				.unwrap_or_default(),
 		};
 		Box::new(formal_param)
@@ -302,9 +487,11 @@ fn parse_formal_params(pair: Pair<Rule>) -> NodeList {
 }
 
 fn parse_if_stmt(pair: Pair<Rule>) -> Node {
+	let span = span(&pair);
 	let mut pairs = pair.into_inner();
 	let if_stmt = If {
-		_attrs: HashMap::new(),
+		_attrs: Default::default(),
+		_span: span,
 		cond: parse_expr(pairs.next().unwrap()),
 		body: to_block(parse_stmt(pairs.next().unwrap())),
 		then: pairs.next().map(parse_stmt).map(to_block),
@@ -313,24 +500,62 @@ fn parse_if_stmt(pair: Pair<Rule>) -> Node {
 }
 
 fn parse_while_stmt(pair: Pair<Rule>) -> Node {
+	let span = span(&pair);
 	let mut pairs = pair.into_inner();
 	let while_stmt = While {
-		_attrs: HashMap::new(),
+		_attrs: Default::default(),
+		_span: span,
 		cond: parse_expr(pairs.next().unwrap()),
 		body: to_block(parse_stmt(pairs.next().unwrap())),
 	};
 	Box::new(while_stmt)
 }
 
+fn parse_for_stmt(pair: Pair<Rule>) -> Node {
+	let span = span(&pair);
+	let mut pairs = pair.into_inner();
+	// each part holds what is written there, if anything
+	let mut part = || {
+		pairs.next().unwrap().into_inner().next().map(|v| match v.as_rule() {
+			Rule::Decl => parse_decl(v),
+			_ => parse_expr(v),
+		})
+	};
+	let (init, cond, step) = (part(), part(), part());
+	let for_stmt = For {
+		_attrs: Default::default(),
+		_span: span,
+		init,
+		cond,
+		step,
+		body: to_block(parse_stmt(pairs.next().unwrap())),
+	};
+	Box::new(for_stmt)
+}
+
+fn parse_do_while_stmt(pair: Pair<Rule>) -> Node {
+	let span = span(&pair);
+	let mut pairs = pair.into_inner().skip(1);
+	let do_while_stmt = DoWhile {
+		_attrs: Default::default(),
+		_span: span,
+		body: to_block(parse_stmt(pairs.next().unwrap())),
+		cond: parse_expr(pairs.next().unwrap()),
+	};
+	Box::new(do_while_stmt)
+}
+
 fn parse_return(pair: Pair<Rule>) -> Node {
 	let return_stmt = Return {
-		_attrs: HashMap::new(),
+		_attrs: Default::default(),
+		_span: span(&pair),
 		value: pair.into_inner().next().map(parse_expr),
 	};
 	Box::new(return_stmt)
 }
 
 fn parse_stmt(pair: Pair<Rule>) -> Node {
+	let stmt_span = span(&pair);
 	let pair = pair.into_inner().next();
 	fn parse_unwrap_stmt(pair: Pair<Rule>) -> Node {
 		match pair.as_rule() {
@@ -338,8 +563,16 @@ fn parse_stmt(pair: Pair<Rule>) -> Node {
 			Rule::Block => parse_block(pair),
 			Rule::IfStmt => parse_if_stmt(pair),
 			Rule::WhileStmt => parse_while_stmt(pair),
-			Rule::Break => Box::<Break>::default(),
-			Rule::Continue => Box::<Continue>::default(),
+			Rule::ForStmt => parse_for_stmt(pair),
+			Rule::DoWhileStmt => parse_do_while_stmt(pair),
+			Rule::Break => Box::new(Break {
+				_attrs: Default::default(),
+				_span: span(&pair),
+			}),
+			Rule::Continue => Box::new(Continue {
+				_attrs: Default::default(),
+				_span: span(&pair),
+			}),
 			Rule::Return => parse_return(pair),
 			_ => unreachable!(),
 		}
@@ -347,7 +580,8 @@ fn parse_stmt(pair: Pair<Rule>) -> Node {
 	match pair {
 		Some(pair) => parse_unwrap_stmt(pair),
 		None => Box::new(Block {
-			_attrs: HashMap::new(),
+			_attrs: Default::default(),
+			_span: stmt_span,
 			stmts: Vec::new(),
 		}),
 	}
@@ -356,44 +590,60 @@ fn parse_stmt(pair: Pair<Rule>) -> Node {
 fn parse_block(pair: Pair<Rule>) -> Node {
 	fn parse_block_item(pair: Pair<Rule>) -> Node {
 		match pair.as_rule() {
+			Rule::StructDecl => parse_struct_decl(pair),
 			Rule::Decl => parse_decl(pair),
 			Rule::Stmt => parse_stmt(pair),
 			_ => unreachable!(),
 		}
 	}
 	let block = Block {
-		_attrs: HashMap::new(),
+		_attrs: Default::default(),
+		_span: span(&pair),
 		stmts: pair.into_inner().map(parse_block_item).collect(),
 	};
 	Box::new(block)
 }
 
 fn parse_func_decl(pair: Pair<Rule>) -> Node {
+	let span = span(&pair);
 	let mut pairs = pair.into_inner();
 	let func_decl = FuncDecl {
-		_attrs: HashMap::new(),
+		_attrs: Default::default(),
+		_span: span,
 		ret_type: parse_func_type(pairs.next().unwrap()),
 		ident: parse_identifier(pairs.next().unwrap()),
 		formal_params: parse_formal_params(pairs.next().unwrap()),
-		block: parse_block(pairs.next().unwrap()),
+		block: pairs.next().map(parse_block),
 	};
 	Box::new(func_decl)
 }
 
 fn parse_comp_unit(pair: Pair<Rule>, program: &mut Program) {
 	match pair.as_rule() {
+		Rule::StructDecl => program.global_vars.push(parse_struct_decl(pair)),
 		Rule::Decl => program.global_vars.push(parse_decl(pair)),
-		Rule::FuncDecl => program.functions.push(parse_func_decl(pair)),
+		Rule::ExternVar => {
+			program.global_vars.push(parse_var_decl(pair, false, true))
+		}
+		Rule::FuncDecl | Rule::ExternFunc => {
+			program.functions.push(parse_func_decl(pair))
+		}
 		Rule::EOI => (),
 		_ => unreachable!(),
 	}
 }
 
 pub fn parse(str: &str) -> Result<Program> {
-	let pairs = SysycParser::parse(Rule::Program, str)
-		.map_err(|e| LexError(e.to_string()))?;
+	let pairs = SysycParser::parse(Rule::Program, str).map_err(|e| {
+		let span = match e.location {
+			InputLocation::Pos(v) => Span::new(v, v),
+			InputLocation::Span((start, end)) => Span::new(start, end),
+		};
+		LexError(e.variant.message().into_owned()).at(span)
+	})?;
 	let mut program = Program {
-		_attrs: HashMap::new(),
+		_attrs: Default::default(),
+		_span: Span::new(0, str.len()),
 		global_vars: Vec::new(),
 		functions: Vec::new(),
 	};
@@ -0,0 +1,167 @@
+use crate::token::{Kind, Token};
+
+// binary operators from the loosest, the longer of two with the same start
+// first
+const BINARY_OPS: [&[&str]; 10] = [
+	&["||"],
+	&["&&"],
+	&["|"],
+	&["^"],
+	&["&"],
+	&["==", "!="],
+	&["<=", ">=", "<", ">"],
+	&["<<", ">>"],
+	&["+", "-"],
+	&["*", "/", "%"],
+];
+
+struct Evaluator<'a> {
+	tokens: &'a [Token],
+	pos: usize,
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+	let text = text.trim_end_matches(['u', 'U', 'l', 'L']);
+	if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+		i64::from_str_radix(hex, 16).ok()
+	} else if text.len() > 1 && text.starts_with('0') {
+		i64::from_str_radix(&text[1..], 8).ok()
+	} else {
+		text.parse().ok()
+	}
+}
+
+fn parse_char(text: &str) -> Option<i64> {
+	let inner = &text[1..text.len() - 1];
+	let value = match inner.strip_prefix('\\') {
+		None => inner.chars().next()?,
+		Some("n") => '\n',
+		Some("t") => '\t',
+		Some("r") => '\r',
+		Some("0") => '\0',
+		Some(v) => v.chars().next()?,
+	};
+	Some(value as i64)
+}
+
+impl Evaluator<'_> {
+	// whether the next tokens spell `op`, which they are taken as
+	fn eat(&mut self, op: &str) -> bool {
+		let mut pos = self.pos;
+		for v in op.chars() {
+			match self.tokens.get(pos) {
+				Some(token) if token.is(&v.to_string()) => pos += 1,
+				_ => return false,
+			}
+		}
+		self.pos = pos;
+		true
+	}
+	fn conditional(&mut self) -> Result<i64, String> {
+		let cond = self.binary(0)?;
+		if !self.eat("?") {
+			return Ok(cond);
+		}
+		let on_true = self.conditional()?;
+		if !self.eat(":") {
+			return Err("expected ':' in #if expression".to_string());
+		}
+		let on_false = self.conditional()?;
+		Ok(if cond != 0 { on_true } else { on_false })
+	}
+	fn binary(&mut self, level: usize) -> Result<i64, String> {
+		if level == BINARY_OPS.len() {
+			return self.unary();
+		}
+		let mut lhs = self.binary(level + 1)?;
+		'outer: loop {
+			for op in BINARY_OPS[level] {
+				// `|` and `&` are not the start of `||` and `&&`
+				let doubled = format!("{}{}", op, op);
+				if op.len() == 1 && "|&".contains(op) && self.at(&doubled) {
+					continue;
+				}
+				if self.eat(op) {
+					let rhs = self.binary(level + 1)?;
+					lhs = apply(op, lhs, rhs)?;
+					continue 'outer;
+				}
+			}
+			return Ok(lhs);
+		}
+	}
+	fn at(&self, op: &str) -> bool {
+		let mut copy = Evaluator {
+			tokens: self.tokens,
+			pos: self.pos,
+		};
+		copy.eat(op)
+	}
+	fn unary(&mut self) -> Result<i64, String> {
+		for op in ["!", "~", "-", "+"] {
+			if self.eat(op) {
+				let value = self.unary()?;
+				return Ok(match op {
+					"!" => (value == 0) as i64,
+					"~" => !value,
+					"-" => value.wrapping_neg(),
+					_ => value,
+				});
+			}
+		}
+		if self.eat("(") {
+			let value = self.conditional()?;
+			if !self.eat(")") {
+				return Err("missing ')' in #if expression".to_string());
+			}
+			return Ok(value);
+		}
+		let Some(token) = self.tokens.get(self.pos) else {
+			return Err("expected an operand in #if expression".to_string());
+		};
+		let value = match token.kind {
+			Kind::Number => parse_number(&token.text),
+			Kind::Char => parse_char(&token.text),
+			_ => None,
+		};
+		self.pos += 1;
+		value.ok_or(format!("`{}` is not valid in #if expression", token.text))
+	}
+}
+
+fn apply(op: &str, x: i64, y: i64) -> Result<i64, String> {
+	Ok(match op {
+		"||" => (x != 0 || y != 0) as i64,
+		"&&" => (x != 0 && y != 0) as i64,
+		"|" => x | y,
+		"^" => x ^ y,
+		"&" => x & y,
+		"==" => (x == y) as i64,
+		"!=" => (x != y) as i64,
+		"<=" => (x <= y) as i64,
+		">=" => (x >= y) as i64,
+		"<" => (x < y) as i64,
+		">" => (x > y) as i64,
+		"<<" => x.wrapping_shl(y as u32),
+		">>" => x.wrapping_shr(y as u32),
+		"+" => x.wrapping_add(y),
+		"-" => x.wrapping_sub(y),
+		"*" => x.wrapping_mul(y),
+		_ if y == 0 => return Err("division by zero in #if".to_string()),
+		"/" => x.wrapping_div(y),
+		_ => x.wrapping_rem(y),
+	})
+}
+
+/// The value of the expression of an `#if`, with its macros expanded and no
+/// blanks
+pub fn evaluate(tokens: &[Token]) -> Result<i64, String> {
+	let mut evaluator = Evaluator { tokens, pos: 0 };
+	let value = evaluator.conditional()?;
+	match tokens.get(evaluator.pos) {
+		Some(token) => {
+			Err(format!("missing binary operator before `{}`", token.text))
+		}
+		None => Ok(value),
+	}
+}
@@ -0,0 +1,236 @@
+use std::collections::VecDeque;
+
+use utils::SysycError;
+
+use crate::{
+	token::{Kind, Origin, Token},
+	tokenize, trim, Preprocessor,
+};
+
+#[derive(Clone, Debug)]
+pub struct Macro {
+	// `None` for an object-like macro
+	pub params: Option<Vec<String>>,
+	pub body: Vec<Token>,
+}
+
+/// Why the tokens of a line are not expanded
+pub enum Stop {
+	/// An invocation goes on in the next line
+	Incomplete,
+	Error(SysycError),
+}
+
+impl From<SysycError> for Stop {
+	fn from(value: SysycError) -> Self {
+		Stop::Error(value)
+	}
+}
+
+// `x` of `#x`, as a string literal
+fn stringify(tokens: &[Token], origin: Origin) -> Token {
+	let mut value = String::new();
+	for token in trim(tokens) {
+		match token.kind {
+			Kind::Space => value.push(' '),
+			Kind::Str | Kind::Char => {
+				value.push_str(&token.text.replace('\\', "\\\\").replace('"', "\\\""))
+			}
+			_ => value.push_str(&token.text),
+		}
+	}
+	Token::new(Kind::Str, &format!("\"{}\"", value), origin)
+}
+
+impl Preprocessor {
+	/// Expand the macros in `tokens`. Unless they are `complete`, an
+	/// invocation running to their end needs the next line.
+	pub(crate) fn expand(
+		&mut self,
+		tokens: Vec<Token>,
+		complete: bool,
+	) -> Result<Vec<Token>, Stop> {
+		let mut queue: VecDeque<_> = tokens.into();
+		let mut output = Vec::new();
+		while let Some(token) = queue.pop_front() {
+			if token.kind != Kind::Ident || token.hide.contains(&token.text) {
+				output.push(token);
+				continue;
+			}
+			let origin = token.invocation(&token);
+			let (Origin::At(pos) | Origin::From(pos, _)) = token.origin;
+			match token.text.as_str() {
+				"__LINE__" => {
+					let line = self.line(pos).to_string();
+					output.push(Token::new(Kind::Number, &line, origin));
+					continue;
+				}
+				"__FILE__" => {
+					let name = format!("{:?}", self.map.name(self.file()));
+					output.push(Token::new(Kind::Str, &name, origin));
+					continue;
+				}
+				_ => {}
+			}
+			let Some(macro_t) = self.macros.get(&token.text).cloned() else {
+				output.push(token);
+				continue;
+			};
+			let (args, origin) = match &macro_t.params {
+				None => (Vec::new(), origin),
+				Some(params) => {
+					let Some((args, end)) = self.collect_args(&mut queue, complete)?
+					else {
+						// the name of a function-like macro alone is left as is
+						output.push(token);
+						continue;
+					};
+					let args = match args.as_slice() {
+						[v] if params.is_empty() && v.is_empty() => Vec::new(),
+						_ => args,
+					};
+					if args.len() != params.len() {
+						let message = format!(
+							"macro `{}` passed {} arguments, but takes {}",
+							token.text,
+							args.len(),
+							params.len()
+						);
+						return Err(self.error_on(message, &[token, end]).into());
+					}
+					(args, token.invocation(&end))
+				}
+			};
+			let body = self.substitute(&macro_t, &args, origin)?;
+			// what it expands to is read again with the rest, but can not
+			// invoke it again
+			for mut v in body.into_iter().rev() {
+				v.hide.extend(token.hide.iter().cloned());
+				v.hide.push(token.text.clone());
+				queue.push_front(v);
+			}
+		}
+		Ok(output)
+	}
+	// the arguments of an invocation in `(...)` at the front of the queue,
+	// which it takes with the `)`
+	#[allow(clippy::type_complexity)]
+	fn collect_args(
+		&mut self,
+		queue: &mut VecDeque<Token>,
+		complete: bool,
+	) -> Result<Option<(Vec<Vec<Token>>, Token)>, Stop> {
+		let incomplete = if complete {
+			Ok(None)
+		} else {
+			Err(Stop::Incomplete)
+		};
+		let Some(open) = queue.iter().position(|v| v.kind != Kind::Space) else {
+			return incomplete;
+		};
+		if !queue[open].is("(") {
+			return Ok(None);
+		}
+		let mut depth = 0;
+		let mut close = None;
+		for (i, v) in queue.iter().enumerate().skip(open) {
+			if v.is("(") {
+				depth += 1;
+			} else if v.is(")") {
+				depth -= 1;
+				if depth == 0 {
+					close = Some(i);
+					break;
+				}
+			}
+		}
+		let Some(close) = close else {
+			return incomplete;
+		};
+		let mut tokens: Vec<_> = queue.drain(..=close).skip(open + 1).collect();
+		let end = tokens.pop().unwrap();
+		let mut args = vec![Vec::new()];
+		let mut depth = 0;
+		for v in tokens {
+			if v.is(",") && depth == 0 {
+				args.push(Vec::new());
+				continue;
+			}
+			if v.is("(") {
+				depth += 1;
+			} else if v.is(")") {
+				depth -= 1;
+			}
+			args.last_mut().unwrap().push(v);
+		}
+		let args = args.iter().map(|v| trim(v).to_vec()).collect();
+		Ok(Some((args, end)))
+	}
+	// the body of a macro with its parameters replaced by the arguments
+	fn substitute(
+		&mut self,
+		macro_t: &Macro,
+		args: &[Vec<Token>],
+		origin: Origin,
+	) -> Result<Vec<Token>, Stop> {
+		let params = macro_t.params.as_deref().unwrap_or_default();
+		let param = |v: &Token| -> Option<usize> {
+			(v.kind == Kind::Ident)
+				.then(|| params.iter().position(|p| *p == v.text))
+				.flatten()
+		};
+		let body = &macro_t.body;
+		// the next token that is not a blank
+		let next =
+			|i: usize| (i + 1..body.len()).find(|v| body[*v].kind != Kind::Space);
+		let mut output: Vec<Token> = Vec::new();
+		let mut i = 0;
+		while i < body.len() {
+			let token = &body[i];
+			// `define` has checked what is around `#` and `##`
+			if token.is("#") && macro_t.params.is_some() {
+				let j = next(i).unwrap();
+				output.push(stringify(&args[param(&body[j]).unwrap()], origin));
+				i = j + 1;
+				continue;
+			}
+			if token.is("##") {
+				let j = next(i).unwrap();
+				while output.last().is_some_and(|v| v.kind == Kind::Space) {
+					output.pop();
+				}
+				let mut right = match param(&body[j]) {
+					Some(k) => args[k].clone(),
+					None => vec![body[j].clone()],
+				};
+				// an empty argument leaves the other side as is
+				if !right.is_empty() {
+					if let Some(left) = output.pop() {
+						let pasted = format!("{}{}", left.text, right.remove(0).text);
+						let tokens = tokenize(&pasted, 0).unwrap_or_default();
+						output.extend(tokens.into_iter().map(|v| Token { origin, ..v }));
+					}
+				}
+				output.extend(right);
+				i = j + 1;
+				continue;
+			}
+			match param(token) {
+				Some(k) => {
+					let pasted = next(i).is_some_and(|v| body[v].is("##"));
+					if pasted {
+						output.extend(args[k].iter().cloned());
+					} else {
+						output.extend(self.expand(args[k].clone(), true)?);
+					}
+				}
+				None => output.push(Token {
+					origin,
+					..token.clone()
+				}),
+			}
+			i += 1;
+		}
+		Ok(output)
+	}
+}
@@ -0,0 +1,527 @@
+mod eval;
+mod expand;
+mod token;
+
+use std::{
+	collections::{HashMap, HashSet},
+	fs,
+	path::{Path, PathBuf},
+};
+
+use utils::{
+	errors::Result,
+	line_col, SourceMap, Span,
+	SysycError::{self, PreprocessError},
+};
+
+use crate::{
+	expand::{Macro, Stop},
+	token::{tokenize, Kind, Origin, Token},
+};
+
+const MAX_INCLUDE_DEPTH: usize = 200;
+
+// macros of the SysY runtime, which times the code between them
+const PREDEFINED: [&str; 2] = [
+	"starttime() _sysy_starttime(__LINE__)",
+	"stoptime() _sysy_stoptime(__LINE__)",
+];
+
+// a group of lines under `#if`, `#ifdef` or `#ifndef`
+struct Cond {
+	// whether its lines are read
+	active: bool,
+	// whether a branch has been taken, or can not be as the group it is in
+	// is skipped
+	done: bool,
+	has_else: bool,
+	// where the directive opening it is in the file
+	start: usize,
+	end: usize,
+}
+
+/// Expands `#include`, `#define` and conditional compilation in a file, like
+/// the C preprocessor does
+pub struct Preprocessor {
+	include_dirs: Vec<PathBuf>,
+	macros: HashMap<String, Macro>,
+	map: SourceMap,
+	output: String,
+	conds: Vec<Cond>,
+	// the files being read, the innermost last
+	files: Vec<usize>,
+	once: HashSet<PathBuf>,
+}
+
+// the text of the tokens, as written
+fn text(tokens: &[Token]) -> String {
+	tokens.iter().map(|v| v.text.as_str()).collect()
+}
+
+// the tokens without blanks on both ends
+fn trim(mut tokens: &[Token]) -> &[Token] {
+	while tokens.first().is_some_and(|v| v.kind == Kind::Space) {
+		tokens = &tokens[1..];
+	}
+	while tokens.last().is_some_and(|v| v.kind == Kind::Space) {
+		tokens = &tokens[..tokens.len() - 1];
+	}
+	tokens
+}
+
+// the end of the line starting at `pos`, after its newline
+fn line_end(code: &str, pos: usize) -> usize {
+	code[pos..].find('\n').map_or(code.len(), |v| pos + v + 1)
+}
+
+fn is_directive(code: &str, pos: usize) -> bool {
+	code[pos..].trim_start_matches([' ', '\t']).starts_with('#')
+}
+
+impl Preprocessor {
+	/// A preprocessor looking for `#include <...>` in `include_dirs`, and
+	/// then for `#include "..."` in them after the directory of the file
+	pub fn new(include_dirs: &[String]) -> Self {
+		let mut preprocessor = Self {
+			include_dirs: include_dirs.iter().map(PathBuf::from).collect(),
+			macros: HashMap::new(),
+			map: SourceMap::default(),
+			output: String::new(),
+			conds: Vec::new(),
+			files: Vec::new(),
+			once: HashSet::new(),
+		};
+		for v in PREDEFINED {
+			let tokens = tokenize(v, 0).unwrap();
+			preprocessor.define(&tokens).unwrap();
+		}
+		preprocessor
+	}
+	/// The code of a file after preprocessing
+	pub fn process(&mut self, file_name: &str, code: &str) -> Result<String> {
+		let file = self.map.add_file(file_name.to_string(), code.to_string());
+		self.process_file(file)?;
+		Ok(std::mem::take(&mut self.output))
+	}
+	/// Where the code given by [`Preprocessor::process`] comes from, or the
+	/// error it gives
+	pub fn into_source_map(self) -> SourceMap {
+		self.map
+	}
+	fn file(&self) -> usize {
+		*self.files.last().unwrap()
+	}
+	fn is_active(&self) -> bool {
+		self.conds.last().map_or(true, |v| v.active)
+	}
+	// an error at `start..end` of the file being read, which the output is
+	// mapped to from its end on
+	fn error_at(
+		&mut self,
+		message: String,
+		start: usize,
+		end: usize,
+	) -> SysycError {
+		let out = self.output.len();
+		self.map.map(out, self.file(), start, None);
+		PreprocessError(message).at(Span::new(out, out + end.max(start) - start))
+	}
+	fn error_on(&mut self, message: String, tokens: &[Token]) -> SysycError {
+		let tokens = trim(tokens);
+		match (tokens.first(), tokens.last()) {
+			(Some(first), Some(last)) => {
+				let (Origin::At(start) | Origin::From(start, _)) = first.origin;
+				let end = match last.origin {
+					Origin::At(v) => v + last.text.len(),
+					Origin::From(v, len) => v + len,
+				};
+				self.error_at(message, start, end)
+			}
+			_ => PreprocessError(message),
+		}
+	}
+	// write the tokens to the output, with a blank between two that are not
+	// next to each other in the file, so that they are not read as one
+	fn emit(&mut self, tokens: &[Token]) {
+		let file = self.file();
+		let mut last: Option<&Token> = None;
+		for token in tokens {
+			let apart = match (last.map(|v| (v.origin, v.text.len())), token.origin) {
+				(Some((Origin::At(x), len)), Origin::At(y)) => x + len != y,
+				(Some((x, _)), y) => x != y,
+				(None, _) => false,
+			};
+			if apart && token.kind != Kind::Space {
+				self.output.push(' ');
+			}
+			match token.origin {
+				Origin::At(v) => self.map.map(self.output.len(), file, v, None),
+				Origin::From(v, len) => {
+					self.map.map(self.output.len(), file, v, Some(len))
+				}
+			}
+			self.output.push_str(&token.text);
+			last = (token.kind != Kind::Space).then_some(token);
+		}
+	}
+	// keep the lines of what is left out, so that the output has as many
+	fn emit_lines(&mut self, start: usize, end: usize) {
+		let file = self.file();
+		let code = self.map.code(file);
+		let newlines: Vec<_> =
+			code[start..end].match_indices('\n').map(|(v, _)| start + v).collect();
+		for v in newlines {
+			self.map.map(self.output.len(), file, v, None);
+			self.output.push('\n');
+		}
+	}
+	fn process_file(&mut self, file: usize) -> Result<()> {
+		self.files.push(file);
+		let depth = self.conds.len();
+		let len = self.map.code(file).len();
+		let mut pos = 0;
+		while pos < len {
+			let code = self.map.code(file);
+			let mut end = line_end(code, pos);
+			if is_directive(code, pos) {
+				// a directive goes on after a line ending in a backslash
+				while code[..end].trim_end_matches(['\n', '\r']).ends_with('\\')
+					&& end < len
+				{
+					end = line_end(code, end);
+				}
+				self.directive(pos, end)?;
+				self.emit_lines(pos, end);
+			} else if !self.is_active() {
+				self.emit_lines(pos, end);
+			} else {
+				end = self.text(pos, end)?;
+			}
+			pos = end;
+		}
+		if self.conds.len() > depth {
+			let cond = self.conds.pop().unwrap();
+			let message = "unterminated conditional directive".to_string();
+			return Err(self.error_at(message, cond.start, cond.end));
+		}
+		self.files.pop();
+		Ok(())
+	}
+	// expand the lines from `start` on, and as many after `end` as a comment
+	// or an invocation goes on, returning where they end
+	fn text(&mut self, start: usize, mut end: usize) -> Result<usize> {
+		let file = self.file();
+		loop {
+			let code = self.map.code(file);
+			let len = code.len();
+			let Ok(tokens) = tokenize(&code[start..end], start) else {
+				if end == len {
+					let message = "unterminated comment".to_string();
+					return Err(self.error_at(message, start, end));
+				}
+				end = line_end(code, end);
+				continue;
+			};
+			let complete = end == len || is_directive(code, end);
+			match self.expand(tokens, complete) {
+				Ok(tokens) => {
+					let out = self.output.len();
+					self.emit(&tokens);
+					// the lines an invocation runs over are made up for after
+					// it, so that the next ones stay where they are
+					let code = self.map.code(file);
+					let lines = code[start..end].matches('\n').count();
+					let missing = lines - self.output[out..].matches('\n').count();
+					if missing > 0 {
+						self.map.map(self.output.len(), file, end - 1, None);
+						self.output.push_str(&"\n".repeat(missing));
+					}
+					return Ok(end);
+				}
+				Err(Stop::Incomplete) => end = line_end(self.map.code(file), end),
+				Err(Stop::Error(err)) => return Err(err),
+			}
+		}
+	}
+	fn directive(&mut self, start: usize, end: usize) -> Result<()> {
+		let code = &self.map.code(self.file())[start..end];
+		let Ok(tokens) = tokenize(code, start) else {
+			let message = "unterminated comment".to_string();
+			return Err(self.error_at(message, start, end));
+		};
+		// drop the backslashes joining the lines
+		let tokens: Vec<_> = tokens
+			.iter()
+			.enumerate()
+			.filter(|(i, v)| {
+				!(v.is("\\")
+					&& tokens
+						.get(i + 1)
+						.is_some_and(|v| v.text.starts_with(['\n', '\r'])))
+			})
+			.map(|(_, v)| v.clone())
+			.collect();
+		let tokens = trim(&tokens);
+		let Origin::At(hash) = tokens[0].origin else {
+			unreachable!()
+		};
+		// the tokens after `#`
+		let tokens = trim(&tokens[1..]);
+		let Some(name) = tokens.first() else {
+			return Ok(());
+		};
+		let rest = trim(&tokens[1..]);
+		let active = self.is_active();
+		match name.text.as_str() {
+			"ifdef" | "ifndef" | "if" => {
+				let value = match name.text.as_str() {
+					_ if !active => false,
+					"if" => self.condition(name, rest)?,
+					"ifdef" => self.is_defined(name, rest)?,
+					_ => !self.is_defined(name, rest)?,
+				};
+				self.conds.push(Cond {
+					active: value,
+					done: value || !active,
+					has_else: false,
+					start: hash,
+					end: hash + name.text.len() + 1,
+				});
+			}
+			"elif" | "else" => {
+				let Some(cond) = self.conds.last() else {
+					let message = format!("#{} without #if", name.text);
+					return Err(self.error_on(message, tokens));
+				};
+				if cond.has_else {
+					let message = format!("#{} after #else", name.text);
+					return Err(self.error_on(message, tokens));
+				}
+				let value = match (cond.done, name.text.as_str()) {
+					(true, _) => false,
+					(false, "elif") => self.condition(name, rest)?,
+					_ => true,
+				};
+				let cond = self.conds.last_mut().unwrap();
+				cond.active = value;
+				cond.done |= value;
+				cond.has_else = name.text == "else";
+			}
+			"endif" => {
+				if self.conds.pop().is_none() {
+					let message = "#endif without #if".to_string();
+					return Err(self.error_on(message, tokens));
+				}
+			}
+			_ if !active => {}
+			"define" => self.define(rest)?,
+			"undef" => {
+				let name = self.macro_name(name, rest)?;
+				self.macros.remove(&name);
+			}
+			"include" => self.include(name, rest)?,
+			"error" => {
+				let message = format!("#error {}", text(rest));
+				return Err(self.error_on(message, tokens));
+			}
+			"pragma" => {
+				if text(rest) == "once" {
+					let path = Path::new(self.map.name(self.file()));
+					self.once.insert(path.canonicalize().unwrap_or(path.into()));
+				}
+			}
+			_ => {
+				let message = format!("invalid preprocessing directive #{}", name.text);
+				return Err(self.error_on(message, &tokens[..1]));
+			}
+		}
+		Ok(())
+	}
+	// the name a directive is about, with nothing after it
+	fn macro_name(
+		&mut self,
+		directive: &Token,
+		rest: &[Token],
+	) -> Result<String> {
+		match rest {
+			[name] if name.kind == Kind::Ident => Ok(name.text.clone()),
+			[] => {
+				let message = format!("no macro name given in #{}", directive.text);
+				Err(self.error_on(message, std::slice::from_ref(directive)))
+			}
+			_ => {
+				let message = format!("#{} expects a macro name", directive.text);
+				Err(self.error_on(message, rest))
+			}
+		}
+	}
+	fn is_defined(&mut self, directive: &Token, rest: &[Token]) -> Result<bool> {
+		let name = self.macro_name(directive, rest)?;
+		Ok(self.macros.contains_key(&name))
+	}
+	fn define(&mut self, tokens: &[Token]) -> Result<()> {
+		let Some(name) = tokens.first().filter(|v| v.kind == Kind::Ident) else {
+			let message = "#define expects a macro name".to_string();
+			return Err(self.error_on(message, tokens));
+		};
+		let mut rest = &tokens[1..];
+		// only a `(` right after the name starts the parameters
+		let params = match rest.first() {
+			Some(v) if v.is("(") => {
+				let close = rest.iter().position(|v| v.is(")"));
+				let Some(close) = close else {
+					let message = "missing ')' in macro parameter list".to_string();
+					return Err(self.error_on(message, rest));
+				};
+				let params = &rest[1..close];
+				rest = &rest[close + 1..];
+				let params: Vec<_> = params.split(|v| v.is(",")).map(trim).collect();
+				let params = match params.as_slice() {
+					[[]] => Vec::new(),
+					_ => params
+						.iter()
+						.map(|v| match v {
+							[name] if name.kind == Kind::Ident => Ok(name.text.clone()),
+							_ => {
+								let message = "expected a parameter name".to_string();
+								Err(self.error_on(message, v))
+							}
+						})
+						.collect::<Result<_>>()?,
+				};
+				Some(params)
+			}
+			_ => None,
+		};
+		let body = trim(rest);
+		if body.first().is_some_and(|v| v.is("##"))
+			|| body.last().is_some_and(|v| v.is("##"))
+		{
+			let message = "'##' can not be at either end of a macro".to_string();
+			return Err(self.error_on(message, body));
+		}
+		if let Some(params) = &params {
+			let words: Vec<_> =
+				body.iter().filter(|v| v.kind != Kind::Space).collect();
+			for (i, v) in words.iter().enumerate() {
+				let next = words.get(i + 1).filter(|v| v.kind == Kind::Ident);
+				if v.is("#") && !next.is_some_and(|v| params.contains(&v.text)) {
+					let message = "'#' is not followed by a macro parameter".to_string();
+					return Err(self.error_on(message, std::slice::from_ref(*v)));
+				}
+			}
+		}
+		// blanks in the body are all alike
+		let body = body
+			.iter()
+			.map(|v| match v.kind {
+				Kind::Space => Token::new(Kind::Space, " ", v.origin),
+				_ => v.clone(),
+			})
+			.collect();
+		self.macros.insert(name.text.clone(), Macro { params, body });
+		Ok(())
+	}
+	fn include(&mut self, directive: &Token, rest: &[Token]) -> Result<()> {
+		let (name, quoted) = match rest {
+			[v] if v.kind == Kind::Str => {
+				(v.text[1..v.text.len() - 1].to_string(), true)
+			}
+			[open, .., close] if open.is("<") && close.is(">") => {
+				(text(&rest[1..rest.len() - 1]), false)
+			}
+			_ => {
+				let message = "#include expects \"FILENAME\" or <FILENAME>".to_string();
+				let tokens = if rest.is_empty() {
+					std::slice::from_ref(directive)
+				} else {
+					rest
+				};
+				return Err(self.error_on(message, tokens));
+			}
+		};
+		let dir =
+			Path::new(self.map.name(self.file())).parent().map(Path::to_path_buf);
+		let path = quoted
+			.then_some(dir)
+			.flatten()
+			.into_iter()
+			.chain(self.include_dirs.iter().cloned())
+			.map(|v| v.join(&name))
+			.find(|v| v.is_file());
+		let Some(path) = path else {
+			return Err(self.error_on(format!("can not find `{}`", name), rest));
+		};
+		if self.once.contains(&path.canonicalize().unwrap_or(path.clone())) {
+			return Ok(());
+		}
+		if self.files.len() >= MAX_INCLUDE_DEPTH {
+			let message = "#include nested too deeply".to_string();
+			return Err(self.error_on(message, rest));
+		}
+		let code = match fs::read_to_string(&path) {
+			Ok(code) => code,
+			Err(err) => {
+				return Err(
+					self.error_on(format!("can not read `{}`: {}", name, err), rest),
+				)
+			}
+		};
+		let file = self.map.add_file(path.to_string_lossy().into_owned(), code);
+		self.process_file(file)
+	}
+	// whether the expression of an `#if` or `#elif` is not zero
+	fn condition(&mut self, directive: &Token, rest: &[Token]) -> Result<bool> {
+		if rest.is_empty() {
+			let message = format!("#{} with no expression", directive.text);
+			return Err(self.error_on(message, std::slice::from_ref(directive)));
+		}
+		let mut tokens = Vec::new();
+		let mut iter = rest.iter().filter(|v| v.kind != Kind::Space);
+		while let Some(token) = iter.next() {
+			if token.kind != Kind::Ident || token.text != "defined" {
+				tokens.push(token.clone());
+				continue;
+			}
+			let name = match iter.next() {
+				Some(v) if v.is("(") => {
+					let name = iter.next().filter(|v| v.kind == Kind::Ident);
+					name.filter(|_| iter.next().is_some_and(|v| v.is(")")))
+				}
+				name => name.filter(|v| v.kind == Kind::Ident),
+			};
+			let Some(name) = name else {
+				let message = "`defined` expects a macro name".to_string();
+				return Err(self.error_on(message, std::slice::from_ref(token)));
+			};
+			let value = if self.macros.contains_key(&name.text) {
+				"1"
+			} else {
+				"0"
+			};
+			tokens.push(Token::new(Kind::Number, value, token.origin));
+		}
+		let tokens = match self.expand(tokens, true) {
+			Ok(tokens) => tokens,
+			Err(Stop::Error(err)) => return Err(err),
+			Err(Stop::Incomplete) => unreachable!(),
+		};
+		// what is not a macro is 0
+		let tokens: Vec<_> = tokens
+			.into_iter()
+			.filter(|v| v.kind != Kind::Space)
+			.map(|v| match v.kind {
+				Kind::Ident => Token::new(Kind::Number, "0", v.origin),
+				_ => v,
+			})
+			.collect();
+		match eval::evaluate(&tokens) {
+			Ok(value) => Ok(value != 0),
+			Err(message) => Err(self.error_on(message, rest)),
+		}
+	}
+	// the line of an offset in the file being read
+	fn line(&self, pos: usize) -> usize {
+		line_col(self.map.code(self.file()), pos).0
+	}
+}
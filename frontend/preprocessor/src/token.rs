@@ -0,0 +1,129 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+	Ident,
+	Number,
+	Str,
+	Char,
+	Punct,
+	// blanks, newlines and comments
+	Space,
+}
+
+/// Where the text of a token comes from in the file being read
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Origin {
+	/// As is, at this offset
+	At(usize),
+	/// Expanded from a macro invocation at `(offset, len)`
+	From(usize, usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+	pub kind: Kind,
+	pub text: String,
+	pub origin: Origin,
+	// the macros it comes from, which it does not invoke again
+	pub hide: Vec<String>,
+}
+
+impl Token {
+	pub fn new(kind: Kind, text: &str, origin: Origin) -> Self {
+		Self {
+			kind,
+			text: text.to_string(),
+			origin,
+			hide: Vec::new(),
+		}
+	}
+	pub fn is(&self, text: &str) -> bool {
+		self.kind == Kind::Punct && self.text == text
+	}
+	/// The invocation that this token starts, ending at `end`
+	pub fn invocation(&self, end: &Token) -> Origin {
+		match (self.origin, end.origin) {
+			(Origin::At(start), Origin::At(v)) => {
+				Origin::From(start, v + end.text.len() - start)
+			}
+			(Origin::At(start), _) => Origin::From(start, self.text.len()),
+			(origin, _) => origin,
+		}
+	}
+}
+
+/// The lexer ran out of text in a comment, a literal or an invocation, so it
+/// needs the next line as well
+#[derive(Debug)]
+pub struct Incomplete;
+
+fn is_ident(v: char) -> bool {
+	v.is_ascii_alphanumeric() || v == '_'
+}
+
+/// Split the text at `offset` of a file into tokens
+pub fn tokenize(text: &str, offset: usize) -> Result<Vec<Token>, Incomplete> {
+	let mut tokens = Vec::new();
+	let mut pos = 0;
+	while pos < text.len() {
+		let rest = &text[pos..];
+		let first = rest.chars().next().unwrap();
+		let (kind, len) = if rest.starts_with("//") {
+			(Kind::Space, rest.find('\n').unwrap_or(rest.len()))
+		} else if let Some(comment) = rest.strip_prefix("/*") {
+			(Kind::Space, comment.find("*/").ok_or(Incomplete)? + 4)
+		} else if first.is_whitespace() {
+			let len = rest.find(|v: char| !v.is_whitespace()).unwrap_or(rest.len());
+			(Kind::Space, len)
+		} else if first == '"' || first == '\'' {
+			let mut chars = rest.char_indices().skip(1);
+			let mut end = None;
+			while let Some((i, v)) = chars.next() {
+				match v {
+					'\\' => {
+						chars.next();
+					}
+					'\n' => break,
+					_ if v == first => {
+						end = Some(i + 1);
+						break;
+					}
+					_ => {}
+				}
+			}
+			// an unterminated literal is left for the parser to complain about
+			match end {
+				Some(len) if first == '"' => (Kind::Str, len),
+				Some(len) => (Kind::Char, len),
+				None => (Kind::Punct, 1),
+			}
+		} else if first.is_ascii_digit()
+			|| first == '.' && rest[1..].starts_with(|v: char| v.is_ascii_digit())
+		{
+			let mut len = 1;
+			let bytes = rest.as_bytes();
+			while len < bytes.len() {
+				let v = bytes[len] as char;
+				let exp = matches!(bytes[len - 1], b'e' | b'E' | b'p' | b'P');
+				if is_ident(v) || v == '.' || exp && (v == '+' || v == '-') {
+					len += 1;
+				} else {
+					break;
+				}
+			}
+			(Kind::Number, len)
+		} else if first.is_ascii_alphabetic() || first == '_' {
+			(
+				Kind::Ident,
+				rest.find(|v| !is_ident(v)).unwrap_or(rest.len()),
+			)
+		} else if rest.starts_with("##") {
+			(Kind::Punct, 2)
+		} else {
+			(Kind::Punct, first.len_utf8())
+		};
+		let origin = Origin::At(offset + pos);
+		tokens.push(Token::new(kind, &rest[..len], origin));
+		pos += len;
+	}
+	Ok(tokens)
+}
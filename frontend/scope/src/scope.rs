@@ -1,12 +1,13 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, rc::Rc};
 
 use rrvm_symbol::Symbol;
 use utils::{errors::Result, SysycError::SyntaxError};
-use value::Value;
+use value::{Record, Value};
 
 pub struct Scope<T> {
 	symbols: HashMap<String, Symbol<T>>,
 	constants: HashMap<i32, Value>,
+	records: HashMap<String, Rc<Record>>,
 }
 
 impl<T> Default for Scope<T> {
@@ -20,6 +21,7 @@ impl<T> Scope<T> {
 		Self {
 			symbols: HashMap::new(),
 			constants: HashMap::new(),
+			records: HashMap::new(),
 		}
 	}
 	pub fn set_constant(&mut self, id: i32, value: Value) {
@@ -28,6 +30,17 @@ impl<T> Scope<T> {
 	pub fn get_constant(&self, id: i32) -> Option<&Value> {
 		self.constants.get(&id)
 	}
+	pub fn get_record(&self, ident: &str) -> Option<&Rc<Record>> {
+		self.records.get(ident)
+	}
+	pub fn new_record(&mut self, record: Rc<Record>) -> Result<()> {
+		let ident = record.ident.clone();
+		if self.records.insert(ident.clone(), record).is_some() {
+			Err(SyntaxError(format!("struct {} is redefinition", ident)))
+		} else {
+			Ok(())
+		}
+	}
 	pub fn get_symbol(&self, ident: &str) -> Option<&Symbol<T>> {
 		self.symbols.get(ident)
 	}
@@ -1,10 +1,12 @@
+use std::rc::Rc;
+
 use crate::scope::Scope;
 use rrvm_symbol::{manager::SymbolManager, FuncSymbol, VarSymbol};
 use utils::{
 	errors::Result,
 	SysycError::{FatalError, SyntaxError},
 };
-use value::{FuncRetType, FuncType, Value, VarType};
+use value::{FuncRetType, FuncType, Record, Value, VarType};
 
 #[derive(Default)]
 pub struct ScopeStack {
@@ -48,6 +50,17 @@ impl ScopeStack {
 			.get_symbol(ident)
 			.ok_or(SyntaxError(format!("{} is not found", ident)))
 	}
+	pub fn set_record(&mut self, record: Rc<Record>) -> Result<()> {
+		self.top()?.new_record(record)
+	}
+	pub fn get_record(&self, ident: &str) -> Result<&Rc<Record>> {
+		self
+			.scopes
+			.iter()
+			.rev()
+			.find_map(|v| v.get_record(ident))
+			.ok_or(SyntaxError(format!("struct {} is not found", ident)))
+	}
 	pub fn set_constant(&mut self, id: i32, value: Value) -> Result<()> {
 		self.top()?.set_constant(id, value);
 		Ok(())
@@ -118,7 +131,7 @@ impl ScopeStack {
 			FuncRetType::Void,
 			vec![VarType {
 				is_lval: true,
-				type_t: value::BType::Int,
+				type_t: value::BType::Char,
 				dims: vec![0],
 			}],
 		);
@@ -2,7 +2,7 @@ use std::hash::{Hash, Hasher};
 
 use crate::{FuncSymbol, Symbol};
 
-use utils::constants::{VEC_EXTERN, VEC_MACRO};
+use utils::constants::{VEC_EXTERN, VEC_MACRO, VEC_VARIADIC};
 
 impl<T> PartialEq for Symbol<T> {
 	fn eq(&self, other: &Self) -> bool {
@@ -25,4 +25,7 @@ impl FuncSymbol {
 	pub fn is_macro(&self) -> bool {
 		VEC_MACRO.contains(&self.ident.as_str())
 	}
+	pub fn is_variadic(&self) -> bool {
+		VEC_VARIADIC.contains(&self.ident.as_str())
+	}
 }
@@ -1,38 +1,74 @@
 use ast::{tree::*, Visitor};
 use attr::Attrs;
 use rrvm_symbol::FuncSymbol;
-use utils::{errors::Result, SysycError::TypeError};
+use utils::{
+	errors::{check_errors, Result},
+	SysycError::{self, TypeError},
+};
 use value::{
-	calc_type::{to_rval, type_binaryop},
+	calc_type::{to_rval, type_binaryop, type_conditional, type_step},
 	BType, UnaryOp, VarType,
 };
 
 use crate::FUNC_IGNORE_TYPE_CHECK;
 
 #[derive(Default)]
-pub struct Typer {}
+pub struct Typer {
+	errors: Vec<SysycError>,
+}
 
 impl Typer {
 	pub fn transform(&mut self, program: &mut Program) -> Result<()> {
-		program.accept(self)
+		program.accept(self)?;
+		check_errors(std::mem::take(&mut self.errors))
+	}
+	// record the error and go on with the next statement
+	fn collect(&mut self, result: Result<()>) {
+		if let Err(err) = result {
+			self.errors.push(err);
+		}
+	}
+}
+
+// an array or a struct is only initialized by a list, or by a string that the
+// namer has made a list
+fn check_init_val(node: &Node) -> Result<()> {
+	match node.get_var_type().cloned() {
+		Some(type_t) if type_t.is_aggregate() => Err(TypeError(format!(
+			"can not initialize with a value of type `{}`",
+			type_t
+		))),
+		_ => Ok(()),
 	}
 }
 
 impl Visitor for Typer {
 	fn visit_program(&mut self, node: &mut Program) -> Result<()> {
 		for v in node.functions.iter_mut() {
-			v.accept(self)?;
+			let result = v.accept(self);
+			self.collect(result);
 		}
 		Ok(())
 	}
 
 	fn visit_func_decl(&mut self, node: &mut FuncDecl) -> Result<()> {
-		node.block.accept(self)
+		match node.block.as_mut() {
+			Some(block) => block.accept(self),
+			None => Ok(()),
+		}
 	}
 
 	fn visit_var_def(&mut self, node: &mut VarDef) -> Result<()> {
+		let type_t = node.var_type()?.clone();
 		if let Some(init) = node.init.as_mut() {
 			init.accept(self)?;
+			check_init_val(init)?;
+			if type_t.is_aggregate() && init.get_slot().is_none() {
+				return Err(TypeError(format!(
+					"`{}` of type `{}` must be initialized by a list",
+					node.ident, type_t
+				)));
+			}
 		}
 		Ok(())
 	}
@@ -44,47 +80,85 @@ impl Visitor for Typer {
 		Ok(())
 	}
 
+	fn visit_struct_decl(&mut self, _node: &mut StructDecl) -> Result<()> {
+		Ok(())
+	}
+
 	fn visit_init_val_list(&mut self, node: &mut InitValList) -> Result<()> {
 		for val in node.val_list.iter_mut() {
 			val.accept(self)?;
+			check_init_val(val)?;
 		}
 		Ok(())
 	}
 
 	fn visit_literal_int(&mut self, node: &mut LiteralInt) -> Result<()> {
-		node.set_attr("type", VarType::new_int().into());
+		node.set_var_type(VarType::new_int());
 		Ok(())
 	}
 
 	fn visit_literal_float(&mut self, node: &mut LiteralFloat) -> Result<()> {
-		node.set_attr("type", VarType::new_float().into());
+		node.set_var_type(VarType::new_float());
+		Ok(())
+	}
+
+	fn visit_literal_string(&mut self, node: &mut LiteralString) -> Result<()> {
+		let type_t = VarType {
+			is_lval: false,
+			type_t: BType::Char,
+			dims: vec![node.value.len() + 1],
+		};
+		node.set_var_type(type_t);
 		Ok(())
 	}
 
 	fn visit_binary_expr(&mut self, node: &mut BinaryExpr) -> Result<()> {
 		node.lhs.accept(self)?;
 		node.rhs.accept(self)?;
-		let lhs = node.lhs.get_attr("type").ok_or(TypeError(
+		let lhs = node.lhs.get_var_type().cloned().ok_or(TypeError(
 			" void value not ignored as it ought to be".to_string(),
 		))?;
-		let rhs = node.rhs.get_attr("type").ok_or(TypeError(
+		let rhs = node.rhs.get_var_type().cloned().ok_or(TypeError(
 			" void value not ignored as it ought to be".to_string(),
 		))?;
-		let type_t = type_binaryop(&lhs.into(), node.op, &rhs.into())?;
-		node.set_attr("type", type_t.into());
+		let type_t = type_binaryop(&lhs, node.op, &rhs)?;
+		node.set_var_type(type_t);
 		Ok(())
 	}
 
 	fn visit_unary_expr(&mut self, node: &mut UnaryExpr) -> Result<()> {
 		node.rhs.accept(self)?;
-		let rhs = node.rhs.get_attr("type").ok_or(TypeError(
+		let rhs = node.rhs.get_var_type().cloned().ok_or(TypeError(
 			" void value not ignored as it ought to be".to_string(),
 		))?;
-		let type_t = to_rval(&rhs.into());
+		if node.op.step().is_some() {
+			node.set_var_type(type_step(&rhs)?);
+			return Ok(());
+		}
+		let type_t = to_rval(&rhs);
+		if type_t.is_struct() {
+			return Err(TypeError(
+				"Can not do arith operation with struct".to_string(),
+			));
+		}
 		if type_t.type_t == BType::Float && node.op == UnaryOp::Not {
 			return Err(TypeError("Only integer can use operator '!'".to_string()));
 		}
-		node.set_attr("type", type_t.into());
+		node.set_var_type(type_t);
+		Ok(())
+	}
+
+	fn visit_conditional(&mut self, node: &mut Conditional) -> Result<()> {
+		let mut types = Vec::new();
+		for v in [&mut node.cond, &mut node.on_true, &mut node.on_false] {
+			v.accept(self)?;
+			let type_t = v.get_var_type().cloned().ok_or(TypeError(
+				" void value not ignored as it ought to be".to_string(),
+			))?;
+			types.push(type_t);
+		}
+		let type_t = type_conditional(&types[0], &types[1], &types[2])?;
+		node.set_var_type(type_t);
 		Ok(())
 	}
 
@@ -93,10 +167,12 @@ impl Visitor for Typer {
 			param.accept(self)?;
 		}
 
-		let symbol: FuncSymbol = node.get_attr("func_symbol").unwrap().into();
-		let (_, params) = symbol.var_type;
+		let symbol: FuncSymbol = node.func_symbol()?.clone();
+		let (_, params) = &symbol.var_type;
 
-		if node.params.len() != params.len() {
+		if node.params.len() < params.len()
+			|| node.params.len() > params.len() && !symbol.is_variadic()
+		{
 			return Err(TypeError(format!(
 				"unmatch numbers of params for function {}",
 				node.ident
@@ -106,11 +182,14 @@ impl Visitor for Typer {
 		// XXX: testcase derich.sy pass pass incorrect type of arguments to the function getfarray.
 		if !FUNC_IGNORE_TYPE_CHECK.contains(&node.ident.as_str()) {
 			for (x_t, y) in params.iter().zip(node.params.iter()) {
-				let y_t: VarType = y.get_attr("type").unwrap().into();
+				let y_t = y.get_var_type().cloned().ok_or(TypeError(
+					" void value not ignored as it ought to be".to_string(),
+				))?;
 				let err_msg =
 					format!("expected `{}` but argument is of type `{}`", x_t, y_t);
 				if x_t.dims.len() != y_t.dims.iter().len()
 					|| x_t.dims.iter().skip(1).ne(y_t.dims.iter().skip(1))
+					|| (x_t.is_aggregate() || y_t.is_struct()) && x_t.type_t != y_t.type_t
 				{
 					return Err(TypeError(err_msg));
 				}
@@ -124,9 +203,37 @@ impl Visitor for Typer {
 		Ok(())
 	}
 
+	fn visit_field_access(&mut self, node: &mut FieldAccess) -> Result<()> {
+		node.base.accept(self)?;
+		let base = node.base.var_type()?.clone();
+		let BType::Struct(record) = &base.type_t else {
+			return Err(TypeError(format!(
+				"request for member `{}` in something not a struct",
+				node.ident
+			)));
+		};
+		if base.is_array() {
+			return Err(TypeError(format!(
+				"request for member `{}` in an array",
+				node.ident
+			)));
+		}
+		let field = record.get_field(&node.ident).ok_or(TypeError(format!(
+			"`{}` has no member named `{}`",
+			base.type_t, node.ident
+		)))?;
+		let type_t = VarType {
+			is_lval: base.is_lval,
+			..field.var_type.clone()
+		};
+		node.set_var_type(type_t);
+		Ok(())
+	}
+
 	fn visit_block(&mut self, node: &mut Block) -> Result<()> {
 		for stmt in node.stmts.iter_mut() {
-			stmt.accept(self)?;
+			let result = stmt.accept(self);
+			self.collect(result);
 		}
 		Ok(())
 	}
@@ -145,6 +252,19 @@ impl Visitor for Typer {
 		node.body.accept(self)
 	}
 
+	fn visit_for(&mut self, node: &mut For) -> Result<()> {
+		let parts = [&mut node.init, &mut node.cond, &mut node.step];
+		for part in parts.into_iter().flatten() {
+			part.accept(self)?;
+		}
+		node.body.accept(self)
+	}
+
+	fn visit_do_while(&mut self, node: &mut DoWhile) -> Result<()> {
+		node.body.accept(self)?;
+		node.cond.accept(self)
+	}
+
 	fn visit_continue(&mut self, _node: &mut Continue) -> Result<()> {
 		Ok(())
 	}
@@ -156,10 +276,15 @@ impl Visitor for Typer {
 	fn visit_return(&mut self, node: &mut Return) -> Result<()> {
 		if let Some(val) = &mut node.value {
 			val.accept(self)?;
-			let val_t: VarType = val.get_attr("type").unwrap().into();
+			let val_t = val.get_var_type().cloned().ok_or(TypeError(
+				" void value not ignored as it ought to be".to_string(),
+			))?;
 			if val_t.is_array() {
 				return Err(TypeError("function cannot return an array".to_string()));
 			}
+			if val_t.is_struct() {
+				return Err(TypeError("function cannot return a struct".to_string()));
+			}
 		}
 		Ok(())
 	}
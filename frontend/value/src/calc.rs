@@ -19,6 +19,18 @@ where
 	}
 }
 
+fn bit_calc<Foo>(x: &Value, y: &Value, on_int: Foo) -> Result<Value>
+where
+	Foo: Fn(i32, i32) -> i32,
+{
+	match (x, y) {
+		(Value::Int(x), Value::Int(y)) => Ok(Value::Int(on_int(*x, *y))),
+		_ => Err(TypeError(
+			"Only integer can do bitwise operation".to_string(),
+		)),
+	}
+}
+
 fn bin_comp<Foo, Bar>(
 	x: &Value,
 	y: &Value,
@@ -51,8 +63,15 @@ fn get_index(index: &[usize], x: &[Value], pos: usize) -> Result<Value> {
 	}
 }
 
+fn is_array(x: &Value) -> bool {
+	matches!(x, Value::Array(_))
+}
+
 #[rustfmt::skip]
 pub fn exec_binaryop(x: &Value, op: BinaryOp, y: &Value) -> Result<Value> {
+	if op != BinaryOp::IDX && (is_array(x) || is_array(y)) {
+		return Err(TypeError("Can not do arith operation with pointer".to_string()));
+	}
 	match op {
 		BinaryOp::IDX => {
 			let pos = match y {
@@ -69,6 +88,12 @@ pub fn exec_binaryop(x: &Value, op: BinaryOp, y: &Value) -> Result<Value> {
 		BinaryOp::Mul => bin_calc(x, y, |x, y| -> i32 {x.wrapping_mul(y)}, |x, y| -> f32 {x * y}),
 		BinaryOp::Div => bin_calc(x, y, |x, y| -> i32 {x.wrapping_div(y)}, |x, y| -> f32 {x / y}),
 		BinaryOp::Mod => bin_calc(x, y, |x, y| -> i32 {x.wrapping_rem(y)}, |_, _| -> f32 {unreachable!()}),
+		BinaryOp::BitAnd => bit_calc(x, y, |x, y| -> i32 {x & y}),
+		BinaryOp::BitOr => bit_calc(x, y, |x, y| -> i32 {x | y}),
+		BinaryOp::BitXor => bit_calc(x, y, |x, y| -> i32 {x ^ y}),
+		// only the low 5 bits of the amount count, as with `sllw` and `sraw`
+		BinaryOp::Shl => bit_calc(x, y, |x, y| -> i32 {x.wrapping_shl(y as u32)}),
+		BinaryOp::Shr => bit_calc(x, y, |x, y| -> i32 {x.wrapping_shr(y as u32)}),
 		BinaryOp::LT => bin_comp(x, y, |x, y| -> bool {x < y}, |x, y| -> bool {x < y}),
 		BinaryOp::LE => bin_comp(x, y, |x, y| -> bool {x <= y}, |x, y| -> bool {x <= y}),
 		BinaryOp::GT => bin_comp(x, y, |x, y| -> bool {x > y}, |x, y| -> bool {x > y}),
@@ -77,7 +102,7 @@ pub fn exec_binaryop(x: &Value, op: BinaryOp, y: &Value) -> Result<Value> {
 		BinaryOp::NE => bin_comp(x, y, |x, y| -> bool {x != y}, |x, y| -> bool {x != y}),
 		BinaryOp::LOr => bin_comp(x, y, |x, y| -> bool {x != 0 || y != 0}, |x, y| -> bool {x != 0.0 || y != 0.0}),
 		BinaryOp::LAnd => bin_comp(x, y, |x, y| -> bool {x != 0 && y != 0}, |x, y| -> bool {x != 0.0 && y != 0.0}),
-    BinaryOp::Assign => unreachable!(),
+    _ => unreachable!(),
 	}
 }
 
@@ -95,10 +120,14 @@ where
 
 #[rustfmt::skip]
 pub fn exec_unaryop(op: UnaryOp, x: &Value) -> Result<Value> {
+	if is_array(x) {
+		return Err(TypeError("Can not do arith operation with pointer".to_string()));
+	}
 	match op {
 	  UnaryOp::Plus => una_calc(x, Value::Int ,Value::Float),
 	  UnaryOp::Neg => una_calc(x, |x| Value::Int(-x) ,|x| Value::Float(-x)),
 	  UnaryOp::BitNot => una_calc(x, |x|Value::Int(!x) ,|_| unreachable!()),
 	  UnaryOp::Not => una_calc(x, |x|Value::Int((x==0) as i32) ,|x|Value::Int((x==0.0) as i32)),
+	  _ => unreachable!(),
 	}
 }
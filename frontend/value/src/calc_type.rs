@@ -1,25 +1,47 @@
 use std::cmp::max;
 
-use utils::{errors::Result, SysycError::*};
+use utils::{
+	errors::Result,
+	SysycError::{self, *},
+};
 
 use crate::{BType, BinaryOp, VarType};
 
+fn struct_error() -> SysycError {
+	TypeError("Can not do arith operation with struct".to_string())
+}
+
+// `char` takes part in arithmetic as `int`
 fn upgrade(x: &VarType, y: &VarType) -> Result<VarType> {
-	if !x.dims.is_empty() || !y.dims.is_empty() {
+	if x.is_struct() || y.is_struct() {
+		Err(struct_error())
+	} else if !x.dims.is_empty() || !y.dims.is_empty() {
 		Err(TypeError(
 			"Can not do arith operation with pointer".to_string(),
 		))
 	} else {
 		Ok(VarType {
 			is_lval: false,
-			type_t: max(x.type_t, y.type_t),
+			type_t: max(max(x.type_t.clone(), y.type_t.clone()), BType::Int),
 			dims: Vec::new(),
 		})
 	}
 }
 
+fn bitwise(x: &VarType, y: &VarType) -> Result<VarType> {
+	let type_t = upgrade(x, y)?;
+	if type_t.type_t != BType::Int {
+		return Err(TypeError(
+			"Only integer can do bitwise operation".to_string(),
+		));
+	}
+	Ok(type_t)
+}
+
 fn to_bool(x: &VarType, y: &VarType) -> Result<VarType> {
-	if !x.dims.is_empty() || !y.dims.is_empty() {
+	if x.is_struct() || y.is_struct() {
+		Err(struct_error())
+	} else if !x.dims.is_empty() || !y.dims.is_empty() {
 		Err(TypeError(
 			"Can not do arith operation with pointer".to_string(),
 		))
@@ -42,23 +64,43 @@ pub fn type_binaryop(x: &VarType, op: BinaryOp, y: &VarType) -> Result<VarType>
       Ok(VarType{
 				is_lval: x.dims.len() == 1,
         dims: x.dims[1..].to_vec(),
-        ..*x
+        ..x.clone()
       })
 		}
     BinaryOp::Assign => {
       if !x.is_lval {
         Err(TypeError("Only lvalue can be assigned".to_string()))
+      } else if x.is_struct() || y.is_struct() {
+        Err(TypeError("Can not assign struct".to_string()))
       } else if !x.dims.is_empty() || !y.dims.is_empty() {
         Err(TypeError("Can not do assign to pointer".to_string()))
       } else {
         Ok(x.clone())
       }
     }
+		BinaryOp::AddAssign
+		| BinaryOp::SubAssign
+		| BinaryOp::MulAssign
+		| BinaryOp::DivAssign
+		| BinaryOp::ModAssign
+		| BinaryOp::BitAndAssign
+		| BinaryOp::BitOrAssign
+		| BinaryOp::BitXorAssign
+		| BinaryOp::ShlAssign
+		| BinaryOp::ShrAssign => {
+			type_binaryop(x, op.compound().unwrap(), y)?;
+			type_binaryop(x, BinaryOp::Assign, y)
+		}
 		BinaryOp::Add
 		| BinaryOp::Sub
 		| BinaryOp::Mul
 		| BinaryOp::Div
 		| BinaryOp::Mod => upgrade(x, y),
+		BinaryOp::BitAnd
+		| BinaryOp::BitOr
+		| BinaryOp::BitXor
+		| BinaryOp::Shl
+		| BinaryOp::Shr => bitwise(x, y),
 		BinaryOp::LT
 		| BinaryOp::LE
 		| BinaryOp::GT
@@ -70,6 +112,38 @@ pub fn type_binaryop(x: &VarType, op: BinaryOp, y: &VarType) -> Result<VarType>
 	}
 }
 
+/// `++` and `--` assign to their operand, which is taken as an rvalue after
+pub fn type_step(x: &VarType) -> Result<VarType> {
+	if !x.is_lval {
+		Err(TypeError(
+			"Only lvalue can be incremented or decremented".to_string(),
+		))
+	} else if x.is_struct() {
+		Err(struct_error())
+	} else if !x.dims.is_empty() {
+		Err(TypeError(
+			"Can not increment or decrement pointer".to_string(),
+		))
+	} else {
+		Ok(to_rval(x))
+	}
+}
+
+/// Both branches of `?:` are converted to the wider of them
+pub fn type_conditional(
+	cond: &VarType,
+	x: &VarType,
+	y: &VarType,
+) -> Result<VarType> {
+	if cond.is_struct() {
+		return Err(struct_error());
+	}
+	if !cond.dims.is_empty() {
+		return Err(TypeError("Can not use pointer as condition".to_string()));
+	}
+	upgrade(x, y)
+}
+
 pub fn to_rval(type_t: &VarType) -> VarType {
 	VarType {
 		is_lval: false,
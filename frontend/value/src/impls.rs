@@ -1,6 +1,8 @@
-use std::fmt::Display;
+use std::{fmt::Display, rc::Rc};
 
-use crate::{Array, BType, FuncRetType, Value, VarType};
+use crate::{
+	Array, BType, BinaryOp, Field, FuncRetType, Record, UnaryOp, Value, VarType,
+};
 use utils::{errors::Result, SysycError::TypeError};
 
 impl From<i32> for Value {
@@ -36,8 +38,10 @@ impl Value {
 			_ => Err(TypeError("try to convert pointer to int".to_string())),
 		}
 	}
-	pub fn conv_type(&self, btype: BType) -> Self {
+	pub fn conv_type(&self, btype: &BType) -> Self {
 		match (btype, self) {
+			(BType::Char, Self::Int(v)) => (*v as i8 as i32).into(),
+			(BType::Char, Self::Float(v)) => (*v as i32 as i8 as i32).into(),
 			(BType::Int, Self::Float(v)) => (*v as i32).into(),
 			(BType::Float, Self::Int(v)) => (*v as f32).into(),
 			(_, Self::Array((dims, v))) => Self::Array((
@@ -56,8 +60,12 @@ impl Value {
 	}
 	pub fn to_type(&self, btype: BType) -> Result<Self> {
 		match btype {
+			BType::Char => Ok((self.to_int()? as i8 as i32).into()),
 			BType::Int => Ok(self.to_int()?.into()),
 			BType::Float => Ok(self.to_float()?.into()),
+			BType::Struct(_) => {
+				Err(TypeError("try to convert a value to struct".to_string()))
+			}
 		}
 	}
 }
@@ -101,15 +109,85 @@ impl VarType {
 	pub fn is_array(&self) -> bool {
 		!self.dims.is_empty()
 	}
+	pub fn is_struct(&self) -> bool {
+		self.dims.is_empty() && matches!(self.type_t, BType::Struct(_))
+	}
+	/// Arrays and structs live in memory, and are initialized by lists
+	pub fn is_aggregate(&self) -> bool {
+		self.is_array() || self.is_struct()
+	}
 	pub fn size(&self) -> i32 {
 		self.dims.iter().map(|v| *v as i32).product::<i32>() * self.type_t.size()
 	}
+	fn elem(&self) -> VarType {
+		VarType {
+			dims: self.dims[1..].to_vec(),
+			..self.clone()
+		}
+	}
+	/// The number of scalars in a variable of this type, each takes a slot of
+	/// an initializer list in turn
+	pub fn slots(&self) -> usize {
+		self.dims.iter().product::<usize>() * self.type_t.slots()
+	}
+	/// The first element or field that starts at `slot` or after, with the
+	/// slot it starts at
+	pub fn member_at(&self, slot: usize) -> Option<(usize, VarType)> {
+		if self.is_array() {
+			let elem = self.elem();
+			let len = elem.slots();
+			let index = slot.div_ceil(len.max(1));
+			return (index < self.dims[0]).then(|| (index * len, elem));
+		}
+		let BType::Struct(record) = &self.type_t else {
+			return None;
+		};
+		let mut start = 0;
+		for field in record.fields.iter() {
+			if start >= slot {
+				return Some((start, field.var_type.clone()));
+			}
+			start += field.var_type.slots();
+		}
+		None
+	}
+	/// The offset in bytes and the type of the scalar in `slot`
+	pub fn scalar_at(&self, slot: usize) -> (i32, VarType) {
+		if self.is_array() {
+			let elem = self.elem();
+			let len = elem.slots();
+			let (offset, type_t) = elem.scalar_at(slot % len);
+			return ((slot / len) as i32 * elem.size() + offset, type_t);
+		}
+		let BType::Struct(record) = &self.type_t else {
+			return (0, self.clone());
+		};
+		let mut slot = slot;
+		for field in record.fields.iter() {
+			let len = field.var_type.slots();
+			if slot < len {
+				let (offset, type_t) = field.var_type.scalar_at(slot);
+				return (field.offset + offset, type_t);
+			}
+			slot -= len;
+		}
+		unreachable!()
+	}
+}
+
+impl Display for BType {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Struct(record) => write!(f, "struct {}", record.ident),
+			_ => write!(f, "{:?}", self),
+		}
+	}
 }
 
 impl Display for VarType {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		if self.dims.is_empty() {
-			write!(f, "{:?}", self.type_t)
+			write!(f, "{}", self.type_t)
 		} else {
 			let v = self
 				.dims
@@ -118,7 +196,7 @@ impl Display for VarType {
 				.map(|v| format!("[{}]", v))
 				.collect::<Vec<_>>()
 				.join("");
-			write!(f, "{:?} (*){}", self.type_t, v)
+			write!(f, "{} (*){}", self.type_t, v)
 		}
 	}
 }
@@ -126,14 +204,109 @@ impl Display for VarType {
 impl BType {
 	pub fn size(&self) -> i32 {
 		match self {
+			Self::Char => 1,
 			Self::Int => 4,
 			Self::Float => 4,
+			Self::Struct(record) => record.size,
+		}
+	}
+	/// The alignment of a variable of this type in bytes
+	pub fn align(&self) -> i32 {
+		match self {
+			Self::Struct(record) => record.align(),
+			_ => self.size(),
+		}
+	}
+	fn slots(&self) -> usize {
+		match self {
+			Self::Struct(record) => {
+				record.fields.iter().map(|v| v.var_type.slots()).sum()
+			}
+			_ => 1,
 		}
 	}
 	pub fn to_value(&self) -> Value {
 		match self {
-			Self::Int => 0.into(),
+			Self::Char | Self::Int => 0.into(),
 			Self::Float => (0.0).into(),
+			Self::Struct(_) => unreachable!(),
 		}
 	}
 }
+
+impl Record {
+	/// The `struct` named `ident` before its fields are known
+	pub fn named(ident: &str) -> Rc<Self> {
+		Rc::new(Self {
+			ident: ident.to_string(),
+			fields: Vec::new(),
+			size: 0,
+		})
+	}
+	pub fn new(ident: &str, fields: Vec<(String, VarType)>) -> Self {
+		let mut size = 0;
+		let fields = fields
+			.into_iter()
+			.map(|(ident, var_type)| {
+				let align = var_type.type_t.align();
+				let offset = (size + align - 1) / align * align;
+				size = offset + var_type.size();
+				Field {
+					ident,
+					var_type,
+					offset,
+				}
+			})
+			.collect();
+		let mut record = Self {
+			ident: ident.to_string(),
+			fields,
+			size,
+		};
+		let align = record.align();
+		record.size = (size + align - 1) / align * align;
+		record
+	}
+	pub fn align(&self) -> i32 {
+		self.fields.iter().map(|v| v.var_type.type_t.align()).max().unwrap_or(1)
+	}
+	pub fn get_field(&self, ident: &str) -> Option<&Field> {
+		self.fields.iter().find(|v| v.ident == ident)
+	}
+}
+
+impl BinaryOp {
+	/// The operation a compound assignment does before assigning
+	pub fn compound(&self) -> Option<BinaryOp> {
+		match self {
+			Self::AddAssign => Some(Self::Add),
+			Self::SubAssign => Some(Self::Sub),
+			Self::MulAssign => Some(Self::Mul),
+			Self::DivAssign => Some(Self::Div),
+			Self::ModAssign => Some(Self::Mod),
+			Self::BitAndAssign => Some(Self::BitAnd),
+			Self::BitOrAssign => Some(Self::BitOr),
+			Self::BitXorAssign => Some(Self::BitXor),
+			Self::ShlAssign => Some(Self::Shl),
+			Self::ShrAssign => Some(Self::Shr),
+			_ => None,
+		}
+	}
+	pub fn is_assign(&self) -> bool {
+		*self == Self::Assign || self.compound().is_some()
+	}
+}
+
+impl UnaryOp {
+	/// The operation `++` or `--` does to its operand before assigning
+	pub fn step(&self) -> Option<BinaryOp> {
+		match self {
+			Self::PreInc | Self::PostInc => Some(BinaryOp::Add),
+			Self::PreDec | Self::PostDec => Some(BinaryOp::Sub),
+			_ => None,
+		}
+	}
+	pub fn is_postfix(&self) -> bool {
+		matches!(self, Self::PostInc | Self::PostDec)
+	}
+}
@@ -1,12 +1,35 @@
+use std::rc::Rc;
+
 pub mod calc;
 pub mod calc_type;
 pub mod impls;
 pub mod utils;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BType {
+	Char,
 	Int,
 	Float,
+	/// A `struct`, which the parser only knows by name, its fields are filled
+	/// in by the namer
+	Struct(Rc<Record>),
+}
+
+/// A field of a `struct`, `offset` bytes after its start
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Field {
+	pub ident: String,
+	pub var_type: VarType,
+	pub offset: i32,
+}
+
+/// The layout of a `struct`, each field is aligned to the size of its
+/// scalars and the whole to the largest of them
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Record {
+	pub ident: String,
+	pub fields: Vec<Field>,
+	pub size: i32,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
@@ -17,7 +40,7 @@ pub enum FuncRetType {
 	Void,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct VarType {
 	pub is_lval: bool,
 	pub type_t: BType,
@@ -37,11 +60,26 @@ pub enum Value {
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum BinaryOp {
 	Assign,
+	AddAssign,
+	SubAssign,
+	MulAssign,
+	DivAssign,
+	ModAssign,
+	BitAndAssign,
+	BitOrAssign,
+	BitXorAssign,
+	ShlAssign,
+	ShrAssign,
 	Add,
 	Sub,
 	Mul,
 	Div,
 	Mod,
+	BitAnd,
+	BitOr,
+	BitXor,
+	Shl,
+	Shr,
 	LT,
 	LE,
 	GE,
@@ -59,4 +97,8 @@ pub enum UnaryOp {
 	Neg,
 	Not,
 	BitNot,
+	PreInc,
+	PreDec,
+	PostInc,
+	PostDec,
 }
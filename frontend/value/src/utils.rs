@@ -1,21 +1,47 @@
 use utils::ValueItem::{self, *};
 
-use crate::Value;
+use crate::{BType, Value, VarType};
 
-pub fn to_data(value: Value) -> Vec<ValueItem> {
-	match value {
-		Value::Int(0) => vec![ValueItem::Zero(4)],
-		Value::Int(v) => vec![ValueItem::Word(v as u32)],
-		Value::Float(v) => vec![ValueItem::Word(v.to_bits())],
-		Value::Array((_index, arr)) => {
-			arr.into_iter().flat_map(to_data).fold(Vec::new(), |mut acc, v| {
-				if let (Some(Zero(last)), Zero(now)) = (acc.last_mut(), &v) {
-					*last += *now;
-				} else {
-					acc.push(v);
-				}
-				acc
-			})
+/// The data of a global variable of base type `type_t`, the value of a
+/// `struct` holds those of its scalars in turn
+pub fn to_data(value: Value, type_t: &BType) -> Vec<ValueItem> {
+	let data: Vec<_> = match (value.conv_type(type_t), type_t) {
+		(Value::Array((_index, arr)), BType::Struct(_)) => {
+			let record = VarType::from((false, type_t.clone(), &Vec::new()));
+			arr.chunks(record.slots()).flat_map(|v| record_data(v, &record)).collect()
 		}
+		(Value::Array((_index, arr)), _) => {
+			arr.into_iter().flat_map(|v| to_data(v, type_t)).collect()
+		}
+		(Value::Int(0), _) => vec![ValueItem::Zero(type_t.size() as usize)],
+		(Value::Int(v), BType::Char) => vec![ValueItem::Byte(v as u8)],
+		(Value::Int(v), _) => vec![ValueItem::Word(v as u32)],
+		(Value::Float(v), _) => vec![ValueItem::Word(v.to_bits())],
+	};
+	data.into_iter().fold(Vec::new(), |mut acc, v| {
+		if let (Some(Zero(last)), Zero(now)) = (acc.last_mut(), &v) {
+			*last += *now;
+		} else {
+			acc.push(v);
+		}
+		acc
+	})
+}
+
+// the scalars of a `struct`, with the padding between them
+fn record_data(values: &[Value], record: &VarType) -> Vec<ValueItem> {
+	let mut data = Vec::new();
+	let mut end = 0;
+	for (slot, value) in values.iter().enumerate() {
+		let (offset, type_t) = record.scalar_at(slot);
+		if offset > end {
+			data.push(Zero((offset - end) as usize));
+		}
+		data.extend(to_data(value.clone(), &type_t.type_t));
+		end = offset + type_t.size();
+	}
+	if record.size() > end {
+		data.push(Zero((record.size() - end) as usize));
 	}
+	data
 }
@@ -0,0 +1,338 @@
+use std::collections::{HashMap, HashSet};
+
+use llvm::{ArithOp, CallInstr, LlvmInstrVariant, LlvmTemp, Value};
+use rrvm::program::LlvmFunc;
+
+use crate::{
+	loops::{indvar_type::IndVarType, loop_data::LoopData},
+	metadata::{syscall_ident, MetaData, VarIdent},
+};
+
+use super::{
+	AliasAnalysis, AliasResult, Location, ModRef, Object, Offset, Symbol,
+};
+
+/// 通过 `addr` 访存的宽度：`char` 数组按字节访问，`int` 和 `float` 是 4 字节
+pub fn access_size(addr: &Value) -> i64 {
+	addr.get_type().get_size() as i64
+}
+
+impl Offset {
+	pub fn constant(value: i64) -> Self {
+		Self {
+			constant: value,
+			terms: HashMap::new(),
+		}
+	}
+	pub fn symbol(symbol: Symbol) -> Self {
+		Self {
+			constant: 0,
+			terms: HashMap::from([(symbol, 1)]),
+		}
+	}
+	/// `self + coef * other`
+	pub fn add(mut self, other: &Self, coef: i64) -> Self {
+		self.constant =
+			self.constant.wrapping_add(other.constant.wrapping_mul(coef));
+		for (symbol, value) in other.terms.iter() {
+			let entry = self.terms.entry(symbol.clone()).or_default();
+			*entry = entry.wrapping_add(value.wrapping_mul(coef));
+		}
+		self.terms.retain(|_, v| *v != 0);
+		self
+	}
+	pub fn scale(self, coef: i64) -> Self {
+		Self::default().add(&self, coef)
+	}
+	pub fn as_constant(&self) -> Option<i64> {
+		self.terms.is_empty().then_some(self.constant)
+	}
+}
+
+impl ModRef {
+	pub fn may_mod(self) -> bool {
+		matches!(self, ModRef::Mod | ModRef::Both)
+	}
+	pub fn may_ref(self) -> bool {
+		matches!(self, ModRef::Ref | ModRef::Both)
+	}
+	fn from_flags(refs: bool, mods: bool) -> Self {
+		match (refs, mods) {
+			(false, false) => ModRef::Neither,
+			(true, false) => ModRef::Ref,
+			(false, true) => ModRef::Mod,
+			(true, true) => ModRef::Both,
+		}
+	}
+}
+
+/// 两个对象是否可能是同一块内存
+fn may_overlap(x: &Object, y: &Object) -> bool {
+	match (x, y) {
+		(Object::Unknown, _) | (_, Object::Unknown) => true,
+		(Object::Global(x), Object::Global(y)) => x == y,
+		(Object::Alloc(x), Object::Alloc(y)) => x == y,
+		// 调用者可能传入全局变量，或把同一个数组传给两个参数
+		(Object::Param(_), Object::Param(_))
+		| (Object::Param(_), Object::Global(_))
+		| (Object::Global(_), Object::Param(_)) => true,
+		// 局部数组只能通过参数传给被调用的函数
+		_ => false,
+	}
+}
+
+impl<'a> AliasAnalysis<'a> {
+	pub fn new(func: &LlvmFunc, metadata: &'a MetaData) -> Self {
+		let mut defs = HashMap::new();
+		let mut phis = HashMap::new();
+		for block in func.cfg.blocks.iter() {
+			let block = block.borrow();
+			for phi in block.phi_instrs.iter() {
+				let source = phi.source.iter().map(|(v, _)| v.clone()).collect();
+				phis.insert(phi.target.clone(), source);
+			}
+			for instr in block.instrs.iter() {
+				if let Some(target) = instr.get_write() {
+					defs.insert(target, instr.clone());
+				}
+			}
+		}
+		let params = func
+			.params
+			.iter()
+			.enumerate()
+			.filter_map(|(index, v)| v.unwrap_temp().map(|v| (v, index)))
+			.collect();
+		Self {
+			metadata,
+			defs,
+			phis,
+			params,
+			indvars: HashMap::new(),
+		}
+	}
+
+	/// 使用循环分析得到的归纳变量，偏移中会出现 `Symbol::Iteration`
+	pub fn add_indvars(&mut self, loopdata: &LoopData) {
+		for (temp, indvar) in loopdata.indvars.iter() {
+			let Some(node) = loopdata.def_map.get(temp) else {
+				continue;
+			};
+			let Some(loop_) = loopdata.loop_map.get(&node.borrow().id) else {
+				continue;
+			};
+			self.indvars.insert(temp.clone(), (indvar.clone(), loop_.borrow().id));
+		}
+	}
+
+	/// 把地址表示为某个对象加上偏移
+	pub fn locate(&self, addr: &Value) -> Location {
+		let mut visited = HashSet::new();
+		addr
+			.unwrap_temp()
+			.and_then(|temp| self.locate_temp(&temp, &mut visited))
+			.unwrap_or(Location {
+				object: Object::Unknown,
+				offset: None,
+			})
+	}
+
+	// 返回 None 表示沿着 phi 回到了正在求的地址
+	fn locate_temp(
+		&self,
+		temp: &LlvmTemp,
+		visited: &mut HashSet<LlvmTemp>,
+	) -> Option<Location> {
+		if !visited.insert(temp.clone()) {
+			return None;
+		}
+		let at = |object| Location {
+			object,
+			offset: Some(Offset::default()),
+		};
+		let unknown = Location {
+			object: Object::Unknown,
+			offset: None,
+		};
+		if temp.is_global {
+			return Some(at(Object::Global(temp.name.clone())));
+		}
+		if let Some(index) = self.params.get(temp) {
+			return Some(at(Object::Param(*index)));
+		}
+		if let Some(source) = self.phis.get(temp) {
+			// 所有来源都指向同一个对象时，phi 也指向它
+			let mut object = None;
+			for value in source.iter() {
+				let Some(temp) = value.unwrap_temp() else {
+					return Some(unknown);
+				};
+				let Some(location) = self.locate_temp(&temp, visited) else {
+					continue;
+				};
+				match &object {
+					None => object = Some(location.object),
+					Some(v) if *v == location.object => {}
+					_ => return Some(unknown),
+				}
+			}
+			let object = object?;
+			let offset = self.indvar_offset(temp, |base| {
+				let location = self.locate(base);
+				(location.object == object).then_some(location.offset).flatten()
+			});
+			return Some(Location { object, offset });
+		}
+		match self.defs.get(temp).map(|v| v.get_variant()) {
+			Some(LlvmInstrVariant::AllocInstr(_)) => {
+				Some(at(Object::Alloc(temp.clone())))
+			}
+			// 从全局变量 load 得到的是其地址
+			Some(LlvmInstrVariant::LoadInstr(instr)) if instr.addr.is_global() => {
+				Some(at(Object::Global(instr.addr.unwrap_temp().unwrap().name)))
+			}
+			Some(LlvmInstrVariant::GEPInstr(instr)) => {
+				let Some(base) = instr.addr.unwrap_temp() else {
+					return Some(unknown);
+				};
+				let location = self.locate_temp(&base, visited)?;
+				let offset = location
+					.offset
+					.zip(self.offset_of(&instr.offset))
+					.map(|(x, y)| x.add(&y, 1));
+				Some(Location {
+					object: location.object,
+					offset,
+				})
+			}
+			_ => Some(unknown),
+		}
+	}
+
+	/// 步长为常数的一阶归纳变量：`base + step * Iteration`
+	fn indvar_offset(
+		&self,
+		temp: &LlvmTemp,
+		base_offset: impl FnOnce(&Value) -> Option<Offset>,
+	) -> Option<Offset> {
+		let (indvar, id) = self.indvars.get(temp)?;
+		if indvar.get_type() != IndVarType::Ordinary {
+			return None;
+		}
+		let Value::Int(step) = indvar.step[0] else {
+			return None;
+		};
+		let base = base_offset(&indvar.base)?;
+		Some(base.add(&Offset::symbol(Symbol::Iteration(*id)), step as i64))
+	}
+
+	/// 把整数表示为线性表达式
+	pub fn offset_of(&self, value: &Value) -> Option<Offset> {
+		let temp = match value {
+			Value::Int(v) => return Some(Offset::constant(*v as i64)),
+			Value::Float(_) => return None,
+			Value::Temp(temp) => temp,
+		};
+		if let Some(LlvmInstrVariant::ArithInstr(instr)) =
+			self.defs.get(temp).map(|v| v.get_variant())
+		{
+			let lhs = self.offset_of(&instr.lhs)?;
+			let rhs = self.offset_of(&instr.rhs)?;
+			let linear = match instr.op {
+				ArithOp::Add | ArithOp::AddD => Some(lhs.add(&rhs, 1)),
+				ArithOp::Sub | ArithOp::SubD => Some(lhs.add(&rhs, -1)),
+				ArithOp::Mul | ArithOp::MulD => {
+					match (lhs.as_constant(), rhs.as_constant()) {
+						(Some(v), _) => Some(rhs.scale(v)),
+						(_, Some(v)) => Some(lhs.scale(v)),
+						_ => None,
+					}
+				}
+				ArithOp::Shl | ArithOp::ShlD => match rhs.as_constant() {
+					Some(v @ 0..=31) => Some(lhs.scale(1 << v)),
+					_ => None,
+				},
+				_ => None,
+			};
+			if linear.is_some() {
+				return linear;
+			}
+		}
+		self
+			.indvar_offset(temp, |base| self.offset_of(base))
+			.or_else(|| Some(Offset::symbol(Symbol::Temp(temp.clone()))))
+	}
+
+	/// 只看两个地址所在的对象
+	pub fn object_alias(&self, x: &Value, y: &Value) -> AliasResult {
+		if may_overlap(&self.locate(x).object, &self.locate(y).object) {
+			AliasResult::May
+		} else {
+			AliasResult::No
+		}
+	}
+
+	/// 两个地址在同一时刻（同一次迭代中）是否指向同一位置
+	pub fn alias(&self, x: &Value, y: &Value) -> AliasResult {
+		if x == y {
+			return AliasResult::Must;
+		}
+		let (x_size, y_size) = (access_size(x), access_size(y));
+		let (x, y) = (self.locate(x), self.locate(y));
+		if !may_overlap(&x.object, &y.object) {
+			return AliasResult::No;
+		}
+		if x.object != y.object {
+			return AliasResult::May;
+		}
+		let difference = x.offset.zip(y.offset).map(|(x, y)| x.add(&y, -1));
+		match difference.and_then(|v| v.as_constant()) {
+			Some(0) if x_size == y_size => AliasResult::Must,
+			// 一个访存区间在另一个之后
+			Some(v) if v >= y_size || -v >= x_size => AliasResult::No,
+			_ => AliasResult::May,
+		}
+	}
+
+	/// 调用是否可能读写 `addr` 指向的位置
+	pub fn call_mod_ref(&self, call: &CallInstr, addr: &Value) -> ModRef {
+		let name = &call.func.name;
+		let Some(data) = self.metadata.func_data.get(name) else {
+			return ModRef::Both;
+		};
+		if data.pure {
+			return ModRef::Neither;
+		}
+		let (mut refs, mut mods) = (false, false);
+		match self.locate(addr).object {
+			Object::Global(var) => {
+				refs |= data.may_load(&(var.clone(), 0));
+				mods |= data.may_store(&(var, 0));
+			}
+			Object::Alloc(_) => {}
+			// 可能是调用者传入的全局变量
+			Object::Param(_) | Object::Unknown => {
+				let named =
+					|set: &HashSet<VarIdent>| set.iter().any(|v| *v != syscall_ident());
+				refs |= named(&data.usage_info.may_loads);
+				mods |= named(&data.usage_info.may_stores);
+			}
+		}
+		for (index, (var_type, value)) in call.params.iter().enumerate() {
+			if !var_type.is_ptr() || self.object_alias(value, addr) == AliasResult::No
+			{
+				continue;
+			}
+			match self.metadata.var_data.get(&(name.clone(), index)) {
+				Some(var_data) => {
+					refs |= var_data.to_load;
+					mods |= var_data.to_store;
+				}
+				// 没有分析过参数的内建函数
+				None if data.may_store(&syscall_ident()) => return ModRef::Both,
+				None => {}
+			}
+		}
+		ModRef::from_flags(refs, mods)
+	}
+}
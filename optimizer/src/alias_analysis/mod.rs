@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use llvm::{LlvmInstr, LlvmTemp, Value};
+
+use crate::{loops::indvar::IndVar, metadata::MetaData};
+
+pub mod impls;
+
+/// Whether two addresses may refer to the same memory
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AliasResult {
+	No,
+	May,
+	Must,
+}
+
+/// What a call may do to a memory location
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModRef {
+	Neither,
+	Ref,
+	Mod,
+	Both,
+}
+
+/// The object an address points into
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Object {
+	Global(String),
+	Alloc(LlvmTemp),
+	// 函数的第几个参数
+	Param(usize),
+	Unknown,
+}
+
+/// A variable an offset depends on
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Symbol {
+	Temp(LlvmTemp),
+	// 所在循环（以 id 表示）已经执行的迭代次数
+	Iteration(i32),
+}
+
+/// `constant + sum(coef * symbol)`, in bytes
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Offset {
+	pub constant: i64,
+	pub terms: HashMap<Symbol, i64>,
+}
+
+/// An address as an offset into an object, `offset` is `None` if unknown
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+	pub object: Object,
+	pub offset: Option<Offset>,
+}
+
+pub struct AliasAnalysis<'a> {
+	pub metadata: &'a MetaData,
+	// 定义每个临时变量的指令，不含 phi
+	defs: HashMap<LlvmTemp, LlvmInstr>,
+	phis: HashMap<LlvmTemp, Vec<Value>>,
+	params: HashMap<LlvmTemp, usize>,
+	// 每个归纳变量及其所在循环
+	indvars: HashMap<LlvmTemp, (IndVar, i32)>,
+}
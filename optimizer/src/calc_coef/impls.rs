@@ -111,14 +111,16 @@ fn can_calc(func: &LlvmFunc, metadata: &mut MetaData) -> bool {
 	let func_data = metadata.get_func_data(&func.name);
 	let mut params_data = Vec::new();
 	for call_instr in call_selfs.iter() {
-		let nums = call_instr
+		// 编号由 gvn 给出
+		let Some(nums) = call_instr
 			.params
 			.iter()
 			.filter(|(_, val)| matches!(val, Value::Temp(_)))
-			.map(|(_, val)| {
-				func_data.get_number(&val.unwrap_temp().unwrap()).unwrap()
-			})
-			.collect::<Vec<_>>();
+			.map(|(_, val)| func_data.get_number(&val.unwrap_temp().unwrap()))
+			.collect::<Option<Vec<_>>>()
+		else {
+			return false;
+		};
 		if params_data.is_empty() {
 			params_data = nums.to_vec();
 		} else if !nums.iter().zip(params_data.iter()).any(|(a, b)| a == b) {
@@ -691,14 +693,14 @@ fn calc_coef(
 				if callinstr.func.name == func.name {
 					let (_ty, recurse_tmp) = callinstr.params[index_pos].clone();
 					if let Value::Temp(t) = recurse_tmp {
-						if recurse_num.is_none() {
-							recurse_num = Some(t);
-						} else {
-							// t 和 recurse_num 不能相等
-							if funcdata.get_number(&t).unwrap()
-								!= funcdata.get_number(&recurse_num.clone().unwrap()).unwrap()
-							{
-								return vec![copied_func];
+						match &recurse_num {
+							None => recurse_num = Some(t),
+							Some(prev) => {
+								// t 和 recurse_num 不能相等
+								let number = funcdata.get_number(&t);
+								if number.is_none() || number != funcdata.get_number(prev) {
+									return vec![copied_func];
+								}
 							}
 						}
 					}
@@ -30,6 +30,7 @@ impl NodeInfo {
 
 struct Solver {
 	dom_tree: LlvmDomTree,
+	post_dom_tree: LlvmDomTree,
 	stack: Vec<NodeInfo>,
 }
 
@@ -37,6 +38,7 @@ impl Solver {
 	pub fn new(func: &LlvmFunc) -> Self {
 		Self {
 			dom_tree: LlvmDomTree::new(&func.cfg, false),
+			post_dom_tree: LlvmDomTree::new(&func.cfg, true),
 			stack: Vec::new(),
 		}
 	}
@@ -63,11 +65,22 @@ impl Solver {
 
 	fn hoist(&mut self, node: &LlvmNode) -> bool {
 		let mut flag = false;
+		let id = node.borrow().id;
 		let node_weight = node.borrow().weight;
+		// only move to blocks that always go on to this one, so that no path
+		// runs an instruction it would not run before
+		let always: Vec<_> = self
+			.stack
+			.iter()
+			.map(|info| self.post_dom_tree.dominates(id, info.node.borrow().id))
+			.collect();
 		node.borrow_mut().instrs.retain(|instr| {
 			let mut best_node: Option<LlvmNode> = None;
 			let mut best_weight = node_weight * 0.99;
-			for info in self.stack.iter_mut().rev() {
+			for (info, always) in self.stack.iter_mut().zip(always.iter()).rev() {
+				if !always {
+					break;
+				}
 				match instr.get_variant() {
 					LoadInstr(instr) if !instr.addr.unwrap_temp().unwrap().is_global => {}
 					StoreInstr(_) => {}
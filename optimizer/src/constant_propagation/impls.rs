@@ -0,0 +1,280 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use llvm::{
+	compare_two_value, compute_two_value, convert_value, ArithInstr, ArithOp::*,
+	CompInstr, ConvertInstr, LlvmInstrVariant, LlvmTemp, LlvmTempManager, Value,
+	VarType,
+};
+use rrvm::{
+	program::{LlvmFunc, LlvmProgram},
+	LlvmNode,
+};
+use utils::{Result, UseTemp};
+
+use crate::{metadata::MetaData, RrvmOptimizer};
+
+use super::ConstantPropagation;
+
+#[derive(Clone, Debug)]
+enum Lattice {
+	/// not known to be computed yet
+	Undef,
+	Const(Value),
+	Overdefined,
+}
+
+use Lattice::*;
+
+fn same_value(x: &Value, y: &Value) -> bool {
+	match (x, y) {
+		(Value::Int(x), Value::Int(y)) => x == y,
+		// `0.0` and `-0.0` are different constants
+		(Value::Float(x), Value::Float(y)) => x.to_bits() == y.to_bits(),
+		_ => false,
+	}
+}
+
+impl Lattice {
+	fn meet(&self, other: &Lattice) -> Lattice {
+		match (self, other) {
+			(Undef, v) | (v, Undef) => v.clone(),
+			(Const(x), Const(y)) if same_value(x, y) => self.clone(),
+			_ => Overdefined,
+		}
+	}
+	fn is_same(&self, other: &Lattice) -> bool {
+		match (self, other) {
+			(Undef, Undef) | (Overdefined, Overdefined) => true,
+			(Const(x), Const(y)) => same_value(x, y),
+			_ => false,
+		}
+	}
+}
+
+struct Solver<'a> {
+	values: HashMap<LlvmTemp, Lattice>,
+	/// temps defined in the function, the others (parameters and globals) are
+	/// overdefined
+	defined: HashSet<LlvmTemp>,
+	/// the blocks reading each temp
+	users: HashMap<LlvmTemp, Vec<LlvmNode>>,
+	/// executable edges, by the ids of the blocks
+	edges: HashSet<(i32, i32)>,
+	reached: HashSet<i32>,
+	worklist: VecDeque<LlvmNode>,
+	mgr: &'a mut LlvmTempManager,
+}
+
+impl<'a> Solver<'a> {
+	pub fn new(func: &LlvmFunc, mgr: &'a mut LlvmTempManager) -> Self {
+		let mut defined = HashSet::new();
+		let mut users: HashMap<_, Vec<_>> = HashMap::new();
+		for node in func.cfg.blocks.iter() {
+			let block = node.borrow();
+			let mut reads = Vec::new();
+			for instr in block.phi_instrs.iter() {
+				defined.insert(instr.target.clone());
+				reads.extend(instr.get_read());
+			}
+			for instr in block.instrs.iter() {
+				defined.extend(instr.get_write());
+				reads.extend(instr.get_read());
+			}
+			reads.extend(block.jump_instr.iter().flat_map(|v| v.get_read()));
+			for temp in reads {
+				users.entry(temp).or_default().push(node.clone());
+			}
+		}
+		Self {
+			values: HashMap::new(),
+			defined,
+			users,
+			edges: HashSet::new(),
+			reached: HashSet::new(),
+			worklist: VecDeque::new(),
+			mgr,
+		}
+	}
+
+	fn get(&self, value: &Value) -> Lattice {
+		match value {
+			Value::Temp(temp) if !self.defined.contains(temp) => Overdefined,
+			Value::Temp(temp) => self.values.get(temp).cloned().unwrap_or(Undef),
+			value => Const(value.clone()),
+		}
+	}
+
+	fn set(&mut self, temp: LlvmTemp, value: Lattice) {
+		let old = self.values.get(&temp).cloned().unwrap_or(Undef);
+		// values only go down, which makes the solver stop
+		let value = old.meet(&value);
+		if !old.is_same(&value) {
+			for node in self.users.get(&temp).into_iter().flatten() {
+				if self.reached.contains(&node.borrow().id) {
+					self.worklist.push_back(node.clone());
+				}
+			}
+			self.values.insert(temp, value);
+		}
+	}
+
+	fn arith(&mut self, instr: &ArithInstr) -> Lattice {
+		// the 64-bit and bit-counting ops are not folded
+		let wide = matches!(instr.op, AddD | SubD | MulD | DivD | RemD)
+			|| matches!(instr.op, ShlD | LshrD | AshrD | MinD | MaxD);
+		let foldable = !wide && !matches!(instr.op, Clz | ClzD | Ctz | CtzD);
+		if !foldable || !matches!(instr.var_type, VarType::I32 | VarType::F32) {
+			return Overdefined;
+		}
+		match (self.get(&instr.lhs), self.get(&instr.rhs)) {
+			(Overdefined, _) | (_, Overdefined) => Overdefined,
+			// a division by zero is left for the program to run into
+			(Const(_), Const(Value::Int(0))) if matches!(instr.op, Div | Rem) => {
+				Overdefined
+			}
+			(Const(x), Const(y)) => {
+				Const(compute_two_value(x, y, instr.op, self.mgr).0)
+			}
+			_ => Undef,
+		}
+	}
+
+	fn comp(&self, instr: &CompInstr) -> Lattice {
+		match (self.get(&instr.lhs), self.get(&instr.rhs)) {
+			(Overdefined, _) | (_, Overdefined) => Overdefined,
+			(Const(x), Const(y)) => {
+				compare_two_value(&x, &y, instr.op).map_or(Overdefined, Const)
+			}
+			_ => Undef,
+		}
+	}
+
+	fn convert(&self, instr: &ConvertInstr) -> Lattice {
+		match self.get(&instr.lhs) {
+			Const(x) => convert_value(&x, instr.op).map_or(Overdefined, Const),
+			value => value,
+		}
+	}
+
+	fn visit(&mut self, node: &LlvmNode) {
+		let block = node.borrow();
+		let preds: HashMap<_, _> =
+			block.prev.iter().map(|v| (v.borrow().label(), v.borrow().id)).collect();
+		for instr in block.phi_instrs.iter() {
+			let mut value = Undef;
+			for (source, label) in instr.source.iter() {
+				// only the values from executable edges count
+				if preds
+					.get(label)
+					.is_some_and(|id| self.edges.contains(&(*id, block.id)))
+				{
+					value = value.meet(&self.get(source));
+				}
+			}
+			self.set(instr.target.clone(), value);
+		}
+		for instr in block.instrs.iter() {
+			let value = match instr.get_variant() {
+				LlvmInstrVariant::ArithInstr(instr) => self.arith(instr),
+				LlvmInstrVariant::CompInstr(instr) => self.comp(instr),
+				LlvmInstrVariant::ConvertInstr(instr) => self.convert(instr),
+				_ => Overdefined,
+			};
+			if let Some(target) = instr.get_write() {
+				self.set(target, value);
+			}
+		}
+		let jump = block.jump_instr.as_ref().unwrap();
+		let targets: Vec<_> = match jump.get_variant() {
+			LlvmInstrVariant::JumpCondInstr(instr) => match self.get(&instr.cond) {
+				Const(cond) => {
+					let label = if cond.always_false() {
+						&instr.target_false
+					} else {
+						&instr.target_true
+					};
+					let succ = block.succ.iter().filter(|v| v.borrow().label() == *label);
+					succ.cloned().collect()
+				}
+				Overdefined => block.succ.clone(),
+				Undef => Vec::new(),
+			},
+			_ => block.succ.clone(),
+		};
+		for succ in targets {
+			let id = succ.borrow().id;
+			if self.edges.insert((block.id, id)) {
+				self.reached.insert(id);
+				self.worklist.push_back(succ);
+			}
+		}
+	}
+
+	pub fn solve(&mut self, entry: LlvmNode) {
+		self.reached.insert(entry.borrow().id);
+		self.worklist.push_back(entry);
+		while let Some(node) = self.worklist.pop_front() {
+			self.visit(&node);
+		}
+	}
+}
+
+impl RrvmOptimizer for ConstantPropagation {
+	fn new() -> Self {
+		Self {}
+	}
+	fn apply(
+		self,
+		program: &mut LlvmProgram,
+		_metadata: &mut MetaData,
+	) -> Result<bool> {
+		let mut flag = false;
+		for func in program.funcs.iter_mut() {
+			let mut solver = Solver::new(func, &mut program.temp_mgr);
+			solver.solve(func.cfg.get_entry());
+			let consts: HashMap<_, _> = solver
+				.values
+				.into_iter()
+				.filter_map(|(temp, value)| match value {
+					Const(value) => Some((temp, value)),
+					_ => None,
+				})
+				.collect();
+			let reached = solver.reached;
+			let size = func.cfg.size();
+			func.cfg.blocks.retain(|v| {
+				reached.contains(&v.borrow().id) || {
+					v.borrow_mut().clear();
+					false
+				}
+			});
+			flag |= size != func.cfg.size() || !consts.is_empty();
+			for node in func.cfg.blocks.iter() {
+				let mut block = node.borrow_mut();
+				block.phi_instrs.retain(|v| !consts.contains_key(&v.target));
+				block.instrs.retain(|v| {
+					v.get_write().map_or(true, |temp| !consts.contains_key(&temp))
+				});
+				block.map_temp(&consts);
+				drop(block);
+				// remove the branches never taken
+				let new_jump = node.borrow().jump_instr.as_ref().unwrap().new_jump();
+				if let Some(instr) = new_jump {
+					flag = true;
+					let succ: Vec<_> = node
+						.borrow()
+						.succ
+						.iter()
+						.filter(|v| v.borrow().label() == instr.target)
+						.take(1)
+						.cloned()
+						.collect();
+					node.borrow_mut().succ = succ;
+					node.borrow_mut().set_jump(Some(Box::new(instr)));
+				}
+			}
+			func.cfg.resolve_prev();
+		}
+		Ok(flag)
+	}
+}
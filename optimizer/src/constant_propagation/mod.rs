@@ -0,0 +1,3 @@
+pub mod impls;
+
+pub struct ConstantPropagation {}
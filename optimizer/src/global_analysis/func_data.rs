@@ -11,7 +11,7 @@ use rrvm::{
 
 use llvm::LlvmInstrVariant::*;
 
-use crate::metadata::{MetaData, UsageInfo, VarIdent};
+use crate::metadata::{syscall_ident, MetaData, UsageInfo, VarIdent};
 
 use super::{impls::BUILTIN_FUNCS, var_data};
 
@@ -143,12 +143,19 @@ pub fn calc_func_data(program: &mut LlvmProgram, metadata: &mut MetaData) {
 		metadata.get_func_data(&func.name).usage_info = solver.usage_info;
 	}
 
-	let source_names: Vec<_> = metadata.var_data.keys().cloned().collect();
+	// a caller of a system call makes one as well
+	let source_names: Vec<_> = metadata
+		.var_data
+		.keys()
+		.cloned()
+		.chain(std::iter::once(syscall_ident()))
+		.collect();
 	let func_names = program
 		.funcs
 		.iter()
 		.map(|f| f.name.as_str())
 		.chain(BUILTIN_FUNCS.iter().copied())
+		.chain(program.externs.iter().map(|v| v.name()))
 		.map(|f| f.to_owned());
 
 	for ident in source_names {
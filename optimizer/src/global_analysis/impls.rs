@@ -1,3 +1,4 @@
+use llvm::LlvmExtern;
 use rrvm::program::LlvmProgram;
 
 use crate::{metadata::MetaData, RrvmOptimizer};
@@ -27,6 +28,51 @@ pub const BUILTIN_FUNCS: &[&str] = &[
 	"__fill_zero_words",
 ];
 
+// a function of another module may use any variable it can name, and a
+// variable of a module may be used by the others
+fn mark_externs(program: &LlvmProgram, metadata: &mut MetaData) {
+	let mut visible: Vec<_> = program
+		.externs
+		.iter()
+		.filter_map(|v| match v {
+			LlvmExtern::Var { name, .. } => Some((name.clone(), 0)),
+			LlvmExtern::Func { .. } => None,
+		})
+		.collect();
+	if metadata.exported {
+		visible.extend(
+			program
+				.global_vars
+				.iter()
+				.filter(|v| !v.ident.starts_with('.'))
+				.map(|v| (v.ident.clone(), 0)),
+		);
+	}
+	for ident in visible.iter() {
+		let var_data = metadata.get_var_data(ident);
+		var_data.to_load = true;
+		var_data.to_store = true;
+	}
+	for symbol in program.externs.iter() {
+		let LlvmExtern::Func { name, params, .. } = symbol else {
+			continue;
+		};
+		let func_data = metadata.get_func_data(name);
+		func_data.set_syscall();
+		for ident in visible.iter() {
+			func_data.set_may_load(ident.clone());
+			func_data.set_may_store(ident.clone());
+		}
+		for (index, var_type) in params.iter().enumerate() {
+			if var_type.is_ptr() {
+				let var_data = metadata.get_var_data(&(name.clone(), index));
+				var_data.to_load = true;
+				var_data.to_store = true;
+			}
+		}
+	}
+}
+
 impl RrvmOptimizer for GlobalAnalysis {
 	fn new() -> Self {
 		Self {}
@@ -47,6 +93,7 @@ impl RrvmOptimizer for GlobalAnalysis {
 		for func in BUILTIN_FUNCS {
 			metadata.get_func_data(func).set_syscall();
 		}
+		mark_externs(program, metadata);
 
 		calc_var_data(program, metadata);
 		calc_func_data(program, metadata);
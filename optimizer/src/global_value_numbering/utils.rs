@@ -35,14 +35,16 @@ impl From<ConvertOp> for HashItem {
 #[derive(Default, Clone)]
 pub struct NodeInfo {
 	pub num_mapper: HashMap<LlvmTemp, Number>,
-	var_mapper: HashMap<Number, Value>,
+	// the addresses of a `struct` and of its first field are the same number,
+	// but they are accessed with different widths
+	var_mapper: HashMap<(Number, VarType), Value>,
 	exp_mapper: HashMap<(HashItem, Number, Number), Number>,
 	func_mapper: HashMap<(String, Vec<Number>), Number>,
 }
 
 impl NodeInfo {
-	pub fn get_value(&self, number: &Number) -> Option<Value> {
-		self.var_mapper.get(number).cloned()
+	pub fn get_value(&self, number: &Number, var_type: VarType) -> Option<Value> {
+		self.var_mapper.get(&(number.clone(), var_type)).cloned()
 	}
 	pub fn get_number(&self, value: &Value) -> Number {
 		match value {
@@ -69,7 +71,7 @@ impl NodeInfo {
 		}
 	}
 	pub fn set_value(&mut self, number: Number, value: Value) {
-		self.var_mapper.insert(number, value);
+		self.var_mapper.insert((number, value.get_type()), value);
 	}
 	pub fn set_number(&mut self, temp: LlvmTemp, number: Number) {
 		self.num_mapper.insert(temp, number);
@@ -82,7 +84,7 @@ impl NodeInfo {
 				_ => unreachable!(),
 			})
 		} else {
-			self.get_value(number)
+			self.get_value(number, var_type)
 		}
 	}
 	pub fn map_value(&self, value: &Value) -> Value {
@@ -143,11 +145,19 @@ pub fn work(
 				(AddD, _, _) => calc(&lhs, &rhs, |x, y| x.wrapping_add(y)),
 				(Sub, _, _) => calc(&lhs, &rhs, |x, y| x.wrapping_sub(y)),
 				(Mul, _, _) => calc(&lhs, &rhs, |x, y| x.wrapping_mul(y)),
-				(Div, Some(x), Some(y)) => Number::from((x as i32 / y as i32) as u32),
-				(Rem, Some(x), Some(y)) => Number::from((x as i32 % y as i32) as u32),
-				(Shl, Some(x), Some(y)) => Number::from(x << y),
-				(Lshr, Some(x), Some(y)) => Number::from(x >> y),
-				(Ashr, Some(x), Some(y)) => Number::from((x as i32 >> y) as u32),
+				// a division by zero is left for the program to run into
+				(Div, Some(x), Some(y)) if y != 0 => {
+					Number::from((x as i32).wrapping_div(y as i32) as u32)
+				}
+				(Rem, Some(x), Some(y)) if y != 0 => {
+					Number::from((x as i32).wrapping_rem(y as i32) as u32)
+				}
+				// the shift amount is masked to 5 bits, as `sllw` and `sraw` do
+				(Shl, Some(x), Some(y)) => Number::from(x.wrapping_shl(y)),
+				(Lshr, Some(x), Some(y)) => Number::from(x.wrapping_shr(y)),
+				(Ashr, Some(x), Some(y)) => {
+					Number::from((x as i32).wrapping_shr(y) as u32)
+				}
 				(And, _, _) => calc(&lhs, &rhs, |x, y| x & y),
 				(Or, _, _) => calc(&lhs, &rhs, |x, y| x | y),
 				(Xor, _, _) => calc(&lhs, &rhs, |x, y| x ^ y),
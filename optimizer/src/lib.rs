@@ -1,6 +1,8 @@
+mod alias_analysis;
 mod alloc_hoisting;
 mod calc_coef;
 mod code_hoisting;
+mod constant_propagation;
 mod dead_code;
 mod fold_constants;
 mod function_inline;
@@ -11,11 +13,15 @@ mod loops;
 mod mem2reg;
 mod metadata;
 mod number;
+mod partial_eval;
+mod partial_redundancy_elimination;
+mod pipeline;
 mod tail_recursion;
 mod unreachable;
 mod useless_code;
 mod useless_phis;
 use metadata::MetaData;
+pub use pipeline::{PassItem, PassManager, Pipeline, PASSES, PIPELINES};
 use rrvm::program::LlvmProgram;
 use utils::errors::Result;
 
@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 
 use crate::{
+	alias_analysis::AliasAnalysis,
 	loops::{
-		indvar_extraction::IndvarExtraction, loop_data::LoopData,
-		loop_simplify::LoopSimplify, loop_unroll::LoopUnroll,
+		indvar_extraction::IndvarExtraction, licm::LoopInvariantCodeMotion,
+		loop_data::LoopData, loop_simplify::LoopSimplify, loop_unroll::LoopUnroll,
 	},
 	metadata::{FuncData, MetaData},
 };
@@ -108,4 +109,24 @@ impl HandleLoops {
 			) || last
 		}))
 	}
+	pub fn licm(
+		&mut self,
+		program: &mut LlvmProgram,
+		metadata: &mut MetaData,
+	) -> Result<bool> {
+		Ok(program.funcs.iter_mut().fold(false, |last, func| {
+			let dom_tree = LlvmDomTree::new(&func.cfg, false);
+			let loopdata = self.loopdatas.get_mut(&func.name).unwrap();
+			let mut alias = AliasAnalysis::new(func, metadata);
+			alias.add_indvars(loopdata);
+			let opter = LoopInvariantCodeMotion::new(
+				func,
+				loopdata,
+				alias,
+				&mut program.temp_mgr,
+				dom_tree,
+			);
+			opter.apply() || last
+		}))
+	}
 }
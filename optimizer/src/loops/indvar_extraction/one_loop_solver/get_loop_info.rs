@@ -1,4 +1,7 @@
+use std::rc::Rc;
+
 use llvm::{CompOp, LlvmInstrVariant, Value};
+use rrvm::LlvmNode;
 
 use crate::loops::loopinfo::LoopInfo;
 
@@ -7,6 +10,8 @@ use super::OneLoopSolver;
 impl<'a> OneLoopSolver<'a> {
 	// 如果不能确定循环总次数，则返回 None
 	// 如果不是单一出口，则返回 None
+	// do-while 循环的 begin 是第一次判断条件时 indvar 的值，循环次数是回边
+	// 被执行的次数，即比 body 执行的次数少一
 	pub fn get_loop_info(&mut self) -> Option<LoopInfo> {
 		let header = self.cur_loop.borrow().header.clone();
 		let preheader = self.get_cur_loop_preheader().clone();
@@ -21,10 +26,31 @@ impl<'a> OneLoopSolver<'a> {
 			_ => return None,
 		};
 
+		let exit_label = single_exit.borrow().label();
+		let exits = |node: &LlvmNode| {
+			node.borrow().jump_instr.as_ref().is_some_and(|v| match v.get_variant() {
+				LlvmInstrVariant::JumpCondInstr(v) => {
+					v.target_true == exit_label || v.target_false == exit_label
+				}
+				_ => false,
+			})
+		};
+		// while 循环在 header 处跳出；do-while 循环在唯一的 latch 处跳出，
+		// 只有一个块时 header 就是 latch
+		let latches =
+			self.cur_loop.borrow().get_loop_latches(&self.loopdata.loop_map);
+		let is_latch =
+			|node: &LlvmNode| latches.iter().any(|v| Rc::ptr_eq(v, node));
+		let (exiting, do_while) = match &latches[..] {
+			_ if exits(&header) && !is_latch(&header) => (header.clone(), false),
+			[latch] if exits(latch) => (latch.clone(), true),
+			_ => return None,
+		};
+
 		// 取非
 		let mut take_not = false;
-		let header_borrowed = header.borrow();
-		if let Some(jump_instr) = header_borrowed.jump_instr.as_ref() {
+		let exiting = exiting.borrow();
+		if let Some(jump_instr) = exiting.jump_instr.as_ref() {
 			match jump_instr.get_variant() {
 				LlvmInstrVariant::JumpCondInstr(cond_inst) => {
 					// 默认条件不成立跳出循环，并且默认 lhs 是 indvar
@@ -68,10 +94,13 @@ impl<'a> OneLoopSolver<'a> {
 													begin: iv.base,
 													end: end_value,
 												};
-												self
-													.loopdata
-													.loop_infos
-													.insert(self.cur_loop.borrow().id, info.clone());
+												// 其他循环优化都假定循环在 header 处跳出
+												if !do_while {
+													self
+														.loopdata
+														.loop_infos
+														.insert(self.cur_loop.borrow().id, info.clone());
+												}
 												return Some(info);
 											}
 										}
@@ -92,7 +92,7 @@ impl<'a> OneLoopSolver<'a> {
 		let step = &info.step;
 		let end = &info.end;
 		let op = info.comp_op;
-		match op {
+		let cnt = match op {
 			CompOp::SLT | CompOp::SGT => {
 				// (end - start + step - 1) / step;
 				let (tmp1, instr) = compute_two_value(
@@ -165,7 +165,14 @@ impl<'a> OneLoopSolver<'a> {
 				tmp3
 			}
 			_ => unreachable!(),
-		}
+		};
+		// 循环一次都不执行时，上面算出的次数可能是负数
+		let (cnt, instr) =
+			compute_two_value(cnt, Value::Int(0), llvm::ArithOp::Max, self.temp_mgr);
+		instr.map(|i| {
+			self.new_invariant_instr.insert(i.get_write().unwrap().clone(), i)
+		});
+		cnt
 	}
 	// 某变量定义在哪个循环中
 	pub fn def_loop(&self, temp: &LlvmTemp) -> LoopPtr {
@@ -45,12 +45,14 @@ impl<'a> OneLoopSolver<'a> {
 					lhs: Value::Temp(target.clone()),
 					rhs: iv.step[0].clone(),
 				}),
-				VarType::I32Ptr | VarType::F32Ptr => Box::new(GEPInstr {
-					target: new_temp.clone(),
-					var_type: new_temp.var_type,
-					addr: Value::Temp(target.clone()),
-					offset: iv.step[0].clone(),
-				}),
+				VarType::I32Ptr | VarType::F32Ptr | VarType::I8Ptr => {
+					Box::new(GEPInstr {
+						target: new_temp.clone(),
+						var_type: new_temp.var_type,
+						addr: Value::Temp(target.clone()),
+						offset: iv.step[0].clone(),
+					})
+				}
 				_ => unreachable!(),
 			};
 			reduce_map.insert(target.clone(), new_temp.clone());
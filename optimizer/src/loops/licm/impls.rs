@@ -0,0 +1,284 @@
+use std::{collections::HashSet, rc::Rc};
+
+use llvm::{
+	ArithOp, CallInstr, LlvmInstr, LlvmInstrVariant, LlvmTemp, LlvmTempManager,
+	PhiInstr, StoreInstr, Value,
+};
+use rrvm::{
+	dominator::LlvmDomTree, program::LlvmFunc, rrvm_loop::LoopPtr, LlvmNode,
+};
+
+use crate::{
+	alias_analysis::{AliasAnalysis, AliasResult, ModRef},
+	loops::{loop_data::LoopData, loopinfo::LoopInfo},
+};
+
+use super::LoopInvariantCodeMotion;
+
+/// 每个循环最多外提的指令数，外提的值在整个循环中都要占用寄存器
+const MAX_HOISTED: usize = 16;
+
+/// 循环中的访存
+#[derive(Default)]
+struct Accesses {
+	loads: Vec<Value>,
+	stores: Vec<Value>,
+	// 非纯函数的调用
+	calls: Vec<CallInstr>,
+}
+
+impl<'a> LoopInvariantCodeMotion<'a> {
+	pub fn new(
+		func: &'a mut LlvmFunc,
+		loopdata: &'a mut LoopData,
+		alias: AliasAnalysis<'a>,
+		temp_mgr: &'a mut LlvmTempManager,
+		dom_tree: LlvmDomTree,
+	) -> Self {
+		Self {
+			func,
+			loopdata,
+			alias,
+			temp_mgr,
+			dom_tree,
+		}
+	}
+	// 先处理内层循环，外提到内层循环 preheader 的指令可以继续外提
+	pub fn apply(mut self) -> bool {
+		self.loopdata.rebuild(self.func);
+		let mut dfs_vec = Vec::new();
+		fn dfs(node: LoopPtr, dfs_vec: &mut Vec<LoopPtr>) {
+			for subloop in node.borrow().subloops.iter() {
+				dfs(subloop.clone(), dfs_vec);
+			}
+			dfs_vec.push(node);
+		}
+		dfs(self.loopdata.root_loop.clone(), &mut dfs_vec);
+		// 移去 root_node
+		dfs_vec.pop();
+		let mut flag = false;
+		for loop_ in dfs_vec {
+			let info = self.loopdata.loop_infos.get(&loop_.borrow().id).cloned();
+			if let Some(info) = info {
+				flag |= self.visit_loop(loop_, info);
+			}
+		}
+		flag
+	}
+
+	/// 循环中的两个地址是否可能访问同一位置
+	fn may_alias(
+		&self,
+		x: &Value,
+		y: &Value,
+		defined: &HashSet<LlvmTemp>,
+	) -> bool {
+		let invariant =
+			|v: &Value| !v.unwrap_temp().is_some_and(|v| defined.contains(&v));
+		// 循环不变的地址在每次迭代中都相同，可以比较偏移
+		let result = if invariant(x) && invariant(y) {
+			self.alias.alias(x, y)
+		} else {
+			self.alias.object_alias(x, y)
+		};
+		result != AliasResult::No
+	}
+
+	fn get_accesses(&mut self, blocks: &[LlvmNode]) -> Accesses {
+		let mut accesses = Accesses::default();
+		for node in blocks.iter() {
+			for instr in node.borrow().instrs.iter() {
+				match instr.get_variant() {
+					LlvmInstrVariant::LoadInstr(instr) if !instr.addr.is_global() => {
+						accesses.loads.push(instr.addr.clone())
+					}
+					LlvmInstrVariant::StoreInstr(instr) => {
+						accesses.stores.push(instr.addr.clone())
+					}
+					LlvmInstrVariant::CallInstr(instr)
+						if !self.alias.metadata.is_pure(&instr.func.name) =>
+					{
+						accesses.calls.push(instr.clone());
+					}
+					_ => {}
+				}
+			}
+		}
+		accesses
+	}
+
+	/// 循环中是否有可能写 `addr` 的指令
+	fn may_store(
+		&self,
+		accesses: &Accesses,
+		addr: &Value,
+		defined: &HashSet<LlvmTemp>,
+	) -> bool {
+		accesses.stores.iter().any(|v| self.may_alias(v, addr, defined))
+			|| accesses
+				.calls
+				.iter()
+				.any(|call| self.alias.call_mod_ref(call, addr).may_mod())
+	}
+
+	fn hoistable(
+		&mut self,
+		instr: &LlvmInstr,
+		defined: &HashSet<LlvmTemp>,
+		accesses: &Accesses,
+		guaranteed: bool,
+	) -> bool {
+		if instr.get_read().iter().any(|v| defined.contains(v)) {
+			return false;
+		}
+		// 可能出错的指令只有在进入循环就一定会执行，且之前没有输出时才能外提
+		let safe = guaranteed && accesses.calls.is_empty();
+		match instr.get_variant() {
+			LlvmInstrVariant::ArithInstr(instr) => match instr.op {
+				ArithOp::Div | ArithOp::Rem | ArithOp::DivD | ArithOp::RemD => {
+					safe || matches!(instr.rhs, Value::Int(v) if v != 0)
+				}
+				_ => true,
+			},
+			LlvmInstrVariant::CompInstr(_)
+			| LlvmInstrVariant::ConvertInstr(_)
+			| LlvmInstrVariant::GEPInstr(_) => true,
+			LlvmInstrVariant::LoadInstr(instr) if instr.addr.is_global() => true,
+			LlvmInstrVariant::LoadInstr(instr) => {
+				safe && !self.may_store(accesses, &instr.addr, defined)
+			}
+			_ => false,
+		}
+	}
+
+	fn visit_loop(&mut self, loop_: LoopPtr, info: LoopInfo) -> bool {
+		let loop_map = &self.loopdata.loop_map;
+		let blocks = loop_.borrow().blocks(loop_map);
+		let latches = loop_.borrow().get_loop_latches(loop_map);
+		let header = info.header.clone();
+		let preheader = info.preheader.clone();
+		let mut defined = HashSet::new();
+		for node in blocks.iter() {
+			let block = node.borrow();
+			defined.extend(block.phi_instrs.iter().map(|v| v.target.clone()));
+			defined.extend(block.instrs.iter().filter_map(|v| v.get_write()));
+		}
+		let accesses = self.get_accesses(&blocks);
+		// 循环至少执行一次时，支配所有 latch 的块在进入循环后一定会执行
+		let runs = info.has_const_loop_cnt().is_some_and(|v| v > 0);
+		let mut guaranteed = HashSet::new();
+		for node in blocks.iter() {
+			let id = node.borrow().id;
+			if Rc::ptr_eq(node, &header)
+				|| runs
+					&& latches.iter().all(|v| self.dom_tree.dominates(id, v.borrow().id))
+			{
+				guaranteed.insert(id);
+			}
+		}
+
+		let preheader_weight = preheader.borrow().weight;
+		let mut hoisted = Vec::new();
+		let mut changed = true;
+		while changed {
+			changed = false;
+			for node in blocks.iter() {
+				// 代价模型：只外提比 preheader 执行更频繁的块中的指令
+				if node.borrow().weight <= preheader_weight {
+					continue;
+				}
+				let is_guaranteed = guaranteed.contains(&node.borrow().id);
+				let instrs = std::mem::take(&mut node.borrow_mut().instrs);
+				let mut kept = Vec::new();
+				for instr in instrs {
+					if hoisted.len() < MAX_HOISTED
+						&& self.hoistable(&instr, &defined, &accesses, is_guaranteed)
+					{
+						defined.remove(&instr.get_write().unwrap());
+						hoisted.push(instr);
+						changed = true;
+					} else {
+						kept.push(instr);
+					}
+				}
+				node.borrow_mut().instrs = kept;
+			}
+		}
+		let mut flag = !hoisted.is_empty();
+		preheader.borrow_mut().instrs.extend(hoisted);
+
+		if latches.len() == 1 {
+			for node in blocks.iter() {
+				let is_guaranteed = guaranteed.contains(&node.borrow().id);
+				flag |= self.sink_stores(
+					node,
+					&info,
+					&latches[0],
+					&defined,
+					&accesses,
+					is_guaranteed,
+				);
+			}
+		}
+		flag
+	}
+
+	/// 将循环中唯一访问某个地址的 store 移到循环出口
+	fn sink_stores(
+		&mut self,
+		node: &LlvmNode,
+		info: &LoopInfo,
+		latch: &LlvmNode,
+		defined: &HashSet<LlvmTemp>,
+		accesses: &Accesses,
+		guaranteed: bool,
+	) -> bool {
+		// 每次迭代都执行的 store 才能由最后一次迭代的值代替
+		if !guaranteed {
+			return false;
+		}
+		let is_header = Rc::ptr_eq(node, &info.header);
+		let mut flag = false;
+		let instrs = std::mem::take(&mut node.borrow_mut().instrs);
+		let mut kept = Vec::new();
+		for instr in instrs {
+			let store = match instr.get_variant() {
+				LlvmInstrVariant::StoreInstr(store) => Some(store.clone()),
+				_ => None,
+			};
+			let Some(StoreInstr { value, addr }) = store.filter(|store| {
+				let addr = &store.addr;
+				let aliases = |v: &&Value| self.may_alias(v, addr, defined);
+				!addr.unwrap_temp().is_some_and(|v| defined.contains(&v))
+					&& accesses.stores.iter().filter(aliases).count() == 1
+					&& !accesses.loads.iter().any(|v| aliases(&v))
+					&& accesses
+						.calls
+						.iter()
+						.all(|call| self.alias.call_mod_ref(call, addr) == ModRef::Neither)
+			}) else {
+				kept.push(instr);
+				continue;
+			};
+			// header 支配出口，其中的值在出口处可用；其他块的值需要经过 header 的 phi
+			let value = if is_header {
+				value
+			} else {
+				let var_type = value.get_type();
+				let target = self.temp_mgr.new_temp(var_type, false);
+				let source = vec![
+					(var_type.default_value(), info.preheader.borrow().label()),
+					(value, latch.borrow().label()),
+				];
+				let phi = PhiInstr::new(target.clone(), source);
+				info.header.borrow_mut().phi_instrs.push(phi);
+				target.into()
+			};
+			let new_instr = Box::new(StoreInstr { value, addr });
+			info.single_exit.borrow_mut().instrs.insert(0, new_instr);
+			flag = true;
+		}
+		node.borrow_mut().instrs = kept;
+		flag
+	}
+}
@@ -0,0 +1,16 @@
+use llvm::LlvmTempManager;
+use rrvm::{dominator::LlvmDomTree, program::LlvmFunc};
+
+use crate::alias_analysis::AliasAnalysis;
+
+use super::loop_data::LoopData;
+
+pub mod impls;
+
+pub struct LoopInvariantCodeMotion<'a> {
+	pub loopdata: &'a mut LoopData,
+	pub alias: AliasAnalysis<'a>,
+	pub temp_mgr: &'a mut LlvmTempManager,
+	pub func: &'a mut LlvmFunc,
+	pub dom_tree: LlvmDomTree,
+}
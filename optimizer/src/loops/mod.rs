@@ -4,10 +4,11 @@ use loop_data::LoopData;
 
 mod chain_node;
 mod impls;
-mod indvar;
+pub mod indvar;
 mod indvar_extraction;
-mod indvar_type;
-mod loop_data;
+pub mod indvar_type;
+mod licm;
+pub mod loop_data;
 mod loop_simplify;
 mod loop_unroll;
 mod loopinfo;
@@ -0,0 +1,257 @@
+// 循环的不同迭代之间是否有访存依赖：对同一对象的两次访存，
+// 偏移都是迭代次数等变量的线性函数，用 GCD 测试与 Banerjee 测试判断
+// 两个不同的迭代是否可能访问同一位置
+
+use std::collections::{HashMap, HashSet};
+
+use llvm::{LlvmInstrVariant, LlvmTemp, Value};
+use rrvm::rrvm_loop::LoopPtr;
+
+use crate::{
+	alias_analysis::{
+		impls::access_size, AliasAnalysis, AliasResult, Object, Offset, Symbol,
+	},
+	loops::loop_data::LoopData,
+};
+
+// 依赖方程中的变量，`usize` 区分两次迭代
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Var {
+	// 内层循环的迭代次数
+	Inner(usize, i32),
+	// 循环中定义的变量，每次迭代的值不同
+	Local(usize, LlvmTemp),
+	// 循环不变量
+	Invariant(Symbol),
+}
+
+// 取值范围，`None` 表示这一侧无界
+#[derive(Clone, Copy)]
+struct Range {
+	lo: Option<i128>,
+	hi: Option<i128>,
+}
+
+impl Range {
+	fn point(value: i128) -> Self {
+		Self {
+			lo: Some(value),
+			hi: Some(value),
+		}
+	}
+	fn unbounded() -> Self {
+		Self { lo: None, hi: None }
+	}
+	// `coef * v`，其中 `0 <= v <= upper`
+	fn scaled(coef: i128, upper: Option<i128>) -> Self {
+		match upper {
+			Some(upper) => Self {
+				lo: Some((coef * upper).min(0)),
+				hi: Some((coef * upper).max(0)),
+			},
+			None if coef > 0 => Self {
+				lo: Some(0),
+				hi: None,
+			},
+			None => Self {
+				lo: None,
+				hi: Some(0),
+			},
+		}
+	}
+	fn add(self, other: Self) -> Self {
+		Self {
+			lo: self.lo.zip(other.lo).map(|(x, y)| x + y),
+			hi: self.hi.zip(other.hi).map(|(x, y)| x + y),
+		}
+	}
+	fn contains_zero(&self) -> bool {
+		self.lo.map_or(true, |v| v <= 0) && self.hi.map_or(true, |v| v >= 0)
+	}
+}
+
+fn gcd(x: i128, y: i128) -> i128 {
+	if y == 0 {
+		x
+	} else {
+		gcd(y, x % y)
+	}
+}
+
+struct Access {
+	addr: Value,
+	is_store: bool,
+}
+
+struct Bounds {
+	id: i32,
+	// 循环头执行的次数，比循环体多一次
+	iterations: Option<i128>,
+	// 内层循环迭代次数的上界
+	inner: HashMap<i32, Option<i128>>,
+	locals: HashSet<LlvmTemp>,
+}
+
+/// 循环的各次迭代可以同时执行时返回 `Some`，其中是循环中是否调用了纯函数
+pub fn check_dependence(
+	loop_: &LoopPtr,
+	loopdata: &LoopData,
+	alias: &AliasAnalysis,
+) -> Option<bool> {
+	let blocks = loop_.borrow().blocks(&loopdata.loop_map);
+	let mut locals = HashSet::new();
+	let mut accesses = Vec::new();
+	let mut has_calls = false;
+	for block in blocks.iter() {
+		let block = block.borrow();
+		locals.extend(block.phi_instrs.iter().map(|v| v.target.clone()));
+		for instr in block.instrs.iter() {
+			locals.extend(instr.get_write());
+			match instr.get_variant() {
+				LlvmInstrVariant::AllocInstr(_) => return None,
+				LlvmInstrVariant::CallInstr(call) => {
+					if !alias.metadata.is_pure(&call.func.name) {
+						return None;
+					}
+					has_calls = true;
+				}
+				// 目标是指针的 load 只是取全局变量的地址
+				LlvmInstrVariant::LoadInstr(load) if !load.target.var_type.is_ptr() => {
+					accesses.push(Access {
+						addr: load.addr.clone(),
+						is_store: false,
+					});
+				}
+				LlvmInstrVariant::StoreInstr(store) => accesses.push(Access {
+					addr: store.addr.clone(),
+					is_store: true,
+				}),
+				_ => {}
+			}
+		}
+	}
+	let trip_count = |id: &i32| {
+		loopdata
+			.loop_infos
+			.get(id)
+			.and_then(|info| info.has_const_loop_cnt())
+			.map(|cnt| cnt as i128)
+	};
+	let id = loop_.borrow().id;
+	let mut inner = HashMap::new();
+	let mut stack = loop_.borrow().subloops.clone();
+	while let Some(subloop) = stack.pop() {
+		let subloop = subloop.borrow();
+		inner.insert(subloop.id, trip_count(&subloop.id));
+		stack.extend(subloop.subloops.iter().cloned());
+	}
+	let bounds = Bounds {
+		id,
+		iterations: trip_count(&id).map(|cnt| cnt + 1),
+		inner,
+		locals,
+	};
+	for store in accesses.iter().filter(|v| v.is_store) {
+		for other in accesses.iter() {
+			if bounds.may_depend(alias, &store.addr, &other.addr) {
+				return None;
+			}
+		}
+	}
+	Some(has_calls)
+}
+
+impl Bounds {
+	// 两个不同的迭代中，`x` 和 `y` 是否可能是同一位置
+	fn may_depend(&self, alias: &AliasAnalysis, x: &Value, y: &Value) -> bool {
+		if alias.object_alias(x, y) == AliasResult::No {
+			return false;
+		}
+		// 宽度不同的访存可能部分重叠，不做判断
+		let size = access_size(x);
+		if size != access_size(y) {
+			return true;
+		}
+		let (x, y) = (alias.locate(x), alias.locate(y));
+		if x.object != y.object || x.object == Object::Unknown {
+			return true;
+		}
+		match x.offset.zip(y.offset) {
+			Some((x, y)) => self.solve(&x, &y, size).unwrap_or(true),
+			None => true,
+		}
+	}
+
+	// 方程 `x(i, ...) = y(i', ...)` 在 `i != i'` 时是否可能有整数解，
+	// 以访存宽度 `size` 为单位，偏移不是其整数倍时返回 `None`
+	fn solve(&self, x: &Offset, y: &Offset, size: i64) -> Option<bool> {
+		let units = |v: i64| (v % size == 0).then_some((v / size) as i128);
+		let constant = units(x.constant)? - units(y.constant)?;
+		// 方程为 a * i - b * i' + sum(coef * var) + constant = 0
+		let (mut a, mut b) = (0, 0);
+		let mut terms: HashMap<Var, i128> = HashMap::new();
+		for (side, offset, sign) in [(0, x, 1), (1, y, -1)] {
+			for (symbol, coef) in offset.terms.iter() {
+				let coef = units(*coef)?;
+				let var = match symbol {
+					Symbol::Iteration(id) if *id == self.id => {
+						if side == 0 {
+							a = coef;
+						} else {
+							b = coef;
+						}
+						continue;
+					}
+					Symbol::Iteration(id) if self.inner.contains_key(id) => {
+						Var::Inner(side, *id)
+					}
+					Symbol::Temp(temp) if self.locals.contains(temp) => {
+						Var::Local(side, temp.clone())
+					}
+					symbol => Var::Invariant(symbol.clone()),
+				};
+				*terms.entry(var).or_default() += sign * coef;
+			}
+		}
+		terms.retain(|_, v| *v != 0);
+		// GCD 测试
+		let divisor =
+			terms.values().chain([&a, &b]).fold(0, |g, v| gcd(g, v.abs()));
+		if divisor == 0 {
+			return Some(constant == 0);
+		}
+		if constant % divisor != 0 {
+			return Some(false);
+		}
+		// Banerjee 测试，分别考虑 i < i' 和 i > i'
+		let rest =
+			terms.iter().fold(Range::point(constant), |range, (var, coef)| {
+				range.add(match var {
+					Var::Inner(_, id) => Range::scaled(*coef, self.inner[id]),
+					_ => Range::unbounded(),
+				})
+			});
+		Some([-b, a].into_iter().any(|coef| {
+			self.directed(a - b, coef).is_some_and(|v| v.add(rest).contains_zero())
+		}))
+	}
+
+	// `ci * i + cd * d` 的范围，其中 `i >= 0, d >= 1, i + d < iterations`，
+	// 只有一次迭代时返回 `None`
+	fn directed(&self, ci: i128, cd: i128) -> Option<Range> {
+		match self.iterations {
+			Some(n) if n < 2 => None,
+			Some(n) => {
+				let values = [cd, ci * (n - 2) + cd, cd * (n - 1)];
+				Some(Range {
+					lo: values.iter().min().copied(),
+					hi: values.iter().max().copied(),
+				})
+			}
+			None => Some(Range {
+				lo: (ci >= 0 && cd >= 0).then_some(cd),
+				hi: (ci <= 0 && cd <= 0).then_some(cd),
+			}),
+		}
+	}
+}
@@ -1,17 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use llvm::{
 	ArithInstr, ArithOp, CallInstr, CompInstr, CompKind, CompOp, GEPInstr,
-	LlvmInstr, LlvmInstrVariant, LlvmTemp, LlvmTempManager, PhiInstr, Value,
-	VarType,
+	LlvmInstr, LlvmInstrVariant, LlvmTemp, LlvmTempManager, LoadInstr,
+	StoreInstr, Value, VarType,
 };
+use rrvm::program::LlvmFunc;
+use utils::{GlobalVar, Label, ValueItem};
 
-use utils::Label;
+use crate::loops::indvar::IndVar;
 
-use crate::loops::{
-	indvar::IndVar, indvar_type::IndVarType, loopinfo::LoopInfo,
+use super::{
+	reduction::{Reduction, ReductionOp},
+	Plan,
 };
 
+// 循环中调用函数时，相邻两个线程的栈帧之间留出的空间
+pub const THREAD_STACK: i32 = 1 << 20;
+
 fn add_arith_instr(
 	instr: &mut Vec<LlvmInstr>,
 	mgr: &mut LlvmTempManager,
@@ -19,7 +25,11 @@ fn add_arith_instr(
 	lhs: Value, // CAN BE PTR
 	rhs: Value,
 ) -> LlvmTemp {
-	let target = mgr.new_temp(VarType::I32, false);
+	let var_type = match &lhs {
+		Value::Temp(t) if t.var_type.is_ptr() => t.var_type,
+		_ => VarType::I32,
+	};
+	let target = mgr.new_temp(var_type, false);
 
 	assert!(
 		rhs.is_num()
@@ -78,27 +88,33 @@ fn add_comp_instr(
 	target
 }
 
+// 用 `replaced` 中合并后的归约结果替换之前的循环中的归约变量
+fn resolve(value: Value, replaced: &HashMap<LlvmTemp, Value>) -> Value {
+	match value {
+		Value::Temp(t) => replaced.get(&t).cloned().unwrap_or(t.into()),
+		value => value,
+	}
+}
+
 pub fn make_parallel(
-	info: LoopInfo,
-	// pre_header: LlvmNode,
-	// header: LlvmNode,
-	// exit: LlvmNode,
+	plan: &Plan,
+	func: &LlvmFunc,
 	mgr: &mut LlvmTempManager,
-	// cmp: &LlvmTemp,
-	// step: Value,
-	// begin: Value,
-	// end: Value,
-	indvars: &mut HashMap<LlvmTemp, IndVar>,
+	global_vars: &mut Vec<GlobalVar>,
+	threads: usize,
+	indvars: &HashMap<LlvmTemp, IndVar>,
+	replaced: &mut HashMap<LlvmTemp, Value>,
 ) -> (Value, Value, LlvmTemp) {
-	// SLT only now!!
+	let info = &plan.info;
 	let pre_header = info.preheader.clone();
 	let header = info.header.clone();
 	let exit = info.single_exit.clone();
 	let cmp_op = info.comp_op;
 	let cmp = info.cmp.clone();
-	let step = info.step.clone();
-	let begin = info.begin.clone();
-	let end = info.end.clone();
+	let step = resolve(info.step.clone(), replaced);
+	let begin = resolve(info.begin.clone(), replaced);
+	let end = resolve(info.end.clone(), replaced);
+	let last = threads as i32 - 1;
 
 	let equal_op = match cmp_op {
 		CompOp::SGT => false,
@@ -108,6 +124,8 @@ pub fn make_parallel(
 		_ => unreachable!(),
 	};
 
+	// 循环中调用函数时，各线程需要各自的栈
+	let stack_gap = if plan.has_calls { THREAD_STACK } else { 0 };
 	let tid_old = mgr.new_temp(llvm::VarType::I32, false);
 	pre_header.borrow_mut().instrs.push(Box::new(CallInstr {
 		target: tid_old.clone(),
@@ -115,22 +133,26 @@ pub fn make_parallel(
 		func: Label {
 			name: "__create_threads".to_string(),
 		},
-		params: vec![],
+		params: vec![
+			(VarType::I32, (threads as i32).into()),
+			(VarType::I32, stack_gap.into()),
+		],
 	}));
 
+	// 主线程的 tid 最大，负责最后一段及余下的迭代
 	let tid = add_arith_instr(
 		pre_header.borrow_mut().instrs.as_mut(),
 		mgr,
 		ArithOp::Sub,
-		3.into(),
-		tid_old.into(),
+		last.into(),
+		tid_old.clone().into(),
 	);
 
 	let is_last = add_comp_instr(
 		pre_header.borrow_mut().instrs.as_mut(),
 		mgr,
 		CompOp::EQ,
-		3.into(),
+		last.into(),
 		tid.clone().into(),
 	);
 	let neg_is_last = add_arith_instr(
@@ -193,21 +215,21 @@ pub fn make_parallel(
 		mgr,
 		ArithOp::Div,
 		total_times.clone().into(),
-		4.into(),
+		(threads as i32).into(),
 	);
-	let per_part_4 = add_arith_instr(
+	let per_part_all = add_arith_instr(
 		pre_header.borrow_mut().instrs.as_mut(),
 		mgr,
 		ArithOp::Mul,
 		per_part.clone().into(),
-		4.into(),
+		(threads as i32).into(),
 	);
 	let remain = add_arith_instr(
 		pre_header.borrow_mut().instrs.as_mut(),
 		mgr,
 		ArithOp::Sub,
 		total_times.clone().into(),
-		per_part_4.into(),
+		per_part_all.into(),
 	);
 
 	let remain_to_add = add_arith_instr(
@@ -247,16 +269,30 @@ pub fn make_parallel(
 		}
 	};
 
+	// 求和的各线程从 0 开始，最后再加上初值
+	let mut inits = HashMap::new();
 	for mut item in header_phi {
-		if let Some(indvar) = indvars.get(&item.target) {
-			if matches!(indvar.get_type(), IndVarType::Ordinary) {
-				let start = indvar.base.clone();
+		let start = item
+			.source
+			.iter()
+			.find(|(_, label)| *label == pre_header.borrow().label())
+			.map(|(value, _)| value.clone())
+			.unwrap();
+		let reduction = plan.reductions.iter().find(|v| v.phi == item.target);
+		match reduction.map(|v| v.op) {
+			Some(ReductionOp::Sum) => {
+				inits.insert(item.target.clone(), start);
+				rewrite_phi(&mut item.source, 0.into());
+			}
+			Some(ReductionOp::Min | ReductionOp::Max) => {}
+			None => {
+				let indvar = &indvars[&item.target];
 				let total_offset = add_arith_instr(
 					pre_header.borrow_mut().instrs.as_mut(),
 					mgr,
 					ArithOp::Mul,
 					offset.clone().into(),
-					indvar.step.first().unwrap().clone(),
+					resolve(indvar.step.first().unwrap().clone(), replaced),
 				);
 				let thread_start = add_arith_instr(
 					pre_header.borrow_mut().instrs.as_mut(),
@@ -266,18 +302,9 @@ pub fn make_parallel(
 					total_offset.into(),
 				);
 				rewrite_phi(&mut item.source, thread_start.into());
-				header.borrow_mut().phi_instrs.push(PhiInstr {
-					target: item.target,
-					var_type: item.var_type,
-					source: item.source,
-				});
-			} else {
-				unreachable!()
 			}
-		//TODO 有后端指令支持之后在这里搞zfp
-		} else {
-			unreachable!()
 		}
+		header.borrow_mut().phi_instrs.push(item);
 	}
 
 	let mut new_phi = header.borrow().phi_instrs.last().unwrap().clone();
@@ -337,17 +364,122 @@ pub fn make_parallel(
 
 	header.borrow_mut().instrs = new_instrs;
 
-	exit.borrow_mut().instrs.insert(
-		0,
-		Box::new(CallInstr {
-			target: mgr.new_temp(VarType::Void, false),
-			var_type: VarType::Void,
-			func: Label {
-				name: "__join_threads".to_string(),
-			},
-			params: vec![(VarType::I32, Value::Temp(tid))],
-		}),
-	);
+	// 各线程把归约的部分结果存入全局数组，汇合后由主线程合并
+	let mut front: Vec<LlvmInstr> = vec![];
+	let mut after: Vec<LlvmInstr> = vec![];
+	let mut map = HashMap::new();
+	for reduction in plan.reductions.iter() {
+		let acc = combine(
+			reduction,
+			inits.get(&reduction.phi).cloned(),
+			&tid_old,
+			threads,
+			(&mut front, &mut after),
+			mgr,
+			global_vars,
+		);
+		map.insert(reduction.phi.clone(), acc);
+	}
+	let inside: HashSet<_> = plan.blocks.iter().map(|v| v.borrow().id).collect();
+	for block in func.cfg.blocks.iter() {
+		if !inside.contains(&block.borrow().id) {
+			block.borrow_mut().map_temp(&map);
+		}
+	}
+	replaced.extend(map);
+
+	front.push(Box::new(CallInstr {
+		target: mgr.new_temp(VarType::Void, false),
+		var_type: VarType::Void,
+		func: Label {
+			name: "__join_threads".to_string(),
+		},
+		params: vec![
+			(VarType::I32, Value::Temp(tid_old)),
+			(VarType::I32, (threads as i32).into()),
+		],
+	}));
+	front.extend(after);
+	let mut exit = exit.borrow_mut();
+	let instrs = std::mem::take(&mut exit.instrs);
+	exit.instrs = front.into_iter().chain(instrs).collect();
 
 	(0i32.into(), this_length.clone().into(), new_index)
 }
+
+// 在汇合前存入部分结果，汇合后读出并合并，返回合并的结果
+fn combine(
+	reduction: &Reduction,
+	init: Option<Value>,
+	tid_old: &LlvmTemp,
+	threads: usize,
+	(front, after): (&mut Vec<LlvmInstr>, &mut Vec<LlvmInstr>),
+	mgr: &mut LlvmTempManager,
+	global_vars: &mut Vec<GlobalVar>,
+) -> Value {
+	let ident = format!(".partial.{}", global_vars.len());
+	let data = vec![ValueItem::Zero(4 * threads)];
+	global_vars.push(GlobalVar::new(&ident, data, false, false));
+	let base = mgr.new_temp(VarType::I32Ptr, false);
+	front.push(Box::new(LoadInstr {
+		target: base.clone(),
+		var_type: VarType::I32Ptr,
+		addr: LlvmTemp::new(&ident, VarType::I32Ptr, true).into(),
+	}));
+	let offset =
+		add_arith_instr(front, mgr, ArithOp::Mul, tid_old.clone().into(), 4.into());
+	let slot = add_arith_instr(
+		front,
+		mgr,
+		ArithOp::Add,
+		base.clone().into(),
+		offset.into(),
+	);
+	front.push(Box::new(StoreInstr {
+		value: reduction.phi.clone().into(),
+		addr: slot.into(),
+	}));
+
+	let mut acc = init;
+	for i in 0..threads {
+		let slot = add_arith_instr(
+			after,
+			mgr,
+			ArithOp::Add,
+			base.clone().into(),
+			(4 * i as i32).into(),
+		);
+		let value = mgr.new_temp(VarType::I32, false);
+		after.push(Box::new(LoadInstr {
+			target: value.clone(),
+			var_type: VarType::I32,
+			addr: slot.into(),
+		}));
+		let Some(prev) = acc else {
+			acc = Some(value.into());
+			continue;
+		};
+		let next = match reduction.op {
+			ReductionOp::Sum => {
+				add_arith_instr(after, mgr, ArithOp::Add, prev, value.into())
+			}
+			// 用 prev + (value - prev) * (value op prev) 选出两者之一
+			ReductionOp::Min | ReductionOp::Max => {
+				let op = if reduction.op == ReductionOp::Max {
+					CompOp::SGT
+				} else {
+					CompOp::SLT
+				};
+				let better =
+					add_comp_instr(after, mgr, op, value.clone().into(), prev.clone());
+				let diff =
+					add_arith_instr(after, mgr, ArithOp::Sub, value.into(), prev.clone());
+				let delta =
+					add_arith_instr(after, mgr, ArithOp::Mul, diff.into(), better.into());
+				add_arith_instr(after, mgr, ArithOp::Add, prev, delta.into())
+			}
+		};
+		acc = Some(next.into());
+	}
+	acc.unwrap()
+}
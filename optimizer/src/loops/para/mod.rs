@@ -1,390 +1,195 @@
-mod check_loop;
+mod dependence;
 mod make_parallel;
-mod pointer_tracer;
+mod reduction;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
-use check_loop::check_ok;
-use llvm::{LlvmInstrVariant, LlvmTemp, LlvmTempManager, Value};
-use make_parallel::make_parallel;
-use pointer_tracer::PointerTracer;
+use dependence::check_dependence;
+use llvm::{CompOp, LlvmInstrVariant, LlvmTempManager, Value, VarType};
+use make_parallel::{make_parallel, THREAD_STACK};
+use reduction::{find_reduction, Reduction};
 use rrvm::{
-	cfg::Node,
-	dominator::{DomTree, LlvmDomTree},
 	program::{LlvmFunc, LlvmProgram},
 	rrvm_loop::LoopPtr,
+	LlvmNode,
 };
+use utils::{GlobalVar, Result};
 
-use crate::metadata::MetaData;
+use crate::{alias_analysis::AliasAnalysis, metadata::MetaData};
 
 use super::{
 	indvar::IndVar, indvar_type::IndVarType, loop_data::LoopData,
 	loopinfo::LoopInfo, HandleLoops,
 };
-use utils::{InstrTrait, Result, TempTrait};
+
+// 迭代次数太少的单层循环不值得创建线程
+const MIN_ITERATIONS: i32 = 4096;
+// 即使有内层循环，迭代次数太少时各线程也分不到几次迭代
+const MIN_TRIPS: i32 = 64;
+// 新线程的栈依次放在主线程之下，各自复制一份栈帧，
+// 局部数组太大时所有线程的栈会超出默认的 8MB
+const STACK_LIMIT: i64 = 6 << 20;
+
+/// 可以并行执行的循环
+pub struct Plan {
+	id: i32,
+	info: LoopInfo,
+	blocks: Vec<LlvmNode>,
+	reductions: Vec<Reduction>,
+	// 循环中调用了（纯）函数
+	has_calls: bool,
+}
 
 impl HandleLoops {
 	pub fn parallel(
 		&mut self,
 		program: &mut LlvmProgram,
-		_metadata: &mut MetaData,
+		metadata: &mut MetaData,
 	) -> Result<bool> {
+		let mut flag = false;
 		for func in program.funcs.iter_mut() {
-			if let Some(loop_data) = self.loopdatas.remove(&func.name) {
-				self.loopdatas.insert(
-					func.name.clone(),
-					handle_function(func, loop_data, &mut program.temp_mgr),
+			// 只在 main 中创建线程，线程不会嵌套
+			if func.name != "main" || metadata.threads < 2 {
+				continue;
+			}
+			if let Some(loopdata) = self.loopdatas.get_mut(&func.name) {
+				flag |= handle_function(
+					func,
+					loopdata,
+					metadata,
+					&mut program.temp_mgr,
+					&mut program.global_vars,
 				);
 			}
 		}
-		Ok(false)
-	}
-}
-
-fn get_temp_ref(value: &Value) -> Option<&LlvmTemp> {
-	match value {
-		Value::Temp(t) => Some(t),
-		_ => None,
-	}
-}
-
-struct DominatorDFS<T, U>
-where
-	T: InstrTrait<U>,
-	U: TempTrait,
-{
-	dom_tree: DomTree<T, U>,
-	stack: Vec<Node<T, U>>,
-}
-
-impl<T, U> Iterator for DominatorDFS<T, U>
-where
-	T: InstrTrait<U>,
-	U: TempTrait,
-{
-	type Item = Node<T, U>;
-
-	fn next(&mut self) -> Option<Self::Item> {
-		if let Some(current) = self.stack.pop() {
-			let current_id = current.borrow().id;
-			self.stack.extend(self.dom_tree.get_children(current_id).iter().cloned());
-			current.into()
-		} else {
-			None
-		}
-	}
-}
-
-impl<T, U> DominatorDFS<T, U>
-where
-	T: InstrTrait<U>,
-	U: TempTrait,
-{
-	pub fn new(dom_tree: DomTree<T, U>, entry: Node<T, U>) -> Self {
-		Self {
-			dom_tree,
-			stack: vec![entry],
-		}
+		Ok(flag)
 	}
 }
 
 fn handle_function(
 	func: &mut LlvmFunc,
-	loop_data: LoopData,
+	loopdata: &mut LoopData,
+	metadata: &MetaData,
 	mgr: &mut LlvmTempManager,
-) -> LoopData {
-	if func.name != "main" {
-		//似乎只在main中工作
-		return loop_data;
-	}
-	let (mut loop_map, root_loop, mut loop_infos, mut indvars) = (
-		loop_data.loop_map,
-		loop_data.root_loop,
-		loop_data.loop_infos,
-		loop_data.indvars,
-	);
-	//loop map: 所有的loop 都有
-	//loop info: 如果没有一定不能并行
-
-	let mut ok_loop_id: HashSet<i32> = HashSet::new();
-
-	let mut ptr_set: pointer_tracer::PointerTracer = PointerTracer::new();
-	let mut indvar_ptr_set: pointer_tracer::PointerTracer = PointerTracer::new();
-
-	fn get_i32(value: &Value) -> Option<i32> {
-		match value {
-			Value::Int(i) => Some(*i),
-			_ => None,
-		}
-	}
-
-	for candidate_loop in &root_loop.borrow().subloops {
-		for block in
-			candidate_loop.borrow().blocks_without_subloops(&func.cfg, &loop_map)
-		{
-			for targets in block.borrow().phi_instrs.iter().map(|instr| &instr.target)
-			{
-				if targets.var_type.is_ptr() {
-					indvar_ptr_set.create(targets);
-				}
-			}
-
-			for gep in block.borrow().instrs.iter().filter_map(|instr| {
-				match instr.get_variant() {
-					LlvmInstrVariant::GEPInstr(gep) => Some(gep),
-					_ => None,
-				}
-			}) {
-				if let Some(target_ind) = indvars.get(&gep.target) {
-					if target_ind.get_type() == IndVarType::Ordinary {
-						if let (Some(step), offset) = (
-							get_i32(target_ind.step.first().unwrap()),
-							get_i32(&gep.offset),
-						) {
-							if step == 0 {
-								//HACK: 取消注释这行可能引起错误
-								// indvar_ptr_set.create(&gep.target);
-							} else if offset.is_some_and(|offset| offset < step) {
-								indvar_ptr_set
-									.link(&gep.target, get_temp_ref(&gep.addr).unwrap());
-							} else {
-								indvar_ptr_set.create(&gep.target);
-							}
-						} else {
-							indvar_ptr_set.create(&gep.target);
-						}
-					}
-				}
-			}
-		}
+	global_vars: &mut Vec<GlobalVar>,
+) -> bool {
+	// 先分析所有最外层循环，再逐个变换
+	let plans: Vec<_> = {
+		let mut alias = AliasAnalysis::new(func, metadata);
+		alias.add_indvars(loopdata);
+		let subloops = loopdata.root_loop.borrow().subloops.clone();
+		let frame = frame_size(func);
+		subloops
+			.iter()
+			.filter_map(|v| check_loop(v, func, loopdata, &alias))
+			.filter(|plan| {
+				let gap = if plan.has_calls { THREAD_STACK } else { 0 };
+				(frame + gap as i64) * metadata.threads as i64 <= STACK_LIMIT
+			})
+			.collect()
+	};
+	if plans.is_empty() {
+		return false;
 	}
-
-	// 假定传入参数的不同的指针指向不重叠的内存
-
-	for param in &func.params {
-		if let Some(t) = get_temp_ref(param) {
-			if t.var_type.is_ptr() {
-				ptr_set.create(t);
-			}
-		}
+	let mut replaced = HashMap::new();
+	for plan in plans.iter() {
+		let (begin, end, index) = make_parallel(
+			plan,
+			func,
+			mgr,
+			global_vars,
+			metadata.threads,
+			&loopdata.indvars,
+			&mut replaced,
+		);
+		loopdata.indvars.insert(
+			index,
+			IndVar {
+				base: 0.into(),
+				scale: 1.into(),
+				step: vec![1.into()],
+				zfp: None,
+			},
+		);
+		let info = loopdata.loop_infos.get_mut(&plan.id).unwrap();
+		info.begin = begin;
+		info.end = end;
 	}
+	loopdata.rebuild(func);
+	true
+}
 
-	let dom_tree = LlvmDomTree::new(&func.cfg, false);
-
-	for block in DominatorDFS::new(dom_tree, func.cfg.get_entry()) {
-		for instr in &block.borrow().phi_instrs {
-			if let Some(to_link) = instr
-				.source
-				.iter()
-				.find(|(v, _)| get_temp_ref(v).is_some_and(|t| ptr_set.know(t)))
-				.and_then(|(v, _)| get_temp_ref(v))
-			{
-				ptr_set.link(&instr.target, to_link);
-				indvar_ptr_set.link(&instr.target, to_link);
-			}
-		}
-
-		for instr in &block.borrow().instrs {
-			match instr.get_variant() {
-				llvm::LlvmInstrVariant::AllocInstr(i) => {
-					ptr_set.create(&i.target);
-				}
-				llvm::LlvmInstrVariant::StoreInstr(i) => {
-					if let Some(t) = get_temp_ref(&i.addr) {
-						ptr_set.get(t);
-					}
-				}
-				llvm::LlvmInstrVariant::LoadInstr(i) => {
-					if let Some(t) = get_temp_ref(&i.addr) {
-						if t.is_global {
-							ptr_set.name(t, &t.name);
-						}
-						if i.target.var_type.is_ptr() {
-							ptr_set.link(&i.target, t);
-						}
-					}
-				}
-				llvm::LlvmInstrVariant::GEPInstr(i) => {
-					if let Some(t) = get_temp_ref(&i.addr) {
-						ptr_set.link(&i.target, t);
-						indvar_ptr_set.link(&i.target, t);
-					}
-				}
-				llvm::LlvmInstrVariant::CallInstr(i) => {
-					if i.target.var_type.is_ptr() {
-						//since function that returns ptr can only be our function to fill zeros, which returns the ptr in same array
-						for (t, value) in i.params.iter() {
-							if t.is_ptr() {
-								ptr_set.link(&i.target, get_temp_ref(value).unwrap());
-							}
-						}
-					}
-				}
-				_ => {}
-			}
+fn check_loop(
+	loop_: &LoopPtr,
+	func: &LlvmFunc,
+	loopdata: &LoopData,
+	alias: &AliasAnalysis,
+) -> Option<Plan> {
+	let id = loop_.borrow().id;
+	let info = loopdata.loop_infos.get(&id)?;
+	if let Some(cnt) = info.has_const_loop_cnt() {
+		let min = if loop_.borrow().subloops.is_empty() {
+			MIN_ITERATIONS
+		} else {
+			MIN_TRIPS
+		};
+		if cnt < min {
+			return None;
 		}
 	}
-
-	// dbg!(&indvar_ptr_set, &ptr_set);
-
-	check_ok(
-		root_loop.clone(),
-		&mut ptr_set,
-		&mut indvar_ptr_set,
-		&mut ok_loop_id,
-		&func.cfg,
-		&loop_map,
-	);
-
-	// dbg!(&ok_loop_id);
-
-	parallel_loop(
-		root_loop.clone(),
-		&ok_loop_id,
-		&mut loop_map,
-		&mut loop_infos,
-		mgr,
-		&mut indvars,
-	);
-
-	let temp_graph = LoopData::build_graph(func);
-	let def_map = LoopData::build_def_map(func);
-
-	LoopData {
-		temp_graph,
-		loop_map,
-		def_map,
-		root_loop,
-		loop_infos,
-		indvars,
-	}
-}
-
-fn last_check(info: LoopInfo, indvars: &HashMap<LlvmTemp, IndVar>) -> bool {
-	let header = info.header.clone();
-	let exit = info.single_exit.clone();
-
 	if !matches!(
 		info.comp_op,
-		llvm::CompOp::SGT
-			| llvm::CompOp::SGE
-			| llvm::CompOp::SLT
-			| llvm::CompOp::SLE
+		CompOp::SGT | CompOp::SGE | CompOp::SLT | CompOp::SLE
 	) {
-		dbg!(1);
-		return false;
-	}
-
-	if header.borrow().phi_instrs.is_empty() {
-		return false;
-	}
-
-	for item in header.borrow().phi_instrs.iter() {
-		// eprintln!("{}", item);
-		// dbg!(&item.target);
-		if !matches!(
-			item.var_type,
-			llvm::VarType::I32 | llvm::VarType::I32Ptr | llvm::VarType::F32Ptr
-		) {
-			return false;
-		}
-		if indvars.get(&item.target).is_some_and(|indvar| {
-			// dbg!(indvar.get_type());
-			matches!(
-				indvar.get_type(),
-				crate::loops::indvar_type::IndVarType::Ordinary // | crate::loops::indvar_type::IndVarType::OrdinaryZFP
-			)
-		}) {
+		return None;
+	}
+	let header = info.header.borrow();
+	// 条件不成立时离开循环
+	match header.jump_instr.as_ref().map(|v| v.get_variant()) {
+		Some(LlvmInstrVariant::JumpCondInstr(jump))
+			if jump.target_false == info.single_exit.borrow().label() => {}
+		_ => return None,
+	}
+	if header.phi_instrs.is_empty() {
+		return None;
+	}
+	// 循环头中的 phi 只能是一阶归纳变量或者归约变量
+	let blocks = loop_.borrow().blocks(&loopdata.loop_map);
+	let mut reductions = Vec::new();
+	for phi in header.phi_instrs.iter() {
+		let ordinary = loopdata
+			.indvars
+			.get(&phi.target)
+			.is_some_and(|v| v.get_type() == IndVarType::Ordinary);
+		if ordinary && (phi.var_type == VarType::I32 || phi.var_type.is_ptr()) {
 			continue;
 		}
-		// dbg!(2);
-		return false;
-	}
-	let jump_ok = match header.borrow().jump_instr.as_ref() {
-		Some(jump) => match jump.get_variant() {
-			llvm::LlvmInstrVariant::JumpCondInstr(cond) => {
-				cond.target_false == exit.borrow().label()
-			}
-			_ => false,
-		},
-		None => false,
-	};
-
-	// dbg!(header.borrow().phi_instrs.len());
-	jump_ok
+		reductions.push(find_reduction(phi, info, &blocks, func)?);
+	}
+	// 合并归约结果的指令放在出口的开头，出口的 phi 不能使用归约变量
+	if !reductions.is_empty() && !info.single_exit.borrow().phi_instrs.is_empty()
+	{
+		return None;
+	}
+	let has_calls = check_dependence(loop_, loopdata, alias)?;
+	Some(Plan {
+		id,
+		info: info.clone(),
+		blocks,
+		reductions,
+		has_calls,
+	})
 }
 
-fn parallel_loop(
-	current: LoopPtr,
-	ok: &HashSet<i32>,
-	loop_map: &mut HashMap<i32, LoopPtr>,
-	loop_info: &mut HashMap<i32, LoopInfo>,
-	mgr: &mut LlvmTempManager,
-	indvars: &mut HashMap<LlvmTemp, IndVar>,
-) {
-	let current_id = current.borrow().id;
-
-	let mut operate_on_this = ok.contains(&current_id);
-
-	let is_single_layer = current.borrow().subloops.is_empty();
-
-	let mut operated = false;
-
-	if let Some(info) = loop_info.get_mut(&current_id) {
-		if let (Value::Int(start), Value::Int(step), Value::Int(end)) =
-			(&info.begin, &info.step, &info.end)
-		{
-			if is_single_layer && ((end - start) / step) < 4096 {
-				operate_on_this = false;
+// 函数中局部数组占用的栈空间
+fn frame_size(func: &LlvmFunc) -> i64 {
+	let mut size = 0;
+	for block in func.cfg.blocks.iter() {
+		for instr in block.borrow().instrs.iter() {
+			if let Some((_, Value::Int(length))) = instr.get_alloc() {
+				size += length as i64;
 			}
 		}
-
-		let preheader = info.preheader.clone();
-
-		if operate_on_this && last_check(info.clone(), indvars) {
-			let pre_id = preheader.borrow().id;
-			if loop_map.get(&pre_id).cloned().is_some() {
-				let (new_start, new_end, new_index) =
-					make_parallel(info.clone(), mgr, indvars);
-
-				indvars.insert(
-					new_index,
-					IndVar {
-						base: 0.into(),
-						scale: 1.into(),
-						step: vec![1.into()],
-						zfp: None,
-					},
-				);
-
-				info.begin = new_start;
-				info.end = new_end;
-
-				operated = true;
-			}
-		}
-	}
-
-	if operated {
-		eprintln!(
-			"para {} B{}",
-			current.borrow().id,
-			current.borrow().header.borrow().id
-		);
-	}
-	// else {
-	// 	eprintln!(
-	// 		"fail {} B{}",
-	// 		current.borrow().id,
-	// 		current.borrow().header.borrow().id
-	// 	);
-	// }
-
-	if operated || current_id != 1 {
-		return;
-	}
-
-	for sub in current.borrow().subloops.iter().cloned() {
-		parallel_loop(sub, ok, loop_map, loop_info, mgr, indvars);
 	}
+	size
 }
@@ -0,0 +1,276 @@
+// 识别循环头中的归约变量：每次迭代只把某个值加到（减去）它上面，
+// 或者与某个值取最小值、最大值，且中间结果不作他用。
+// 这样各线程可以分别归约自己的部分，最后再合并
+
+use std::collections::{HashMap, HashSet};
+
+use llvm::{
+	ArithInstr, ArithOp, CompInstr, CompKind, CompOp, LlvmInstr,
+	LlvmInstrVariant, LlvmTemp, PhiInstr, Value, VarType,
+};
+use rrvm::{program::LlvmFunc, LlvmNode};
+use utils::Label;
+
+use crate::loops::loopinfo::LoopInfo;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReductionOp {
+	Sum,
+	Min,
+	Max,
+}
+
+#[derive(Clone, Debug)]
+pub struct Reduction {
+	pub phi: LlvmTemp,
+	pub op: ReductionOp,
+}
+
+// 循环中对一个临时变量的使用
+enum Use {
+	Instr(LlvmInstr),
+	Phi(LlvmTemp),
+	Jump,
+}
+
+struct LoopBody {
+	uses: HashMap<LlvmTemp, Vec<Use>>,
+	ariths: HashMap<LlvmTemp, ArithInstr>,
+	comps: HashMap<LlvmTemp, CompInstr>,
+	// 循环中的 phi 及其所在的块
+	phis: HashMap<LlvmTemp, (PhiInstr, LlvmNode)>,
+}
+
+impl LoopBody {
+	fn new(blocks: &[LlvmNode]) -> Self {
+		let mut body = Self {
+			uses: HashMap::new(),
+			ariths: HashMap::new(),
+			comps: HashMap::new(),
+			phis: HashMap::new(),
+		};
+		for node in blocks.iter() {
+			let block = node.borrow();
+			for phi in block.phi_instrs.iter() {
+				for temp in phi.source.iter().filter_map(|(v, _)| v.unwrap_temp()) {
+					body.uses.entry(temp).or_default().push(Use::Phi(phi.target.clone()));
+				}
+				body.phis.insert(phi.target.clone(), (phi.clone(), node.clone()));
+			}
+			for instr in block.instrs.iter() {
+				for temp in instr.get_read() {
+					body.uses.entry(temp).or_default().push(Use::Instr(instr.clone()));
+				}
+				match instr.get_variant() {
+					LlvmInstrVariant::ArithInstr(v) => {
+						body.ariths.insert(v.target.clone(), v.clone());
+					}
+					LlvmInstrVariant::CompInstr(v) => {
+						body.comps.insert(v.target.clone(), v.clone());
+					}
+					_ => {}
+				}
+			}
+			for temp in block.jump_instr.iter().flat_map(|v| v.get_read()) {
+				body.uses.entry(temp).or_default().push(Use::Jump);
+			}
+		}
+		body
+	}
+}
+
+/// 如果循环头中的 `phi` 是归约变量，找出归约的运算
+pub fn find_reduction(
+	phi: &PhiInstr,
+	info: &LoopInfo,
+	blocks: &[LlvmNode],
+	func: &LlvmFunc,
+) -> Option<Reduction> {
+	if phi.var_type != VarType::I32 || phi.source.len() != 2 {
+		return None;
+	}
+	let preheader = info.preheader.borrow().label();
+	let (latch, _) = phi.source.iter().find(|(_, label)| *label != preheader)?;
+	let body = LoopBody::new(blocks);
+	// 从 phi 出发，经过运算与 phi 得到的中间结果
+	let mut chain = HashSet::from([phi.target.clone()]);
+	let mut stack = vec![phi.target.clone()];
+	let mut comps = HashSet::new();
+	while let Some(temp) = stack.pop() {
+		for use_ in body.uses.get(&temp).into_iter().flatten() {
+			let target = match use_ {
+				Use::Instr(instr) => match instr.get_variant() {
+					LlvmInstrVariant::ArithInstr(v) => v.target.clone(),
+					LlvmInstrVariant::CompInstr(v) => {
+						comps.insert(v.target.clone());
+						continue;
+					}
+					_ => return None,
+				},
+				Use::Phi(target) => target.clone(),
+				Use::Jump => return None,
+			};
+			if chain.insert(target.clone()) {
+				stack.push(target);
+			}
+		}
+	}
+	let in_chain =
+		|v: &Value| v.unwrap_temp().is_some_and(|v| chain.contains(&v));
+	if !in_chain(latch) {
+		return None;
+	}
+	let mut op = None;
+	let mut selects = HashSet::new();
+	for temp in chain.iter().filter(|v| **v != phi.target) {
+		if temp.var_type != VarType::I32 {
+			return None;
+		}
+		let kind = if let Some(instr) = body.ariths.get(temp) {
+			match instr.op {
+				ArithOp::Add if in_chain(&instr.lhs) != in_chain(&instr.rhs) => {
+					ReductionOp::Sum
+				}
+				ArithOp::Sub if in_chain(&instr.lhs) && !in_chain(&instr.rhs) => {
+					ReductionOp::Sum
+				}
+				_ => return None,
+			}
+		} else {
+			let (instr, node) = body.phis.get(temp)?;
+			// 合并分支时不改变归约的运算
+			if instr.source.iter().all(|(v, _)| in_chain(v)) {
+				continue;
+			}
+			let (kind, comp) = select(instr, node, &in_chain, &body)?;
+			selects.insert(comp);
+			kind
+		};
+		if op.is_some_and(|op| op != kind) {
+			return None;
+		}
+		op = Some(kind);
+	}
+	if comps != selects {
+		return None;
+	}
+	// 中间结果不能在循环外使用，循环结束后 phi 的值会被替换为合并的结果
+	let inside: HashSet<_> = blocks.iter().map(|v| v.borrow().id).collect();
+	for block in func.cfg.blocks.iter() {
+		let block = block.borrow();
+		if inside.contains(&block.id) {
+			continue;
+		}
+		let reads = block
+			.phi_instrs
+			.iter()
+			.flat_map(|v| v.source.iter().filter_map(|(v, _)| v.unwrap_temp()))
+			.chain(block.instrs.iter().flat_map(|v| v.get_read()))
+			.chain(block.jump_instr.iter().flat_map(|v| v.get_read()));
+		for temp in reads {
+			if temp != phi.target && chain.contains(&temp) {
+				return None;
+			}
+		}
+	}
+	Some(Reduction {
+		phi: phi.target.clone(),
+		op: op?,
+	})
+}
+
+// 形如 `x > m ? x : m` 的分支与 phi，其中 `m` 是中间结果，
+// 返回取最小值还是最大值，以及用作条件的比较
+fn select(
+	phi: &PhiInstr,
+	node: &LlvmNode,
+	in_chain: &impl Fn(&Value) -> bool,
+	body: &LoopBody,
+) -> Option<(ReductionOp, LlvmTemp)> {
+	let [(v0, l0), (v1, l1)] = phi.source.as_slice() else {
+		return None;
+	};
+	let ((m, lm), (x, lx)) = match (in_chain(v0), in_chain(v1)) {
+		(true, false) => ((v0, l0), (v1, l1)),
+		(false, true) => ((v1, l1), (v0, l0)),
+		_ => return None,
+	};
+	let block = node.borrow();
+	let pred = |label: &Label| {
+		block.prev.iter().find(|v| v.borrow().label() == *label).cloned()
+	};
+	let (pm, px) = (pred(lm)?, pred(lx)?);
+	// 分支所在的块是某个来源块本身，或是来源块唯一的前驱
+	let mut candidates = vec![pm.clone(), px.clone()];
+	for pred in [&pm, &px] {
+		if pred.borrow().prev.len() == 1 {
+			candidates.push(pred.borrow().prev[0].clone());
+		}
+	}
+	for branch in candidates {
+		let branch = branch.borrow();
+		let jump = branch.jump_instr.as_ref().map(|v| v.get_variant());
+		let Some(LlvmInstrVariant::JumpCondInstr(jump)) = jump else {
+			continue;
+		};
+		let id = branch.id;
+		// 经过来源块到达 phi 时分支条件的值
+		let arm = |pred: &LlvmNode| {
+			let pred = pred.borrow();
+			if pred.id == id {
+				let label = block.label();
+				match (jump.target_true == label, jump.target_false == label) {
+					(true, false) => Some(true),
+					(false, true) => Some(false),
+					_ => None,
+				}
+			} else if pred.prev.len() == 1
+				&& pred.prev[0].borrow().id == id
+				&& pred.succ.len() == 1
+			{
+				Some(jump.target_true == pred.label())
+			} else {
+				None
+			}
+		};
+		let (Some(arm_m), Some(arm_x)) = (arm(&pm), arm(&px)) else {
+			continue;
+		};
+		if arm_m == arm_x {
+			continue;
+		}
+		let comp = body.comps.get(&jump.cond.unwrap_temp()?)?;
+		if !matches!(comp.kind, CompKind::Icmp)
+			|| body.uses.get(&comp.target).map_or(0, |v| v.len()) != 1
+		{
+			return None;
+		}
+		// 条件化为 `x op m`
+		let op = if comp.lhs == *x && comp.rhs == *m {
+			comp.op
+		} else if comp.lhs == *m && comp.rhs == *x {
+			match comp.op {
+				CompOp::SGT => CompOp::SLT,
+				CompOp::SGE => CompOp::SLE,
+				CompOp::SLT => CompOp::SGT,
+				CompOp::SLE => CompOp::SGE,
+				_ => return None,
+			}
+		} else {
+			return None;
+		};
+		let greater = match op {
+			CompOp::SGT | CompOp::SGE => true,
+			CompOp::SLT | CompOp::SLE => false,
+			_ => return None,
+		};
+		// 条件成立时选 `x` 且 `x` 更大，即为取最大值
+		let kind = if greater == arm_x {
+			ReductionOp::Max
+		} else {
+			ReductionOp::Min
+		};
+		return Some((kind, comp.target.clone()));
+	}
+	None
+}
@@ -7,11 +7,14 @@ use super::{
 	Mem2Reg,
 };
 use crate::{
+	alias_analysis::{AliasAnalysis, ModRef, Object},
 	metadata::MetaData,
 	number::{str2num, Number},
 	RrvmOptimizer,
 };
-use llvm::{LlvmInstrVariant::*, LlvmTemp, LlvmTempManager, Value, VarType};
+use llvm::{
+	CallInstr, LlvmInstrVariant::*, LlvmTemp, LlvmTempManager, Value, VarType,
+};
 use rand::{rngs::StdRng, SeedableRng};
 use rrvm::{
 	dominator::LlvmDomTree,
@@ -20,9 +23,61 @@ use rrvm::{
 };
 use utils::{errors::Result, Label, MEM_TO_REG_LIMIT};
 
+// 每个地址所在的对象，以及每个调用（以其 target 表示）对各个对象的读写
+type CallEffects = (
+	HashMap<LlvmTemp, Object>,
+	HashMap<(LlvmTemp, Object), ModRef>,
+);
+
+// 用别名分析求出调用的副作用，Solver 持有 MetaData 的可变引用，所以预先求出
+fn call_effects(func: &LlvmFunc, metadata: &MetaData) -> CallEffects {
+	let alias = AliasAnalysis::new(func, metadata);
+	let mut objects = HashMap::new();
+	let mut calls = Vec::new();
+	for block in func.cfg.blocks.iter() {
+		let block = block.borrow();
+		for instr in block.instrs.iter() {
+			let addrs: Vec<_> = match instr.get_variant() {
+				LoadInstr(instr) if !instr.addr.is_global() => vec![&instr.addr],
+				StoreInstr(instr) => vec![&instr.addr],
+				CallInstr(instr) => {
+					calls.push(instr.clone());
+					instr
+						.params
+						.iter()
+						.filter(|(var_type, _)| var_type.is_ptr())
+						.map(|(_, value)| value)
+						.collect()
+				}
+				_ => Vec::new(),
+			};
+			for addr in addrs {
+				if let Some(temp) = addr.unwrap_temp() {
+					objects.entry(temp).or_insert_with(|| alias.locate(addr).object);
+				}
+			}
+		}
+	}
+	// 调用对一个对象的读写只与对象有关，每个对象取一个地址询问即可
+	let mut addrs: HashMap<Object, Value> = HashMap::new();
+	for (temp, object) in objects.iter() {
+		addrs.entry(object.clone()).or_insert_with(|| temp.clone().into());
+	}
+	let mut mod_refs = HashMap::new();
+	for call in calls.iter() {
+		for (object, addr) in addrs.iter() {
+			let mod_ref = alias.call_mod_ref(call, addr);
+			mod_refs.insert((call.target.clone(), object.clone()), mod_ref);
+		}
+	}
+	(objects, mod_refs)
+}
+
 struct Solver<'a> {
 	func_name: String,
 	dom_tree: LlvmDomTree,
+	// 反向支配树，用于判断每个块后必经的块
+	post_dom_tree: LlvmDomTree,
 	rng: StdRng,
 	metadata: &'a mut MetaData,
 	mgr: &'a mut LlvmTempManager,
@@ -36,6 +91,10 @@ struct Solver<'a> {
 	array_states: HashMap<i32, ArrayState>,
 	use_states: HashMap<i32, UseStateItem>,
 	global_base: HashSet<Number>,
+	objects: HashMap<LlvmTemp, Object>,
+	// 每个 base 所在的对象
+	base_objects: HashMap<Number, Object>,
+	mod_refs: HashMap<(LlvmTemp, Object), ModRef>,
 	stack: Vec<(LlvmNode, HashSet<Number>)>,
 }
 
@@ -44,14 +103,15 @@ impl<'a> Solver<'a> {
 		func: &LlvmFunc,
 		mgr: &'a mut LlvmTempManager,
 		metadata: &'a mut MetaData,
-	) -> Self {
+		(objects, mod_refs): CallEffects,
+	) -> Option<Self> {
 		let mut addr_mapper = HashMap::new();
 		let mut global_base = HashSet::new();
 		let func_data = metadata.get_func_data(&func.name);
 		for param in func.params.iter() {
 			if param.get_type().is_ptr() {
 				let temp = param.unwrap_temp().unwrap();
-				let number = func_data.get_number(&temp).unwrap();
+				let number = func_data.get_number(&temp)?;
 				addr_mapper.insert(temp, Addr::new(number.clone(), 0u32.into()));
 				global_base.insert(number.clone());
 			}
@@ -60,9 +120,10 @@ impl<'a> Solver<'a> {
 			let base = str2num(store_var);
 			global_base.insert(base);
 		}
-		Self {
+		Some(Self {
 			func_name: func.name.clone(),
 			dom_tree: LlvmDomTree::new(&func.cfg, false),
+			post_dom_tree: LlvmDomTree::new(&func.cfg, true),
 			rng: StdRng::from_entropy(),
 			base_addrs: HashMap::new(),
 			addr_info: HashMap::new(),
@@ -77,7 +138,10 @@ impl<'a> Solver<'a> {
 			global_base,
 			addr_mapper,
 			metadata,
-		}
+			objects,
+			base_objects: HashMap::new(),
+			mod_refs,
+		})
 	}
 	fn get_addr(&self, temp: &LlvmTemp) -> Addr {
 		self.addr_mapper.get(temp).unwrap().clone()
@@ -103,19 +167,57 @@ impl<'a> Solver<'a> {
 	fn addr_anticipate(&self, id: i32, addr: &Addr) -> bool {
 		self.addr2temp.get(&id).map(|v| v.contains_key(addr)).unwrap_or(false)
 	}
+	fn insert_addr(&mut self, temp: &LlvmTemp) {
+		let addr = self.get_addr(temp);
+		let object = self.objects.get(temp).cloned().unwrap_or(Object::Unknown);
+		let entry =
+			self.base_objects.entry(addr.base.clone()).or_insert(object.clone());
+		// 同一个 base 的地址指向不同对象时，看作未知对象
+		if *entry != object {
+			*entry = Object::Unknown;
+		}
+		self.addrs.insert(addr);
+	}
+	fn call_mod_ref(&self, call: &CallInstr, base: &Number) -> ModRef {
+		self
+			.base_objects
+			.get(base)
+			.and_then(|object| {
+				self.mod_refs.get(&(call.target.clone(), object.clone()))
+			})
+			.copied()
+			.unwrap_or(ModRef::Both)
+	}
+	// 调用可能写入的 base
+	fn call_mods(&self, call: &CallInstr) -> Vec<Number> {
+		let bases = self.base_addrs.keys();
+		bases
+			.filter(|base| self.call_mod_ref(call, base).may_mod())
+			.cloned()
+			.collect()
+	}
+	// 调用可能读取的 base
+	fn call_refs(&self, call: &CallInstr) -> Vec<Number> {
+		let bases = self.base_addrs.keys();
+		bases
+			.filter(|base| self.call_mod_ref(call, base).may_ref())
+			.cloned()
+			.collect()
+	}
 
 	// part1: get all address that used in function
+	// 地址的编号由 gvn 给出，缺少编号时返回 None
 	pub fn calc_addr(
 		&mut self,
 		node: LlvmNode,
 		mut addr2temp: HashMap<Addr, Value>,
-	) {
+	) -> Option<()> {
 		for instr in node.borrow().phi_instrs.iter() {
 			if instr.var_type.is_ptr() {
 				let src_addr = instr.source.iter().find_map(|(value, _)| {
 					self.try_get_addr(&value.unwrap_temp().unwrap())
 				});
-				let number = self.get_number(&instr.target).unwrap();
+				let number = self.get_number(&instr.target)?;
 				let addr = Addr::new(src_addr.unwrap().base, number);
 				self.addr_mapper.insert(instr.target.clone(), addr);
 			}
@@ -123,36 +225,36 @@ impl<'a> Solver<'a> {
 		for instr in node.borrow().instrs.iter() {
 			match instr.get_variant() {
 				AllocInstr(instr) => {
-					let number = self.get_number(&instr.target).unwrap();
+					let number = self.get_number(&instr.target)?;
 					let addr = Addr::new(number.clone(), 0u32.into());
 					self.addr_mapper.insert(instr.target.clone(), addr);
 				}
 				LoadInstr(instr) => {
 					let temp = instr.addr.unwrap_temp().unwrap();
 					if temp.is_global {
-						let number = self.get_number(&temp).unwrap();
+						let number = self.get_number(&temp)?;
 						let addr = Addr::new(number.clone(), 0u32.into());
 						addr2temp.insert(addr.clone(), instr.target.clone().into());
 						self.addr_mapper.insert(instr.target.clone(), addr);
 						self.global_base.insert(number.clone());
 					} else {
-						self.addrs.insert(self.get_addr(&temp));
+						self.insert_addr(&temp);
 					}
 				}
 				StoreInstr(instr) => {
 					let temp = instr.addr.unwrap_temp().unwrap();
-					self.addrs.insert(self.get_addr(&temp));
+					self.insert_addr(&temp);
 				}
 				CallInstr(instr) => {
 					for (var_type, param) in instr.params.iter() {
 						if var_type.is_ptr() {
 							let temp = param.unwrap_temp().unwrap();
-							self.addrs.insert(self.get_addr(&temp));
+							self.insert_addr(&temp);
 						}
 					}
 				}
 				GEPInstr(instr) => {
-					let offset = self.get_val_number(&instr.offset).unwrap();
+					let offset = self.get_val_number(&instr.offset)?;
 					let base =
 						self.addr_mapper.get(&instr.addr.unwrap_temp().unwrap()).unwrap();
 					let addr = Addr::new(base.base.clone(), &base.offset + offset);
@@ -163,9 +265,10 @@ impl<'a> Solver<'a> {
 			}
 		}
 		for v in self.dom_tree.get_children(node.borrow().id).clone() {
-			self.calc_addr(v, addr2temp.clone());
+			self.calc_addr(v, addr2temp.clone())?;
 		}
 		self.addr2temp.insert(node.borrow().id, addr2temp);
+		Some(())
 	}
 
 	// part2: get all places that value is defined
@@ -195,25 +298,9 @@ impl<'a> Solver<'a> {
 					}
 
 					CallInstr(instr) => {
-						for (index, (var_type, param)) in instr.params.iter().enumerate() {
-							if var_type.is_ptr()
-								&& self
-									.metadata
-									.get_var_data(&(instr.func.name.clone(), index))
-									.to_store
-							{
-								let addr = self.get_val_addr(param);
-								for addr in info.remove(&addr.base) {
-									self.insert_def(addr, block.id);
-								}
-							}
-						}
-						let func_data = self.metadata.get_func_data(&instr.func.name);
-						for (global_var, _) in func_data.usage_info.may_stores.iter() {
-							let base = str2num(global_var);
-							let addrs = info.remove(&base);
-							for addr in addrs {
-								self.addr_info.entry(addr).or_default().insert_def(block.id);
+						for base in self.call_mods(instr) {
+							for addr in info.remove(&base) {
+								self.insert_def(addr, block.id);
 							}
 						}
 					}
@@ -304,20 +391,7 @@ impl<'a> Solver<'a> {
 						vec![ori_instr]
 					}
 					CallInstr(instr) => {
-						for (index, (var_type, param)) in instr.params.iter().enumerate() {
-							if var_type.is_ptr()
-								&& self
-									.metadata
-									.get_var_data(&(instr.func.name.clone(), index))
-									.to_store
-							{
-								let addr = self.get_val_addr(param);
-								array_state.remove_base(&addr.base);
-							}
-						}
-						let func_data = self.metadata.get_func_data(&instr.func.name);
-						for (global_var, _) in func_data.usage_info.may_stores.iter() {
-							let base = str2num(global_var);
+						for base in self.call_mods(instr) {
 							array_state.remove_base(&base);
 							phi_info.remove(&base);
 						}
@@ -475,29 +549,9 @@ impl<'a> Solver<'a> {
 							}
 						}
 						CallInstr(instr) => {
-							for (index, (var_type, param)) in instr.params.iter().enumerate()
-							{
-								if var_type.is_ptr() {
-									let addr = self.get_val_addr(param);
-									if var_type.is_ptr() {
-										let var_data = self
-											.metadata
-											.get_var_data(&(instr.func.name.clone(), index));
-										if var_data.to_load {
-											let addrs =
-												self.base_addrs.get(&addr.base).unwrap().clone();
-											loads.extend(addrs);
-										}
-									}
-								}
-							}
-							let func_data = self.metadata.get_func_data(&instr.func.name);
-							for (global_var, _) in func_data.usage_info.may_loads.iter() {
-								let base = str2num(global_var);
+							for base in self.call_refs(instr) {
 								stores.retain(|v, _| v.base != base);
-								let addrs =
-									self.base_addrs.get(&base).cloned().unwrap_or_default();
-								loads.extend(addrs);
+								loads.extend(self.base_addrs[&base].iter().cloned());
 							}
 						}
 						_ => {}
@@ -550,24 +604,9 @@ impl<'a> Solver<'a> {
 						}
 				}
 				CallInstr(instr) => {
-					for (index, (var_type, param)) in instr.params.iter().enumerate() {
-						if var_type.is_ptr() {
-							let addr = self.get_val_addr(param);
-							let var_data =
-								self.metadata.get_var_data(&(instr.func.name.clone(), index));
-							if var_data.to_load {
-								let addrs = self.base_addrs.get(&addr.base).unwrap().clone();
-								state.loads.extend(addrs);
-							}
-						}
-					}
-
-					let func_data = self.metadata.get_func_data(&instr.func.name);
-					for (global_var, _) in func_data.usage_info.may_loads.iter() {
-						let base = str2num(global_var);
+					for base in self.call_refs(instr) {
 						state.stores.retain(|v, _| v.base != base);
-						let addrs = self.base_addrs.get(&base).cloned().unwrap_or_default();
-						state.loads.extend(addrs);
+						state.loads.extend(self.base_addrs[&base].iter().cloned());
 					}
 					true
 				}
@@ -579,6 +618,13 @@ impl<'a> Solver<'a> {
 
 	// optional part 1: hoist load instruction
 	fn load_hoisting(&mut self, node: &LlvmNode) -> HashSet<Number> {
+		// 只外提到一定会执行到这里的块，下标可能被条件保护
+		let id = node.borrow().id;
+		let always: Vec<_> = self
+			.stack
+			.iter()
+			.map(|(v, _)| self.post_dom_tree.dominates(id, v.borrow().id))
+			.collect();
 		let mut block = node.borrow_mut();
 		let init_weight = block.weight;
 		let mut store_base = HashSet::new();
@@ -594,8 +640,10 @@ impl<'a> Solver<'a> {
 					store_base.contains(&addr.base) || {
 						let mut best_weight = init_weight * 0.99;
 						let mut best_node = None;
-						for (node, store_base) in self.stack.iter().rev() {
-							if !self.addr_anticipate(node.borrow().id, &addr) {
+						for ((node, store_base), always) in
+							self.stack.iter().zip(always.iter()).rev()
+						{
+							if !always || !self.addr_anticipate(node.borrow().id, &addr) {
 								break;
 							}
 							let weight = node.borrow().weight;
@@ -622,23 +670,7 @@ impl<'a> Solver<'a> {
 				true
 			}
 			CallInstr(instr) => {
-				for (index, (var_type, param)) in instr.params.iter().enumerate() {
-					if var_type.is_ptr() {
-						let addr = self.get_val_addr(param);
-						let var_data =
-							self.metadata.get_var_data(&(instr.func.name.clone(), index));
-						if var_data.to_store {
-							store_base.insert(addr.base);
-						}
-					}
-				}
-				let func_data = self.metadata.get_func_data(&instr.func.name);
-				for (global_var, _) in func_data.usage_info.may_stores.iter() {
-					let base = str2num(global_var);
-					if self.global_base.contains(&base) {
-						store_base.insert(base);
-					}
-				}
+				store_base.extend(self.call_mods(instr));
 				true
 			}
 			_ => true,
@@ -671,9 +703,14 @@ impl RrvmOptimizer for Mem2Reg {
 			mgr: &mut LlvmTempManager,
 			metadata: &mut MetaData,
 		) -> bool {
-			let mut solver = Solver::new(func, mgr, metadata);
-			solver.calc_addr(func.cfg.get_entry(), HashMap::new());
-			if solver.addrs.is_empty() || solver.addrs.len() > MEM_TO_REG_LIMIT {
+			let effects = call_effects(func, metadata);
+			let Some(mut solver) = Solver::new(func, mgr, metadata, effects) else {
+				return false;
+			};
+			if solver.calc_addr(func.cfg.get_entry(), HashMap::new()).is_none()
+				|| solver.addrs.is_empty()
+				|| solver.addrs.len() > MEM_TO_REG_LIMIT
+			{
 				return false;
 			}
 			solver.calc_defs(func);
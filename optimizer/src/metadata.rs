@@ -7,6 +7,11 @@ use crate::number::Number;
 /// Identifier of global variable (as long to func params)
 pub type VarIdent = (String, usize);
 
+/// What a system call loads and stores, which is none of the variables
+pub fn syscall_ident() -> VarIdent {
+	("系统调用".to_owned(), 0)
+}
+
 #[derive(Default, Debug, Clone, Copy)]
 pub struct VarData {
 	pub to_load: bool,
@@ -67,15 +72,39 @@ impl FuncData {
 	}
 	pub fn set_syscall(&mut self) {
 		self.pure = false;
-		self.usage_info.may_loads.insert(("系统调用".to_owned(), 0));
-		self.usage_info.may_stores.insert(("系统调用".to_owned(), 0));
+		self.usage_info.may_loads.insert(syscall_ident());
+		self.usage_info.may_stores.insert(syscall_ident());
 	}
 }
 
+/// Calls of pure functions evaluated at compile time
 #[derive(Default)]
+pub struct EvalData {
+	/// the results by the callee and the bits of the arguments, `None` for a
+	/// call given up
+	pub results: HashMap<(String, Vec<u32>), Option<Value>>,
+	/// steps run so far
+	pub steps: u64,
+}
+
+/// Threads a parallel loop runs on unless `--threads` says otherwise
+pub const DEFAULT_THREADS: usize = 4;
+
 pub struct MetaData {
 	pub func_data: HashMap<String, FuncData>,
 	pub var_data: HashMap<VarIdent, VarData>,
+	/// the program is a module linked with others, which may use what it
+	/// defines
+	pub exported: bool,
+	pub eval_data: EvalData,
+	/// threads `parallel` splits a loop among, less than 2 to not split
+	pub threads: usize,
+}
+
+impl Default for MetaData {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl MetaData {
@@ -83,25 +112,28 @@ impl MetaData {
 		Self {
 			func_data: HashMap::new(),
 			var_data: HashMap::new(),
+			exported: false,
+			eval_data: EvalData::default(),
+			threads: DEFAULT_THREADS,
 		}
 	}
 	pub fn get_func_data(&mut self, func_name: &str) -> &mut FuncData {
 		self.func_data.entry(func_name.to_string()).or_default()
 	}
-	pub fn is_pure(&mut self, func_name: &str) -> bool {
+	pub fn is_pure(&self, func_name: &str) -> bool {
 		self.func_data.get(func_name).map(|data| data.pure).unwrap_or(false)
 	}
 	pub fn get_var_data(&mut self, var_ident: &VarIdent) -> &mut VarData {
 		self.var_data.entry(var_ident.clone()).or_default()
 	}
-	pub fn may_load(&mut self, func_name: &str, var_ident: &VarIdent) -> bool {
+	pub fn may_load(&self, func_name: &str, var_ident: &VarIdent) -> bool {
 		self
 			.func_data
 			.get(func_name)
 			.map(|data| data.may_load(var_ident))
 			.unwrap_or(false)
 	}
-	pub fn may_store(&mut self, func_name: &str, var_ident: &VarIdent) -> bool {
+	pub fn may_store(&self, func_name: &str, var_ident: &VarIdent) -> bool {
 		self
 			.func_data
 			.get(func_name)
@@ -0,0 +1,96 @@
+use interpreter::{Evaluator, Val};
+use llvm::{ArithInstr, LlvmInstrVariant, Value, VarType};
+use rrvm::program::LlvmProgram;
+use utils::errors::Result;
+
+use super::PartialEvaluation;
+use crate::{metadata::MetaData, RrvmOptimizer};
+
+// the steps one call may run, and those all the calls may run
+const CALL_LIMIT: u64 = 1 << 20;
+const TOTAL_LIMIT: u64 = 1 << 22;
+
+// the bits of the arguments if they are all constants
+fn constant_args(params: &[(VarType, Value)]) -> Option<Vec<u32>> {
+	params
+		.iter()
+		.map(|(_, value)| match value {
+			Value::Int(v) => Some(*v as u32),
+			Value::Float(v) => Some(v.to_bits()),
+			Value::Temp(_) => None,
+		})
+		.collect()
+}
+
+fn evaluate(
+	evaluator: &Evaluator,
+	metadata: &mut MetaData,
+	name: &str,
+	ret: VarType,
+	params: &[(VarType, Value)],
+	bits: Vec<u32>,
+) -> Option<Value> {
+	let data = &mut metadata.eval_data;
+	let key = (name.to_string(), bits);
+	if let Some(result) = data.results.get(&key) {
+		return result.clone();
+	}
+	let args = params
+		.iter()
+		.zip(key.1.iter())
+		.map(|((var_type, _), v)| match var_type {
+			VarType::F32 => Val::Float(f32::from_bits(*v)),
+			_ => Val::from(*v as i32),
+		})
+		.collect();
+	let limit = CALL_LIMIT.min(TOTAL_LIMIT.saturating_sub(data.steps));
+	let mut fuel = limit;
+	let result = evaluator.call(name, args, &mut fuel).ok();
+	data.steps += limit - fuel;
+	let result = result.map(|v| match ret {
+		VarType::F32 => Value::Float(v.as_float()),
+		_ => Value::Int(v.as_int() as i32),
+	});
+	data.results.insert(key, result.clone());
+	result
+}
+
+impl RrvmOptimizer for PartialEvaluation {
+	fn new() -> Self {
+		Self {}
+	}
+	fn apply(
+		self,
+		program: &mut LlvmProgram,
+		metadata: &mut MetaData,
+	) -> Result<bool> {
+		let Ok(evaluator) = Evaluator::new(program) else {
+			return Ok(false);
+		};
+		let mut flag = false;
+		for func in program.funcs.iter() {
+			for block in func.cfg.blocks.iter() {
+				for instr in block.borrow_mut().instrs.iter_mut() {
+					let LlvmInstrVariant::CallInstr(v) = instr.get_variant() else {
+						continue;
+					};
+					if v.var_type == VarType::Void || !metadata.is_pure(&v.func.name) {
+						continue;
+					}
+					let Some(bits) = constant_args(&v.params) else {
+						continue;
+					};
+					let name = &v.func.name;
+					let Some(value) =
+						evaluate(&evaluator, metadata, name, v.var_type, &v.params, bits)
+					else {
+						continue;
+					};
+					*instr = ArithInstr::constant(v.target.clone(), value);
+					flag = true;
+				}
+			}
+		}
+		Ok(flag)
+	}
+}
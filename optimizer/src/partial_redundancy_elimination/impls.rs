@@ -0,0 +1,287 @@
+use std::collections::{HashMap, HashSet};
+
+use llvm::{
+	ArithOp, CompOp, ConvertOp, LlvmInstr, LlvmInstrVariant::*, LlvmTemp,
+	LlvmTempManager, PhiInstr, Value, VarType,
+};
+use rrvm::{
+	dominator::LlvmDomTree,
+	program::{LlvmFunc, LlvmProgram},
+	LlvmNode,
+};
+use utils::Result;
+
+use crate::{
+	metadata::{FuncData, MetaData},
+	number::{str2num, Number},
+	RrvmOptimizer,
+};
+
+use super::PartialRedundancyElimination;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum ExprOp {
+	Arith(ArithOp),
+	Comp(CompOp),
+	Convert(ConvertOp),
+	Gep,
+}
+
+/// An expression, by its operator and the numbers of its operands
+type Expr = (ExprOp, VarType, Vec<Number>);
+
+fn get_op(instr: &LlvmInstr) -> Option<(ExprOp, VarType)> {
+	match instr.get_variant() {
+		ArithInstr(instr) => Some((ExprOp::Arith(instr.op), instr.var_type)),
+		CompInstr(instr) => Some((ExprOp::Comp(instr.op), instr.var_type)),
+		ConvertInstr(instr) => Some((ExprOp::Convert(instr.op), instr.var_type)),
+		GEPInstr(instr) => Some((ExprOp::Gep, instr.var_type)),
+		_ => None,
+	}
+}
+
+fn may_trap(op: ExprOp) -> bool {
+	matches!(op, ExprOp::Arith(ArithOp::Div | ArithOp::Rem))
+}
+
+struct Solver<'a> {
+	dom_tree: LlvmDomTree,
+	data: &'a mut FuncData,
+	/// expressions computed in each block, and the temps holding them
+	exprs: HashMap<i32, HashMap<Expr, LlvmTemp>>,
+}
+
+impl<'a> Solver<'a> {
+	pub fn new(func: &LlvmFunc, data: &'a mut FuncData) -> Self {
+		Self {
+			dom_tree: LlvmDomTree::new(&func.cfg, false),
+			data,
+			exprs: HashMap::new(),
+		}
+	}
+
+	/// Record the expressions computed in each block
+	fn collect_exprs(&mut self, func: &LlvmFunc) {
+		for node in func.cfg.blocks.iter() {
+			let block = node.borrow();
+			let mut exprs = HashMap::new();
+			for instr in block.instrs.iter() {
+				if let Some(expr) = self.get_expr(instr, &instr.get_read_values()) {
+					exprs.insert(expr, instr.get_write().unwrap());
+				}
+			}
+			self.exprs.insert(block.id, exprs);
+		}
+	}
+
+	/// Move the loop-invariant expressions of each block right after a loop
+	/// header into the header, where `solve` takes them out of the loop. They
+	/// cannot trap, so computing them when the loop is left is harmless.
+	/// Return whether any is moved
+	fn speculate(&mut self, func: &LlvmFunc) -> bool {
+		let mut flag = false;
+		let mut defs = HashMap::new();
+		for node in func.cfg.blocks.iter() {
+			let block = node.borrow();
+			let phis = block.phi_instrs.iter().map(|v| v.target.clone());
+			for temp in phis.chain(block.instrs.iter().filter_map(|v| v.get_write()))
+			{
+				defs.insert(temp, block.id);
+			}
+		}
+		for node in func.cfg.blocks.iter() {
+			let id = node.borrow().id;
+			let header = match &node.borrow().prev[..] {
+				[header] if header.borrow().id != id => header.clone(),
+				_ => continue,
+			};
+			let header_id = header.borrow().id;
+			let latches: Vec<_> =
+				header.borrow().prev.iter().map(|v| v.borrow().id).collect();
+			if !latches.into_iter().any(|v| self.dom_tree.dominates(id, v)) {
+				continue;
+			}
+			let mut moved = HashSet::new();
+			let instrs = std::mem::take(&mut node.borrow_mut().instrs);
+			let mut kept = Vec::new();
+			for instr in instrs {
+				let invariant = get_op(&instr).is_some_and(|(op, _)| !may_trap(op))
+					&& instr.get_read_values().iter().all(|v| {
+						let Some(temp) = v.unwrap_temp() else {
+							return true;
+						};
+						match defs.get(&temp) {
+							_ if moved.contains(&temp) => true,
+							Some(&def) => {
+								def != header_id && self.dom_tree.dominates(def, header_id)
+							}
+							None => true,
+						}
+					});
+				if invariant {
+					moved.insert(instr.get_write().unwrap());
+					header.borrow_mut().instrs.push(instr);
+				} else {
+					kept.push(instr);
+				}
+			}
+			flag |= !moved.is_empty();
+			node.borrow_mut().instrs = kept;
+		}
+		flag
+	}
+
+	fn get_number(&mut self, value: &Value) -> Number {
+		if let Some(number) = self.data.get_val_number(value) {
+			return number;
+		}
+		// temps created after the latest GVN get a number of their own, drawn
+		// from their name so that every run of the compiler gives the same
+		let temp = value.unwrap_temp().unwrap();
+		let number = str2num(&temp.to_string());
+		self.data.set_number(temp, number.clone());
+		number
+	}
+
+	fn get_expr(
+		&mut self,
+		instr: &LlvmInstr,
+		operands: &[Value],
+	) -> Option<Expr> {
+		let (op, var_type) = get_op(instr)?;
+		let numbers = operands.iter().map(|v| self.get_number(v)).collect();
+		Some((op, var_type, numbers))
+	}
+
+	/// The temp holding `expr` at the end of `node`, if any
+	fn find(&mut self, node: &LlvmNode, expr: &Expr) -> Option<LlvmTemp> {
+		let mut id = node.borrow().id;
+		loop {
+			if let Some(temp) = self.exprs.get(&id).and_then(|v| v.get(expr)) {
+				return Some(temp.clone());
+			}
+			id = self.dom_tree.get_dominator(id)?.borrow().id;
+		}
+	}
+
+	/// Turn the expressions of `node` which are computed in some of its
+	/// predecessors into phis, computing them in the other predecessors
+	fn solve(&mut self, node: &LlvmNode, mgr: &mut LlvmTempManager) -> bool {
+		let preds = node.borrow().prev.clone();
+		if preds.len() < 2 {
+			return false;
+		}
+		let labels: Vec<_> = preds.iter().map(|v| v.borrow().label()).collect();
+		// what temps of `node` are in each predecessor
+		let mut trans = vec![HashMap::new(); preds.len()];
+		for phi in node.borrow().phi_instrs.iter() {
+			for (value, label) in phi.source.iter() {
+				if let Some(i) = labels.iter().position(|v| v == label) {
+					trans[i].insert(phi.target.clone(), value.clone());
+				}
+			}
+		}
+		let locals: HashSet<_> =
+			node.borrow().instrs.iter().filter_map(|v| v.get_write()).collect();
+		let instrs = std::mem::take(&mut node.borrow_mut().instrs);
+		let mut kept = Vec::new();
+		let mut phis = Vec::new();
+		let mut called = false;
+		for instr in instrs {
+			match self.eliminate(&instr, &preds, &mut trans, &locals, called, mgr) {
+				Some(phi) => phis.push(phi),
+				None => {
+					called |= instr.is_call();
+					kept.push(instr);
+				}
+			}
+		}
+		let mut block = node.borrow_mut();
+		block.instrs = kept;
+		let flag = !phis.is_empty();
+		block.phi_instrs.extend(phis);
+		flag
+	}
+
+	fn eliminate(
+		&mut self,
+		instr: &LlvmInstr,
+		preds: &[LlvmNode],
+		trans: &mut [HashMap<LlvmTemp, Value>],
+		locals: &HashSet<LlvmTemp>,
+		called: bool,
+		mgr: &mut LlvmTempManager,
+	) -> Option<PhiInstr> {
+		let (op, _) = get_op(instr)?;
+		// a trap must not happen before what the calls before it output
+		if called && may_trap(op) {
+			return None;
+		}
+		let operands = instr.get_read_values();
+		let mut items = Vec::new();
+		for (pred, trans) in preds.iter().zip(trans.iter()) {
+			let operands = operands
+				.iter()
+				.map(|v| match v.unwrap_temp() {
+					Some(temp) if trans.contains_key(&temp) => Some(trans[&temp].clone()),
+					Some(temp) if locals.contains(&temp) => None,
+					_ => Some(v.clone()),
+				})
+				.collect::<Option<Vec<_>>>()?;
+			let expr = self.get_expr(instr, &operands)?;
+			let temp = self.find(pred, &expr);
+			// computing it at the end of a predecessor is only down-safe if
+			// the predecessor always goes on to `node`
+			if temp.is_none() && !pred.borrow().single_succ() {
+				return None;
+			}
+			items.push((operands, expr, temp));
+		}
+		if items.iter().all(|(_, _, temp)| temp.is_none()) {
+			return None;
+		}
+		let target = instr.get_write().unwrap();
+		let mut source = Vec::new();
+		for ((pred, trans), (operands, expr, temp)) in
+			preds.iter().zip(trans.iter_mut()).zip(items)
+		{
+			let temp = temp.unwrap_or_else(|| {
+				let temp = mgr.new_temp(target.var_type, false);
+				let mut new_instr = instr.clone();
+				new_instr.set_target(temp.clone());
+				for (i, value) in operands.into_iter().enumerate() {
+					new_instr.set_read_values(i, value);
+				}
+				let mut pred = pred.borrow_mut();
+				pred.instrs.push(new_instr);
+				self.exprs.entry(pred.id).or_default().insert(expr, temp.clone());
+				temp
+			});
+			trans.insert(target.clone(), temp.clone().into());
+			source.push((temp.into(), pred.borrow().label()));
+		}
+		Some(PhiInstr::new(target, source))
+	}
+}
+
+impl RrvmOptimizer for PartialRedundancyElimination {
+	fn new() -> Self {
+		Self {}
+	}
+	fn apply(
+		self,
+		program: &mut LlvmProgram,
+		metadata: &mut MetaData,
+	) -> Result<bool> {
+		let mut flag = false;
+		for func in program.funcs.iter() {
+			let mut solver = Solver::new(func, metadata.get_func_data(&func.name));
+			flag |= solver.speculate(func);
+			solver.collect_exprs(func);
+			for node in func.cfg.blocks.iter() {
+				flag |= solver.solve(node, &mut program.temp_mgr);
+			}
+		}
+		Ok(flag)
+	}
+}
@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+
+use rrvm::program::LlvmProgram;
+use utils::{errors::Result, SysycError::LlvmVerifyError};
+
+use super::{PassContext, PassItem, PassManager, Pipeline, PASSES};
+use crate::{loops::HandleLoops, metadata::MetaData};
+
+impl PassContext {
+	/// Loop data of the latest `loop_analysis`, built now if there is none
+	pub fn split(
+		&mut self,
+		program: &mut LlvmProgram,
+	) -> (&mut HandleLoops, &mut MetaData) {
+		let loops = self.loops.get_or_insert_with(|| HandleLoops::new(program));
+		(loops, &mut self.metadata)
+	}
+}
+
+impl PassManager {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	pub fn set_print_after(&mut self, passes: HashSet<String>) {
+		self.print_after = passes;
+	}
+	pub fn set_verify_each(&mut self, verify_each: bool) {
+		self.verify_each = verify_each;
+	}
+	/// Keep what other modules may use, see `MetaData::exported`
+	pub fn set_exported(&mut self, exported: bool) {
+		self.context.metadata.exported = exported;
+	}
+	/// Split parallel loops among this many threads
+	pub fn set_threads(&mut self, threads: usize) {
+		self.context.metadata.threads = threads;
+	}
+	fn verify(&self, name: &str, program: &LlvmProgram) -> Result<()> {
+		if !self.verify_each {
+			return Ok(());
+		}
+		for func in program.funcs.iter() {
+			func.verify().map_err(|e| {
+				LlvmVerifyError(format!(
+					"after {}, in function `{}`: {}",
+					name, func.name, e
+				))
+			})?;
+		}
+		Ok(())
+	}
+	pub fn run_pass(
+		&mut self,
+		name: &str,
+		program: &mut LlvmProgram,
+	) -> Result<bool> {
+		let (.., pass) = PASSES.iter().find(|(v, ..)| *v == name).unwrap();
+		self.current = Some(name.to_string());
+		let flag = pass(program, &mut self.context)?;
+		self.current = None;
+		if self.print_after.contains(name) || self.print_after.contains("all") {
+			eprintln!("; *** IR after {} ***\n{}", name, program);
+		}
+		self.verify(name, program)?;
+		Ok(flag)
+	}
+	fn run_items(
+		&mut self,
+		items: &[PassItem],
+		program: &mut LlvmProgram,
+	) -> Result<bool> {
+		let mut flag = false;
+		for item in items {
+			flag |= match item {
+				PassItem::Pass(name) => self.run_pass(name, program)?,
+				PassItem::Group(items, false) => self.run_items(items, program)?,
+				PassItem::Group(items, true) => {
+					let mut changed = false;
+					while self.run_items(items, program)? {
+						changed = true;
+					}
+					changed
+				}
+			};
+		}
+		Ok(flag)
+	}
+	pub fn apply(
+		&mut self,
+		pipeline: &Pipeline,
+		program: &mut LlvmProgram,
+	) -> Result<bool> {
+		self.verify("input", program)?;
+		let flag = self.run_items(&pipeline.items, program)?;
+		program.analysis();
+		Ok(flag)
+	}
+}
@@ -0,0 +1,42 @@
+use std::collections::HashSet;
+
+use crate::{loops::HandleLoops, metadata::MetaData};
+
+mod impls;
+mod parse;
+mod registry;
+
+pub use registry::{PASSES, PIPELINES};
+
+/// One step of a pipeline, written as `name`, `(a,b,c)` or `(a,b,c)*`.
+/// A starred group runs until none of its passes changes the program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PassItem {
+	Pass(String),
+	Group(Vec<PassItem>, bool),
+}
+
+/// Textual pipeline such as `fold_constants,gvn,(mem2reg,useless_phis)*`,
+/// names of `PIPELINES` are expanded in place
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pipeline {
+	pub items: Vec<PassItem>,
+}
+
+/// States shared by the passes of one pipeline
+#[derive(Default)]
+pub struct PassContext {
+	pub metadata: MetaData,
+	pub loops: Option<HandleLoops>,
+}
+
+#[derive(Default)]
+pub struct PassManager {
+	pub context: PassContext,
+	/// print IR to stderr after these passes, `all` for every pass
+	pub print_after: HashSet<String>,
+	/// verify the IR before the pipeline and after every pass
+	pub verify_each: bool,
+	/// the pass being run, which is left here if it panics
+	pub current: Option<String>,
+}
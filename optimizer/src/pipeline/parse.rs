@@ -0,0 +1,100 @@
+use std::{collections::HashSet, iter::Peekable, str::Chars};
+
+use utils::{errors::Result, SysycError::FatalError};
+
+use super::{PassItem, Pipeline, PASSES, PIPELINES};
+
+fn pipeline_error(msg: impl std::fmt::Display) -> utils::SysycError {
+	FatalError(format!("invalid pipeline: {msg}"))
+}
+
+fn parse_name(chars: &mut Peekable<Chars>) -> String {
+	let mut name = String::new();
+	while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_')
+	{
+		name.push(c);
+	}
+	name
+}
+
+fn parse_item(chars: &mut Peekable<Chars>) -> Result<PassItem> {
+	if chars.next_if_eq(&'(').is_some() {
+		let items = parse_items(chars)?;
+		if chars.next() != Some(')') {
+			return Err(pipeline_error("missing `)`"));
+		}
+		let repeat = chars.next_if_eq(&'*').is_some();
+		return Ok(PassItem::Group(items, repeat));
+	}
+	let name = parse_name(chars);
+	if let Some((_, desc)) = PIPELINES.iter().find(|(v, _)| *v == name) {
+		return Ok(PassItem::Group(parse_desc(desc)?, false));
+	}
+	if PASSES.iter().any(|(v, ..)| *v == name) {
+		return Ok(PassItem::Pass(name));
+	}
+	if name.is_empty() {
+		return Err(match chars.peek() {
+			Some(c) => pipeline_error(format!("unexpected `{c}`")),
+			None => pipeline_error("unexpected end"),
+		});
+	}
+	let names: Vec<_> = PASSES.iter().map(|(v, ..)| *v).collect();
+	Err(pipeline_error(format!(
+		"unknown pass `{name}`, available passes are: {}",
+		names.join(", ")
+	)))
+}
+
+fn parse_items(chars: &mut Peekable<Chars>) -> Result<Vec<PassItem>> {
+	let mut items = vec![parse_item(chars)?];
+	while chars.next_if_eq(&',').is_some() {
+		items.push(parse_item(chars)?);
+	}
+	Ok(items)
+}
+
+fn parse_desc(desc: &str) -> Result<Vec<PassItem>> {
+	let desc: String = desc.chars().filter(|c| !c.is_whitespace()).collect();
+	let mut chars = desc.chars().peekable();
+	let items = parse_items(&mut chars)?;
+	match chars.next() {
+		Some(c) => Err(pipeline_error(format!("unexpected `{c}`"))),
+		None => Ok(items),
+	}
+}
+
+// Put the passes a pass relies on right before it, unless they have run
+// earlier in the pipeline
+fn insert_required(
+	items: Vec<PassItem>,
+	seen: &mut HashSet<String>,
+) -> Vec<PassItem> {
+	let insert = |item| match item {
+		PassItem::Pass(name) => {
+			let (_, required, _) = PASSES.iter().find(|(v, ..)| *v == name).unwrap();
+			let mut items: Vec<_> = required
+				.iter()
+				.filter(|v| seen.insert(v.to_string()))
+				.map(|v| PassItem::Pass(v.to_string()))
+				.collect();
+			seen.insert(name.clone());
+			if items.is_empty() {
+				return PassItem::Pass(name);
+			}
+			items.push(PassItem::Pass(name));
+			PassItem::Group(items, false)
+		}
+		PassItem::Group(items, repeat) => {
+			PassItem::Group(insert_required(items, seen), repeat)
+		}
+	};
+	items.into_iter().map(insert).collect()
+}
+
+impl Pipeline {
+	pub fn parse(desc: &str) -> Result<Self> {
+		let items = insert_required(parse_desc(desc)?, &mut HashSet::new());
+		Ok(Self { items })
+	}
+}
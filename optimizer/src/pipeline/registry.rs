@@ -0,0 +1,108 @@
+use rrvm::program::LlvmProgram;
+use utils::errors::Result;
+
+use super::PassContext;
+use crate::{
+	alloc_hoisting::AllocHoisting, calc_coef::CalcCoef,
+	code_hoisting::CodeHoisting, constant_propagation::ConstantPropagation,
+	dead_code::RemoveDeadCode, fold_constants::FoldConstants,
+	function_inline::InlineFunction, global_analysis::GlobalAnalysis,
+	global_value_numbering::GlobalValueNumbering, loops::HandleLoops,
+	mem2reg::Mem2Reg, partial_eval::PartialEvaluation,
+	partial_redundancy_elimination::PartialRedundancyElimination,
+	tail_recursion::SolveTailRecursion, unreachable::RemoveUnreachCode,
+	useless_code::RemoveUselessCode, useless_phis::RemoveUselessPhis,
+	RrvmOptimizer,
+};
+
+pub type PassFn = fn(&mut LlvmProgram, &mut PassContext) -> Result<bool>;
+
+fn apply<T: RrvmOptimizer>(
+	program: &mut LlvmProgram,
+	context: &mut PassContext,
+) -> Result<bool> {
+	T::new().apply(program, &mut context.metadata)
+}
+
+/// Each pass with the passes it relies on, which have to run before it in
+/// the pipeline, such as the value numbers `gvn` leaves in `MetaData`
+pub static PASSES: [(&str, &[&str], PassFn); 22] = [
+	("unreachable", &[], apply::<RemoveUnreachCode>),
+	("dead_code", &[], apply::<RemoveDeadCode>),
+	("useless_code", &[], apply::<RemoveUselessCode>),
+	("useless_phis", &[], apply::<RemoveUselessPhis>),
+	("fold_constants", &[], apply::<FoldConstants>),
+	("sccp", &[], apply::<ConstantPropagation>),
+	("global_analysis", &[], apply::<GlobalAnalysis>),
+	("gvn", &[], apply::<GlobalValueNumbering>),
+	("calc_coef", &["gvn"], apply::<CalcCoef>),
+	("partial_eval", &[], apply::<PartialEvaluation>),
+	("mem2reg", &["global_analysis", "gvn"], apply::<Mem2Reg>),
+	("pre", &["gvn"], apply::<PartialRedundancyElimination>),
+	("function_inline", &[], apply::<InlineFunction>),
+	("alloc_hoisting", &[], apply::<AllocHoisting>),
+	("code_hoisting", &[], apply::<CodeHoisting>),
+	("tail_recursion", &[], apply::<SolveTailRecursion>),
+	// loop passes share the loop data built by the latest `loop_analysis`
+	("loop_analysis", &[], |program, context| {
+		context.loops = Some(HandleLoops::new(program));
+		Ok(false)
+	}),
+	("loop_simplify", &[], |program, context| {
+		let (loops, metadata) = context.split(program);
+		loops.loop_simplify(program, metadata)
+	}),
+	("indvar_extraction", &[], |program, context| {
+		let (loops, metadata) = context.split(program);
+		loops.indvar_extraction(program, metadata)
+	}),
+	("loop_unroll", &[], |program, context| {
+		let (loops, metadata) = context.split(program);
+		loops.loop_unroll(program, metadata)
+	}),
+	("licm", &[], |program, context| {
+		let (loops, metadata) = context.split(program);
+		loops.licm(program, metadata)
+	}),
+	("parallel", &[], |program, context| {
+		let (loops, metadata) = context.split(program);
+		loops.parallel(program, metadata)
+	}),
+];
+
+/// The pipeline `full` is built from, with `$extra` run after each
+/// `mem2reg`
+macro_rules! full_pipeline {
+	($extra:literal) => {
+		concat!(
+			"unreachable,",
+			"(dead_code,global_analysis,useless_code,unreachable,fold_constants,",
+			"gvn,calc_coef,partial_eval,gvn,mem2reg,useless_phis,sccp,",
+			$extra,
+			"function_inline,alloc_hoisting,code_hoisting,tail_recursion)*,",
+			"loop_analysis,",
+			"(dead_code,global_analysis,useless_code,unreachable,fold_constants,",
+			"gvn,mem2reg,useless_phis,sccp,",
+			$extra,
+			"function_inline,alloc_hoisting,code_hoisting,tail_recursion,",
+			"loop_analysis,loop_simplify,indvar_extraction,licm,loop_unroll)*,",
+			"loop_analysis,loop_simplify,indvar_extraction,parallel,",
+			"(dead_code,global_analysis,useless_code,unreachable,fold_constants,",
+			"gvn,mem2reg,useless_phis,sccp,",
+			$extra,
+			"function_inline,alloc_hoisting,code_hoisting,tail_recursion)*"
+		)
+	};
+}
+
+/// Named pipelines, which are what `-O` levels run
+pub static PIPELINES: [(&str, &str); 4] = [
+	("basic", "(unreachable,dead_code)*"),
+	(
+		"fast",
+		"(dead_code,unreachable,function_inline,alloc_hoisting,fold_constants,\
+		useless_phis)*",
+	),
+	("full", full_pipeline!("")),
+	("full_pre", full_pipeline!("gvn,pre,useless_phis,")),
+];
@@ -12,7 +12,7 @@ impl RrvmOptimizer for RemoveUnreachCode {
 	fn apply(
 		self,
 		program: &mut LlvmProgram,
-		_metadata: &mut MetaData,
+		metadata: &mut MetaData,
 	) -> Result<bool> {
 		let flag = program.funcs.iter_mut().fold(false, |last, func| {
 			let size = func.cfg.size();
@@ -36,7 +36,12 @@ impl RrvmOptimizer for RemoveUnreachCode {
 				}
 			});
 			for block in cfg.blocks.iter() {
-				block.borrow_mut().prev.retain(|v| visited.contains(&v.borrow().id));
+				// a block may be its own predecessor
+				let prev = block.borrow().prev.clone();
+				block.borrow_mut().prev = prev
+					.into_iter()
+					.filter(|v| visited.contains(&v.borrow().id))
+					.collect();
 			}
 			last || size != cfg.blocks.len()
 		});
@@ -51,7 +56,10 @@ impl RrvmOptimizer for RemoveUnreachCode {
 				}
 			}
 		}
-		program.funcs.retain(|func| used_func.contains(&func.name));
+		// another module may call any function of this one
+		if !metadata.exported {
+			program.funcs.retain(|func| used_func.contains(&func.name));
+		}
 		Ok(flag)
 	}
 }
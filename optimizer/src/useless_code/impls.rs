@@ -80,6 +80,7 @@ impl RrvmOptimizer for RemoveUselessCode {
 					for (_, label) in instr.source.iter() {
 						add_edge(u.clone().into(), label.clone().into());
 					}
+					add_edge(u.clone().into(), block.label().into());
 				}
 				if let Some(instr) = block.jump_instr.as_ref() {
 					if instr.is_ret() {
@@ -148,16 +149,27 @@ impl RrvmOptimizer for RemoveUselessCode {
 				mapper.iter().map(|(k, v)| (k.clone(), v.borrow().label())).collect();
 
 			for block in cfg.blocks.iter() {
-				let mut block = block.borrow_mut();
-				block.jump_instr.as_mut().unwrap().map_label(&label_mapper);
-				let succ = std::mem::take(&mut block.succ);
-				block.succ = succ
+				// a block may be its own successor, so it is not borrowed meanwhile
+				let succ = std::mem::take(&mut block.borrow_mut().succ);
+				let mut succ: Vec<_> = succ
 					.into_iter()
 					.map(|v| {
 						let label = v.borrow().label();
-						mapper.get(&label).cloned().unwrap_or(v)
+						let v = mapper.get(&label).cloned().unwrap_or(v);
+						let label = v.borrow().label();
+						(label, v)
 					})
 					.collect();
+				let mut block = block.borrow_mut();
+				block.jump_instr.as_mut().unwrap().map_label(&label_mapper);
+				// both targets of a branch may be mapped to the same block
+				let new_jump = block.jump_instr.as_ref().unwrap().new_jump();
+				if let Some(instr) = new_jump {
+					succ.retain(|(label, _)| *label == instr.target);
+					succ.truncate(1);
+					block.set_jump(Some(Box::new(instr)));
+				}
+				block.succ = succ.into_iter().map(|(_, v)| v).collect();
 			}
 
 			cfg.resolve_prev();
@@ -0,0 +1,301 @@
+use ast::{tree::*, Visitor};
+use utils::{errors::Result, Span};
+use value::BinaryOp;
+
+/// A kind of change that makes a program smaller
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edit {
+	/// remove this many neighbouring declarations, functions, statements or
+	/// initializers
+	Remove(usize),
+	/// replace `if` or a loop with one of its bodies
+	Unwrap,
+	/// replace an operation with one of its operands
+	Hoist,
+	/// replace an expression with `0`
+	Zero,
+}
+
+/// Applies an `Edit` at one site, sites are numbered in the order they are
+/// visited
+pub struct Mutator {
+	edit: Edit,
+	target: usize,
+	count: usize,
+	// what the node just visited is to be replaced with
+	replace: Option<Node>,
+	// the expression visited is assigned to or indexed, and must stay so
+	lvalue: bool,
+}
+
+fn empty_block(span: Span) -> Node {
+	Box::new(Block {
+		_attrs: Default::default(),
+		_span: span,
+		stmts: Vec::new(),
+	})
+}
+
+// take the node away, leaving an empty block in its place
+fn take(node: &mut Node) -> Node {
+	let span = node.span();
+	std::mem::replace(node, empty_block(span))
+}
+
+impl Mutator {
+	/// Apply `edit` at site `target`, false if there are not so many sites
+	pub fn apply(
+		edit: Edit,
+		target: usize,
+		program: &mut Program,
+	) -> Result<bool> {
+		let mut mutator = Self {
+			edit,
+			target,
+			count: 0,
+			replace: None,
+			lvalue: false,
+		};
+		mutator.visit_program(program)?;
+		Ok(mutator.done())
+	}
+	fn done(&self) -> bool {
+		self.count > self.target
+	}
+	// count a site of the current edit, true if it is the one to change
+	fn hit(&mut self, edit: Edit) -> bool {
+		if edit != self.edit || self.done() {
+			return false;
+		}
+		self.count += 1;
+		self.done()
+	}
+	fn child(&mut self, node: &mut Node) -> Result<()> {
+		if self.done() {
+			return Ok(());
+		}
+		node.accept(self)?;
+		if let Some(v) = self.replace.take() {
+			*node = v;
+		}
+		Ok(())
+	}
+	fn expr(&mut self, node: &mut Node) -> Result<()> {
+		let lvalue = std::mem::replace(&mut self.lvalue, false);
+		self.child(node)?;
+		self.lvalue = lvalue;
+		Ok(())
+	}
+	fn lvalue(&mut self, node: &mut Node) -> Result<()> {
+		let lvalue = std::mem::replace(&mut self.lvalue, true);
+		self.child(node)?;
+		self.lvalue = lvalue;
+		Ok(())
+	}
+	fn list(&mut self, nodes: &mut NodeList) -> Result<()> {
+		if let Edit::Remove(size) = self.edit {
+			for start in (0..nodes.len()).step_by(size) {
+				if start + size <= nodes.len() && self.hit(self.edit) {
+					nodes.drain(start..start + size);
+					return Ok(());
+				}
+			}
+		}
+		for node in nodes.iter_mut() {
+			self.child(node)?;
+		}
+		Ok(())
+	}
+	fn exprs(&mut self, nodes: &mut NodeList) -> Result<()> {
+		for node in nodes.iter_mut() {
+			self.expr(node)?;
+		}
+		Ok(())
+	}
+	// an expression may become `0` unless it is assigned to
+	fn zero(&mut self, span: Span) -> bool {
+		if self.lvalue || !self.hit(Edit::Zero) {
+			return false;
+		}
+		self.replace = Some(LiteralInt::node(0, span));
+		true
+	}
+}
+
+impl Visitor for Mutator {
+	fn visit_program(&mut self, node: &mut Program) -> Result<()> {
+		self.list(&mut node.global_vars)?;
+		self.list(&mut node.functions)
+	}
+	fn visit_var_def(&mut self, node: &mut VarDef) -> Result<()> {
+		if let Some(init) = node.init.as_mut() {
+			self.expr(init)?;
+		}
+		Ok(())
+	}
+	fn visit_var_decl(&mut self, node: &mut VarDecl) -> Result<()> {
+		self.list(&mut node.defs)
+	}
+	fn visit_func_decl(&mut self, node: &mut FuncDecl) -> Result<()> {
+		match node.block.as_mut() {
+			Some(block) => self.child(block),
+			None => Ok(()),
+		}
+	}
+	fn visit_struct_decl(&mut self, _node: &mut StructDecl) -> Result<()> {
+		Ok(())
+	}
+	fn visit_init_val_list(&mut self, node: &mut InitValList) -> Result<()> {
+		self.list(&mut node.val_list)
+	}
+	fn visit_literal_int(&mut self, node: &mut LiteralInt) -> Result<()> {
+		if node.value != 0 {
+			self.zero(node._span);
+		}
+		Ok(())
+	}
+	fn visit_literal_float(&mut self, node: &mut LiteralFloat) -> Result<()> {
+		self.zero(node._span);
+		Ok(())
+	}
+	fn visit_literal_string(&mut self, _node: &mut LiteralString) -> Result<()> {
+		Ok(())
+	}
+	fn visit_binary_expr(&mut self, node: &mut BinaryExpr) -> Result<()> {
+		match node.op {
+			op if op.is_assign() => {
+				self.lvalue(&mut node.lhs)?;
+				return self.expr(&mut node.rhs);
+			}
+			BinaryOp::IDX => {
+				if self.zero(node._span) {
+					return Ok(());
+				}
+				self.lvalue(&mut node.lhs)?;
+				return self.expr(&mut node.rhs);
+			}
+			_ => {}
+		}
+		if self.zero(node._span) {
+			return Ok(());
+		}
+		if self.hit(Edit::Hoist) {
+			self.replace = Some(take(&mut node.lhs));
+			return Ok(());
+		}
+		if self.hit(Edit::Hoist) {
+			self.replace = Some(take(&mut node.rhs));
+			return Ok(());
+		}
+		self.expr(&mut node.lhs)?;
+		self.expr(&mut node.rhs)
+	}
+	fn visit_field_access(&mut self, node: &mut FieldAccess) -> Result<()> {
+		if self.zero(node._span) {
+			return Ok(());
+		}
+		self.lvalue(&mut node.base)
+	}
+	fn visit_unary_expr(&mut self, node: &mut UnaryExpr) -> Result<()> {
+		if self.zero(node._span) {
+			return Ok(());
+		}
+		if self.hit(Edit::Hoist) {
+			self.replace = Some(take(&mut node.rhs));
+			return Ok(());
+		}
+		if node.op.step().is_some() {
+			return self.lvalue(&mut node.rhs);
+		}
+		self.expr(&mut node.rhs)
+	}
+	fn visit_conditional(&mut self, node: &mut Conditional) -> Result<()> {
+		if self.zero(node._span) {
+			return Ok(());
+		}
+		for branch in [&mut node.on_true, &mut node.on_false] {
+			if self.hit(Edit::Hoist) {
+				self.replace = Some(take(branch));
+				return Ok(());
+			}
+		}
+		self.expr(&mut node.cond)?;
+		self.expr(&mut node.on_true)?;
+		self.expr(&mut node.on_false)
+	}
+	fn visit_func_call(&mut self, node: &mut FuncCall) -> Result<()> {
+		if self.zero(node._span) {
+			return Ok(());
+		}
+		self.exprs(&mut node.params)
+	}
+	fn visit_formal_param(&mut self, _node: &mut FormalParam) -> Result<()> {
+		Ok(())
+	}
+	fn visit_variable(&mut self, node: &mut Variable) -> Result<()> {
+		self.zero(node._span);
+		Ok(())
+	}
+	fn visit_block(&mut self, node: &mut Block) -> Result<()> {
+		self.list(&mut node.stmts)
+	}
+	fn visit_if(&mut self, node: &mut If) -> Result<()> {
+		if self.hit(Edit::Unwrap) {
+			self.replace = Some(take(&mut node.body));
+			return Ok(());
+		}
+		if let Some(then) = node.then.as_mut() {
+			if self.hit(Edit::Unwrap) {
+				self.replace = Some(take(then));
+				return Ok(());
+			}
+		}
+		self.expr(&mut node.cond)?;
+		self.child(&mut node.body)?;
+		if let Some(then) = node.then.as_mut() {
+			self.child(then)?;
+		}
+		Ok(())
+	}
+	fn visit_while(&mut self, node: &mut While) -> Result<()> {
+		if self.hit(Edit::Unwrap) {
+			self.replace = Some(take(&mut node.body));
+			return Ok(());
+		}
+		self.expr(&mut node.cond)?;
+		self.child(&mut node.body)
+	}
+	fn visit_for(&mut self, node: &mut For) -> Result<()> {
+		if self.hit(Edit::Unwrap) {
+			self.replace = Some(take(&mut node.body));
+			return Ok(());
+		}
+		if let Some(init) = node.init.as_mut() {
+			self.child(init)?;
+		}
+		for part in [&mut node.cond, &mut node.step].into_iter().flatten() {
+			self.expr(part)?;
+		}
+		self.child(&mut node.body)
+	}
+	fn visit_do_while(&mut self, node: &mut DoWhile) -> Result<()> {
+		if self.hit(Edit::Unwrap) {
+			self.replace = Some(take(&mut node.body));
+			return Ok(());
+		}
+		self.child(&mut node.body)?;
+		self.expr(&mut node.cond)
+	}
+	fn visit_continue(&mut self, _node: &mut Continue) -> Result<()> {
+		Ok(())
+	}
+	fn visit_break(&mut self, _node: &mut Break) -> Result<()> {
+		Ok(())
+	}
+	fn visit_return(&mut self, node: &mut Return) -> Result<()> {
+		if let Some(value) = node.value.as_mut() {
+			self.expr(value)?;
+		}
+		Ok(())
+	}
+}
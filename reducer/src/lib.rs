@@ -0,0 +1,84 @@
+mod edit;
+mod oracle;
+
+pub use edit::{Edit, Mutator};
+pub use oracle::{Interesting, Oracle};
+
+use ast::printer::SourcePrinter;
+use namer::visitor::Namer;
+use parser::parser::parse;
+use utils::{errors::Result, SysycError::SystemError};
+
+/// Parse and print again, so that the text is in the form the edits give
+pub fn normalize(code: &str) -> Result<String> {
+	SourcePrinter::print(&mut parse(code)?)
+}
+
+// the program with the edit made, `None` if there is no such site
+fn edited(code: &str, edit: Edit, target: usize) -> Result<Option<String>> {
+	let mut program = parse(code)?;
+	if !Mutator::apply(edit, target, &mut program)? {
+		return Ok(None);
+	}
+	SourcePrinter::print(&mut program).map(Some)
+}
+
+// constants replaced with their values, which the namer does on the fly
+fn inlined(code: &str) -> Result<String> {
+	let mut program = parse(code)?;
+	Namer::default().transform(&mut program)?;
+	SourcePrinter::print(&mut program)
+}
+
+/// Delta debugging on the syntax tree: keep making edits that leave the
+/// program interesting, big ones first, until none of them does. `log` is
+/// told the size of the program after each successful edit.
+pub fn reduce(
+	code: &str,
+	interesting: &mut dyn FnMut(&str) -> bool,
+	log: &mut dyn FnMut(usize),
+) -> Result<String> {
+	let mut best = normalize(code)?;
+	if !interesting(&best) {
+		return Err(SystemError(
+			"the program is not interesting to begin with".to_string(),
+		));
+	}
+	let mut accept = |best: &mut String, code: String, smaller: bool| {
+		if (!smaller || code.len() < best.len()) && interesting(&code) {
+			*best = code;
+			log(best.len());
+			return true;
+		}
+		false
+	};
+	loop {
+		let before = best.clone();
+		// constants may be longer than their names, but their declarations
+		// can go once they are not used
+		if let Ok(code) = inlined(&best) {
+			if code != best {
+				accept(&mut best, code, false);
+			}
+		}
+		let mut edits = Vec::new();
+		let mut chunk = best.lines().count().next_power_of_two();
+		while chunk > 0 {
+			edits.push(Edit::Remove(chunk));
+			chunk /= 2;
+		}
+		edits.extend([Edit::Unwrap, Edit::Hoist, Edit::Zero]);
+		for edit in edits {
+			let mut target = 0;
+			// a site is tried again after a success, as it now holds another node
+			while let Some(code) = edited(&best, edit, target)? {
+				if !accept(&mut best, code, true) {
+					target += 1;
+				}
+			}
+		}
+		if best == before {
+			return Ok(best);
+		}
+	}
+}
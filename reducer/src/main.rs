@@ -0,0 +1,85 @@
+use std::{fs, panic, path::PathBuf, process::exit, thread};
+
+use clap::Parser;
+use reducer::{reduce, Interesting, Oracle};
+
+// the compiler recurses on the syntax tree, give it some room
+const STACK_SIZE: usize = 256 << 20;
+
+/// Shrink a SysY program while it stays interesting, e.g. miscompiled
+#[derive(Parser, Debug)]
+struct Args {
+	/// The program to reduce
+	#[arg(value_parser)]
+	input: PathBuf,
+
+	/// Input of the program
+	#[arg(long)]
+	stdin: Option<PathBuf>,
+
+	/// Where to write the reduced program, stdout if not given
+	#[arg(short)]
+	output: Option<PathBuf>,
+
+	/// Optimization level that goes wrong
+	#[arg(short = 'O', default_value_t = 2)]
+	level: i32,
+
+	/// Keep programs on which the compiler panics instead of those whose
+	/// output at `-O<level>` differs from `-O0`
+	#[arg(long)]
+	panic: bool,
+
+	/// With `--panic`, the optimizer pass that must panic
+	#[arg(long, requires = "panic")]
+	pass: Option<String>,
+
+	/// Instructions a program may run before it is taken as looping forever
+	#[arg(long, default_value_t = 10_000_000)]
+	steps: u64,
+}
+
+fn read(path: &PathBuf) -> Vec<u8> {
+	fs::read(path).unwrap_or_else(|e| {
+		eprintln!("{}: {e}", path.display());
+		exit(2);
+	})
+}
+
+fn main() {
+	let args = Args::parse();
+	let code = String::from_utf8_lossy(&read(&args.input)).into_owned();
+	let interesting = if args.panic {
+		Interesting::Panic(args.level, args.pass.clone())
+	} else {
+		Interesting::Differ(args.level)
+	};
+	let mut oracle = Oracle::new(
+		interesting,
+		args.stdin.as_ref().map(read).unwrap_or_default(),
+	);
+	oracle.steps = args.steps;
+	// the panics looked for are expected, and would flood the output
+	panic::set_hook(Box::new(|_| {}));
+	let result = thread::Builder::new()
+		.stack_size(STACK_SIZE)
+		.spawn(move || {
+			reduce(&code, &mut |code| oracle.check(code), &mut |size| {
+				eprintln!("reduced to {size} bytes")
+			})
+		})
+		.expect("failed to spawn the reducer")
+		.join()
+		.unwrap();
+	let code = result.unwrap_or_else(|e| {
+		eprintln!("{e}");
+		exit(1);
+	});
+	match args.output {
+		Some(path) => fs::write(&path, code).unwrap_or_else(|e| {
+			eprintln!("{}: {e}", path.display());
+			exit(2);
+		}),
+		None => print!("{code}"),
+	}
+}
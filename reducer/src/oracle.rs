@@ -0,0 +1,93 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use interpreter::{simulate_limited, Execution};
+use optimizer::PassManager;
+use rrvm::program::RiscvProgram;
+use sysyc::{cli::Args, *};
+use utils::{
+	errors::Result,
+	SysycError::{RuntimeError, StepLimitError},
+};
+
+/// What makes a program worth keeping while it is reduced
+#[derive(Clone, Debug)]
+pub enum Interesting {
+	/// it runs at `-O0`, but gives another output or fails at this level
+	Differ(i32),
+	/// compiling it at this level panics, in this optimizer pass if given
+	Panic(i32, Option<String>),
+}
+
+/// Tells whether a program is interesting, running it with `input`
+pub struct Oracle {
+	pub interesting: Interesting,
+	pub input: Vec<u8>,
+	/// instructions a program may run at `-O0`, so that the loops broken by
+	/// reducing are given up on
+	pub steps: u64,
+}
+
+// `Err` with the optimizer pass running, if any, when the compiler panics
+fn compile(
+	code: &str,
+	level: i32,
+) -> std::result::Result<Result<RiscvProgram>, Option<String>> {
+	let args = Args {
+		opimizer: Some(level),
+		..Default::default()
+	};
+	let mut manager = PassManager::new();
+	let compile = AssertUnwindSafe(|| -> Result<_> {
+		let mut program = step_irgen(step_parse(code)?)?;
+		manager.apply(&step_pipeline(&args)?, &mut program)?;
+		step_riscv(program, level)
+	});
+	panic::catch_unwind(compile).map_err(|_| manager.current.take())
+}
+
+fn run(
+	code: &str,
+	level: i32,
+	input: Vec<u8>,
+	steps: u64,
+) -> Result<Execution> {
+	match compile(code, level) {
+		Ok(program) => simulate_limited(&program?, input, Some(steps))
+			.map(|v| v.0)
+			.map_err(Into::into),
+		Err(pass) => Err(RuntimeError(format!(
+			"the compiler panics{}",
+			pass.map(|v| format!(" in `{v}`")).unwrap_or_default()
+		))),
+	}
+}
+
+impl Oracle {
+	pub fn new(interesting: Interesting, input: Vec<u8>) -> Self {
+		Self {
+			interesting,
+			input,
+			steps: 10_000_000,
+		}
+	}
+	pub fn check(&self, code: &str) -> bool {
+		match &self.interesting {
+			Interesting::Differ(level) => {
+				let Ok(expected) = run(code, 0, self.input.clone(), self.steps) else {
+					return false;
+				};
+				// optimized code may run longer, but not much, and running out of
+				// steps is no sign of a miscompile
+				match run(code, *level, self.input.clone(), self.steps * 2) {
+					Ok(v) => v != expected,
+					Err(StepLimitError(_)) => false,
+					Err(_) => true,
+				}
+			}
+			Interesting::Panic(level, pass) => match compile(code, *level) {
+				Err(current) => pass.is_none() || *pass == current,
+				Ok(_) => false,
+			},
+		}
+	}
+}
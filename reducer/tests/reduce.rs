@@ -0,0 +1,79 @@
+use interpreter::interpret_limited;
+use reducer::{reduce, Interesting, Oracle};
+use sysyc::*;
+use utils::SysycError::StepLimitError;
+
+const PROGRAM: &str = r"
+const int N = 4, M[2] = {3, N * 2};
+int g[N][2] = {{1}, 2, 3};
+float f = 1.5e-3;
+int fib(int n) {
+	if (n < 2) return n;
+	return fib(n - 1) + fib(n - 2);
+}
+int sum(int a[][2], int n) {
+	int s = 0, i = 0;
+	while (i < n) {
+		if (a[i][0] > 1 && !(a[i][1] < 0) || -a[i][0] == -3) s = s + a[i][0] * (a[i][1] - -1);
+		else {
+			s = s - 1;
+		}
+		i = i + 1;
+	}
+	return s;
+}
+int main() {
+	int i = 0;
+	while (i < N) {
+		g[i][1] = fib(i + M[1]);
+		i = i + 1;
+	}
+	putint(sum(g, N));
+	putch(10);
+	putfloat(f * -2 / (3 - 1));
+	putch(10);
+	putint(M[0] + 42);
+	starttime();
+	return i % 3;
+}
+";
+
+fn output(code: &str) -> Option<String> {
+	let program = step_irgen(step_parse(code).ok()?).ok()?;
+	let execution =
+		interpret_limited(&program, Vec::new(), Some(1 << 20)).ok()?;
+	Some(String::from_utf8_lossy(&execution.output).into_owned())
+}
+
+#[test]
+fn reduce_program() {
+	let mut interesting =
+		|code: &str| output(code).is_some_and(|v| v.contains("45"));
+	let reduced = reduce(PROGRAM, &mut interesting, &mut |_| {}).unwrap();
+	assert!(interesting(&reduced));
+	assert!(reduced.len() * 4 < PROGRAM.len(), "{reduced}");
+	assert!(!reduced.contains("fib"), "{reduced}");
+}
+
+#[test]
+fn reject_boring_programs() {
+	let oracle = Oracle::new(Interesting::Differ(1), Vec::new());
+	assert!(!oracle.check(PROGRAM));
+	assert!(!oracle.check("int main() { return x; }"));
+	let mut interesting = |code: &str| oracle.check(code);
+	assert!(reduce(PROGRAM, &mut interesting, &mut |_| {}).is_err());
+	let oracle = Oracle::new(Interesting::Panic(2, None), Vec::new());
+	assert!(!oracle.check(PROGRAM));
+}
+
+#[test]
+fn tell_running_out_of_steps() {
+	let code = "int main() { while (1) {} return 0; }";
+	let program = step_irgen(step_parse(code).unwrap()).unwrap();
+	let crash = interpret_limited(&program, Vec::new(), Some(1000)).unwrap_err();
+	assert!(
+		matches!(crash.error, StepLimitError(1000)),
+		"{}",
+		crash.error
+	);
+}
@@ -1,10 +1,14 @@
 pub use clap::Parser;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Default)]
 pub struct Args {
 	#[arg(long)]
 	pub parse: bool,
 
+	/// Print the parsed program back as SysY source
+	#[arg(long)]
+	pub sysy: bool,
+
 	#[arg(short = 'S')]
 	pub assemble: bool,
 
@@ -14,12 +18,88 @@ pub struct Args {
 	#[arg(long)]
 	pub riscv: bool,
 
+	/// Print the control flow graph of each function in Graphviz format, of
+	/// the RISC-V code if `--riscv` is also given
+	#[arg(long)]
+	pub dot_cfg: bool,
+
+	/// Print the dominator tree of each function in Graphviz format, of the
+	/// RISC-V code if `--riscv` is also given
+	#[arg(long)]
+	pub dot_domtree: bool,
+
+	/// Print the loops of each function in Graphviz format, of the RISC-V
+	/// code if `--riscv` is also given
+	#[arg(long)]
+	pub dot_loops: bool,
+
+	/// Read the input as the IR printed by `--llvm` instead of SysY source
+	#[arg(long)]
+	pub from_llvm: bool,
+
+	/// Run the optimized IR with stdin as its input instead of compiling,
+	/// and exit with the return value of `main`
+	#[arg(long)]
+	pub interpret: bool,
+
+	/// Run the RISC-V code in a simulator with stdin as its input instead of
+	/// emitting it, and exit with the return value of `main`
+	#[arg(long)]
+	pub simulate: bool,
+
+	/// With `--simulate`, print the instructions and cycles spent in each
+	/// function to stderr
+	#[arg(long)]
+	pub profile: bool,
+
 	#[arg(short)]
 	pub output: Option<String>,
 
 	#[arg(short = 'O')]
 	pub opimizer: Option<i32>,
 
+	/// Split parallel loops among this many threads, 4 by default, 1 to
+	/// leave them as they are
+	#[arg(long, value_name = "N")]
+	pub threads: Option<usize>,
+
+	/// Run this pipeline instead of the one chosen by `-O`,
+	/// e.g. `fold_constants,gvn,(mem2reg,useless_phis)*`
+	#[arg(long)]
+	pub passes: Option<String>,
+
+	/// Print IR to stderr after these passes (comma separated, or `all`)
+	#[arg(long, value_delimiter = ',')]
+	pub print_after: Vec<String>,
+
+	/// Verify the IR after every optimizer pass
+	#[arg(long)]
+	pub verify_each: bool,
+
+	/// Compile each input on its own into `<input>.s`, or the `-o` file if
+	/// there is one input, with its functions and variables visible to the
+	/// files it is linked with. Otherwise the inputs are linked into one
+	/// program first.
+	#[arg(long)]
+	pub separate: bool,
+
+	/// Look for the files of `#include` in this directory as well
+	#[arg(short = 'I', value_name = "DIR")]
+	pub include: Vec<String>,
+
+	/// Turn a warning on, or off as `-Wno-<name>`: `unused`,
+	/// `uninitialized`, `unreachable`, `return-type`, `div-by-zero`, or `all`
+	/// of them. `-Werror` makes warnings errors.
+	#[arg(short = 'W', value_name = "WARNING")]
+	pub warnings: Vec<String>,
+
 	#[arg(value_parser)]
-	pub input: Option<String>,
+	pub input: Vec<String>,
+}
+
+impl Args {
+	/// Whether any `--dot-*` graph is asked for
+	pub fn dot(&self) -> bool {
+		self.dot_cfg || self.dot_domtree || self.dot_loops
+	}
 }
@@ -0,0 +1,119 @@
+pub mod cli;
+
+use ast::tree::Program;
+use cli::Args;
+use instruction::temp::TempManager;
+use irgen::IRGenerator;
+use linter::{visitor::Linter, LintConfig, Warning};
+use namer::visitor::Namer;
+use optimizer::*;
+use parser::parser::parse;
+use post_optimizer::post_backend_optimize;
+use pre_optimizer::prereg_backend_optimize;
+use preprocessor::Preprocessor;
+use register::solve_register;
+use rrvm::program::*;
+use transform::get_functions;
+use typer::visitor::Typer;
+use utils::{errors::Result, warning, SourceMap};
+
+/// Expand the directives and macros of a file. The map points the code, or
+/// the error, back at the files it comes from.
+pub fn step_preprocess(
+	file_name: &str,
+	code: &str,
+	args: &Args,
+) -> (Result<String>, SourceMap) {
+	let mut preprocessor = Preprocessor::new(&args.include);
+	let result = preprocessor.process(file_name, code);
+	(result, preprocessor.into_source_map())
+}
+
+pub fn step_parse(code: &str) -> Result<Program> {
+	parse(code)
+}
+
+pub fn step_parse_llvm(code: &str) -> Result<LlvmProgram> {
+	LlvmProgram::parse(code)
+}
+
+/// Check names and types
+pub fn step_check(program: &mut Program) -> Result<()> {
+	Namer::default().transform(program)?;
+	Typer::default().transform(program)
+}
+
+/// The warnings about a checked program that the `-W` flags ask for
+pub fn step_lint(program: &mut Program, config: &LintConfig) -> Vec<Warning> {
+	let warnings = Linter::default().transform(program);
+	warnings.into_iter().filter(|v| config.is_enabled(v.lint)).collect()
+}
+
+/// Generate the IR of a checked program without optimizing it
+pub fn step_gen(program: Program) -> Result<LlvmProgram> {
+	IRGenerator::new().to_rrvm(program)
+}
+
+/// Check names and types, then generate the IR without optimizing it
+pub fn step_irgen(mut program: Program) -> Result<LlvmProgram> {
+	step_check(&mut program)?;
+	step_gen(program)
+}
+
+/// Merge the IR of several files, resolving the symbols declared `extern`
+pub fn step_link(programs: Vec<LlvmProgram>) -> Result<LlvmProgram> {
+	LlvmProgram::link(programs)
+}
+
+pub fn step_llvm(program: Program, args: &Args) -> Result<LlvmProgram> {
+	step_optimize(step_irgen(program)?, args)
+}
+
+/// The pipeline chosen by `--passes`, or by `-O` if it is not given
+pub fn step_pipeline(args: &Args) -> Result<Pipeline> {
+	let level = args.opimizer.unwrap_or(0);
+	let desc = match (&args.passes, level) {
+		(Some(passes), _) => passes.as_str(),
+		(None, 0) => "fast",
+		(None, 1) => "full",
+		(None, 2) => "full_pre",
+		_ => {
+			warning(format!(
+				"optimization level '-O{level}' is not supported; using '-O0' instead",
+			));
+			"basic"
+		}
+	};
+	Pipeline::parse(desc)
+}
+
+pub fn step_optimize(
+	mut program: LlvmProgram,
+	args: &Args,
+) -> Result<LlvmProgram> {
+	let pipeline = step_pipeline(args)?;
+	for name in args.print_after.iter() {
+		if name != "all" && !PASSES.iter().any(|(v, ..)| v == name) {
+			warning(format!("'--print-after={name}' names no pass"));
+		}
+	}
+	let mut manager = PassManager::new();
+	manager.set_print_after(args.print_after.iter().cloned().collect());
+	manager.set_verify_each(args.verify_each);
+	manager.set_exported(args.separate);
+	if let Some(threads) = args.threads {
+		manager.set_threads(threads);
+	}
+	manager.apply(&pipeline, &mut program)?;
+	Ok(program)
+}
+
+pub fn step_riscv(program: LlvmProgram, level: i32) -> Result<RiscvProgram> {
+	let mut riscv_program = RiscvProgram::new(TempManager::default());
+	riscv_program.global_vars = program.global_vars;
+	get_functions(&mut riscv_program, program.funcs)?;
+	prereg_backend_optimize(&mut riscv_program, level);
+	solve_register(&mut riscv_program);
+	post_backend_optimize(&mut riscv_program, level);
+	Ok(riscv_program)
+}
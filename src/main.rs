@@ -1,102 +1,200 @@
-mod cli;
 mod config;
 mod printer;
 
 use crate::{config::PARSER_INDENT, printer::trans_indent};
 use anyhow::Result;
-use ast::tree::Program;
+use ast::printer::SourcePrinter;
 use clap::Parser;
-use cli::Args;
 use emission::code_emission;
-use instruction::temp::TempManager;
-use irgen::IRGenerator;
-use namer::visitor::Namer;
-use optimizer::*;
-use parser::parser::parse;
-use post_optimizer::post_backend_optimize;
-use pre_optimizer::prereg_backend_optimize;
-use register::solve_register;
-use rrvm::program::*;
+use interpreter::{Crash, Execution, Input};
+use linter::LintConfig;
 use std::{
 	fs::{self, File},
 	io,
 	io::Write,
+	path::Path,
 };
-use transform::get_functions;
-use typer::visitor::Typer;
-use utils::{fatal_error, map_sys_err, warning};
+use sysyc::{cli::Args, *};
+use utils::{fatal_error, map_sys_err, SourceMap};
 
-fn step_parse(file_name: &str) -> Result<Program> {
-	let code = fs::read_to_string(file_name)
+fn read_input(file_name: &str) -> String {
+	fs::read_to_string(file_name)
 		.map_err(|_| fatal_error("no input files"))
-		.unwrap();
-	Ok(parse(&code)?)
+		.unwrap()
 }
 
-fn step_llvm(mut program: Program, level: i32) -> Result<LlvmProgram> {
-	Namer::default().transform(&mut program)?;
-	Typer::default().transform(&mut program)?;
-	let mut program = IRGenerator::new().to_rrvm(program)?;
-	match level {
-		0 => Optimizer1::new().apply(&mut program)?,
-		1 => Optimizer2::new().apply(&mut program)?,
-		2 => Optimizer2::new().apply(&mut program)?,
-		_ => {
-			warning(format!(
-				"optimization level '-O{level}' is not supported; using '-O0' instead",
-			));
-			Optimizer0::new().apply(&mut program)?
-		}
+// print the errors of the frontend with their source lines, then quit
+fn check<T>(result: utils::errors::Result<T>, sources: &SourceMap) -> T {
+	result.unwrap_or_else(|err| {
+		eprint!("{}", err.render_in(sources));
+		std::process::exit(1)
+	})
+}
+
+// print the warnings of a checked file, then quit if they are errors
+fn lint(
+	program: &mut ast::tree::Program,
+	config: &LintConfig,
+	sources: &SourceMap,
+) {
+	let warnings = step_lint(program, config);
+	for warning in warnings.iter() {
+		eprint!("{}", warning.render_in(sources, config.werror));
+	}
+	if config.werror && !warnings.is_empty() {
+		std::process::exit(1)
+	}
+}
+
+// write what the program printed and exit like it does, or report the error
+// it stops at after what it printed before
+fn finish(
+	writer: &mut dyn Write,
+	result: std::result::Result<Execution, Crash>,
+) -> Result<()> {
+	let output = match &result {
+		Ok(v) => &v.output,
+		Err(v) => &v.output,
 	};
-	Ok(program)
+	writer.write_all(output)?;
+	writer.flush()?;
+	match result {
+		Ok(v) => std::process::exit(v.exit_code as i32),
+		Err(v) => Err(v.error.into()),
+	}
 }
 
-fn step_riscv(program: LlvmProgram, level: i32) -> Result<RiscvProgram> {
-	let mut riscv_program = RiscvProgram::new(TempManager::default());
-	riscv_program.global_vars = program.global_vars;
-	get_functions(&mut riscv_program, program.funcs)?;
-	prereg_backend_optimize(&mut riscv_program, level);
-	solve_register(&mut riscv_program);
-	post_backend_optimize(&mut riscv_program, level);
-	Ok(riscv_program)
+// the file `-S` writes for an input, in the current directory like gcc does
+fn asm_name(file_name: &str) -> String {
+	let path = Path::new(file_name).with_extension("s");
+	path.file_name().unwrap().to_string_lossy().into_owned()
+}
+
+fn create(output: Option<&str>) -> Result<Box<dyn Write>> {
+	Ok(match output {
+		Some(o) => Box::new(File::create(o).map_err(map_sys_err)?),
+		None => Box::new(io::stdout()),
+	})
 }
 
 fn main() -> Result<()> {
 	let args = Args::parse();
 
-	let mut writer: Box<dyn Write> = if let Some(o) = args.output {
-		Box::new(File::create(o).map_err(map_sys_err)?)
-	} else {
-		Box::new(io::stdout())
-	};
+	if args.input.is_empty() {
+		fatal_error("no input files");
+	}
+	if !args.separate {
+		return compile(&args, &args.input, create(args.output.as_deref())?);
+	}
+	if args.output.is_some() && args.input.len() > 1 {
+		fatal_error("cannot specify '-o' with '--separate' and multiple files");
+	}
+	if args.interpret || args.simulate {
+		fatal_error("a file compiled with '--separate' can not be run");
+	}
+	for file_name in args.input.iter() {
+		let output = args.output.clone().unwrap_or_else(|| asm_name(file_name));
+		let writer = create(Some(&output))?;
+		compile(&args, std::slice::from_ref(file_name), writer)?;
+	}
+	Ok(())
+}
 
+// compile the files as one program
+fn compile(
+	args: &Args,
+	files: &[String],
+	mut writer: Box<dyn Write>,
+) -> Result<()> {
 	let level = args.opimizer.unwrap_or(0);
+	let file_name = files[0].clone();
+	let config = LintConfig::new(&args.warnings);
 
-	let file_name = args.input.unwrap_or_else(|| {
-		fatal_error("no input files");
-		unreachable!()
-	});
-
-	let program = step_parse(&file_name)?;
-	if args.parse {
-		let x = format!("{:#?}", program);
-		write!(writer, "{}", trans_indent(&x, PARSER_INDENT))?;
+	let mut programs = Vec::new();
+	for file_name in files {
+		let code = read_input(file_name);
+		if args.from_llvm {
+			programs.push(step_parse_llvm(&code)?);
+			continue;
+		}
+		let (code, sources) = step_preprocess(file_name, &code, args);
+		let code = check(code, &sources);
+		let mut program = check(step_parse(&code), &sources);
+		if args.sysy {
+			write!(writer, "{}", SourcePrinter::print(&mut program)?)?;
+			continue;
+		}
+		if args.parse {
+			let x = format!("{:#?}", program);
+			write!(writer, "{}", trans_indent(&x, PARSER_INDENT))?;
+			continue;
+		}
+		check(step_check(&mut program), &sources);
+		lint(&mut program, &config, &sources);
+		programs.push(check(step_gen(program), &sources));
+	}
+	if !args.from_llvm && (args.sysy || args.parse) {
+		return Ok(());
+	}
+	// a module keeps what it declares `extern` for the linker to resolve
+	let llvm = if args.separate {
+		programs.pop().unwrap()
+	} else {
+		check(step_link(programs), &SourceMap::default())
+	};
+	let llvm = step_optimize(llvm, args)?;
+	if args.dot() && !args.riscv {
+		if args.dot_cfg {
+			write!(writer, "{}", llvm.dot_cfg())?;
+		}
+		if args.dot_domtree {
+			write!(writer, "{}", llvm.dot_domtree())?;
+		}
+		if args.dot_loops {
+			write!(writer, "{}", llvm.dot_loops())?;
+		}
 		return Ok(());
 	}
-
-	let llvm = step_llvm(program, level)?;
 	if args.llvm {
 		write!(writer, "{}", llvm)?;
 		return Ok(());
 	}
+	if args.interpret {
+		let result = interpreter::interpret(&llvm, Input::new(io::stdin()));
+		finish(&mut writer, result)?;
+	}
 
 	let riscv = step_riscv(llvm, level)?;
+	if args.dot() {
+		if args.dot_cfg {
+			write!(writer, "{}", riscv.dot_cfg())?;
+		}
+		if args.dot_domtree {
+			write!(writer, "{}", riscv.dot_domtree())?;
+		}
+		if args.dot_loops {
+			write!(writer, "{}", riscv.dot_loops())?;
+		}
+		return Ok(());
+	}
 	if args.riscv {
 		write!(writer, "{}", riscv)?;
 		return Ok(());
 	}
+	if args.simulate {
+		let result = interpreter::simulate(&riscv, Input::new(io::stdin()));
+		if let (true, Ok((_, profile))) = (args.profile, &result) {
+			for func in profile.iter().filter(|v| v.instrs > 0) {
+				eprintln!(
+					"{}: {} instrs, {} cycles",
+					func.name, func.instrs, func.cycles
+				);
+			}
+		}
+		finish(&mut writer, result.map(|v| v.0))?;
+	}
 
-	let code = code_emission(riscv, file_name);
+	let code = code_emission(riscv, file_name, args.separate);
 	write!(writer, "{}", code)?;
 
 	Ok(())
@@ -4,6 +4,15 @@ use proc_macro2::Span;
 use quote::quote;
 use syn::*;
 
+// the slots of `attr::AttrSlots` and what is in them
+const ATTR_SLOTS: [(&str, &str); 5] = [
+	("symbol", "::attr::VarSymbol"),
+	("func_symbol", "::attr::FuncSymbol"),
+	("var_type", "::attr::VarType"),
+	("value", "::attr::Value"),
+	("slot", "usize"),
+];
+
 #[proc_macro_attribute]
 pub fn has_attrs(_: TokenStream, item: TokenStream) -> TokenStream {
 	let mut input = parse_macro_input!(item as DeriveInput);
@@ -24,23 +33,51 @@ pub fn has_attrs(_: TokenStream, item: TokenStream) -> TokenStream {
 			mutability: FieldMutability::None,
 			ident: Some(Ident::new("_attrs", Span::call_site())),
 			colon_token: None,
-			ty: parse2(quote!(::std::collections::HashMap<String, Attr>)).unwrap(),
+			ty: parse2(quote!(::attr::AttrSlots)).unwrap(),
+		},
+	);
+	fields.named.insert(
+		1,
+		Field {
+			attrs: Vec::new(),
+			vis: Visibility::Public(parse_quote!(pub)),
+			mutability: FieldMutability::None,
+			ident: Some(Ident::new("_span", Span::call_site())),
+			colon_token: None,
+			ty: parse2(quote!(::utils::Span)).unwrap(),
 		},
 	);
 
 	let name = &input.ident;
+	let accessors = ATTR_SLOTS.iter().map(|(slot, type_name)| {
+		let slot_ident = Ident::new(slot, Span::call_site());
+		let get_ident = Ident::new(&format!("get_{}", slot), Span::call_site());
+		let set_ident = Ident::new(&format!("set_{}", slot), Span::call_site());
+		let type_t: Type = parse_str(type_name).unwrap();
+		let message = format!("`{}` of `{}` is read before it is set", slot, name);
+		quote! {
+			fn #slot_ident(&self) -> ::utils::errors::Result<&#type_t> {
+				self._attrs.#slot_ident.as_ref().ok_or_else(|| {
+					::utils::SysycError::FatalError(#message.to_string())
+				})
+			}
+			fn #get_ident(&self) -> Option<&#type_t> {
+				self._attrs.#slot_ident.as_ref()
+			}
+			fn #set_ident(&mut self, value: #type_t) {
+				self._attrs.#slot_ident = Some(value);
+			}
+		}
+	});
 
 	quote! {
 		#input
 
-		impl Attrs for #name {
-			fn set_attr(&mut self, name: &str, attr: Attr) {
-				self._attrs.insert(String::from(name), attr);
-			}
-
-			fn get_attr(&self, name: &str) -> Option<&Attr> {
-				self._attrs.get(name)
+		impl ::attr::Attrs for #name {
+			fn attrs(&self) -> &::attr::AttrSlots {
+				&self._attrs
 			}
+			#( #accessors )*
 		}
 	}
 	.into()
@@ -60,7 +97,11 @@ pub fn ast_node_derive(input: TokenStream) -> TokenStream {
 	let expanded = quote! {
 		impl AstNode for #name {
 			fn accept(&mut self, visitor: &mut dyn Visitor) -> Result<()> {
-				visitor.#visitor_fn_ident(self)
+				let span = self._span;
+				visitor.#visitor_fn_ident(self).map_err(|e| e.at(span))
+			}
+			fn span(&self) -> ::utils::Span {
+				self._span
 			}
 			fn is_end(&self) -> bool {
 				#is_end
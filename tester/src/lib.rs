@@ -0,0 +1,266 @@
+use std::{
+	fmt, fs,
+	panic::{self, AssertUnwindSafe},
+	path::Path,
+	sync::mpsc::{self, RecvTimeoutError, Sender},
+	thread,
+	time::{Duration, Instant},
+};
+
+use interpreter::{interpret, simulate, Crash, Execution};
+use sysyc::{cli::Args, *};
+use utils::{errors::Result, SysycError::SystemError};
+
+// the compiler recurses on the syntax tree, give it some room
+const STACK_SIZE: usize = 256 << 20;
+// lines of output shown with a runtime error
+const CRASH_LINES: usize = 5;
+
+/// A `.sy` program with its input (`.in`, may be absent) and the expected
+/// output (`.out`)
+pub struct Case {
+	pub name: String,
+	pub code: String,
+	pub input: Vec<u8>,
+	pub expected: String,
+}
+
+/// The point of the pipeline at which a program is checked, in order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+	Frontend,
+	Irgen,
+	Optimizer,
+	Backend,
+}
+
+impl fmt::Display for Stage {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let desc = match self {
+			Stage::Frontend => "frontend",
+			Stage::Irgen => "IR before optimization",
+			Stage::Optimizer => "IR after optimization",
+			Stage::Backend => "RISC-V after register allocation",
+		};
+		write!(f, "{desc}")
+	}
+}
+
+/// The first stage at which a case stops giving the expected output
+#[derive(Debug)]
+pub struct Failure {
+	pub case: String,
+	/// `None` if the stage comes before the optimizer
+	pub level: Option<i32>,
+	pub stage: Stage,
+	pub reason: String,
+}
+
+impl fmt::Display for Failure {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.case)?;
+		if let Some(level) = self.level {
+			write!(f, " -O{level}")?;
+		}
+		write!(f, ": {} diverges: {}", self.stage, self.reason)
+	}
+}
+
+fn read_file(path: &Path) -> Result<String> {
+	fs::read_to_string(path)
+		.map_err(|e| SystemError(format!("{}: {e}", path.display())))
+}
+
+/// All the `.sy` files under `dir` that come with a `.out` file, sorted by
+/// name
+pub fn load_cases(dir: &Path) -> Result<Vec<Case>> {
+	let entries = fs::read_dir(dir)
+		.map_err(|e| SystemError(format!("{}: {e}", dir.display())))?;
+	let mut paths: Vec<_> = entries
+		.filter_map(|v| v.ok().map(|v| v.path()))
+		.filter(|v| v.extension().is_some_and(|v| v == "sy"))
+		.filter(|v| v.with_extension("out").exists())
+		.collect();
+	paths.sort();
+	let mut cases = Vec::new();
+	for path in paths {
+		let input = path.with_extension("in");
+		cases.push(Case {
+			name: path.file_stem().unwrap().to_string_lossy().into_owned(),
+			code: read_file(&path)?,
+			input: if input.exists() {
+				read_file(&input)?.into_bytes()
+			} else {
+				Vec::new()
+			},
+			expected: read_file(&path.with_extension("out"))?,
+		});
+	}
+	Ok(cases)
+}
+
+/// Output followed by the exit code, the way `.out` files are written
+fn to_expected(execution: &Execution) -> String {
+	let mut text = String::from_utf8_lossy(&execution.output).into_owned();
+	if !text.is_empty() && !text.ends_with('\n') {
+		text.push('\n');
+	}
+	text.push_str(&format!("{}\n", execution.exit_code));
+	text
+}
+
+fn compare(execution: &Execution, expected: &str) -> Option<String> {
+	let actual = to_expected(execution);
+	let actual: Vec<_> = actual.trim_end().lines().map(str::trim_end).collect();
+	let expected: Vec<_> =
+		expected.trim_end().lines().map(str::trim_end).collect();
+	if actual == expected {
+		return None;
+	}
+	let line =
+		actual.iter().zip(expected.iter()).take_while(|(a, b)| a == b).count();
+	let show = |v: Option<&&str>| {
+		v.map_or("end of output".to_string(), |v| format!("`{v}`"))
+	};
+	Some(format!(
+		"line {}: expected {}, got {}",
+		line + 1,
+		show(expected.get(line)),
+		show(actual.get(line))
+	))
+}
+
+// the error a run stops at, with the last lines it printed before
+fn crash_reason(crash: Crash) -> String {
+	let output = String::from_utf8_lossy(&crash.output);
+	let lines: Vec<_> = output.trim_end().lines().collect();
+	if lines.is_empty() {
+		return crash.error.to_string();
+	}
+	let shown = &lines[lines.len().saturating_sub(CRASH_LINES)..];
+	format!("{}, after printing:\n{}", crash.error, shown.join("\n"))
+}
+
+fn guard<T>(f: impl FnOnce() -> Result<T>) -> std::result::Result<T, String> {
+	match panic::catch_unwind(AssertUnwindSafe(f)) {
+		Ok(v) => v.map_err(|e| e.to_string()),
+		Err(e) => {
+			let message = e
+				.downcast_ref::<&str>()
+				.map(|v| v.to_string())
+				.or_else(|| e.downcast_ref::<String>().cloned())
+				.unwrap_or_default();
+			Err(format!("panicked: {message}"))
+		}
+	}
+}
+
+enum Event {
+	Enter(Option<i32>, Stage),
+	Fail(Option<i32>, Stage, String),
+}
+
+fn run_stages(case: &Case, levels: &[i32], sender: &Sender<Event>) {
+	let send = |event| sender.send(event).unwrap_or(());
+	type Run = std::result::Result<Execution, Crash>;
+	let check = |level, stage, result: std::result::Result<Run, String>| {
+		let reason = match result {
+			Ok(Ok(execution)) => compare(&execution, &case.expected),
+			Ok(Err(crash)) => Some(crash_reason(crash)),
+			Err(e) => Some(e),
+		};
+		reason.map(|reason| send(Event::Fail(level, stage, reason))).is_none()
+	};
+	// the IR is generated again for every level, as it can not be copied
+	let irgen = || step_irgen(step_parse(&case.code)?);
+	send(Event::Enter(None, Stage::Frontend));
+	let program = match guard(irgen) {
+		Ok(v) => v,
+		Err(e) => return send(Event::Fail(None, Stage::Frontend, e)),
+	};
+	send(Event::Enter(None, Stage::Irgen));
+	let result = guard(|| Ok(interpret(&program, case.input.clone())));
+	if !check(None, Stage::Irgen, result) {
+		return;
+	}
+	for &level in levels {
+		let at = Some(level);
+		send(Event::Enter(at, Stage::Optimizer));
+		let args = Args {
+			opimizer: at,
+			..Default::default()
+		};
+		let llvm = match guard(|| step_optimize(irgen()?, &args)) {
+			Ok(v) => v,
+			Err(e) => {
+				send(Event::Fail(at, Stage::Optimizer, e));
+				continue;
+			}
+		};
+		let result = guard(|| Ok(interpret(&llvm, case.input.clone())));
+		if !check(at, Stage::Optimizer, result) {
+			continue;
+		}
+		send(Event::Enter(at, Stage::Backend));
+		let result = guard(|| {
+			let riscv = step_riscv(llvm, level)?;
+			Ok(simulate(&riscv, case.input.clone()).map(|v| v.0))
+		});
+		check(at, Stage::Backend, result);
+	}
+}
+
+/// Run a case through the pipeline at each optimization level, checking the
+/// output after each stage. A level is given up at its first divergence, and
+/// all of them are if the IR is already wrong before optimizing. A case
+/// taking more than `timeout` fails at the stage it is stuck in.
+pub fn run_case(case: Case, levels: &[i32], timeout: Duration) -> Vec<Failure> {
+	let (sender, receiver) = mpsc::channel();
+	let name = case.name.clone();
+	let levels = levels.to_vec();
+	thread::Builder::new()
+		.stack_size(STACK_SIZE)
+		.spawn(move || run_stages(&case, &levels, &sender))
+		.expect("failed to spawn a test thread");
+	let deadline = Instant::now() + timeout;
+	let mut current = (None, Stage::Frontend);
+	let mut failures = Vec::new();
+	loop {
+		let left = deadline.saturating_duration_since(Instant::now());
+		match receiver.recv_timeout(left) {
+			Ok(Event::Enter(level, stage)) => current = (level, stage),
+			Ok(Event::Fail(level, stage, reason)) => failures.push(Failure {
+				case: name.clone(),
+				level,
+				stage,
+				reason,
+			}),
+			Err(RecvTimeoutError::Disconnected) => break,
+			Err(RecvTimeoutError::Timeout) => {
+				// the thread is left running, there is no way to stop it
+				failures.push(Failure {
+					case: name,
+					level: current.0,
+					stage: current.1,
+					reason: format!("timed out after {}s", timeout.as_secs()),
+				});
+				break;
+			}
+		}
+	}
+	failures
+}
+
+/// Run all the cases under `dir`, returning the number of cases and the
+/// failures
+pub fn run_dir(
+	dir: &Path,
+	levels: &[i32],
+	timeout: Duration,
+) -> Result<(usize, Vec<Failure>)> {
+	let cases = load_cases(dir)?;
+	let total = cases.len();
+	let failures =
+		cases.into_iter().flat_map(|v| run_case(v, levels, timeout)).collect();
+	Ok((total, failures))
+}
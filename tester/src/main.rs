@@ -0,0 +1,42 @@
+use std::{path::PathBuf, process::exit, time::Duration};
+
+use clap::Parser;
+use tester::run_dir;
+
+/// Compile every `.sy` file under a directory and compare the output of each
+/// stage of the pipeline with the `.out` file next to it
+#[derive(Parser, Debug)]
+struct Args {
+	/// Directory of the test cases
+	#[arg(value_parser)]
+	dir: PathBuf,
+
+	/// Optimization levels to test, comma separated
+	#[arg(short = 'O', value_delimiter = ',', default_values_t = [0, 1, 2])]
+	levels: Vec<i32>,
+
+	/// Seconds a case may take over all the levels
+	#[arg(long, default_value_t = 60)]
+	timeout: u64,
+}
+
+fn main() {
+	let args = Args::parse();
+	let timeout = Duration::from_secs(args.timeout);
+	let (total, failures) = match run_dir(&args.dir, &args.levels, timeout) {
+		Ok(v) => v,
+		Err(e) => {
+			eprintln!("{e}");
+			exit(2);
+		}
+	};
+	for failure in failures.iter() {
+		println!("{failure}");
+	}
+	let mut failed: Vec<_> = failures.iter().map(|v| &v.case).collect();
+	failed.dedup();
+	println!("{}/{total} cases passed", total - failed.len());
+	if !failures.is_empty() {
+		exit(1);
+	}
+}
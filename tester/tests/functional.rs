@@ -0,0 +1,56 @@
+use std::{path::Path, time::Duration};
+
+use tester::{load_cases, run_case, run_dir, Case, Stage};
+
+fn functional_dir() -> &'static Path {
+	Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../tests/functional"))
+}
+
+#[test]
+fn functional() {
+	let timeout = Duration::from_secs(300);
+	let (total, failures) =
+		run_dir(functional_dir(), &[0, 1, 2], timeout).unwrap();
+	assert!(total > 0);
+	let report: Vec<_> = failures.iter().map(|v| v.to_string()).collect();
+	assert!(failures.is_empty(), "{}", report.join("\n"));
+}
+
+#[test]
+fn load_inputs() {
+	let cases = load_cases(functional_dir()).unwrap();
+	let sort = cases.iter().find(|v| v.name == "sort").unwrap();
+	assert!(!sort.input.is_empty());
+	let fib = cases.iter().find(|v| v.name == "fib_recursion").unwrap();
+	assert!(fib.input.is_empty());
+}
+
+#[test]
+fn report_first_stage() {
+	let case = |code: &str, expected: &str| Case {
+		name: "case".to_string(),
+		code: code.to_string(),
+		input: Vec::new(),
+		expected: expected.to_string(),
+	};
+	let timeout = Duration::from_secs(60);
+	let failures = run_case(case("int main() { return; }", "0\n"), &[0], timeout);
+	assert_eq!(failures.len(), 1);
+	assert_eq!(failures[0].stage, Stage::Frontend);
+	let code = "int main() { putint(7); return 3; }";
+	let failures = run_case(case(code, "7\n4\n"), &[0, 1], timeout);
+	assert_eq!(failures.len(), 1);
+	assert_eq!((failures[0].level, failures[0].stage), (None, Stage::Irgen));
+	assert!(failures[0].reason.contains("line 2"), "{}", failures[0]);
+	assert!(run_case(case(code, "7\n3\n"), &[0, 1], timeout).is_empty());
+	// a runtime error is shown with the output before it
+	let code = "int main() { putint(7); putch(10); return getint(); }";
+	let failures = run_case(case(code, "7\n3\n"), &[0], timeout);
+	assert_eq!(failures.len(), 1);
+	assert_eq!(failures[0].stage, Stage::Irgen);
+	assert!(
+		failures[0].reason.ends_with("after printing:\n7"),
+		"{}",
+		failures[0]
+	);
+}
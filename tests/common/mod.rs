@@ -0,0 +1,50 @@
+#![allow(dead_code)]
+
+use rrvm::program::LlvmProgram;
+use sysyc::{cli::Args, *};
+
+/// Compile `code` to IR, not optimized
+pub fn irgen(code: &str) -> LlvmProgram {
+	step_irgen(step_parse(code).unwrap()).unwrap()
+}
+
+/// Compile `code` to IR, optimized as `args` asks
+pub fn compile_with(code: &str, args: &Args) -> LlvmProgram {
+	step_optimize(irgen(code), args).unwrap()
+}
+
+pub fn compile(code: &str, level: i32) -> LlvmProgram {
+	let args = Args {
+		opimizer: Some(level),
+		..Args::default()
+	};
+	compile_with(code, &args)
+}
+
+/// Compile `code` to IR, optimized by the pipeline `passes`
+pub fn compile_passes(code: &str, passes: &str) -> LlvmProgram {
+	let args = Args {
+		passes: Some(passes.to_string()),
+		..Args::default()
+	};
+	compile_with(code, &args)
+}
+
+pub fn main_of(program: &LlvmProgram) -> String {
+	let func = program.funcs.iter().find(|v| v.name == "main").unwrap();
+	func.to_string()
+}
+
+/// The instructions of each block of `main`
+pub fn blocks_of(program: &LlvmProgram) -> Vec<Vec<String>> {
+	let func = program.funcs.iter().find(|v| v.name == "main").unwrap();
+	let mut blocks: Vec<Vec<String>> = Vec::new();
+	for line in func.to_string().lines().map(str::trim) {
+		if line.ends_with(':') {
+			blocks.push(Vec::new());
+		} else if let Some(block) = blocks.last_mut() {
+			block.push(line.to_string());
+		}
+	}
+	blocks
+}
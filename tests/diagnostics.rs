@@ -0,0 +1,123 @@
+use irgen::IRGenerator;
+use namer::visitor::Namer;
+use parser::parser::parse;
+use typer::visitor::Typer;
+use utils::{Span, SysycError};
+
+fn render(code: &str) -> (SysycError, String) {
+	let err = parse(code)
+		.and_then(|mut program| {
+			Namer::default().transform(&mut program)?;
+			Typer::default().transform(&mut program)
+		})
+		.unwrap_err();
+	let text = err.render("a.sy", code);
+	(err, text)
+}
+
+#[test]
+fn record_spans() {
+	let code = "int main() {\n\treturn 1 + 2;\n}\n";
+	let program = parse(code).unwrap();
+	let func = &program.functions[0];
+	assert_eq!(func.span(), Span::new(0, code.len() - 1));
+}
+
+#[test]
+fn locate_errors() {
+	let code = "int main() {\n\tfloat y = 1.0;\n\treturn !y;\n}\n";
+	let (_, text) = render(code);
+	assert_eq!(
+		text,
+		"error: Type Error: Only integer can use operator '!'\n --> a.sy:3:9\n  |\n3 | \treturn !y;\n  | \t       ^^\n"
+	);
+	let code = "int main() {\n  int a = 1\n  return a;\n}\n";
+	let (_, text) = render(code);
+	assert!(text.contains(" --> a.sy:3:3\n"));
+}
+
+#[test]
+fn collect_errors() {
+	let code = "int main() {\n\tx = 1;\n\tint y;\n\ty = z;\n\treturn y;\n}\n";
+	let (err, text) = render(code);
+	let SysycError::Errors(errors) = err else {
+		panic!("expected several errors");
+	};
+	assert_eq!(errors.len(), 2);
+	assert!(text.contains("a.sy:2:2"));
+	assert!(text.contains("a.sy:4:6"));
+}
+
+#[test]
+fn check_lvalues() {
+	let code =
+		"int main() {\n\tconst int c = 1;\n\tc += 2;\n\t3++;\n\treturn --c;\n}\n";
+	let (err, text) = render(code);
+	let SysycError::Errors(errors) = err else {
+		panic!("expected several errors");
+	};
+	assert_eq!(errors.len(), 3);
+	assert!(text.contains("Only lvalue can be assigned"));
+	assert!(text.contains("Only lvalue can be incremented or decremented"));
+	assert!(text.contains("a.sy:5:9"));
+}
+
+#[test]
+fn check_strings() {
+	let code =
+		"int main() {\n\tchar c = \"ab\";\n\tint a[3] = \"ab\";\n\treturn 0;\n}\n";
+	let (err, text) = render(code);
+	let SysycError::Errors(errors) = err else {
+		panic!("expected several errors");
+	};
+	assert_eq!(errors.len(), 2);
+	assert!(text.contains("can not initialize with a value of type"));
+	assert!(text.contains("a.sy:3:6"));
+	let code = "int main() {\n\treturn \"ab\" + 1;\n}\n";
+	let (_, text) = render(code);
+	assert!(text.contains("Can not do arith operation with pointer"));
+}
+
+#[test]
+fn check_structs() {
+	let code = "struct P {\n\tint x;\n};\nint main() {\n\tstruct P p = {1};\n\tp.z = 1;\n\treturn p + 1;\n}\n";
+	let (err, text) = render(code);
+	let SysycError::Errors(errors) = err else {
+		panic!("expected several errors");
+	};
+	assert_eq!(errors.len(), 2);
+	assert!(text.contains("`struct P` has no member named `z`"));
+	assert!(text.contains("Can not do arith operation with struct"));
+	let code = "struct P {\n\tint x, x;\n};\nint main() {\n\tstruct Q q;\n\treturn 0;\n}\n";
+	let (err, text) = render(code);
+	let SysycError::Errors(errors) = err else {
+		panic!("expected several errors");
+	};
+	assert_eq!(errors.len(), 2);
+	assert!(text.contains("duplicate member 'x'"));
+	assert!(text.contains("struct Q is not found"));
+}
+
+#[test]
+fn check_missing_attrs() {
+	// a stage run before the one that sets what it reads
+	let code = "int main() {\n\tint a = 1;\n\treturn a + 2;\n}\n";
+	let mut program = parse(code).unwrap();
+	let text =
+		Typer::default().transform(&mut program).unwrap_err().render("a.sy", code);
+	assert!(text.contains(
+		"`var_type` of `VarDef` is read before it is set\n --> a.sy:2:6\n"
+	));
+	let mut program = parse(code).unwrap();
+	Namer::default().transform(&mut program).unwrap();
+	let Err(err) = IRGenerator::new().to_rrvm(program) else {
+		panic!("generated IR without types");
+	};
+	assert!(err
+		.to_string()
+		.contains("`var_type` of `BinaryExpr` is read before it is set"));
+	let code =
+		"void f() {}\nint g(int x) { return x; }\nint main() { return g(f()); }\n";
+	let (_, text) = render(code);
+	assert!(text.contains("void value not ignored as it ought to be"));
+}
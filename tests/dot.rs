@@ -0,0 +1,74 @@
+mod common;
+
+use common::compile;
+use sysyc::*;
+
+const PROGRAM: &str = r"
+int main() {
+	int i = 0, s = 0;
+	while (i < 10) {
+		int j = 0;
+		while (j < i) {
+			s = s + j;
+			j = j + 1;
+		}
+		if (s > 5) s = s - 1;
+		i = i + 1;
+	}
+	return s;
+}
+";
+
+fn edges(graph: &str) -> Vec<&str> {
+	graph.lines().map(str::trim).filter(|v| v.contains("->")).collect()
+}
+
+#[test]
+fn llvm_graphs() {
+	let program = compile(PROGRAM, 0);
+	let cfg = program.dot_cfg();
+	assert!(cfg.starts_with("digraph {"));
+	assert!(cfg.contains("subgraph \"cluster_main\""));
+	assert!(cfg.contains("\"main.B3\" [label=\"B3 (weight 10)\\l"));
+	assert!(edges(&cfg).contains(&"\"main.B3\" -> \"main.B5\" [label=\"%3\"];"));
+	assert!(edges(&cfg).contains(&"\"main.B3\" -> \"main.B28\" [label=\"!%3\"];"));
+	assert!(edges(&cfg).contains(&"\"main.B9\" -> \"main.B7\";"));
+
+	let domtree = program.dot_domtree();
+	assert!(edges(&domtree).contains(&"\"main.B16\" -> \"main.B23\";"));
+	assert!(edges(&domtree).contains(&"\"main.entry\" -> \"main.B3\";"));
+	// B9 jumps back to B7, which dominates it
+	assert!(!edges(&domtree)
+		.iter()
+		.any(|v| v.ends_with("-> \"main.B7\";") && !v.starts_with("\"main.B5\"")));
+
+	// the inner loop is a cluster inside the outer one
+	let loops = program.dot_loops();
+	let outer = loops.find("label=\"loop at B3, level 1\"").unwrap();
+	let inner = loops.find("label=\"loop at B7, level 2\"").unwrap();
+	assert!(outer < inner);
+	assert!(loops[inner..].contains("\"main.B9\" [label="));
+	assert!(edges(&loops).contains(&"\"main.B9\" -> \"main.B7\" [style=bold];"));
+	assert!(edges(&loops).contains(&"\"main.B23\" -> \"main.B3\" [style=bold];"));
+	assert!(edges(&loops).contains(&"\"main.B3\" -> \"main.B5\";"));
+}
+
+#[test]
+fn riscv_graphs() {
+	let program = step_riscv(compile(PROGRAM, 0), 0).unwrap();
+	let cfg = program.dot_cfg();
+	let branch = edges(&cfg)
+		.into_iter()
+		.filter(|v| v.starts_with("\"main.B7\""))
+		.collect::<Vec<_>>();
+	assert_eq!(branch.len(), 2);
+	assert!(branch.iter().any(|v| v.contains("-> \"main.B9\" [label=\"blt ")));
+	assert!(branch
+		.iter()
+		.any(|v| v.ends_with("-> \"main.B16\" [label=\"else\"];")));
+
+	let loops = program.dot_loops();
+	assert!(loops.contains("label=\"loop at B7, level 2\""));
+	assert!(edges(&loops).contains(&"\"main.B23\" -> \"main.B3\" [style=bold];"));
+	assert!(program.dot_domtree().contains("\"main.B-1\" [label=\"B-1\"];"));
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use common::compile;
+use emission::code_emission;
+use sysyc::step_riscv;
+
+const PROGRAM: &str = r#"
+struct P { int x; int y; };
+const int a[2] = {1, 2};
+const struct P p = {3, 4};
+void set(int b[]) { b[0] = 9; }
+void move(struct P q) { q.x = 5; }
+int main() {
+	set(a);
+	move(p);
+	putf("%d %d\n", a[0], p.x);
+	return 0;
+}
+"#;
+
+// what a section holds, up to the next section
+fn section<'a>(asm: &'a str, name: &str) -> &'a str {
+	let Some((_, rest)) = asm.split_once(&format!(".section\t{name}")) else {
+		return "";
+	};
+	rest.split(".section").next().unwrap().split("  .text").next().unwrap()
+}
+
+#[test]
+fn place_read_only_data() {
+	let program = step_riscv(compile(PROGRAM, 0), 0).unwrap();
+	let asm = code_emission(program, "x.sy".into(), false);
+	// a const array may still be written through a parameter
+	let rodata = section(&asm, ".rodata");
+	assert!(rodata.contains(".str."), "{asm}");
+	assert!(!rodata.contains("a:") && !rodata.contains("p:"), "{asm}");
+	let data = section(&asm, ".sdata");
+	assert!(data.contains("a:") && data.contains("p:"), "{asm}");
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use common::*;
+use interpreter::interpret;
+
+const PASSES: &str = "unreachable,dead_code,global_analysis,gvn,mem2reg,\
+	useless_phis,gvn,loop_analysis,loop_simplify,indvar_extraction,dead_code";
+
+#[test]
+fn extract_do_while_indvars() {
+	let code = r"
+int main() {
+	int n = getint(), i = 0, s = 0;
+	do {
+		s = s + 3;
+		i = i + 1;
+	} while (i < n);
+	putint(s);
+	return i;
+}";
+	let program = compile_passes(code, PASSES);
+	let blocks = blocks_of(&program);
+	// only `i` is left in the loop, `s` is computed before it
+	let phis = blocks.concat().iter().filter(|v| v.contains("phi")).count();
+	assert_eq!(phis, 1, "{blocks:?}");
+	// the body runs once even if the condition never holds
+	for (input, output, exit_code) in
+		[("7", "21", 7), ("1", "3", 1), ("-4", "3", 1)]
+	{
+		let result = interpret(&program, input.as_bytes().to_vec()).unwrap();
+		assert_eq!(result.output, output.as_bytes(), "{input}");
+		assert_eq!(result.exit_code, exit_code, "{input}");
+	}
+}
+
+#[test]
+fn extract_indvars_of_empty_loops() {
+	let code = r"
+int main() {
+	int n = getint(), i = 0, s = 0;
+	while (i < n) {
+		s = s + 3;
+		i = i + 1;
+	}
+	return s;
+}";
+	let program = compile_passes(code, PASSES);
+	let phis =
+		blocks_of(&program).concat().iter().filter(|v| v.contains("phi")).count();
+	assert_eq!(phis, 1);
+	for (input, exit_code) in [("7", 21), ("0", 0), ("-4", 0)] {
+		let result = interpret(&program, input.as_bytes().to_vec()).unwrap();
+		assert_eq!(result.exit_code, exit_code, "{input}");
+	}
+}
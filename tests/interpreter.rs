@@ -0,0 +1,120 @@
+mod common;
+
+use std::io::{self, Read};
+
+use common::compile_passes;
+use interpreter::{format_hex_float, interpret, Execution, Input};
+
+const PROGRAM: &str = r"
+int a[100];
+float f[10] = {1.5, 2.5};
+int fib(int n) {
+	if (n < 2) return n;
+	return fib(n - 1) + fib(n - 2);
+}
+int sum(int x[], int n) {
+	int s = 0, i = 0;
+	while (i < n) {
+		if (i % 3 == 0) { i = i + 1; continue; }
+		s = s + x[i];
+		if (s > 1000) break;
+		i = i + 1;
+	}
+	return s;
+}
+int main() {
+	int n = getint(), i = 0;
+	int b[3][4];
+	while (i < n) { a[i] = getint(); i = i + 1; }
+	b[2][3] = sum(a, n);
+	putint(b[2][3]);
+	putch(10);
+	putint(fib(15));
+	putch(10);
+	i = 2;
+	while (i < 10) { f[i] = f[i - 1] * 1.5 + f[i - 2]; i = i + 1; }
+	putfloat(f[9]);
+	putch(10);
+	putarray(4, a);
+	return -7 / 2 - n % 4;
+}
+";
+
+#[test]
+fn interpret_program() {
+	let mut f = [1.5f32, 2.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+	for i in 2..10 {
+		f[i] = f[i - 1] * 1.5 + f[i - 2];
+	}
+	let expected = Execution {
+		output: format!("-895\n610\n{}\n4: 5 -2 100 300\n", format_hex_float(f[9]))
+			.into_bytes(),
+		exit_code: (-3 - 6 % 4) as u8,
+	};
+	for desc in ["basic", "fast", "full"] {
+		let program = compile_passes(PROGRAM, desc);
+		let input = b"6\n5 -2 100\n300 7 -1000".to_vec();
+		assert_eq!(interpret(&program, input).unwrap(), expected, "{desc}");
+	}
+}
+
+#[test]
+fn report_runtime_errors() {
+	let program = compile_passes(PROGRAM, "basic");
+	assert!(interpret(&program, b"x".to_vec()).is_err());
+}
+
+#[test]
+fn keep_output_before_errors() {
+	let code = r"
+int main() {
+	putint(42);
+	putch(10);
+	return getint();
+}";
+	let program = compile_passes(code, "basic");
+	let crash = interpret(&program, b"x".to_vec()).unwrap_err();
+	assert_eq!(crash.output, b"42\n");
+	assert!(crash.error.to_string().contains("getint"), "{crash:?}");
+}
+
+// gives one chunk of input, then fails like a closed terminal
+struct OneChunk(Option<&'static [u8]>);
+
+impl Read for OneChunk {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let chunk = self.0.take().ok_or(io::ErrorKind::BrokenPipe)?;
+		buf[..chunk.len()].copy_from_slice(chunk);
+		Ok(chunk.len())
+	}
+}
+
+#[test]
+fn read_input_lazily() {
+	let code = r"
+int main() {
+	int a = getint();
+	putint(a * 2);
+	return getch();
+}";
+	let program = compile_passes(code, "basic");
+	// the input after the first chunk is never asked for
+	let input = Input::new(OneChunk(Some(b"21\n")));
+	let result = interpret(&program, input).unwrap();
+	assert_eq!(result.output, b"42");
+	assert_eq!(result.exit_code, b'\n');
+	let input = Input::new(OneChunk(Some(b"21")));
+	let crash = interpret(&program, input).unwrap_err();
+	assert!(
+		crash.error.to_string().contains("reading input"),
+		"{crash:?}"
+	);
+}
+
+#[test]
+fn format_floats() {
+	assert_eq!(format_hex_float(1.0), "0x1p+0");
+	assert_eq!(format_hex_float(-7.0), "-0x1.cp+2");
+	assert_eq!(format_hex_float(0.1), "0x1.99999ap-4");
+	assert_eq!(format_hex_float(0.0), "0x0p+0");
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use common::*;
+use interpreter::{interpret, simulate};
+use sysyc::step_riscv;
+
+const PASSES: &str = "unreachable,dead_code,global_analysis,gvn,mem2reg,\
+	useless_phis,gvn,loop_analysis,loop_simplify,indvar_extraction,licm";
+
+fn count(block: &[String], pattern: &str) -> usize {
+	block.iter().filter(|v| v.contains(pattern)).count()
+}
+
+#[test]
+fn hoist_guaranteed_instructions() {
+	let code = r"
+int g[10];
+int main() {
+	int a = getint(), b = getint(), i = 0, s = 0;
+	while (i < 100) {
+		s = s + a * b + a / b;
+		g[3] = s;
+		i = i + 1;
+	}
+	putint(g[3]);
+	return s;
+}";
+	let program = compile_passes(code, PASSES);
+	let blocks = blocks_of(&program);
+	// the loop runs, so the division would be run anyway
+	assert_eq!(count(&blocks[0], " mul "), 1, "{blocks:?}");
+	assert_eq!(count(&blocks[0], " div "), 1, "{blocks:?}");
+	// the store is only done after the loop
+	let exit = blocks.iter().find(|v| count(v, "@putint") > 0).unwrap();
+	assert_eq!(count(exit, "store"), 1, "{blocks:?}");
+	assert_eq!(count(&blocks.concat(), "store"), 1, "{blocks:?}");
+	let result = interpret(&program, b"3 2".to_vec()).unwrap();
+	assert_eq!(result.output, b"700");
+	assert_eq!(result.exit_code, 188);
+}
+
+#[test]
+fn keep_unsafe_instructions() {
+	let code = r"
+int main() {
+	int a = getint(), b = getint(), n = getint(), i = 0, s = 0;
+	int arr[4] = {};
+	while (i < n) {
+		s = s + a / b;
+		if (b != 0) s = s + a % b;
+		if (i < 4) s = s + arr[a];
+		i = i + 1;
+	}
+	return s;
+}";
+	let program = compile_passes(code, PASSES);
+	let blocks = blocks_of(&program);
+	// the loop may not run, or only run the division behind the guard
+	assert_eq!(count(&blocks[0], " div "), 0, "{blocks:?}");
+	assert_eq!(count(&blocks[0], " rem "), 0, "{blocks:?}");
+	assert_eq!(count(&blocks[0], "load"), 0, "{blocks:?}");
+	let result = interpret(&program, b"100 0 0".to_vec()).unwrap();
+	assert_eq!(result.exit_code, 0);
+	let result = interpret(&program, b"1 2 3".to_vec()).unwrap();
+	assert_eq!(result.exit_code, 3);
+}
+
+#[test]
+fn move_accesses_apart_from_others() {
+	let code = r"
+int g[10];
+int main() {
+	g[5] = getint();
+	int i = 0;
+	while (i < 10) {
+		g[3] = g[5] + i;
+		i = i + 1;
+	}
+	i = 0;
+	while (i < 10) {
+		g[4] = g[3] + i;
+		putch(48 + i);
+		i = i + 1;
+	}
+	return g[4];
+}";
+	let program = compile_passes(code, PASSES);
+	let blocks = blocks_of(&program);
+	// `g[3]` and `g[5]` do not overlap, so the load leaves the first loop
+	let first = blocks.iter().position(|v| count(v, "phi") > 0).unwrap();
+	assert_eq!(count(&blocks[first + 1], "load"), 0, "{blocks:?}");
+	// `putch` does not touch `g`, so both stores are sunk
+	assert_eq!(count(&blocks.concat(), "store"), 3, "{blocks:?}");
+	let call = blocks.iter().find(|v| count(v, "@putch") > 0).unwrap();
+	assert_eq!(count(call, "store"), 0, "{blocks:?}");
+	let result = interpret(&program, b"5".to_vec()).unwrap();
+	assert_eq!(result.output, b"0123456789");
+	assert_eq!(result.exit_code, 23);
+}
+
+#[test]
+fn keep_guarded_loads() {
+	let code = r"
+int a[10] = {3, 1, 4, 1, 5, 9, 2, 6, 5, 3};
+int main() {
+	int n = getint(), k = getint(), i = 0, s = 0;
+	while (i < n) {
+		if (k < 10) s = s + a[k];
+		i = i + 1;
+	}
+	putint(s);
+	return 0;
+}";
+	for level in [1, 2] {
+		for (input, output) in [("5 1000000000", "0"), ("5 5", "45")] {
+			let riscv = step_riscv(compile(code, level), level).unwrap();
+			let (result, _) = simulate(&riscv, input.as_bytes().to_vec()).unwrap();
+			assert_eq!(result.output, output.as_bytes(), "-O{level} {input}");
+		}
+	}
+}
+
+#[test]
+fn hoist_code_down_safely() {
+	let code = r"
+int main() {
+	int a = getint(), b = getint(), i = 0, s = 0;
+	while (i < 10) {
+		if (b != 0) s = s + a / b;
+		i = i + 1;
+	}
+	return s;
+}";
+	let program = compile_passes(code, "gvn,mem2reg,useless_phis,code_hoisting");
+	let blocks = blocks_of(&program);
+	// the division is guarded, so it is not moved out of the loop
+	let header = blocks.iter().position(|v| count(v, "phi") > 0).unwrap();
+	assert_eq!(count(&blocks[..header].concat(), " div "), 0, "{blocks:?}");
+	let result = interpret(&program, b"7 0".to_vec()).unwrap();
+	assert_eq!(result.exit_code, 0);
+	let result = interpret(&program, b"7 2".to_vec()).unwrap();
+	assert_eq!(result.exit_code, 30);
+}
+
+#[test]
+fn move_char_accesses_apart_from_others() {
+	let code = r"
+char s[4] = {0, 7};
+int main() {
+	int i = 0;
+	while (i < 10) {
+		s[2] = s[1] + i;
+		i = i + 1;
+	}
+	return s[2];
+}";
+	let program = compile_passes(code, PASSES);
+	let blocks = blocks_of(&program);
+	// `s[1]` and `s[2]` are bytes next to each other, they do not overlap
+	let header = blocks.iter().position(|v| count(v, "phi") > 0).unwrap();
+	assert_eq!(count(&blocks[header + 1], "load"), 0, "{blocks:?}");
+	let result = interpret(&program, Vec::new()).unwrap();
+	assert_eq!(result.exit_code, 16);
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::irgen;
+use emission::code_emission;
+use interpreter::interpret;
+use rrvm::program::LlvmProgram;
+use sysyc::{cli::Args, *};
+
+const MAIN: &str = r#"
+extern int counter;
+extern float scale[2];
+extern int add(int x, int y);
+extern void fill(int a[], int n);
+int twice() { return add(1, 2) + add(1, 2); }
+int main() {
+	int b[4];
+	fill(b, 4);
+	counter = 7;
+	putf("%d %d ", twice(), counter);
+	putf("%d %f\n", add(b[1], b[3]), scale[1]);
+	return counter;
+}
+"#;
+
+const LIB: &str = r#"
+int counter = 5;
+float scale[2] = {0.5, 1.5};
+int add(int x, int y) { counter = counter + 1; return x + y + counter; }
+void fill(int a[], int n) {
+	int i = 0;
+	while (i < n) { a[i] = i * 10; i = i + 1; }
+	putf("fill\n");
+}
+"#;
+
+fn link(codes: &[&str]) -> utils::errors::Result<LlvmProgram> {
+	step_link(codes.iter().map(|v| irgen(v)).collect())
+}
+
+fn link_error(codes: &[&str]) -> String {
+	match link(codes) {
+		Ok(_) => panic!("linked without errors"),
+		Err(err) => err.to_string(),
+	}
+}
+
+#[test]
+fn run_linked() {
+	for level in 0..3 {
+		let args = Args {
+			opimizer: Some(level),
+			..Args::default()
+		};
+		let program = step_optimize(link(&[MAIN, LIB]).unwrap(), &args).unwrap();
+		assert!(program.externs.is_empty());
+		let result = interpret(&program, Vec::new()).unwrap();
+		let output = String::from_utf8(result.output).unwrap();
+		assert_eq!(output, "fill\n23 9 50 1.500000\n");
+		assert_eq!(result.exit_code, 10);
+	}
+}
+
+#[test]
+fn report_conflicts() {
+	let other = "int add(int x, int y) { return x; }\nint counter;";
+	let err = link_error(&[MAIN, LIB, other]);
+	assert!(err.contains("multiple definition of `add`"));
+	assert!(err.contains("multiple definition of `counter`"));
+
+	let err = link_error(&[MAIN]);
+	assert!(err.contains("undefined reference to `fill`"));
+	assert!(err.contains("undefined reference to `scale`"));
+
+	let lib = LIB.replace("int add(int x, int y)", "int add(int x, float y)");
+	let err = link_error(&[MAIN, &lib]);
+	assert!(err.contains(
+		"conflicting types for `add`, declared as `i32 (i32, i32)` but defined as \
+		 `i32 (i32, f32)`"
+	));
+
+	let lib = LIB.replace("int counter = 5", "float counter = 5");
+	let err = link_error(&[MAIN, &lib]);
+	assert!(err.contains("conflicting types for `counter`, declared as `i32`"));
+
+	let lib = LIB.replace(
+		"void fill(int a[], int n)",
+		"int fill;\nvoid f(int a[], int n)",
+	);
+	let err = link_error(&[MAIN, &lib]);
+	assert!(err.contains("`fill` is declared as a different kind of symbol"));
+}
+
+#[test]
+fn print_externs() {
+	let text = irgen(MAIN).to_string();
+	assert!(text.contains("declare global i32 @counter\n"));
+	assert!(text.contains("declare global f32* @scale\n"));
+	assert!(text.contains("declare void @fill(i32*, i32)\n"));
+	assert_eq!(LlvmProgram::parse(&text).unwrap().to_string(), text);
+}
+
+#[test]
+fn emit_modules() {
+	let args = Args {
+		opimizer: Some(2),
+		separate: true,
+		..Args::default()
+	};
+	let emit = |code: &str| {
+		let program = step_optimize(irgen(code), &args).unwrap();
+		code_emission(step_riscv(program, 2).unwrap(), "x.sy".into(), true)
+	};
+	let main = emit(MAIN);
+	assert!(main.contains(".global main\n"));
+	assert!(main.contains(".global twice\n"));
+	// what another module defines is not emitted here
+	assert!(!main.contains("counter:"));
+	assert!(main.contains("call add"));
+	assert!(!main.contains(".global .str"));
+	assert!(!main.contains(".global __"));
+
+	let lib = emit(LIB);
+	for name in ["counter", "scale", "add", "fill"] {
+		assert!(lib.contains(&format!(".global {name}\n")));
+	}
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::compile;
+use interpreter::interpret;
+use linter::{Lint, LintConfig, Warning};
+use sysyc::*;
+
+const CODE: &str = r#"int f(int a, int b) {
+	int x;
+	int unused = 3;
+	if (a > 0) x = 1;
+	if (a > 1) return x / 0;
+}
+int g(int n) {
+	int y;
+	while (1) {
+		if (n > 3) { y = n; break; }
+		n = n + 1;
+	}
+	return y;
+	n = 2;
+}
+int main() {
+	const int z = 0;
+	int k = 5, i;
+	for (i = 0; i < 3; i = i + 1) k = k % z;
+	return f(1, 2) + g(k);
+}
+"#;
+
+fn lint(code: &str, flags: &[&str]) -> Vec<Warning> {
+	let mut program = step_parse(code).unwrap();
+	step_check(&mut program).unwrap();
+	let flags: Vec<_> = flags.iter().map(|v| v.to_string()).collect();
+	step_lint(&mut program, &LintConfig::new(&flags))
+}
+
+fn messages(warnings: &[Warning]) -> Vec<&str> {
+	warnings.iter().map(|v| v.message.as_str()).collect()
+}
+
+#[test]
+fn report_warnings() {
+	let warnings = lint(CODE, &["all"]);
+	assert_eq!(
+		messages(&warnings),
+		[
+			"unused parameter `b`",
+			"unused variable `unused`",
+			"division by zero",
+			"`x` may be used uninitialized",
+			"control reaches end of non-void function `f`",
+			"unreachable statement",
+			"division by zero",
+		]
+	);
+	let text = warnings[3].render("a.sy", CODE, false);
+	assert_eq!(
+		text,
+		"warning: `x` may be used uninitialized [-Wuninitialized]\n --> a.sy:5:20\n  |\n5 | \tif (a > 1) return x / 0;\n  | \t                  ^\n"
+	);
+	let text = warnings[0].render("a.sy", CODE, true);
+	assert!(text.starts_with("error: unused parameter `b` [-Werror=unused]\n"));
+}
+
+#[test]
+fn follow_paths() {
+	let code = r#"int main() {
+	int a, b, c, d, e;
+	if (getint()) { a = 1; b = 1; } else { a = 2; }
+	do { c = 1; } while (0);
+	while (getint()) { d = 1; }
+	getint() && (e = 1);
+	return a + b + c + d + e;
+}
+"#;
+	let warnings = lint(code, &["uninitialized"]);
+	let uses: Vec<_> =
+		warnings.iter().map(|v| &code[v.span.start..v.span.end]).collect();
+	assert_eq!(uses, ["b", "d", "e"]);
+}
+
+#[test]
+fn choose_lints() {
+	let kinds = |flags: &[&str]| {
+		let mut kinds: Vec<_> = lint(CODE, flags).iter().map(|v| v.lint).collect();
+		kinds.dedup();
+		kinds
+	};
+	assert_eq!(
+		kinds(&[]),
+		[Lint::DivByZero, Lint::ReturnType, Lint::DivByZero]
+	);
+	assert_eq!(kinds(&["no-all", "unused"]), [Lint::Unused]);
+	assert_eq!(
+		kinds(&["all", "no-div-by-zero", "no-return-type"]),
+		[Lint::Unused, Lint::Uninitialized, Lint::Unreachable]
+	);
+	assert!(!LintConfig::new(&[]).werror);
+	assert!(LintConfig::new(&["error".to_string()]).werror);
+}
+
+#[test]
+fn run_division_by_zero() {
+	let code = "int main() {\n\tint a = getint();\n\tputint(a / 0);\n\tputch(32);\n\tputint(a % 0);\n\treturn 7 / 0 + 2;\n}\n";
+	for level in 0..3 {
+		let program = compile(code, level);
+		let result = interpret(&program, b"9".to_vec()).unwrap();
+		assert_eq!(String::from_utf8(result.output).unwrap(), "-1 9");
+		assert_eq!(result.exit_code, 1);
+		let program = step_riscv(program, level).unwrap();
+		let (result, _) = interpreter::simulate(&program, b"9".to_vec()).unwrap();
+		assert_eq!(String::from_utf8(result.output).unwrap(), "-1 9");
+		assert_eq!(result.exit_code, 1);
+	}
+}
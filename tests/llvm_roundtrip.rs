@@ -0,0 +1,122 @@
+mod common;
+
+use common::compile;
+use rrvm::program::LlvmProgram;
+
+const PROGRAMS: [&str; 3] = [
+	r"
+const int N = 10;
+int g[N] = {1, 2, 3};
+float fg = 1.5;
+int fib(int n) {
+	if (n < 2) return n;
+	return fib(n - 1) + fib(n - 2);
+}
+int main() {
+	int a[4][3];
+	int i = 0;
+	float f = 2.0;
+	while (i < 4) {
+		a[i][1] = i * 2;
+		i = i + 1;
+	}
+	f = f * fg + i;
+	putint(fib(10) + a[2][1] + g[1]);
+	putfloat(f);
+	putch(10);
+	return 0;
+}
+",
+	r"
+float arr[8];
+void fill(float a[], int n, float x) {
+	int i = 0;
+	while (i < n) {
+		if (x > 0.5 && i % 2 == 0) a[i] = x * i;
+		else a[i] = -x;
+		i = i + 1;
+	}
+}
+int main() {
+	int n = getint();
+	fill(arr, 8, 1.0e-3);
+	if (!(arr[3] < 0.25)) return 1;
+	putfarray(8, arr);
+	return n / 3 - 7;
+}
+",
+	r"
+int sum(int a[][4], int n) {
+	int s = 0, i = 0, j;
+	while (i < n) {
+		j = 0;
+		while (j < 4) {
+			if (j == 2) { j = j + 1; continue; }
+			s = s + a[i][j];
+			j = j + 1;
+		}
+		if (s > 1000) break;
+		i = i + 1;
+	}
+	return s;
+}
+int main() {
+	int a[16][4] = {{1}, {2, 3}, 4, 5, 6};
+	putint(sum(a, 16));
+	return 0;
+}
+",
+];
+
+fn assert_roundtrip(text: &str) {
+	let program = LlvmProgram::parse(text).unwrap();
+	assert_eq!(text, program.to_string());
+}
+
+#[test]
+fn roundtrip_compiled() {
+	for code in PROGRAMS {
+		for level in 0..3 {
+			assert_roundtrip(&compile(code, level).to_string());
+		}
+	}
+}
+
+#[test]
+fn roundtrip_hand_written() {
+	let text = "\
+GlobalVar { ident: \"x\", data: [Word(3212836864)], is_float: true, is_const: false }
+define f32 @f(f32 %1, i32* %2){
+  entry:
+    %3 = icmp olt i32 %1, 2
+    %4 = load f32*, f32 @x
+    br i32 %3, label %B1, label %B2
+  B1:
+    %5 = fadd f32 %1, -0.5
+    %6 f32 = sitofp 7
+    br label %B2
+  B2:
+    %7 = phi f32 [inf, label %entry], [%5, label %B1]
+    ret f32 %7
+}";
+	assert_roundtrip(text);
+	let program = LlvmProgram::parse(text).unwrap();
+	let func = &program.funcs[0];
+	assert_eq!(func.total, 2);
+	assert_eq!(program.temp_mgr.total, 7);
+	let entry = func.cfg.get_entry();
+	assert_eq!(entry.borrow().succ.len(), 2);
+	assert_eq!(func.cfg.blocks[2].borrow().prev.len(), 2);
+}
+
+#[test]
+fn reject_bad_input() {
+	assert!(LlvmProgram::parse("define void @f(){\n  entry:\n}").is_err());
+	assert!(LlvmProgram::parse(
+		"define void @f(){\n  entry:\n    br label %B9\n}"
+	)
+	.is_err());
+	assert!(
+		LlvmProgram::parse("define void @f(){\n  entry:\n    ret\n}").is_err()
+	);
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::*;
+use interpreter::interpret;
+
+const PASSES: &str = "unreachable,dead_code,global_analysis,gvn,mem2reg";
+
+#[test]
+fn reload_after_calls_writing_params() {
+	let code = r"
+int g[2];
+void set() {
+	g[0] = 2;
+}
+int get(int a[]) {
+	a[0] = 1;
+	set();
+	return a[0];
+}
+int main() {
+	return get(g) * 10 + g[0];
+}";
+	let program = compile_passes(code, PASSES);
+	// `a` may be `g`, which `set` writes
+	let result = interpret(&program, Vec::new()).unwrap();
+	assert_eq!(result.exit_code, 22);
+}
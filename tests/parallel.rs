@@ -0,0 +1,159 @@
+mod common;
+
+use common::compile_with;
+use interpreter::{interpret, simulate, Execution};
+use rrvm::program::LlvmProgram;
+use sysyc::{cli::Args, *};
+
+fn compile(code: &str, threads: usize) -> LlvmProgram {
+	let args = Args {
+		opimizer: Some(1),
+		threads: Some(threads),
+		..Args::default()
+	};
+	compile_with(code, &args)
+}
+
+/// How many loops of `program` are split among threads
+fn parallel_loops(program: &LlvmProgram) -> usize {
+	program.to_string().matches("call i32 @__create_threads").count()
+}
+
+/// Run `code` compiled with each number of threads on both the interpreter
+/// and the simulator, they have to agree with the program not split
+fn run(code: &str, input: &[u8]) -> Execution {
+	let expected = interpret(&compile(code, 1), input.to_vec()).unwrap();
+	for threads in [2, 3, 4] {
+		let program = compile(code, threads);
+		let result = interpret(&program, input.to_vec()).unwrap();
+		assert_eq!(result, expected, "{threads} threads");
+		let riscv = step_riscv(program, 1).unwrap();
+		let (result, _) = simulate(&riscv, input.to_vec()).unwrap();
+		assert_eq!(result, expected, "{threads} threads");
+	}
+	expected
+}
+
+#[test]
+fn split_reductions() {
+	let code = r"
+int a[100000];
+int main() {
+	int n = getint(), i = 0, s = 7, hi = -1000000, lo = 1000000;
+	while (i < n) { a[i] = (i * 37 + 11) % 1009 - 500; i = i + 1; }
+	i = 0;
+	while (i < n) { s = s - a[i] % 7; i = i + 1; }
+	i = 0;
+	while (i < n) { if (a[i] > hi) hi = a[i]; i = i + 1; }
+	i = 0;
+	while (i < n) { if (lo > a[i]) lo = a[i]; i = i + 1; }
+	putint(s); putch(32); putint(hi); putch(32); putint(lo);
+	return 0;
+}";
+	assert_eq!(parallel_loops(&compile(code, 4)), 4);
+	assert_eq!(parallel_loops(&compile(code, 1)), 0);
+	let result = run(code, b"10001");
+	assert_eq!(result.output, b"-215 508 -500");
+}
+
+#[test]
+fn split_loops_calling_pure_functions() {
+	let code = r"
+int a[100000];
+int fib(int n) {
+	if (n < 2) return n;
+	return fib(n - 1) + fib(n - 2);
+}
+int main() {
+	int n = getint(), i = 0, s = 0;
+	while (i < n) { a[i] = fib(i % 10) + i; i = i + 1; }
+	i = 0;
+	while (i < n) { s = s + a[i]; i = i + 1; }
+	putint(s);
+	return 0;
+}";
+	let program = compile(code, 4);
+	assert_eq!(parallel_loops(&program), 2);
+	// threads running calls get stacks of their own
+	assert!(program
+		.to_string()
+		.contains("@__create_threads(i32 4, i32 1048576)"));
+	let result = run(code, b"5000");
+	assert_eq!(result.output, b"12541500");
+}
+
+#[test]
+fn keep_dependent_loops() {
+	let code = r"
+int a[100000], b[100000];
+int main() {
+	int n = getint(), i = 1, s = 0;
+	while (i < n) { a[i] = a[i - 1] + i % 3; i = i + 1; }
+	i = 0;
+	while (i < n) { b[i] = a[i] + a[n - 1 - i]; i = i + 1; }
+	i = 0;
+	while (i < n) { b[i * 2 % n] = b[i] + 1; i = i + 1; }
+	i = 0;
+	while (i < n) { s = s * 3 + b[i]; i = i + 1; }
+	putint(s);
+	return 0;
+}";
+	// only the loop filling `b` from `a` may be split
+	assert_eq!(parallel_loops(&compile(code, 4)), 1);
+	run(code, b"9000");
+}
+
+#[test]
+fn copy_large_frames() {
+	let code = r"
+int a[100000];
+int fib(int n) {
+	if (n < 2) return n;
+	return fib(n - 1) + fib(n - 2);
+}
+int main() {
+	int b[300], n = getint(), i = 0;
+	int c0 = getint(), c1 = getint(), c2 = getint(), c3 = getint();
+	int c4 = getint(), c5 = getint(), c6 = getint(), c7 = getint();
+	int c8 = getint(), c9 = getint(), c10 = getint(), c11 = getint();
+	int c12 = getint(), c13 = getint(), c14 = getint(), c15 = getint();
+	while (i < 300) { b[i] = getint(); i = i + 1; }
+	i = 0;
+	while (i < n) {
+		int d0 = i % c0, d1 = i % c1, d2 = i % c2, d3 = i % c3, d4 = i % c4;
+		int d5 = i % c5, d6 = i % c6, d7 = i % c7, d8 = i % c8, d9 = i % c9;
+		int d10 = i % c10, d11 = i % c11, d12 = i % c12, d13 = i % c13;
+		int d14 = i % c14, d15 = i % c15, f = fib(i % 8);
+		a[i] = f * d0 + d1 * c1 - d2 * c2 + d3 * c3 - d4 * c4 + d5 * c5 - d6 * c6
+			+ d7 * c7 - d8 * c8 + d9 * c9 - d10 * c10 + d11 * c11 - d12 * c12
+			+ d13 * c13 - d14 * c14 + d15 * c15 + b[i % 300];
+		i = i + 1;
+	}
+	putint(a[n / 2] + a[n / 3] + a[n - 1] + b[299]);
+	return 0;
+}";
+	// the spilled values are above `b`, far from sp, each thread needs a copy
+	assert_eq!(parallel_loops(&compile(code, 4)), 1);
+	let mut input = String::from("6000");
+	for i in 0..316 {
+		input += &format!(" {}", i * 7 % 23 + 1);
+	}
+	run(code, input.as_bytes());
+}
+
+#[test]
+fn keep_short_loops() {
+	let code = r"
+int a[5][10000];
+int main() {
+	int n = getint(), i = 0;
+	while (i < 5) {
+		int j = 0;
+		while (j < n) { a[i][j] = i * j; j = j + 1; }
+		i = i + 1;
+	}
+	putint(a[4][9999]);
+	return 0;
+}";
+	assert_eq!(parallel_loops(&compile(code, 4)), 0);
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::*;
+use interpreter::interpret;
+use sysyc::*;
+
+const PROGRAM: &str = r"
+int g = 3;
+int fib(int n) {
+	if (n < 2) return n;
+	return fib(n - 1) + fib(n - 2);
+}
+float half(float x) { return x / 2.0; }
+int forever(int n) {
+	while (n > 0) n = n + 1;
+	return n;
+}
+int load(int n) { return n + g; }
+int main() {
+	const int a[3] = {fib(6), fib(7)};
+	putint(fib(20) + a[1]);
+	putfloat(half(3.0));
+	putint(forever(getint()));
+	return load(1);
+}
+";
+
+#[test]
+fn evaluate_pure_calls() {
+	let program = compile(PROGRAM, 1);
+	let main = main_of(&program);
+	assert!(main.contains("call void @putint(i32 6778)"), "{main}");
+	assert!(main.contains("call void @putfloat(f32 1.5)"), "{main}");
+	assert!(!main.contains("@fib"), "{main}");
+	let result = interpret(&program, b"0".to_vec()).unwrap();
+	assert_eq!(result.output, b"67780x1.8p+00");
+	assert_eq!(result.exit_code, 4);
+}
+
+#[test]
+fn leave_endless_calls() {
+	let code = "int forever(int n) { while (n > 0) n = n + 1; return n; }
+int main() { return forever(1); }";
+	let program = compile(code, 1);
+	assert!(program.funcs.iter().any(|v| v.name == "main"));
+}
+
+#[test]
+fn evaluate_global_calls() {
+	let code = "int f() { return 1; }\nconst int a[1] = {f()};\nint main() { return a[0]; }";
+	let program = step_irgen(step_parse(code).unwrap()).unwrap();
+	assert_eq!(interpret(&program, Vec::new()).unwrap().exit_code, 1);
+	let code =
+		"int f() { return 1; }\nint main() { const int n = f(); return n; }";
+	assert!(step_parse(code).and_then(step_irgen).is_err());
+}
+
+#[test]
+fn reject_runtime_calls() {
+	for init in ["getint(), 1", "f(getint())", "g(1)"] {
+		let code = format!(
+			"int x;\nint f(int n) {{ return n; }}\nint g(int n) {{ x = n; return n; }}\nconst int a[2] = {{{init}}};\nint main() {{ return a[0]; }}"
+		);
+		assert!(step_parse(&code).and_then(step_irgen).is_err());
+		let code = format!(
+			"int x;\nint f(int n) {{ return n; }}\nint g(int n) {{ x = n; return n; }}\nint main() {{ const int a[2] = {{{init}}}; return a[0]; }}"
+		);
+		assert!(step_parse(&code).and_then(step_irgen).is_err());
+	}
+}
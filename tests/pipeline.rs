@@ -0,0 +1,108 @@
+mod common;
+
+use common::*;
+use interpreter::interpret;
+use optimizer::{PassItem, Pipeline, PASSES, PIPELINES};
+
+fn pass(name: &str) -> PassItem {
+	PassItem::Pass(name.to_string())
+}
+
+#[test]
+fn parse_pipeline() {
+	let pipeline =
+		Pipeline::parse("fold_constants, gvn,(mem2reg,useless_phis)*,(dead_code)")
+			.unwrap();
+	assert_eq!(
+		pipeline.items,
+		vec![
+			pass("fold_constants"),
+			pass("gvn"),
+			PassItem::Group(
+				vec![
+					// `mem2reg` needs `global_analysis`, which has not run yet
+					PassItem::Group(
+						vec![pass("global_analysis"), pass("mem2reg")],
+						false
+					),
+					pass("useless_phis")
+				],
+				true
+			),
+			PassItem::Group(vec![pass("dead_code")], false),
+		]
+	);
+}
+
+#[test]
+fn parse_named_pipelines() {
+	for (name, _) in PIPELINES {
+		let pipeline = Pipeline::parse(name).unwrap();
+		assert!(matches!(pipeline.items[..], [PassItem::Group(_, false)]));
+	}
+}
+
+#[test]
+fn reject_bad_pipeline() {
+	for desc in ["", "gvn,", "(gvn", "gvn)", "gvn*", "no_such_pass", "(,)"] {
+		assert!(Pipeline::parse(desc).is_err(), "{desc}");
+	}
+}
+
+#[test]
+fn insert_required_passes() {
+	let pipeline = Pipeline::parse("calc_coef,mem2reg").unwrap();
+	assert_eq!(
+		pipeline.items,
+		vec![
+			PassItem::Group(vec![pass("gvn"), pass("calc_coef")], false),
+			PassItem::Group(vec![pass("global_analysis"), pass("mem2reg")], false),
+		]
+	);
+	let pipeline = Pipeline::parse("pre").unwrap();
+	assert_eq!(
+		pipeline.items,
+		vec![PassItem::Group(vec![pass("gvn"), pass("pre")], false)]
+	);
+}
+
+#[test]
+fn run_each_pass_alone() {
+	let code = r"
+int g[10];
+int f(int a[], int n) {
+	int i = 0, s = 0;
+	while (i < n) {
+		s = s + a[i];
+		i = i + 1;
+	}
+	return s;
+}
+int h(int n, int m) {
+	if (n == 0) return m;
+	return h(n - 1, m) + h(n - 1, m) + 1;
+}
+int main() {
+	int a[10] = {1, 2};
+	int i = 0, n = getint();
+	float x = 1.5;
+	do {
+		a[i] = i * 3 + n;
+		g[i] = a[i] / 2;
+		x = x * 2.0;
+		i = i + 1;
+	} while (i < 10);
+	if (n > 3) putint(f(a, 10));
+	else putint(f(g, 5));
+	putfloat(x);
+	putint(h(n, n + 1));
+	return a[3];
+}";
+	let expected = interpret(&irgen(code), b"4".to_vec()).unwrap();
+	for (name, ..) in PASSES.iter() {
+		let program = compile_passes(code, name);
+		let result = interpret(&program, b"4".to_vec()).unwrap();
+		assert_eq!(result.output, expected.output, "{name}");
+		assert_eq!(result.exit_code, expected.exit_code, "{name}");
+	}
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::*;
+use interpreter::interpret;
+use optimizer::PassManager;
+use rrvm::program::LlvmProgram;
+
+const PROGRAM: &str = r"
+int main() {
+	int a = getint(), b = getint(), n = getint();
+	int x = 1;
+	if (a > 0) x = a / b;
+	int y = a / b + x;
+	putint(y);
+	return y * n;
+}
+";
+
+#[test]
+fn remove_partial_redundancy() {
+	let program = compile(PROGRAM, 2);
+	let blocks = blocks_of(&program);
+	// the division after the join is computed on the path that lacked it
+	for block in blocks.iter() {
+		let has_phi = block.iter().any(|v| v.contains("phi"));
+		let has_div = block.iter().any(|v| v.contains(" div "));
+		assert!(!(has_phi && has_div), "{blocks:?}");
+	}
+	let result = interpret(&program, b"7 2 4".to_vec()).unwrap();
+	assert_eq!(result.output, b"6");
+	assert_eq!(result.exit_code, 24);
+	let result = interpret(&program, b"-7 2 4".to_vec()).unwrap();
+	assert_eq!(result.output, b"-2");
+	assert_eq!(result.exit_code, 248);
+}
+
+#[test]
+fn keep_redundancy_at_o1() {
+	let program = compile(PROGRAM, 1);
+	let blocks = blocks_of(&program);
+	let divs = blocks.concat().iter().filter(|v| v.contains(" div ")).count();
+	assert_eq!(divs, 2, "{blocks:?}");
+}
+
+#[test]
+fn hoist_loop_invariants() {
+	let code = r"
+int g[100];
+int main() {
+	int a = getint(), b = getint(), n = getint(), i = 0;
+	while (i < n) {
+		g[i] = a * b + i;
+		i = i + 1;
+	}
+	putint(g[n - 1]);
+	return g[0];
+}";
+	let passes = "unreachable,dead_code,global_analysis,gvn,mem2reg,\
+		useless_phis,gvn,pre,useless_phis";
+	let program = compile_passes(code, passes);
+	let blocks = blocks_of(&program);
+	// the product is computed once before the loop
+	let muls = |block: &Vec<String>| {
+		block.iter().filter(|v| v.contains(" mul i32 %1, %2")).count()
+	};
+	assert_eq!(muls(&blocks[0]), 1, "{blocks:?}");
+	assert_eq!(muls(&blocks.concat()), 1, "{blocks:?}");
+	let result = interpret(&program, b"3 4 5".to_vec()).unwrap();
+	assert_eq!(result.output, b"16");
+	assert_eq!(result.exit_code, 12);
+}
+
+#[test]
+fn report_speculation() {
+	// `entry` has two successors, so nothing can be inserted at its end, but
+	// the product is still moved into the loop header
+	let text = "\
+define i32 @f(i32 %1, i32 %2){
+  entry:
+    %3 = icmp sgt i32 %1, 0
+    br i32 %3, label %B1, label %B3
+  B1:
+    %4 = phi i32 [0, label %entry], [%6, label %B2]
+    %5 = icmp slt i32 %4, 10
+    br i32 %5, label %B2, label %B3
+  B2:
+    %7 = mul i32 %1, %2
+    %6 = add i32 %4, %7
+    br label %B1
+  B3:
+    ret i32 0
+}";
+	let mut program = LlvmProgram::parse(text).unwrap();
+	let mut manager = PassManager::new();
+	assert!(manager.run_pass("pre", &mut program).unwrap());
+	let blocks = program.funcs[0].to_string();
+	let header = blocks.split("B2:").next().unwrap();
+	assert!(header.contains(" mul "), "{blocks}");
+}
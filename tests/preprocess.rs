@@ -0,0 +1,178 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::irgen;
+use interpreter::interpret;
+use sysyc::{cli::Args, *};
+use utils::SourceMap;
+
+// a directory of its own for each test, with the files in it
+fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+	let dir = std::env::temp_dir().join(format!("sysyc_preprocess_{test}"));
+	for (name, code) in files {
+		let path = dir.join(name);
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(path, code).unwrap();
+	}
+	dir
+}
+
+fn preprocess(
+	file_name: &str,
+	code: &str,
+	include: &[PathBuf],
+) -> (utils::errors::Result<String>, SourceMap) {
+	let args = Args {
+		include: include.iter().map(|v| v.to_string_lossy().into_owned()).collect(),
+		..Args::default()
+	};
+	step_preprocess(file_name, code, &args)
+}
+
+fn run(code: &str) -> (String, i32) {
+	let result = interpret(&irgen(code), Vec::new()).unwrap();
+	(
+		String::from_utf8(result.output).unwrap(),
+		result.exit_code as i32,
+	)
+}
+
+fn error(file_name: &str, code: &str, include: &[PathBuf]) -> String {
+	let (result, sources) = preprocess(file_name, code, include);
+	match result.and_then(|v| step_parse(&v).and_then(step_irgen)) {
+		Ok(_) => panic!("preprocessed without errors"),
+		Err(err) => err.render_in(&sources),
+	}
+}
+
+#[test]
+fn expand_macros() {
+	let code = r#"#define N 3
+#define SQUARE(x) ((x) * (x))
+#define NAME(a, b) a ## b
+#define SHOW(x) putf(#x); putf(" = %d\n", x)
+#define EMPTY()
+#undef N
+#define N 4
+int NAME(val, ue) = SQUARE(N + 1) EMPTY();
+int main() {
+	SHOW(value - N);
+	return SQUARE(
+		2) + __LINE__;
+}
+"#;
+	let (result, _) = preprocess("a.sy", code, &[]);
+	let text = result.unwrap();
+	assert!(text.contains("int value = (( 4 + 1 ) * ( 4 + 1 )) ;"));
+	assert!(text.contains("putf(\"value - N\"); putf(\" = %d\\n\", value - 4 )"));
+	assert_eq!(text.lines().count(), code.lines().count());
+	assert_eq!(run(&text), ("value - N = 21\n".to_string(), 16));
+}
+
+#[test]
+fn choose_groups() {
+	let code = r#"#define A 2
+#if A * 2 == 4 && !defined(B)
+int x = 1;
+#elif defined A
+int x = 2;
+#else
+int x = 3;
+#endif
+#ifdef B
+int y = 1;
+#elif A > 1 ? 0 : 1
+int y = 2;
+#else
+#if 1
+int y = 3;
+#endif
+#endif
+#ifndef A
+#error not here
+#endif
+int main() { return x * 10 + y; }
+"#;
+	let (result, _) = preprocess("a.sy", code, &[]);
+	assert_eq!(run(&result.unwrap()).1, 13);
+}
+
+#[test]
+fn include_files() {
+	let dir = write_files(
+		"include",
+		&[
+			("lib/consts.h", "#pragma once\n#define N 5\n"),
+			(
+				"util.h",
+				"#ifndef UTIL_H\n#define UTIL_H\n#include <consts.h>\nint twice(int x) { return x * 2; }\n#endif\n",
+			),
+			(
+				"main.sy",
+				"#include \"util.h\"\n#include \"util.h\"\n#include <consts.h>\nint main() { return twice(N); }\n",
+			),
+			("bad.h", "int f() {\n\treturn x;\n}\n"),
+		],
+	);
+	let main = dir.join("main.sy");
+	let code = fs::read_to_string(&main).unwrap();
+	let (result, _) =
+		preprocess(main.to_str().unwrap(), &code, &[dir.join("lib")]);
+	assert_eq!(run(&result.unwrap()).1, 10);
+
+	// an error is shown in the file it comes from
+	let main = dir.join("b.sy");
+	let text = error(
+		main.to_str().unwrap(),
+		"#include \"bad.h\"\nint main() { return 0; }\n",
+		&[],
+	);
+	assert!(text.contains(&format!(" --> {}:2:9\n", dir.join("bad.h").display())));
+	assert!(text.contains("2 | \treturn x;\n"));
+}
+
+#[test]
+fn report_errors() {
+	let text = error("a.sy", "#include \"missing.h\"\n", &[]);
+	assert_eq!(
+		text,
+		"error: Preprocess error: can not find `missing.h`\n --> a.sy:1:10\n  |\n1 | #include \"missing.h\"\n  |          ^^^^^^^^^^^\n"
+	);
+	let text = error("a.sy", "int a;\n#if 1\nint b;\n", &[]);
+	assert!(text.contains("unterminated conditional directive\n --> a.sy:2:1\n"));
+	let text = error("a.sy", "#define F(a, b) a\nint x = F(1);\n", &[]);
+	assert!(
+		text.contains("macro `F` passed 1 arguments, but takes 2\n --> a.sy:2:9\n")
+	);
+	let text = error("a.sy", "#define N 1\nint main() { return N + y; }\n", &[]);
+	assert!(text.contains("y is not found\n --> a.sy:2:25\n"));
+	let text = error("a.sy", "#else\n", &[]);
+	assert!(text.contains("#else without #if"));
+	let text = error("a.sy", "#if 1 +\n#endif\n", &[]);
+	assert!(text.contains("expected an operand in #if expression"));
+	let text = error("a.sy", "#error stop here\n", &[]);
+	assert!(text.contains("#error stop here"));
+	let text = error("a.sy", "#foo\n", &[]);
+	assert!(text.contains("invalid preprocessing directive #foo"));
+}
+
+#[test]
+fn time_lines() {
+	let dir = write_files(
+		"time",
+		&[(
+			"time.h",
+			"int timed() {\n\tstarttime();\n\tstoptime();\n\treturn 0;\n}\n",
+		)],
+	);
+	let main = dir.join("main.sy");
+	let code =
+		"#include \"time.h\"\nint main() {\n\tstarttime();\n\treturn timed();\n}\n";
+	let (result, _) = preprocess(main.to_str().unwrap(), code, &[]);
+	let text = irgen(&result.unwrap()).to_string();
+	for line in [2, 3] {
+		assert!(text.contains(&format!("(i32 {line})")));
+	}
+	assert!(text.contains("call void @_sysy_starttime(i32 3)"));
+}
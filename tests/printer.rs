@@ -0,0 +1,104 @@
+mod common;
+
+use std::fs;
+
+use ast::printer::SourcePrinter;
+use common::irgen;
+use interpreter::interpret;
+use sysyc::*;
+
+const PROGRAM: &str = r"
+// comments go away
+const int N = 4, M[2][2] = {{1}, {N * 2, 010}};
+int g[N][2] = {{1}, 2, 3}, h;
+float f = 1.5e-3, e[3] = {.5f, 1e1};
+void nothing() { return; }
+int sum(int a[][2], int n, float x) {
+	int s = 0, i = 0;
+	while (i < n) {
+		if (a[i][0] > 1 && !(a[i][1] < 0) || -a[i][0] == -3) s = s + a[i][0] * (a[i][1] - -1);
+		else if (i) { s = s - 1; } else ;
+		{ int s; s = (1 + 2) * 3 - (4 - 5) - -(-s); }
+		i = i + 1;
+		if (i > 100) break; else continue;
+	}
+	return s;
+}
+int main() {
+	starttime();
+	putint(sum(g, N, 2.0) / (M[1][0] % 7));
+	putfloat(f * -2 / (3 - 1));
+	stoptime();
+	return 0;
+}
+";
+
+const EXPECTED: &str = "\
+const int N = 4, M[2][2] = {{1}, {N * 2, 8}};
+int g[N][2] = {{1}, 2, 3}, h;
+float f = 0.0015, e[3] = {0.5, 10.0};
+
+void nothing() {
+	return;
+}
+
+int sum(int a[][2], int n, float x) {
+	int s = 0, i = 0;
+	while (i < n) {
+		if (a[i][0] > 1 && !(a[i][1] < 0) || -a[i][0] == -3) {
+			s = s + a[i][0] * (a[i][1] - -1);
+		} else {
+			if (i) {
+				s = s - 1;
+			} else {
+			}
+		}
+		{
+			int s;
+			s = (1 + 2) * 3 - (4 - 5) - - -s;
+		}
+		i = i + 1;
+		if (i > 100) {
+			break;
+		} else {
+			continue;
+		}
+	}
+	return s;
+}
+
+int main() {
+	starttime();
+	putint(sum(g, N, 2.0) / (M[1][0] % 7));
+	putfloat(f * -2 / (3 - 1));
+	stoptime();
+	return 0;
+}
+";
+
+fn print(code: &str) -> String {
+	SourcePrinter::print(&mut step_parse(code).unwrap()).unwrap()
+}
+
+#[test]
+fn print_program() {
+	assert_eq!(print(PROGRAM), EXPECTED);
+	assert_eq!(print(EXPECTED), EXPECTED);
+}
+
+#[test]
+fn keep_meaning() {
+	let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/functional");
+	for entry in fs::read_dir(dir).unwrap() {
+		let path = entry.unwrap().path();
+		if path.extension().map_or(true, |v| v != "sy") {
+			continue;
+		}
+		let code = fs::read_to_string(&path).unwrap();
+		let input = fs::read(path.with_extension("in")).unwrap_or_default();
+		let printed = print(&code);
+		assert_eq!(print(&printed), printed);
+		let run = |code: &str| interpret(&irgen(code), input.clone()).unwrap();
+		assert_eq!(run(&printed), run(&code), "{}", path.display());
+	}
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::*;
+use interpreter::interpret;
+
+const PASSES: &str =
+	"unreachable,dead_code,global_analysis,gvn,mem2reg,useless_phis,sccp";
+
+#[test]
+fn propagate_through_phis() {
+	let code = r"
+int main() {
+	int a = getint(), flag;
+	if (a > 0) flag = 1; else flag = 1;
+	int x = flag * 4 + 2;
+	float f = x;
+	if (f > 5.5) putint(x);
+	else putint(a);
+	return x;
+}";
+	let program = compile_passes(code, PASSES);
+	let main = main_of(&program);
+	assert!(main.contains("call void @putint(i32 6)"), "{main}");
+	assert!(main.contains("ret i32 6"), "{main}");
+	assert!(!main.contains("putint(i32 %"), "{main}");
+	let result = interpret(&program, b"-3".to_vec()).unwrap();
+	assert_eq!(result.output, b"6");
+	assert_eq!(result.exit_code, 6);
+}
+
+#[test]
+fn ignore_unexecutable_edges() {
+	// `k` only changes on a branch that is never taken
+	let code = r"
+int main() {
+	int n = getint(), i = 0, k = 1;
+	while (i < n) {
+		if (k != 1) k = 2;
+		i = i + 1;
+	}
+	return k;
+}";
+	let program = compile_passes(code, PASSES);
+	let main = main_of(&program);
+	assert!(main.contains("ret i32 1"), "{main}");
+	assert!(!main.contains(" 2"), "{main}");
+	assert_eq!(interpret(&program, b"5".to_vec()).unwrap().exit_code, 1);
+}
+
+#[test]
+fn keep_division_by_zero() {
+	let code = "int main() { int z = 0; if (getint()) z = 0; return 7 / z; }";
+	let program = compile_passes(code, PASSES);
+	let main = main_of(&program);
+	assert!(main.contains("div i32 7, 0"), "{main}");
+}
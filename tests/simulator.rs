@@ -0,0 +1,116 @@
+mod common;
+
+use common::compile_passes;
+use instruction::temp::TempManager;
+use interpreter::{interpret, simulate};
+use post_optimizer::post_backend_optimize;
+use pre_optimizer::prereg_backend_optimize;
+use register::solve_register;
+use rrvm::program::{LlvmProgram, RiscvProgram};
+use transform::get_functions;
+
+const PROGRAM: &str = r"
+int a[1000];
+float f[10] = {1.5, 2.5};
+int fib(int n) {
+	if (n < 2) return n;
+	return fib(n - 1) + fib(n - 2);
+}
+int many(int a, int b, int c, int d, int e, int f, int g, int h, int i, int j,
+	float x, float y) {
+	return a * 2 + b - c * d / (e + 1) + f % 7 - g + h * i - j + x / y;
+}
+int main() {
+	int n = getint(), i = 0, s = 0;
+	int b[3][40];
+	while (i < n) { a[i] = i * 3 - 500; i = i + 1; }
+	i = 0;
+	while (i < 40) { b[i % 3][i] = a[i * 7] / 3 + fib(i % 9); i = i + 1; }
+	i = 0;
+	while (i < n) { s = s + a[i] % 11 + b[2][i % 40]; i = i + 1; }
+	putint(s);
+	putch(10);
+	putint(many(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 1.5, 0.25));
+	putch(10);
+	i = 2;
+	while (i < 10) { f[i] = f[i - 1] * 1.5 - f[i - 2]; i = i + 1; }
+	putfarray(10, f);
+	return s;
+}
+";
+
+fn to_riscv(program: LlvmProgram, level: i32) -> RiscvProgram {
+	let mut riscv_program = RiscvProgram::new(TempManager::default());
+	riscv_program.global_vars = program.global_vars;
+	get_functions(&mut riscv_program, program.funcs).unwrap();
+	prereg_backend_optimize(&mut riscv_program, level);
+	solve_register(&mut riscv_program);
+	post_backend_optimize(&mut riscv_program, level);
+	riscv_program
+}
+
+#[test]
+fn agree_with_interpreter() {
+	let input = b"1000".to_vec();
+	let expected =
+		interpret(&compile_passes(PROGRAM, "basic"), input.clone()).unwrap();
+	for (desc, level) in [("fast", 0), ("full", 1), ("full", 2)] {
+		let program = to_riscv(compile_passes(PROGRAM, desc), level);
+		let (execution, _) = simulate(&program, input.clone()).unwrap();
+		assert_eq!(execution, expected, "-O{level}");
+	}
+}
+
+#[test]
+fn profile_functions() {
+	let program = to_riscv(compile_passes(PROGRAM, "fast"), 0);
+	let (_, profile) = simulate(&program, b"10".to_vec()).unwrap();
+	let fib = profile.iter().find(|v| v.name == "fib").unwrap();
+	let main = profile.iter().find(|v| v.name == "main").unwrap();
+	assert!(fib.instrs > 0 && main.instrs > 0);
+	assert!(fib.cycles >= fib.instrs);
+	let (_, more) = simulate(&program, b"1000".to_vec()).unwrap();
+	let main_more = more.iter().find(|v| v.name == "main").unwrap();
+	assert!(main_more.instrs > main.instrs);
+}
+
+#[test]
+fn report_runtime_errors() {
+	let program = to_riscv(compile_passes(PROGRAM, "fast"), 0);
+	let err = simulate(&program, b"x".to_vec()).unwrap_err();
+	assert!(err.error.to_string().contains("in `main`"), "{err:?}");
+}
+
+#[test]
+fn keep_output_before_errors() {
+	let code = r"
+int main() {
+	putint(42);
+	putch(10);
+	return getint();
+}";
+	let program = to_riscv(compile_passes(code, "fast"), 0);
+	let crash = simulate(&program, b"x".to_vec()).unwrap_err();
+	assert_eq!(crash.output, b"42\n");
+}
+
+#[test]
+fn run_threads() {
+	let code = r"
+int a[100000];
+int main() {
+	int n = getint(), i = 0, s = 0;
+	while (i < n) { a[i] = i * 3 + 1; i = i + 1; }
+	i = 0;
+	while (i < n) { s = s + a[i] % 7; i = i + 1; }
+	putint(s);
+	return 0;
+}
+";
+	let program = compile_passes(code, "full");
+	assert!(program.to_string().contains("@__create_threads"));
+	let input = b"10000".to_vec();
+	let expected = interpret(&program, input.clone()).unwrap();
+	let (execution, _) = simulate(&to_riscv(program, 2), input).unwrap();
+	assert_eq!(execution, expected);
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use std::fs;
+
+use common::irgen;
+use optimizer::{PassManager, Pipeline};
+use rrvm::program::LlvmProgram;
+use sysyc::{cli::Args, step_optimize};
+
+const PROGRAM: &str = r"
+int a[100];
+int sum(int n) {
+	int s = 0, i = 0;
+	while (i < n) {
+		if (i % 3 == 0) { i = i + 1; continue; }
+		s = s + a[i];
+		if (s > 1000) break;
+		i = i + 1;
+	}
+	return s;
+}
+int main() {
+	int i = 0;
+	float f = 0.5;
+	while (i < 100) { a[i] = i * i; f = f * 1.5 + i; i = i + 1; }
+	putint(sum(100));
+	putfloat(f);
+	return 0;
+}
+";
+
+#[test]
+fn verify_each_pass() {
+	for desc in ["basic", "fast", "full"] {
+		let mut program = irgen(PROGRAM);
+		let mut manager = PassManager::new();
+		manager.set_verify_each(true);
+		manager.apply(&Pipeline::parse(desc).unwrap(), &mut program).unwrap();
+	}
+}
+
+#[test]
+fn verify_functional_suite() {
+	let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/functional");
+	for entry in fs::read_dir(dir).unwrap() {
+		let path = entry.unwrap().path();
+		if path.extension().map_or(true, |v| v != "sy") {
+			continue;
+		}
+		let code = fs::read_to_string(&path).unwrap();
+		for level in [1, 2] {
+			let args = Args {
+				opimizer: Some(level),
+				verify_each: true,
+				..Args::default()
+			};
+			if let Err(err) = step_optimize(irgen(&code), &args) {
+				panic!("{} at -O{level}: {err}", path.display());
+			}
+		}
+	}
+}
+
+fn verify(text: &str) -> Result<(), String> {
+	LlvmProgram::parse(text).unwrap().funcs[0].verify()
+}
+
+#[test]
+fn reject_broken_ir() {
+	let valid = "\
+define i32 @f(i32 %1){
+  entry:
+    %2 = icmp slt i32 %1, 0
+    br i32 %2, label %B1, label %B2
+  B1:
+    %3 = sub i32 0, %1
+    br label %B2
+  B2:
+    %4 = phi i32 [%1, label %entry], [%3, label %B1]
+    ret i32 %4
+}";
+	assert_eq!(verify(valid), Ok(()));
+	// the definition does not dominate the use
+	let text = valid.replace("ret i32 %4", "ret i32 %3");
+	assert!(verify(&text).unwrap_err().contains("not dominated"));
+	// used before defined in the same block
+	let text = valid.replace("sub i32 0, %1", "sub i32 0, %3");
+	assert!(verify(&text).unwrap_err().contains("not dominated"));
+	// incoming label is not a predecessor
+	let text = valid.replace("[%3, label %B1]", "[%3, label %B2]");
+	assert!(verify(&text).unwrap_err().contains("incoming labels"));
+	// defined twice
+	let text = valid.replace("%3 = sub", "%2 = sub").replace("[%3,", "[%2,");
+	assert!(verify(&text).unwrap_err().contains("more than once"));
+	// never defined
+	let text = valid.replace("[%1, label %entry]", "[%9, label %entry]");
+	assert!(verify(&text).unwrap_err().contains("never defined"));
+	// operand types disagree
+	let text = valid.replace("sub i32 0, %1", "sub i32 0.5, %1");
+	assert!(verify(&text).unwrap_err().contains("operand types"));
+}
@@ -1,5 +1,6 @@
 use llvm::{ArithOp, CompOp, ConvertOp, Value};
 use utils::{
+	constants::VEC_VARIADIC,
 	errors::{Result, SysycError::*},
 	math::{align16, is_pow2},
 	Label,
@@ -198,8 +199,9 @@ fn get_arith(
 		(ArithOp::Fadd, _, Value::Float(0.0)) => {
 			instrs.push(RBinInstr::new(FMv, rd, lhs));
 		}
-		(ArithOp::Div, _, Value::Int(num)) => match num {
-			0 => return Err(SemanticError("divided by zero".to_string())),
+		// a division by zero is left to `divw` and `remw`, the linter warns
+		// about it
+		(ArithOp::Div, _, Value::Int(num)) if *num != 0 => match num {
 			1 => instrs.push(RBinInstr::new(Mv, rd, lhs)),
 			-1 => instrs.push(RBinInstr::new(Neg, rd, lhs)),
 			_ => {
@@ -209,8 +211,7 @@ fn get_arith(
 				}
 			}
 		},
-		(ArithOp::Rem, _, Value::Int(num)) => match num {
-			0 => return Err(SemanticError("moduled by zero".to_string())),
+		(ArithOp::Rem, _, Value::Int(num)) if *num != 0 => match num {
 			1 | -1 => instrs.push(RBinInstr::new(Mv, rd, X0.into())),
 			_ => solve_rem(rd, lhs, num.abs(), instrs, mgr),
 		},
@@ -416,12 +417,12 @@ pub fn riscv_store(
 	let mut instrs: RiscvInstrSet = Vec::new();
 	let addr = into_reg(&instr.addr, &mut instrs, mgr);
 	let value = into_reg(&instr.value, &mut instrs, mgr);
-	match instr.value.get_type() {
-		llvm::VarType::F32 => {
-			instrs.push(IBinInstr::new(FSW, value, (0, addr).into()))
-		}
-		_ => instrs.push(IBinInstr::new(SW, value, (0, addr).into())),
-	}
+	let op = match (instr.value.get_type(), instr.addr.get_type()) {
+		(llvm::VarType::F32, _) => FSW,
+		(_, llvm::VarType::I8Ptr) => SB,
+		_ => SW,
+	};
+	instrs.push(IBinInstr::new(op, value, (0, addr).into()));
 	Ok(instrs)
 }
 
@@ -437,12 +438,12 @@ pub fn riscv_load(
 	} else {
 		let addr = into_reg(&instr.addr, &mut instrs, mgr);
 		let rd = mgr.get(&instr.target);
-		match instr.target.var_type {
-			llvm::VarType::F32 => {
-				instrs.push(IBinInstr::new(FLW, rd, (0, addr).into()))
-			}
-			_ => instrs.push(IBinInstr::new(LW, rd, (0, addr).into())),
-		}
+		let op = match (instr.target.var_type, instr.addr.get_type()) {
+			(llvm::VarType::F32, _) => FLW,
+			(_, llvm::VarType::I8Ptr) => LB,
+			_ => LW,
+		};
+		instrs.push(IBinInstr::new(op, rd, (0, addr).into()));
 	}
 	Ok(instrs)
 }
@@ -458,20 +459,52 @@ pub fn riscv_gep(
 	Ok(instrs)
 }
 
+// every argument of a variadic function goes to an integer register or an
+// 8-byte stack slot, and a float is passed as a double
+fn variadic_params(
+	params: &[(llvm::VarType, Value)],
+	instrs: &mut RiscvInstrSet,
+	mgr: &mut TempManager,
+) -> (Vec<(RiscvTemp, RiscvReg)>, Vec<RiscvTemp>) {
+	let mut regs = Vec::new();
+	let mut stack = Vec::new();
+	// a double in a register that can not be spilled as a float, shared so
+	// that the conversions keep their order
+	let double = mgr.new_pre_color_temp(Ft0);
+	for (index, (_, val)) in params.iter().enumerate() {
+		let mut temp = into_reg(val, instrs, mgr);
+		if temp.get_type() == VarType::Float {
+			instrs.push(RBinInstr::new(Float2Double, double, temp));
+			temp = mgr.new_temp(VarType::Int);
+			instrs.push(RBinInstr::new(MvDouble2Int, temp, double));
+		}
+		match PARAMETER_REGS.get(index) {
+			Some(reg) => regs.push((temp, *reg)),
+			None => stack.push(temp),
+		}
+	}
+	(regs, stack)
+}
+
 pub fn riscv_call(
 	instr: &llvm::CallInstr,
 	mgr: &mut TempManager,
 ) -> Result<RiscvInstrSet> {
 	let mut instrs: RiscvInstrSet = Vec::new();
-	let (regs, stack) = alloc_params_register(
-		instr.params.iter().map(|(_, v)| v.clone()).collect(),
-	);
-	let regs: Vec<_> = regs
-		.into_iter()
-		.map(|(k, v)| (into_reg(&k, &mut instrs, mgr), v))
-		.collect();
-	let stack: Vec<_> =
-		stack.into_iter().map(|v| into_reg(&v, &mut instrs, mgr)).collect();
+	let (regs, stack) = if VEC_VARIADIC.contains(&instr.func.name.as_str()) {
+		variadic_params(&instr.params, &mut instrs, mgr)
+	} else {
+		let (regs, stack) = alloc_params_register(
+			instr.params.iter().map(|(_, v)| v.clone()).collect(),
+		);
+		let regs = regs
+			.into_iter()
+			.map(|(k, v)| (into_reg(&k, &mut instrs, mgr), v))
+			.collect();
+		let stack =
+			stack.into_iter().map(|v| into_reg(&v, &mut instrs, mgr)).collect();
+		(regs, stack)
+	};
 	instrs.push(TemporayInstr::new(Save, instr.var_type));
 	let size = align16(stack.len() as i32 * 8);
 	if size > 0 {
@@ -494,6 +527,10 @@ pub fn riscv_call(
 			VarType::Float => instrs.push(RBinInstr::new(FMv, rd, val)),
 		}
 	}
+	// new threads copy the frame between sp and fp, so the caller keeps fp
+	if instr.func.name == "__create_threads" {
+		params.push(FP.into());
+	}
 
 	instrs.push(CallInstr::new(instr.func.clone(), params));
 
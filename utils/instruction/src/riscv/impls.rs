@@ -175,7 +175,10 @@ impl RiscvInstrTrait for IBinInstr {
 		}
 	}
 	fn map_dst_temp(&mut self, map: &HashMap<Temp, RiscvTemp>) {
-		if matches!(self.op, SB | Li | LD | LW | LWU | LA | FLW | FLD | Auipc) {
+		if matches!(
+			self.op,
+			Li | LD | LW | LWU | LB | LBU | LA | FLW | FLD | Auipc
+		) {
 			map_temp(&mut self.rd, map);
 		}
 	}
@@ -190,20 +193,22 @@ impl RiscvInstrTrait for IBinInstr {
 	}
 	fn get_virt_mem_read(&self) -> Option<VirtAddr> {
 		match self.op {
-			Li | LD | LW | LWU | LA | FLW | FLD | Auipc => self.rs1.to_virt_mem(),
+			Li | LD | LW | LWU | LB | LBU | LA | FLW | FLD | Auipc => {
+				self.rs1.to_virt_mem()
+			}
 			_ => None,
 		}
 	}
 	fn get_riscv_write(&self) -> Vec<RiscvTemp> {
 		match self.op {
-			Li | LD | LW | LWU | LA | FLW | FLD | Auipc => vec![self.rd],
+			Li | LD | LW | LWU | LB | LBU | LA | FLW | FLD | Auipc => vec![self.rd],
 			SB | SH | SW | SD | FSW | FSD => vec![],
 		}
 	}
 	fn get_riscv_read(&self) -> Vec<RiscvTemp> {
 		[
 			match self.op {
-				Li | LD | LW | LWU | LA | FLW | FLD | Auipc => vec![],
+				Li | LD | LW | LWU | LB | LBU | LA | FLW | FLD | Auipc => vec![],
 				SB | SH | SW | SD | FSW | FSD => vec![self.rd],
 			},
 			unwarp_imms(vec![&self.rs1]),
@@ -223,13 +228,13 @@ impl RiscvInstrTrait for IBinInstr {
 	}
 	fn is_load(&self) -> bool {
 		match self.op {
-			Li | LD | LW | LWU | LA | FLD | FLW | Auipc => true,
+			Li | LD | LW | LWU | LB | LBU | LA | FLD | FLW | Auipc => true,
 			SB | SH | SW | SD | FSD | FSW => false,
 		}
 	}
 	fn is_store(&self) -> bool {
 		match self.op {
-			Li | LD | LW | LWU | LA | FLD | FLW | Auipc => false,
+			Li | LD | LW | LWU | LB | LBU | LA | FLD | FLW | Auipc => false,
 			SB | SH | SW | SD | FSD | FSW => true,
 		}
 	}
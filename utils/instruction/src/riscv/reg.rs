@@ -15,7 +15,7 @@ pub const FP_ALLOCABLE_REGS: &[RiscvReg] = &[
 	Fs10, Fs11,
 ];
 
-const PARAMETER_REGS: &[RiscvReg] = &[A0, A1, A2, A3, A4, A5, A6, A7];
+pub const PARAMETER_REGS: &[RiscvReg] = &[A0, A1, A2, A3, A4, A5, A6, A7];
 const FP_PARAMETER_REGS: &[RiscvReg] =
 	&[Fa0, Fa1, Fa2, Fa3, Fa4, Fa5, Fa6, Fa7];
 
@@ -29,7 +29,10 @@ pub fn alloc_params_register(
 
 	for param in params {
 		match param.get_type() {
-			llvm::VarType::I32 | llvm::VarType::I32Ptr | llvm::VarType::F32Ptr => {
+			llvm::VarType::I32
+			| llvm::VarType::I32Ptr
+			| llvm::VarType::F32Ptr
+			| llvm::VarType::I8Ptr => {
 				if int_cnt < PARAMETER_REGS.len() {
 					regs.push((param, PARAMETER_REGS[int_cnt]));
 					int_cnt += 1;
@@ -191,6 +191,8 @@ impl RTN for IBinInstr {
 			IBinInstrOp::Li => [0, 0, 0, 0, 1],
 			IBinInstrOp::LW => [1, 0, 0, 0, 3],
 			IBinInstrOp::LWU => [1, 0, 0, 0, 3],
+			IBinInstrOp::LB => [1, 0, 0, 0, 3],
+			IBinInstrOp::LBU => [1, 0, 0, 0, 3],
 			IBinInstrOp::FSD => [0, 0, 0, 1, 4],
 			IBinInstrOp::FSW => [0, 0, 0, 1, 4],
 			IBinInstrOp::FLD => [0, 0, 0, 1, 2],
@@ -212,6 +214,8 @@ impl RTN for RBinInstr {
 			RBinInstrOp::Int2Float => [0, 0, 0, 1, 2],
 			RBinInstrOp::FMv => [0, 0, 0, 1, 2],
 			RBinInstrOp::MvInt2Float => [0, 0, 0, 1, 2],
+			RBinInstrOp::Float2Double => [0, 0, 0, 1, 2],
+			RBinInstrOp::MvDouble2Int => [0, 0, 0, 1, 2],
 			_ => [0, 0, 0, 0, 1],
 		}
 	}
@@ -109,6 +109,8 @@ pub enum IBinInstrOp {
 	LD,
 	LW,
 	LWU,
+	LB,
+	LBU,
 	SB,
 	SH,
 	SW,
@@ -133,6 +135,12 @@ pub enum RBinInstrOp {
 	Int2Float,
 	#[style("fcvt.w.s")]
 	Float2Int,
+	// a `float` passed to a variadic function is passed as a `double` in an
+	// integer register
+	#[style("fcvt.d.s")]
+	Float2Double,
+	#[style("fmv.x.d")]
+	MvDouble2Int,
 	#[style("sext.w")]
 	Sextw,
 	Seqz,
@@ -178,9 +186,9 @@ pub fn can_to_iop(op: &ArithOp) -> bool {
 pub fn to_iop(op: &ArithOp) -> ITriInstrOp {
 	match op {
 		ArithOp::Add => Addiw,
-		ArithOp::Shl => Slli,
-		ArithOp::Lshr => Srli,
-		ArithOp::Ashr => Srai,
+		ArithOp::Shl => Slliw,
+		ArithOp::Lshr => Srliw,
+		ArithOp::Ashr => Sraiw,
 		ArithOp::And => Andi,
 		ArithOp::Or => Ori,
 		ArithOp::Xor => Xori,
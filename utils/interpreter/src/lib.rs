@@ -0,0 +1,67 @@
+mod llvm_interp;
+mod memory;
+mod riscv_sim;
+mod runtime;
+
+pub use llvm_interp::{interpret, interpret_limited, Evaluator};
+pub use riscv_sim::{simulate, simulate_limited, FuncProfile};
+pub use runtime::{format_hex_float, Input};
+use utils::SysycError;
+
+/// A value in a register, pointers are addresses in `Int`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Val {
+	Int(i64),
+	Float(f32),
+}
+
+impl Val {
+	pub fn as_int(&self) -> i64 {
+		match self {
+			Self::Int(v) => *v,
+			Self::Float(v) => v.to_bits() as i64,
+		}
+	}
+	pub fn as_float(&self) -> f32 {
+		match self {
+			Self::Int(v) => f32::from_bits(*v as u32),
+			Self::Float(v) => *v,
+		}
+	}
+}
+
+impl From<i32> for Val {
+	fn from(value: i32) -> Self {
+		Self::Int(value as i64)
+	}
+}
+
+/// What a program leaves behind
+#[derive(Debug, PartialEq)]
+pub struct Execution {
+	pub output: Vec<u8>,
+	/// the return value of `main`, truncated to a byte like a process exit code
+	pub exit_code: u8,
+}
+
+/// A run stopped by an error, with what the program printed before it
+#[derive(Debug)]
+pub struct Crash {
+	pub output: Vec<u8>,
+	pub error: SysycError,
+}
+
+impl From<SysycError> for Crash {
+	fn from(error: SysycError) -> Self {
+		Self {
+			output: Vec::new(),
+			error,
+		}
+	}
+}
+
+impl From<Crash> for SysycError {
+	fn from(value: Crash) -> Self {
+		value.error
+	}
+}
@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+
+use llvm::{
+	ArithOp, CompOp, ConvertOp, LlvmInstr, LlvmInstrVariant, LlvmTemp, PhiInstr,
+	Value, VarType,
+};
+use rrvm::program::{LlvmFunc, LlvmProgram};
+use utils::{errors::Result, Label, SysycError::RuntimeError};
+
+use crate::{
+	memory::Memory,
+	runtime::{Input, Runtime},
+	Crash, Execution, Val,
+};
+
+// a basic block, the terminator is the last instruction
+struct Block {
+	label: Label,
+	phis: Vec<PhiInstr>,
+	instrs: Vec<LlvmInstr>,
+}
+
+struct Func {
+	params: Vec<LlvmTemp>,
+	blocks: Vec<Block>,
+	labels: HashMap<Label, usize>,
+	entry: usize,
+}
+
+impl Func {
+	fn new(func: &LlvmFunc) -> Result<Self> {
+		let mut blocks = Vec::new();
+		let mut labels = HashMap::new();
+		for node in func.cfg.blocks.iter() {
+			let block = node.borrow();
+			let jump = block.jump_instr.clone().ok_or_else(|| {
+				RuntimeError(format!(
+					"block `{}` of `{}` has no terminator",
+					block.label(),
+					func.name
+				))
+			})?;
+			labels.insert(block.label(), blocks.len());
+			blocks.push(Block {
+				label: block.label(),
+				phis: block.phi_instrs.clone(),
+				instrs: block.instrs.iter().cloned().chain([jump]).collect(),
+			});
+		}
+		Ok(Self {
+			params: func.params.iter().filter_map(|v| v.unwrap_temp()).collect(),
+			entry: labels[&func.cfg.get_entry().borrow().label()],
+			blocks,
+			labels,
+		})
+	}
+}
+
+#[derive(Clone)]
+struct Frame<'a> {
+	func: &'a Func,
+	block: usize,
+	pc: usize,
+	temps: HashMap<String, Val>,
+	// stack pointer when the function is called
+	sp: usize,
+	// temp of the caller receiving the return value
+	ret: Option<LlvmTemp>,
+}
+
+// `parallel` splits a loop among the threads created by `__create_threads`,
+// here they run one after another, each until it reaches `__join_threads`
+#[derive(Clone)]
+struct Thread<'a> {
+	frames: Vec<Frame<'a>>,
+	forked: bool,
+}
+
+struct Machine<'a> {
+	funcs: &'a HashMap<String, Func>,
+	memory: Memory,
+	runtime: Runtime,
+	// the functions of the runtime may not be called
+	isolated: bool,
+}
+
+fn arith(op: ArithOp, lhs: Val, rhs: Val) -> Val {
+	use ArithOp::*;
+	let (a, b) = (lhs.as_int(), rhs.as_int());
+	// instructions without `D` work on the low 32 bits like `addw` of RV64
+	let (x, y) = (a as i32, b as i32);
+	let word = |v: i32| Val::Int(v as i64);
+	let (f, g) = (lhs.as_float(), rhs.as_float());
+	match op {
+		Add => word(x.wrapping_add(y)),
+		AddD => Val::Int(a.wrapping_add(b)),
+		Sub => word(x.wrapping_sub(y)),
+		SubD => Val::Int(a.wrapping_sub(b)),
+		Mul => word(x.wrapping_mul(y)),
+		MulD => Val::Int(a.wrapping_mul(b)),
+		// division by zero gives what RISC-V gives
+		Div => word(if y == 0 { -1 } else { x.wrapping_div(y) }),
+		DivD => Val::Int(if b == 0 { -1 } else { a.wrapping_div(b) }),
+		Rem => word(if y == 0 { x } else { x.wrapping_rem(y) }),
+		RemD => Val::Int(if b == 0 { a } else { a.wrapping_rem(b) }),
+		Shl => word(x.wrapping_shl(y as u32)),
+		ShlD => Val::Int(a.wrapping_shl(b as u32)),
+		Lshr => word((x as u32).wrapping_shr(y as u32) as i32),
+		LshrD => Val::Int((a as u64).wrapping_shr(b as u32) as i64),
+		Ashr => word(x.wrapping_shr(y as u32)),
+		AshrD => Val::Int(a.wrapping_shr(b as u32)),
+		And => Val::Int(a & b),
+		Or => Val::Int(a | b),
+		Xor => Val::Int(a ^ b),
+		Clz => word(x.leading_zeros() as i32),
+		ClzD => Val::Int(a.leading_zeros() as i64),
+		Ctz => word(x.trailing_zeros() as i32),
+		CtzD => Val::Int(a.trailing_zeros() as i64),
+		Min => word(x.min(y)),
+		MinD => Val::Int(a.min(b)),
+		Max => word(x.max(y)),
+		MaxD => Val::Int(a.max(b)),
+		Fadd => Val::Float(f + g),
+		Fsub => Val::Float(f - g),
+		Fmul => Val::Float(f * g),
+		Fdiv => Val::Float(f / g),
+		Fmin => Val::Float(f.min(g)),
+		Fmax => Val::Float(f.max(g)),
+	}
+}
+
+// the operator tells how to compare, whatever the kind is
+fn compare(op: CompOp, lhs: Val, rhs: Val) -> Val {
+	use std::cmp::Ordering;
+	use CompOp::*;
+	let order = match op {
+		EQ | NE | SGT | SGE | SLT | SLE => Some(lhs.as_int().cmp(&rhs.as_int())),
+		_ => lhs.as_float().partial_cmp(&rhs.as_float()),
+	};
+	let result = match op {
+		EQ | OEQ => order == Some(Ordering::Equal),
+		NE | ONE => order != Some(Ordering::Equal),
+		SGT | OGT => order == Some(Ordering::Greater),
+		SGE | OGE => matches!(order, Some(Ordering::Greater | Ordering::Equal)),
+		SLT | OLT => order == Some(Ordering::Less),
+		SLE | OLE => matches!(order, Some(Ordering::Less | Ordering::Equal)),
+	};
+	Val::Int(result as i64)
+}
+
+fn convert(op: ConvertOp, value: Val) -> Val {
+	match op {
+		ConvertOp::Int2Float => Val::Float(value.as_int() as i32 as f32),
+		// `fcvt.w.s` saturates, and gives the largest integer for NaN
+		ConvertOp::Float2Int => match value.as_float() {
+			v if v.is_nan() => Val::from(i32::MAX),
+			v => Val::from(v as i32),
+		},
+	}
+}
+
+impl<'a> Machine<'a> {
+	fn get(&self, frame: &Frame, value: &Value) -> Result<Val> {
+		match value {
+			Value::Int(v) => Ok(Val::Int(*v as i64)),
+			Value::Float(v) => Ok(Val::Float(*v)),
+			Value::Temp(v) if v.is_global => {
+				self.memory.global(&v.name).map(Val::Int)
+			}
+			Value::Temp(v) => frame
+				.temps
+				.get(&v.name)
+				.copied()
+				.ok_or_else(|| RuntimeError(format!("`{v}` is used before defined"))),
+		}
+	}
+
+	// jump to `label`, the phis read the values of the current block
+	fn enter(&self, frame: &mut Frame, label: &Label) -> Result<()> {
+		let from = &frame.func.blocks[frame.block].label;
+		let next = *frame.func.labels.get(label).ok_or_else(|| {
+			RuntimeError(format!("jump to unknown block `{label}`"))
+		})?;
+		let mut values = Vec::new();
+		for phi in frame.func.blocks[next].phis.iter() {
+			let (value, _) =
+				phi.source.iter().find(|(_, v)| v == from).ok_or_else(|| {
+					RuntimeError(format!(
+						"`{}` has no value for the coming edge",
+						phi.target
+					))
+				})?;
+			values.push((phi.target.name.clone(), self.get(frame, value)?));
+		}
+		frame.temps.extend(values);
+		frame.block = next;
+		frame.pc = 0;
+		Ok(())
+	}
+
+	fn new_frame(
+		&mut self,
+		name: &str,
+		args: Vec<Val>,
+		ret: Option<LlvmTemp>,
+	) -> Option<Frame<'a>> {
+		let func = self.funcs.get(name)?;
+		Some(Frame {
+			func,
+			block: func.entry,
+			pc: 0,
+			temps: func.params.iter().map(|v| v.name.clone()).zip(args).collect(),
+			sp: self.memory.sp,
+			ret,
+		})
+	}
+
+	// run until `main` returns, `None` if the thread exits at `__join_threads`
+	fn run(&mut self, thread: &mut Thread<'a>) -> Result<Option<Val>> {
+		loop {
+			self.runtime.burn()?;
+			let frame = thread.frames.last_mut().unwrap();
+			let instr = &frame.func.blocks[frame.block].instrs[frame.pc];
+			frame.pc += 1;
+			match instr.get_variant() {
+				LlvmInstrVariant::ArithInstr(v) => {
+					let value =
+						arith(v.op, self.get(frame, &v.lhs)?, self.get(frame, &v.rhs)?);
+					frame.temps.insert(v.target.name.clone(), value);
+				}
+				LlvmInstrVariant::CompInstr(v) => {
+					let (lhs, rhs) = (self.get(frame, &v.lhs)?, self.get(frame, &v.rhs)?);
+					let value = compare(v.op, lhs, rhs);
+					frame.temps.insert(v.target.name.clone(), value);
+				}
+				LlvmInstrVariant::ConvertInstr(v) => {
+					let value = convert(v.op, self.get(frame, &v.lhs)?);
+					frame.temps.insert(v.target.name.clone(), value);
+				}
+				LlvmInstrVariant::JumpInstr(v) => self.enter(frame, &v.target)?,
+				LlvmInstrVariant::JumpCondInstr(v) => {
+					let cond = match self.get(frame, &v.cond)? {
+						Val::Int(v) => v != 0,
+						Val::Float(v) => v != 0.0,
+					};
+					let target = if cond {
+						&v.target_true
+					} else {
+						&v.target_false
+					};
+					self.enter(frame, target)?;
+				}
+				LlvmInstrVariant::PhiInstr(_) => unreachable!(),
+				LlvmInstrVariant::RetInstr(v) => {
+					let value = match &v.value {
+						Some(value) => self.get(frame, value)?,
+						None => Val::Int(0),
+					};
+					let frame = thread.frames.pop().unwrap();
+					self.memory.sp = frame.sp;
+					let Some(caller) = thread.frames.last_mut() else {
+						return Ok(Some(value));
+					};
+					if let Some(target) = frame.ret {
+						caller.temps.insert(target.name, value);
+					}
+				}
+				LlvmInstrVariant::AllocInstr(v) => {
+					let length = self.get(frame, &v.length)?.as_int() as usize;
+					let addr = self.memory.alloc(length)?;
+					frame.temps.insert(v.target.name.clone(), Val::Int(addr));
+				}
+				LlvmInstrVariant::StoreInstr(v) => {
+					let bits = match self.get(frame, &v.value)? {
+						Val::Int(v) => v as u32,
+						Val::Float(v) => v.to_bits(),
+					};
+					let addr = self.get(frame, &v.addr)?.as_int();
+					let size = v.addr.get_type().get_size();
+					self.memory.store(addr, size, bits as u64)?;
+				}
+				// loading a global variable takes its address, like `la`
+				LlvmInstrVariant::LoadInstr(v) if v.addr.is_global() => {
+					let addr = self.get(frame, &v.addr)?;
+					frame.temps.insert(v.target.name.clone(), addr);
+				}
+				LlvmInstrVariant::LoadInstr(v) => {
+					let addr = self.get(frame, &v.addr)?.as_int();
+					let value = match v.addr.get_type() {
+						VarType::I8Ptr => {
+							Val::from(self.memory.load(addr, 1)? as i8 as i32)
+						}
+						_ => {
+							let bits = self.memory.load_u32(addr)?;
+							match v.var_type {
+								VarType::F32 => Val::Float(f32::from_bits(bits)),
+								_ => Val::from(bits as i32),
+							}
+						}
+					};
+					frame.temps.insert(v.target.name.clone(), value);
+				}
+				LlvmInstrVariant::GEPInstr(v) => {
+					let addr = self.get(frame, &v.addr)?.as_int();
+					let offset = self.get(frame, &v.offset)?.as_int();
+					frame.temps.insert(v.target.name.clone(), Val::Int(addr + offset));
+				}
+				LlvmInstrVariant::CallInstr(v) => {
+					let args = v
+						.params
+						.iter()
+						.map(|(_, value)| self.get(frame, value))
+						.collect::<Result<Vec<_>>>()?;
+					let target = (v.var_type != VarType::Void).then(|| v.target.clone());
+					let name = v.func.name.as_str();
+					if let Some(callee) =
+						self.new_frame(name, args.clone(), target.clone())
+					{
+						thread.frames.push(callee);
+						continue;
+					}
+					let value = match name {
+						// the argument is the number of threads, the one seeing 0 goes on
+						// after the loop, the others exit at `__join_threads`
+						"__create_threads" => {
+							let threads = args.first().map_or(1, |v| v.as_int());
+							for id in (1..threads).rev() {
+								let mut child = thread.clone();
+								child.forked = true;
+								if let Some(target) = target.as_ref() {
+									let frame = child.frames.last_mut().unwrap();
+									frame.temps.insert(target.name.clone(), Val::Int(id));
+								}
+								if self.run(&mut child)?.is_some() {
+									return Err(RuntimeError(
+										"a thread returns from `main` without joining".to_string(),
+									));
+								}
+							}
+							Val::Int(0)
+						}
+						"__join_threads" if thread.forked => return Ok(None),
+						"__join_threads" => Val::Int(0),
+						_ if self.isolated => {
+							return Err(RuntimeError(format!("`{name}` needs the runtime")))
+						}
+						_ => self.runtime.call(name, &args, &mut self.memory).ok_or_else(
+							|| RuntimeError(format!("call to undefined function `{name}`")),
+						)??,
+					};
+					if let Some(target) = target {
+						let frame = thread.frames.last_mut().unwrap();
+						frame.temps.insert(target.name, value);
+					}
+				}
+			}
+		}
+	}
+}
+
+fn load_funcs(program: &LlvmProgram) -> Result<HashMap<String, Func>> {
+	program.funcs.iter().map(|v| Ok((v.name.clone(), Func::new(v)?))).collect()
+}
+
+/// Functions of a program called one at a time, apart from the rest of it:
+/// a call sees its arguments and its stack, but no global variables, input
+/// or output
+pub struct Evaluator {
+	funcs: HashMap<String, Func>,
+}
+
+impl Evaluator {
+	pub fn new(program: &LlvmProgram) -> Result<Self> {
+		Ok(Self {
+			funcs: load_funcs(program)?,
+		})
+	}
+	/// The return value of `name` called with `args`, each instruction run
+	/// takes one of `fuel`, and the call fails once there is none left
+	pub fn call(
+		&self,
+		name: &str,
+		args: Vec<Val>,
+		fuel: &mut u64,
+	) -> Result<Val> {
+		let mut machine = Machine {
+			funcs: &self.funcs,
+			memory: Memory::new(&[]),
+			runtime: Runtime::new(Input::default(), Some(*fuel)),
+			isolated: true,
+		};
+		let frame = machine.new_frame(name, args, None).ok_or_else(|| {
+			RuntimeError(format!("call to undefined function `{name}`"))
+		})?;
+		let mut thread = Thread {
+			frames: vec![frame],
+			forked: false,
+		};
+		let value = machine.run(&mut thread);
+		*fuel = machine.runtime.fuel().unwrap_or_default();
+		value?.ok_or_else(|| {
+			RuntimeError(format!("`{name}` exits at `__join_threads`"))
+		})
+	}
+}
+
+/// Run `main` of the program with `input` as stdin
+pub fn interpret(
+	program: &LlvmProgram,
+	input: impl Into<Input>,
+) -> Result<Execution, Crash> {
+	interpret_limited(program, input, None)
+}
+
+/// Like `interpret`, but fail once more than `limit` instructions are run
+pub fn interpret_limited(
+	program: &LlvmProgram,
+	input: impl Into<Input>,
+	limit: Option<u64>,
+) -> Result<Execution, Crash> {
+	let funcs = load_funcs(program)?;
+	let mut machine = Machine {
+		funcs: &funcs,
+		memory: Memory::new(&program.global_vars),
+		runtime: Runtime::new(input.into(), limit),
+		isolated: false,
+	};
+	let main = machine
+		.new_frame("main", Vec::new(), None)
+		.ok_or_else(|| RuntimeError("no `main` function".to_string()))?;
+	let mut thread = Thread {
+		frames: vec![main],
+		forked: false,
+	};
+	let value = match machine.run(&mut thread) {
+		Ok(value) => value.unwrap(),
+		Err(error) => {
+			return Err(Crash {
+				output: machine.runtime.output,
+				error,
+			})
+		}
+	};
+	Ok(Execution {
+		output: machine.runtime.output,
+		exit_code: value.as_int() as u8,
+	})
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use utils::{errors::Result, GlobalVar, SysycError::RuntimeError, ValueItem};
+
+// addresses below this are never valid, so null pointers are caught
+const DATA_BASE: usize = 0x10000;
+// no more stack than this, deep recursion is reported instead of eating up
+// all the memory
+pub const STACK_LIMIT: usize = 256 << 20;
+
+/// Flat little-endian memory with the global variables at the bottom, laid
+/// out like the `.sdata` and `.sbss` sections of `code_emission`, and the
+/// stack right after them
+pub struct Memory {
+	data: Vec<u8>,
+	pub globals: HashMap<String, i64>,
+	/// lowest address of the stack
+	pub stack_base: usize,
+	/// first free byte of the stack when it grows upwards
+	pub sp: usize,
+}
+
+fn align(v: usize, n: usize) -> usize {
+	v.div_ceil(n) * n
+}
+
+impl Memory {
+	/// Memory whose stack grows upwards by `alloc`
+	pub fn new(global_vars: &[GlobalVar]) -> Self {
+		Self::with_stack(global_vars, 0)
+	}
+	/// Memory with `size` bytes of stack ending at `data_end`
+	pub fn with_stack(global_vars: &[GlobalVar], size: usize) -> Self {
+		let (bss, data): (Vec<_>, Vec<_>) =
+			global_vars.iter().partition(|v| v.is_bss());
+		let mut globals = HashMap::new();
+		let mut end = DATA_BASE;
+		for var in data.iter().chain(bss.iter()) {
+			// `.align 2`
+			end = align(end, 4);
+			globals.insert(var.ident.clone(), end as i64);
+			end += var.size();
+		}
+		let stack_base = align(end, 16);
+		let mut bytes = vec![0u8; stack_base + size];
+		for var in data.iter() {
+			let mut addr = globals[&var.ident] as usize;
+			for item in var.data.iter() {
+				match item {
+					ValueItem::Byte(v) => {
+						bytes[addr] = *v;
+						addr += 1;
+					}
+					ValueItem::Word(v) => {
+						bytes[addr..addr + 4].copy_from_slice(&v.to_le_bytes());
+						addr += 4;
+					}
+					ValueItem::Zero(n) => addr += n,
+				}
+			}
+		}
+		Self {
+			data: bytes,
+			globals,
+			stack_base,
+			sp: stack_base,
+		}
+	}
+	/// One past the highest valid address
+	pub fn data_end(&self) -> usize {
+		self.data.len()
+	}
+	pub fn global(&self, name: &str) -> Result<i64> {
+		self
+			.globals
+			.get(name)
+			.copied()
+			.ok_or_else(|| RuntimeError(format!("unknown global variable `{name}`")))
+	}
+	/// Take `size` bytes from the stack, they are zeroed
+	pub fn alloc(&mut self, size: usize) -> Result<i64> {
+		let addr = self.sp;
+		self.sp = align(self.sp + size, 16);
+		if self.sp - self.stack_base > STACK_LIMIT {
+			return Err(RuntimeError("stack overflow".to_string()));
+		}
+		if self.data.len() < self.sp {
+			self.data.resize(self.sp, 0);
+		}
+		self.data[addr..self.sp].fill(0);
+		Ok(addr as i64)
+	}
+	fn range(&self, addr: i64, size: usize) -> Result<std::ops::Range<usize>> {
+		let start = addr as usize;
+		if addr < DATA_BASE as i64 || start + size > self.data.len() {
+			return Err(RuntimeError(format!(
+				"invalid memory access of {size} bytes at {addr:#x}"
+			)));
+		}
+		Ok(start..start + size)
+	}
+	pub fn read_bytes(&self, addr: i64, size: usize) -> Result<&[u8]> {
+		let range = self.range(addr, size)?;
+		Ok(&self.data[range])
+	}
+	pub fn write_bytes(&mut self, addr: i64, bytes: &[u8]) -> Result<()> {
+		let range = self.range(addr, bytes.len())?;
+		self.data[range].copy_from_slice(bytes);
+		Ok(())
+	}
+	/// Read `size` bytes as an unsigned number
+	pub fn load(&self, addr: i64, size: usize) -> Result<u64> {
+		let mut bytes = [0u8; 8];
+		bytes[..size].copy_from_slice(self.read_bytes(addr, size)?);
+		Ok(u64::from_le_bytes(bytes))
+	}
+	/// Write the lowest `size` bytes of `value`
+	pub fn store(&mut self, addr: i64, size: usize, value: u64) -> Result<()> {
+		self.write_bytes(addr, &value.to_le_bytes()[..size])
+	}
+	pub fn load_u32(&self, addr: i64) -> Result<u32> {
+		self.load(addr, 4).map(|v| v as u32)
+	}
+	pub fn store_u32(&mut self, addr: i64, value: u32) -> Result<()> {
+		self.store(addr, 4, value as u64)
+	}
+}
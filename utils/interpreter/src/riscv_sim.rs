@@ -0,0 +1,573 @@
+use std::collections::HashMap;
+
+use instruction::riscv::prelude::*;
+use rrvm::program::RiscvProgram;
+use utils::{
+	errors::Result,
+	Label,
+	SysycError::{self, RuntimeError},
+};
+
+use crate::{
+	memory::{Memory, STACK_LIMIT},
+	runtime::{Input, Runtime},
+	Crash, Execution, Val,
+};
+
+// code addresses, away from the data so that a return address is never a
+// valid pointer, `main` returns to 0
+const TEXT_BASE: u64 = 1 << 40;
+// what caller-saved registers hold after a call into the runtime, a value
+// living across such a call is likely to show up as garbage
+const POISON: u64 = 0xdead_beef_dead_beef;
+
+/// Instructions and cycles spent in a function, its callees excluded
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FuncProfile {
+	pub name: String,
+	pub instrs: u64,
+	/// estimated by the latencies the instruction scheduler uses
+	pub cycles: u64,
+}
+
+// all the functions in one piece of code, blocks in the order of the CFG,
+// each ending with its jump instruction
+struct Code {
+	instrs: Vec<RiscvInstr>,
+	// index of the function an instruction belongs to
+	owner: Vec<usize>,
+	names: Vec<String>,
+	entries: HashMap<String, usize>,
+	// block labels of each function
+	labels: Vec<HashMap<Label, usize>>,
+}
+
+impl Code {
+	fn new(program: &RiscvProgram) -> Result<Self> {
+		let mut code = Code {
+			instrs: Vec::new(),
+			owner: Vec::new(),
+			names: Vec::new(),
+			entries: HashMap::new(),
+			labels: Vec::new(),
+		};
+		for (index, func) in program.funcs.iter().enumerate() {
+			let mut labels = HashMap::new();
+			code.entries.insert(func.name.clone(), code.instrs.len());
+			let entry = func.cfg.get_entry();
+			let blocks = std::iter::once(entry.clone()).chain(
+				func
+					.cfg
+					.blocks
+					.iter()
+					.filter(|v| v.borrow().id != entry.borrow().id)
+					.cloned(),
+			);
+			for node in blocks {
+				let block = node.borrow();
+				labels.insert(block.label(), code.instrs.len());
+				let jump = block.jump_instr.clone().ok_or_else(|| {
+					RuntimeError(format!(
+						"block `{}` of `{}` has no jump instruction",
+						block.label(),
+						func.name
+					))
+				})?;
+				// `code_emission` drops these
+				let instrs = block.instrs.iter().filter(|v| !v.useless()).cloned();
+				code.instrs.extend(instrs.chain([jump]));
+			}
+			code.owner.resize(code.instrs.len(), index);
+			code.names.push(func.name.clone());
+			code.labels.push(labels);
+		}
+		Ok(code)
+	}
+}
+
+#[derive(Clone)]
+struct Hart {
+	regs: [u64; 64],
+	pc: usize,
+	// waiting at `__join_threads` for the other threads
+	joining: bool,
+}
+
+// what the simulator does after an instruction
+enum Event {
+	Next,
+	Exit(u64),
+	Fork,
+	Join,
+}
+
+struct Simulator<'a> {
+	code: &'a Code,
+	memory: Memory,
+	runtime: Runtime,
+	profile: Vec<FuncProfile>,
+}
+
+fn sext(v: u64) -> u64 {
+	v as i32 as i64 as u64
+}
+
+fn float(v: u64) -> f32 {
+	f32::from_bits(v as u32)
+}
+
+fn bits(v: f32) -> u64 {
+	v.to_bits() as u64
+}
+
+fn rtri(op: RTriInstrOp, a: u64, b: u64) -> u64 {
+	use RTriInstrOp::*;
+	let (x, y) = (a as i64, b as i64);
+	let (w, z) = (a as i32, b as i32);
+	let word = |v: i32| v as i64 as u64;
+	match op {
+		Add => a.wrapping_add(b),
+		Addw => word(w.wrapping_add(z)),
+		Sub => a.wrapping_sub(b),
+		Subw => word(w.wrapping_sub(z)),
+		Mul => a.wrapping_mul(b),
+		Mulw => word(w.wrapping_mul(z)),
+		Div => (if y == 0 { -1 } else { x.wrapping_div(y) }) as u64,
+		Divw => word(if z == 0 { -1 } else { w.wrapping_div(z) }),
+		Rem => (if y == 0 { x } else { x.wrapping_rem(y) }) as u64,
+		Remw => word(if z == 0 { w } else { w.wrapping_rem(z) }),
+		Xor => a ^ b,
+		Xorw => sext(a ^ b),
+		Or => a | b,
+		Orw => sext(a | b),
+		And => a & b,
+		Andw => sext(a & b),
+		Sll => a << (b & 63),
+		Sllw => word(w << (b & 31)),
+		Srl => a >> (b & 63),
+		Srlw => word(((a as u32) >> (b & 31)) as i32),
+		Sra => (x >> (b & 63)) as u64,
+		Sraw => word(w >> (b & 31)),
+		Clz => a.leading_zeros() as u64,
+		Clzw => (a as u32).leading_zeros() as u64,
+		Ctz => a.trailing_zeros() as u64,
+		Ctzw => (a as u32).trailing_zeros() as u64,
+		Slt => (x < y) as u64,
+		Sltu => (a < b) as u64,
+		Min => x.min(y) as u64,
+		Minw => word(w.min(z)),
+		Max => x.max(y) as u64,
+		Maxw => word(w.max(z)),
+		Sh1add => (a << 1).wrapping_add(b),
+		Sh2add => (a << 2).wrapping_add(b),
+		Sh3add => (a << 3).wrapping_add(b),
+		Fadd => bits(float(a) + float(b)),
+		Fsub => bits(float(a) - float(b)),
+		Fmul => bits(float(a) * float(b)),
+		Fdiv => bits(float(a) / float(b)),
+		Feq => (float(a) == float(b)) as u64,
+		Flt => (float(a) < float(b)) as u64,
+		Fle => (float(a) <= float(b)) as u64,
+		Fabs => bits(float(a).abs()),
+		Fneg => bits(-float(a)),
+		Fmin => bits(float(a).min(float(b))),
+		Fmax => bits(float(a).max(float(b))),
+	}
+}
+
+fn itri(op: ITriInstrOp, a: u64, imm: i64) -> u64 {
+	use ITriInstrOp::*;
+	let b = imm as u64;
+	match op {
+		Addi => a.wrapping_add(b),
+		Xori => a ^ b,
+		Ori => a | b,
+		Andi => a & b,
+		Slli => a << (b & 63),
+		Srli => a >> (b & 63),
+		Srai => ((a as i64) >> (b & 63)) as u64,
+		Slti => ((a as i64) < imm) as u64,
+		Sltiu => (a < b) as u64,
+		Addiw => sext(a.wrapping_add(b)),
+		Slliw => sext(a << (b & 31)),
+		Srliw => sext(((a as u32) >> (b & 31)) as u64),
+		Sraiw => ((a as i32) >> (b & 31)) as i64 as u64,
+		Sltiw => ((a as i32 as i64) < imm) as u64,
+	}
+}
+
+fn branch(op: BranInstrOp, a: u64, b: u64) -> bool {
+	use BranInstrOp::*;
+	match op {
+		Beq => a == b,
+		Bne => a != b,
+		Blt => (a as i64) < (b as i64),
+		Bge => (a as i64) >= (b as i64),
+		Bltu => a < b,
+		Bgeu => a >= b,
+	}
+}
+
+// byte width of a load or store, and whether a load sign-extends
+fn access(op: IBinInstrOp) -> (usize, bool) {
+	match op {
+		LB => (1, true),
+		LBU | SB => (1, false),
+		SH => (2, false),
+		LW | SW => (4, true),
+		LWU | FLW | FSW => (4, false),
+		_ => (8, false),
+	}
+}
+
+impl<'a> Simulator<'a> {
+	fn reg(hart: &Hart, temp: &RiscvTemp) -> Result<u64> {
+		match temp {
+			PhysReg(RiscvReg::X0) => Ok(0),
+			PhysReg(v) => Ok(hart.regs[*v as usize]),
+			VirtReg(v) => {
+				Err(RuntimeError(format!("virtual register `{v}` is left")))
+			}
+		}
+	}
+	fn set_reg(
+		&self,
+		hart: &mut Hart,
+		temp: &RiscvTemp,
+		value: u64,
+	) -> Result<()> {
+		match temp {
+			PhysReg(RiscvReg::X0) => Ok(()),
+			PhysReg(RiscvReg::SP)
+				if (value as usize) < self.memory.stack_base
+					|| value as usize > self.memory.data_end() =>
+			{
+				Err(RuntimeError("stack overflow".to_string()))
+			}
+			PhysReg(v) => {
+				hart.regs[*v as usize] = value;
+				Ok(())
+			}
+			VirtReg(v) => {
+				Err(RuntimeError(format!("virtual register `{v}` is left")))
+			}
+		}
+	}
+	// `auipc` gives the address itself, so `%pcrel_lo` is 0
+	fn imm(&self, imm: &RiscvImm) -> Result<i64> {
+		match imm {
+			RiscvImm::RiscvNumber(RiscvNumber::Int(v)) => Ok(*v as i64),
+			RiscvImm::RiscvNumber(RiscvNumber::Lo(_)) => Ok(0),
+			RiscvImm::RiscvNumber(RiscvNumber::Hi(v)) | RiscvImm::Label(v) => {
+				self.memory.global(&v.name)
+			}
+			RiscvImm::LongLong(v) => Ok(*v),
+			_ => Err(RuntimeError(format!("unexpected operand `{imm}`"))),
+		}
+	}
+	fn addr(&self, hart: &Hart, imm: &RiscvImm) -> Result<i64> {
+		match imm {
+			RiscvImm::OffsetReg(offset, base) => {
+				let offset = self.imm(&RiscvImm::RiscvNumber(offset.clone()))?;
+				Ok((Self::reg(hart, base)? as i64).wrapping_add(offset))
+			}
+			_ => Err(RuntimeError(format!("unexpected address `{imm}`"))),
+		}
+	}
+	fn jump(&self, hart: &mut Hart, label: &RiscvImm) -> Result<()> {
+		let func = self.code.owner[hart.pc - 1];
+		hart.pc = match label {
+			RiscvImm::Label(label) => self.code.labels[func].get(label).copied(),
+			_ => None,
+		}
+		.ok_or_else(|| RuntimeError(format!("jump to unknown label `{label}`")))?;
+		Ok(())
+	}
+
+	// call a function of the runtime, they use the registers of the ABI
+	fn host_call(&mut self, hart: &mut Hart, name: &str) -> Result<()> {
+		let int_args = [A0, A1, A2, A3, A4, A5, A6, A7];
+		let args: Vec<_> = match name {
+			"putfloat" => vec![Val::Float(float(hart.regs[Fa0 as usize]))],
+			// the arguments after `a7` are in 8-byte slots on the stack, as many
+			// as there can be are passed since their number is not known
+			"putf" => {
+				let sp = hart.regs[SP as usize] as i64;
+				let stack = (0..8).map_while(|i| self.memory.load(sp + i * 8, 8).ok());
+				int_args
+					.iter()
+					.map(|v| hart.regs[*v as usize])
+					.chain(stack)
+					.map(|v| Val::Int(v as i64))
+					.collect()
+			}
+			_ => int_args
+				.iter()
+				.map(|v| Val::Int(hart.regs[*v as usize] as i64))
+				.collect(),
+		};
+		let value = match name {
+			"__fill_zero_words" => {
+				let (addr, n) = (args[0].as_int(), args[1].as_int() as i32);
+				for i in 0..n.max(0) as i64 {
+					self.memory.store_u32(addr + i * 4, 0)?;
+				}
+				Val::Int(0)
+			}
+			_ => self.runtime.call(name, &args, &mut self.memory).ok_or_else(
+				|| RuntimeError(format!("call to undefined function `{name}`")),
+			)??,
+		};
+		for reg in ALLOCABLE_REGS.iter().chain(FP_ALLOCABLE_REGS.iter()) {
+			if need_caller_save(reg, llvm::VarType::Void) {
+				hart.regs[*reg as usize] = POISON;
+			}
+		}
+		match value {
+			Val::Int(v) => hart.regs[A0 as usize] = v as u64,
+			Val::Float(v) => hart.regs[Fa0 as usize] = bits(v),
+		}
+		Ok(())
+	}
+
+	fn step(&mut self, hart: &mut Hart) -> Result<Event> {
+		self.runtime.burn()?;
+		let instr = &self.code.instrs[hart.pc];
+		let func = self.code.owner[hart.pc];
+		self.profile[func].instrs += 1;
+		self.profile[func].cycles += instr.get_rtn_array()[4] as u64;
+		hart.pc += 1;
+		match instr.get_variant() {
+			RiscvInstrVariant::RTriInstr(v) => {
+				let a = Self::reg(hart, &v.rs1)?;
+				let b = Self::reg(hart, &v.rs2)?;
+				self.set_reg(hart, &v.rd, rtri(v.op, a, b))?;
+			}
+			RiscvInstrVariant::ITriInstr(v) => {
+				let a = Self::reg(hart, &v.rs1)?;
+				let value = itri(v.op, a, self.imm(&v.rs2)?);
+				self.set_reg(hart, &v.rd, value)?;
+			}
+			RiscvInstrVariant::IBinInstr(v) => match v.op {
+				Li | LA | Auipc => {
+					let value = self.imm(&v.rs1)?;
+					self.set_reg(hart, &v.rd, value as u64)?;
+				}
+				LD | LW | LWU | LB | LBU | FLW | FLD => {
+					let (size, signed) = access(v.op);
+					let value = self.memory.load(self.addr(hart, &v.rs1)?, size)?;
+					let value = match (signed, size) {
+						(true, 1) => value as i8 as i64 as u64,
+						(true, _) => sext(value),
+						_ => value,
+					};
+					self.set_reg(hart, &v.rd, value)?;
+				}
+				SB | SH | SW | SD | FSW | FSD => {
+					let (size, _) = access(v.op);
+					let value = Self::reg(hart, &v.rd)?;
+					self.memory.store(self.addr(hart, &v.rs1)?, size, value)?;
+				}
+			},
+			RiscvInstrVariant::RBinInstr(v) => {
+				let a = Self::reg(hart, &v.rs1)?;
+				let value = match v.op {
+					Mv | FMv => a,
+					MvInt2Float => a & 0xffff_ffff,
+					Int2Float => bits(a as i32 as f32),
+					// `rtz`, saturating, and the largest integer for NaN
+					Float2Int => match float(a) {
+						v if v.is_nan() => i32::MAX as u64,
+						v => v as i32 as i64 as u64,
+					},
+					Float2Double => (float(a) as f64).to_bits(),
+					MvDouble2Int => a,
+					Sextw => sext(a),
+					Seqz => (a == 0) as u64,
+					Snez => (a != 0) as u64,
+					Negw => sext(a.wrapping_neg()),
+					Neg => a.wrapping_neg(),
+				};
+				self.set_reg(hart, &v.rd, value)?;
+			}
+			RiscvInstrVariant::LabelInstr(_)
+			| RiscvInstrVariant::PCRelLabelInstr(_) => {}
+			RiscvInstrVariant::BranInstr(v) => {
+				let a = Self::reg(hart, &v.rs1)?;
+				let b = Self::reg(hart, &v.rs2)?;
+				if branch(v.op, a, b) {
+					self.jump(hart, &v.to)?;
+				}
+			}
+			RiscvInstrVariant::NoArgInstr(_) => {
+				let ra = hart.regs[RA as usize];
+				if ra == 0 {
+					return Ok(Event::Exit(hart.regs[A0 as usize]));
+				}
+				let pc = ra.wrapping_sub(TEXT_BASE) / 4;
+				if ra < TEXT_BASE
+					|| ra % 4 != 0
+					|| pc as usize >= self.code.instrs.len()
+				{
+					return Err(RuntimeError(format!(
+						"return to invalid address {ra:#x}"
+					)));
+				}
+				hart.pc = pc as usize;
+			}
+			RiscvInstrVariant::CallInstr(v) => {
+				let name = v.func_label.name.as_str();
+				if let Some(entry) = self.code.entries.get(name) {
+					hart.regs[RA as usize] = TEXT_BASE + hart.pc as u64 * 4;
+					hart.pc = *entry;
+					return Ok(Event::Next);
+				}
+				match name {
+					"__create_threads" => return Ok(Event::Fork),
+					"__join_threads" => return Ok(Event::Join),
+					_ => self.host_call(hart, name)?,
+				}
+			}
+			RiscvInstrVariant::TemporayInstr(v) => {
+				return Err(RuntimeError(format!(
+					"`{v}` is left after register allocation"
+				)))
+			}
+		}
+		Ok(Event::Next)
+	}
+
+	// tell where an error happens
+	fn locate(&self, pc: usize, err: SysycError) -> SysycError {
+		match err {
+			RuntimeError(msg) => RuntimeError(format!(
+				"{msg}, at `{}` in `{}`",
+				self.code.instrs[pc].to_string().trim(),
+				self.code.names[self.code.owner[pc]]
+			)),
+			err => err,
+		}
+	}
+
+	// run until `main` returns, the threads made by `__create_threads` take
+	// turns to run an instruction like they run at the same time, since they
+	// share the stack, running them one after another would not work
+	fn run(&mut self, main: Hart) -> Result<u64> {
+		// the main thread comes first, and waits at `__join_threads` until the
+		// others exit there
+		let mut harts = vec![main];
+		let mut turn = 0;
+		loop {
+			if turn >= harts.len() {
+				turn = 0;
+			}
+			if turn == 0 && harts[0].joining {
+				if harts.len() > 1 {
+					turn = 1;
+				} else {
+					harts[0].joining = false;
+				}
+			}
+			let hart = &mut harts[turn];
+			let pc = hart.pc;
+			match self.step(hart).map_err(|e| self.locate(pc, e))? {
+				Event::Next => turn += 1,
+				Event::Exit(value) if turn == 0 => return Ok(value),
+				Event::Exit(_) => {
+					return Err(RuntimeError(
+						"a thread returns from `main` without joining".to_string(),
+					))
+				}
+				Event::Fork if turn == 0 && harts.len() == 1 => {
+					// the arguments are the number of threads and the room a thread
+					// needs below the frame of the caller, which is between sp and fp.
+					// a thread restores the registers saved before the call and finds
+					// the spilled values in its own copy of the frame
+					let threads = harts[0].regs[A0 as usize] as i64;
+					let gap = harts[0].regs[A1 as usize] as i64;
+					let sp = harts[0].regs[SP as usize] as i64;
+					let fp = harts[0].regs[FP as usize] as i64;
+					let saved = self.memory.read_bytes(sp, (fp - sp) as usize)?.to_vec();
+					let mut children = Vec::new();
+					for id in (1..threads).rev() {
+						let mut child = harts[0].clone();
+						child.regs[A0 as usize] = id as u64;
+						let child_sp = sp - id * (fp - sp + gap);
+						self.memory.write_bytes(child_sp, &saved)?;
+						child.regs[SP as usize] = child_sp as u64;
+						child.regs[FP as usize] = (child_sp + fp - sp) as u64;
+						children.push(child);
+					}
+					harts[0].regs[A0 as usize] = 0;
+					harts.extend(children);
+					turn += 1;
+				}
+				Event::Fork => {
+					return Err(RuntimeError("nested `__create_threads`".to_string()))
+				}
+				Event::Join if turn == 0 => {
+					harts[0].joining = true;
+					turn += 1;
+				}
+				Event::Join => {
+					harts.remove(turn);
+				}
+			}
+		}
+	}
+}
+
+/// Run `main` of a program after register allocation, with `input` as stdin,
+/// and count the instructions executed in each function
+pub fn simulate(
+	program: &RiscvProgram,
+	input: impl Into<Input>,
+) -> Result<(Execution, Vec<FuncProfile>), Crash> {
+	simulate_limited(program, input, None)
+}
+
+/// Like `simulate`, but fail once more than `limit` instructions are run
+pub fn simulate_limited(
+	program: &RiscvProgram,
+	input: impl Into<Input>,
+	limit: Option<u64>,
+) -> Result<(Execution, Vec<FuncProfile>), Crash> {
+	let code = Code::new(program)?;
+	let memory = Memory::with_stack(&program.global_vars, STACK_LIMIT);
+	let mut hart = Hart {
+		regs: [0; 64],
+		pc: *code
+			.entries
+			.get("main")
+			.ok_or_else(|| RuntimeError("no `main` function".to_string()))?,
+		joining: false,
+	};
+	hart.regs[SP as usize] = memory.data_end() as u64;
+	let mut simulator = Simulator {
+		profile: code
+			.names
+			.iter()
+			.map(|v| FuncProfile {
+				name: v.clone(),
+				..Default::default()
+			})
+			.collect(),
+		code: &code,
+		memory,
+		runtime: Runtime::new(input.into(), limit),
+	};
+	let value = match simulator.run(hart) {
+		Ok(value) => value,
+		Err(error) => {
+			return Err(Crash {
+				output: simulator.runtime.output,
+				error,
+			})
+		}
+	};
+	let execution = Execution {
+		output: simulator.runtime.output,
+		exit_code: value as u8,
+	};
+	Ok((execution, simulator.profile))
+}
@@ -0,0 +1,306 @@
+// The SysY runtime library (`sylib`), reading from an `Input` and writing to
+// an in-memory output instead of stdin and stdout.
+
+use std::io::{self, ErrorKind, Read};
+
+use utils::{
+	errors::Result,
+	SysycError::{RuntimeError, StepLimitError},
+};
+
+use crate::{memory::Memory, Val};
+
+/// `printf("%a", v)` of C, which prints a float promoted to double
+pub fn format_hex_float(v: f32) -> String {
+	let v = v as f64;
+	let sign = if v.is_sign_negative() { "-" } else { "" };
+	if v.is_nan() {
+		return format!("{sign}nan");
+	}
+	if v.is_infinite() {
+		return format!("{sign}inf");
+	}
+	if v == 0.0 {
+		return format!("{sign}0x0p+0");
+	}
+	let bits = v.to_bits();
+	// floats are never subnormal as doubles
+	let exp = ((bits >> 52) & 0x7ff) as i64 - 1023;
+	let mantissa = format!("{:013x}", bits & ((1 << 52) - 1));
+	let mantissa = mantissa.trim_end_matches('0');
+	if mantissa.is_empty() {
+		format!("{sign}0x1p{exp:+}")
+	} else {
+		format!("{sign}0x1.{mantissa}p{exp:+}")
+	}
+}
+
+fn parse_hex_float(s: &str) -> Option<f32> {
+	let (sign, s) = match s.strip_prefix('-') {
+		Some(s) => (-1.0, s),
+		None => (1.0, s.strip_prefix('+').unwrap_or(s)),
+	};
+	let s = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X"))?;
+	let (mantissa, exp) = match s.find(['p', 'P']) {
+		Some(pos) => (&s[..pos], s[pos + 1..].parse::<i32>().ok()?),
+		None => (s, 0),
+	};
+	let (int_part, frac_part) =
+		mantissa.split_once('.').unwrap_or((mantissa, ""));
+	let mut value = 0f64;
+	for c in int_part.chars() {
+		value = value * 16.0 + c.to_digit(16)? as f64;
+	}
+	let mut scale = 1.0 / 16.0;
+	for c in frac_part.chars() {
+		value += c.to_digit(16)? as f64 * scale;
+		scale /= 16.0;
+	}
+	Some((sign * value * 2f64.powi(exp)) as f32)
+}
+
+// a float given to `putf` is promoted to double, which is passed in an
+// integer register, the LLVM interpreter passes the float as it is
+fn as_double(v: Val) -> f64 {
+	match v {
+		Val::Int(v) => f64::from_bits(v as u64),
+		Val::Float(v) => v as f64,
+	}
+}
+
+/// What a program reads as stdin, taken from the reader only when the program
+/// asks for it
+pub struct Input {
+	reader: Box<dyn Read>,
+	buffer: Vec<u8>,
+	pos: usize,
+}
+
+impl Input {
+	pub fn new(reader: impl Read + 'static) -> Self {
+		Self {
+			reader: Box::new(reader),
+			buffer: Vec::new(),
+			pos: 0,
+		}
+	}
+	// the next byte, `None` at the end of the input
+	fn peek(&mut self) -> Result<Option<u8>> {
+		let mut chunk = [0; 4096];
+		while self.pos == self.buffer.len() {
+			match self.reader.read(&mut chunk) {
+				Ok(0) => return Ok(None),
+				Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+				Err(e) if e.kind() == ErrorKind::Interrupted => {}
+				Err(e) => return Err(RuntimeError(format!("reading input: {e}"))),
+			}
+		}
+		Ok(Some(self.buffer[self.pos]))
+	}
+}
+
+impl Default for Input {
+	fn default() -> Self {
+		Self::new(io::empty())
+	}
+}
+
+impl From<Vec<u8>> for Input {
+	fn from(value: Vec<u8>) -> Self {
+		Self {
+			reader: Box::new(io::empty()),
+			buffer: value,
+			pos: 0,
+		}
+	}
+}
+
+#[derive(Default)]
+pub struct Runtime {
+	input: Input,
+	pub output: Vec<u8>,
+	/// steps the program may still take, `None` for no limit
+	fuel: Option<u64>,
+	limit: u64,
+}
+
+impl Runtime {
+	pub fn new(input: Input, limit: Option<u64>) -> Self {
+		Self {
+			input,
+			fuel: limit,
+			limit: limit.unwrap_or_default(),
+			..Default::default()
+		}
+	}
+	/// The steps left, `None` for no limit
+	pub fn fuel(&self) -> Option<u64> {
+		self.fuel
+	}
+	/// Count one step, so that programs which never end are stopped
+	pub fn burn(&mut self) -> Result<()> {
+		match &mut self.fuel {
+			Some(0) => Err(StepLimitError(self.limit)),
+			Some(v) => {
+				*v -= 1;
+				Ok(())
+			}
+			None => Ok(()),
+		}
+	}
+	// skip the characters that `accept`s, returning them
+	fn skip(&mut self, accept: impl Fn(u8) -> bool) -> Result<&[u8]> {
+		let input = &mut self.input;
+		let start = input.pos;
+		while input.peek()?.is_some_and(&accept) {
+			input.pos += 1;
+		}
+		Ok(&input.buffer[start..input.pos])
+	}
+	// the longest prefix of the input made of `accept` characters
+	fn token(&mut self, accept: impl Fn(u8) -> bool) -> Result<String> {
+		self.skip(|v| v.is_ascii_whitespace())?;
+		Ok(String::from_utf8_lossy(self.skip(accept)?).into_owned())
+	}
+	fn get_int(&mut self) -> Result<i32> {
+		let token = self.token(|v| v.is_ascii_digit() || v == b'-' || v == b'+')?;
+		token
+			.parse::<i64>()
+			.map(|v| v as i32)
+			.map_err(|_| RuntimeError(format!("getint: invalid input `{token}`")))
+	}
+	fn get_float(&mut self) -> Result<f32> {
+		let token =
+			self.token(|v| v.is_ascii_alphanumeric() || b"+-.".contains(&v))?;
+		token
+			.parse::<f32>()
+			.ok()
+			.or_else(|| parse_hex_float(&token))
+			.ok_or_else(|| RuntimeError(format!("getfloat: invalid input `{token}`")))
+	}
+	fn get_ch(&mut self) -> Result<i32> {
+		let v = self.input.peek()?;
+		if v.is_some() {
+			self.input.pos += 1;
+		}
+		Ok(v.map_or(-1, |v| v as i32))
+	}
+	fn print(&mut self, s: impl AsRef<[u8]>) {
+		self.output.extend_from_slice(s.as_ref());
+	}
+	// C `printf` with the conversions the test programs use
+	fn putf(&mut self, args: &[Val], memory: &Memory) -> Result<()> {
+		let mut addr = args[0].as_int();
+		let mut fmt = Vec::new();
+		loop {
+			let byte = memory.read_bytes(addr, 1)?[0];
+			if byte == 0 {
+				break;
+			}
+			fmt.push(byte);
+			addr += 1;
+		}
+		let mut args = args[1..].iter();
+		let mut next = || {
+			args.next().copied().ok_or_else(|| {
+				RuntimeError("putf: too few arguments for the format".to_string())
+			})
+		};
+		let mut iter = fmt.into_iter();
+		while let Some(c) = iter.next() {
+			if c != b'%' {
+				self.output.push(c);
+				continue;
+			}
+			match iter.next() {
+				Some(b'd') => {
+					let v = next()?.as_int() as i32;
+					self.print(v.to_string())
+				}
+				Some(b'c') => self.output.push(next()?.as_int() as u8),
+				Some(b'f') => {
+					let v = as_double(next()?);
+					self.print(format!("{v:.6}"))
+				}
+				Some(b'a') => {
+					let v = as_double(next()?) as f32;
+					self.print(format_hex_float(v))
+				}
+				Some(b'x') => {
+					let v = next()?.as_int() as u32;
+					self.print(format!("{v:x}"))
+				}
+				Some(b'%') => self.output.push(b'%'),
+				v => {
+					return Err(RuntimeError(format!(
+						"putf: unsupported conversion `%{}`",
+						v.map_or(String::new(), |v| (v as char).to_string())
+					)))
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Call the runtime function `name`, `None` if there is no such function
+	pub fn call(
+		&mut self,
+		name: &str,
+		args: &[Val],
+		memory: &mut Memory,
+	) -> Option<Result<Val>> {
+		let arg = |i: usize| args[i].as_int();
+		let result = match name {
+			"getint" => self.get_int().map(Val::from),
+			"getch" => self.get_ch().map(Val::from),
+			"getfloat" => self.get_float().map(Val::Float),
+			"getarray" => (|| {
+				let n = self.get_int()?;
+				for i in 0..n as i64 {
+					let v = self.get_int()?;
+					memory.store_u32(arg(0) + i * 4, v as u32)?;
+				}
+				Ok(Val::from(n))
+			})(),
+			"getfarray" => (|| {
+				let n = self.get_int()?;
+				for i in 0..n as i64 {
+					let v = self.get_float()?;
+					memory.store_u32(arg(0) + i * 4, v.to_bits())?;
+				}
+				Ok(Val::from(n))
+			})(),
+			"putint" => {
+				self.print((arg(0) as i32).to_string());
+				Ok(Val::Int(0))
+			}
+			"putch" => {
+				self.output.push(arg(0) as u8);
+				Ok(Val::Int(0))
+			}
+			"putfloat" => {
+				self.print(format_hex_float(args[0].as_float()));
+				Ok(Val::Int(0))
+			}
+			"putarray" | "putfarray" => (|| {
+				let n = arg(0) as i32;
+				self.print(format!("{n}:"));
+				for i in 0..n as i64 {
+					let v = memory.load_u32(arg(1) + i * 4)?;
+					if name == "putarray" {
+						self.print(format!(" {}", v as i32));
+					} else {
+						self.print(format!(" {}", format_hex_float(f32::from_bits(v))));
+					}
+				}
+				self.print("\n");
+				Ok(Val::Int(0))
+			})(),
+			"putf" => self.putf(args, memory).map(|_| Val::Int(0)),
+			"before_main" | "after_main" | "starttime" | "stoptime"
+			| "_sysy_starttime" | "_sysy_stoptime" => Ok(Val::Int(0)),
+			_ => return None,
+		};
+		Some(result)
+	}
+}
@@ -163,6 +163,16 @@ impl ArithInstr {
 			var_type,
 		})
 	}
+	/// `target = value + 0`, which gives `target` a value known at compile
+	/// time as an instruction GVN can fold
+	pub fn constant(target: LlvmTemp, value: impl Into<Value>) -> LlvmInstr {
+		let var_type = target.var_type;
+		let op = match var_type {
+			VarType::F32 => ArithOp::Fadd,
+			_ => ArithOp::Add,
+		};
+		Self::new(target, value, op, var_type.default_value(), var_type)
+	}
 }
 
 impl Display for CompInstr {
@@ -1,15 +1,19 @@
 mod impls;
+mod llvmextern;
 mod llvminstr;
 mod llvmop;
 mod llvmvar;
+mod parser;
 mod temp;
 mod utils;
 
+pub use llvmextern::LlvmExtern;
 pub use llvminstr::*;
 pub use llvmop::{Value, *};
 pub use llvmvar::*;
+pub use parser::{parse_llvm, ParsedBlock, ParsedFunc, ParsedProgram};
 pub use temp::{LlvmTemp, LlvmTempManager};
-pub use utils::compute_two_value;
+pub use utils::{compare_two_value, compute_two_value, convert_value};
 
 pub enum LlvmInstrVariant<'a> {
 	ArithInstr(&'a ArithInstr),
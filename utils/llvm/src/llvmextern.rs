@@ -0,0 +1,43 @@
+use std::fmt::Display;
+
+use crate::llvmvar::VarType;
+
+/// A symbol declared `extern`, which another module defines
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LlvmExtern {
+	Func {
+		name: String,
+		ret_type: VarType,
+		params: Vec<VarType>,
+	},
+	/// `var_type` is the type of the temp naming the variable, `i32*` for an
+	/// array of `int` or `i32` for an `int`
+	Var { name: String, var_type: VarType },
+}
+
+impl LlvmExtern {
+	pub fn name(&self) -> &str {
+		match self {
+			Self::Func { name, .. } | Self::Var { name, .. } => name,
+		}
+	}
+}
+
+impl Display for LlvmExtern {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::Func {
+				name,
+				ret_type,
+				params,
+			} => {
+				let params =
+					params.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ");
+				write!(f, "declare {} @{}({})", ret_type, name, params)
+			}
+			Self::Var { name, var_type } => {
+				write!(f, "declare global {} @{}", var_type, name)
+			}
+		}
+	}
+}
@@ -8,6 +8,8 @@ pub enum VarType {
 	F32,
 	I32Ptr,
 	F32Ptr,
+	/// pointer to `char`, which is loaded as a sign-extended `i32`
+	I8Ptr,
 	Void,
 }
 
@@ -24,6 +26,7 @@ impl Display for VarType {
 			Self::I32Ptr => "i32*",
 			Self::F32 => "f32",
 			Self::F32Ptr => "f32*",
+			Self::I8Ptr => "i8*",
 			Self::Void => "void",
 		};
 		write!(f, "{}", type_str)
@@ -59,7 +62,7 @@ impl VarType {
 	}
 	pub fn deref_type(&self) -> VarType {
 		match self {
-			Self::I32Ptr => Self::I32,
+			Self::I32Ptr | Self::I8Ptr => Self::I32,
 			Self::F32Ptr => Self::F32,
 			_ => unreachable!(),
 		}
@@ -81,6 +84,7 @@ impl VarType {
 	pub fn get_size(&self) -> usize {
 		match self {
 			Self::I32 | Self::I32Ptr | Self::F32 | Self::F32Ptr => 4,
+			Self::I8Ptr => 1,
 			Self::Void => unreachable!(),
 		}
 	}
@@ -88,7 +92,7 @@ impl VarType {
 		matches!(self, Self::Void)
 	}
 	pub fn is_ptr(&self) -> bool {
-		matches!(self, Self::F32Ptr | Self::I32Ptr)
+		matches!(self, Self::F32Ptr | Self::I32Ptr | Self::I8Ptr)
 	}
 	pub fn is_float(&self) -> bool {
 		matches!(self, Self::F32)
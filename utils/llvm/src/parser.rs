@@ -0,0 +1,489 @@
+#![allow(clippy::empty_docs)]
+
+use std::collections::HashMap;
+
+use pest::{iterators::Pair, Parser};
+use pest_derive::Parser;
+use utils::{
+	errors::Result,
+	GlobalVar, Label,
+	SysycError::{self, LlvmSyntexError},
+	ValueItem,
+};
+
+use crate::{
+	llvminstr::*, llvmop::*, LlvmExtern, LlvmTemp, LlvmTempManager, VarType,
+};
+
+#[derive(Parser)]
+#[grammar = "llvmir.pest"]
+struct LlvmParser;
+
+pub struct ParsedBlock {
+	pub label: Label,
+	pub phi_instrs: Vec<PhiInstr>,
+	pub instrs: Vec<LlvmInstr>,
+	pub jump_instr: Option<LlvmInstr>,
+}
+
+pub struct ParsedFunc {
+	pub name: String,
+	pub ret_type: VarType,
+	pub params: Vec<Value>,
+	pub blocks: Vec<ParsedBlock>,
+}
+
+/// Result of parsing the text printed by `--llvm`, the CFG is not built yet
+pub struct ParsedProgram {
+	pub global_vars: Vec<GlobalVar>,
+	pub externs: Vec<LlvmExtern>,
+	pub funcs: Vec<ParsedFunc>,
+	pub temp_mgr: LlvmTempManager,
+}
+
+fn syntax_error(pair: &Pair<Rule>, msg: impl std::fmt::Display) -> SysycError {
+	let (line, col) = pair.line_col();
+	LlvmSyntexError(format!("{msg} at line {line}, column {col}"))
+}
+
+fn parse_type(pair: Pair<Rule>) -> VarType {
+	match pair.as_rule() {
+		Rule::i32_t => VarType::I32,
+		Rule::f32_t => VarType::F32,
+		Rule::i32_ptr => VarType::I32Ptr,
+		Rule::i8_ptr => VarType::I8Ptr,
+		Rule::f32_ptr => VarType::F32Ptr,
+		Rule::void_t => VarType::Void,
+		_ => unreachable!(),
+	}
+}
+
+fn parse_arith_op(pair: &Pair<Rule>) -> ArithOp {
+	use ArithOp::*;
+	match pair.as_str() {
+		"add" => Add,
+		"addd" => AddD,
+		"mul" => Mul,
+		"muld" => MulD,
+		"sub" => Sub,
+		"subd" => SubD,
+		"div" => Div,
+		"divd" => DivD,
+		"rem" => Rem,
+		"remd" => RemD,
+		"shl" => Shl,
+		"shld" => ShlD,
+		"lshr" => Lshr,
+		"lshrd" => LshrD,
+		"ashr" => Ashr,
+		"ashrd" => AshrD,
+		"and" => And,
+		"or" => Or,
+		"xor" => Xor,
+		"clz" => Clz,
+		"clzd" => ClzD,
+		"ctz" => Ctz,
+		"ctzd" => CtzD,
+		"min" => Min,
+		"mind" => MinD,
+		"max" => Max,
+		"maxd" => MaxD,
+		"fadd" => Fadd,
+		"fsub" => Fsub,
+		"fdiv" => Fdiv,
+		"fmul" => Fmul,
+		"fmin" => Fmin,
+		"fmax" => Fmax,
+		_ => unreachable!(),
+	}
+}
+
+fn parse_comp_op(pair: &Pair<Rule>) -> CompOp {
+	use CompOp::*;
+	match pair.as_str() {
+		"eq" => EQ,
+		"ne" => NE,
+		"sgt" => SGT,
+		"sge" => SGE,
+		"slt" => SLT,
+		"sle" => SLE,
+		"oeq" => OEQ,
+		"one" => ONE,
+		"ogt" => OGT,
+		"oge" => OGE,
+		"olt" => OLT,
+		"ole" => OLE,
+		_ => unreachable!(),
+	}
+}
+
+fn is_float_comp(op: CompOp) -> bool {
+	use CompOp::*;
+	matches!(op, OEQ | ONE | OGT | OGE | OLT | OLE)
+}
+
+/// Types of local temps defined in the function being parsed. The type
+/// printed next to an operand is the type of the instruction, which is not
+/// always the type of the temp (e.g. `icmp` on floats), so the type at the
+/// definition is preferred.
+struct TempTypes {
+	defs: HashMap<String, VarType>,
+}
+
+impl TempTypes {
+	fn parse_value(&self, pair: Pair<Rule>, hint: VarType) -> Result<Value> {
+		let str = pair.as_str();
+		match pair.as_rule() {
+			Rule::Integer if hint == VarType::F32 => {
+				str.parse::<f32>().map(Value::Float).map_err(|e| syntax_error(&pair, e))
+			}
+			Rule::Integer => {
+				str.parse::<i32>().map(Value::Int).map_err(|e| syntax_error(&pair, e))
+			}
+			Rule::Float => {
+				str.parse::<f32>().map(Value::Float).map_err(|e| syntax_error(&pair, e))
+			}
+			Rule::Temp => {
+				let (name, is_global) = (&str[1..], str.starts_with('@'));
+				let var_type = match is_global {
+					true => hint,
+					false => self.defs.get(name).copied().unwrap_or(hint),
+				};
+				Ok(LlvmTemp::new(name, var_type, is_global).into())
+			}
+			_ => unreachable!(),
+		}
+	}
+	fn parse_target(&self, pair: Pair<Rule>) -> Result<LlvmTemp> {
+		if pair.as_str().starts_with('@') {
+			return Err(syntax_error(&pair, "assign to global variable"));
+		}
+		let name = &pair.as_str()[1..];
+		Ok(LlvmTemp::new(name, self.defs[name], false))
+	}
+	fn parse_typed_value(&self, pair: Pair<Rule>) -> Result<(VarType, Value)> {
+		let mut pairs = pair.into_inner();
+		let var_type = parse_type(pairs.next().unwrap());
+		Ok((var_type, self.parse_value(pairs.next().unwrap(), var_type)?))
+	}
+}
+
+fn block_ref(pair: Pair<Rule>) -> Label {
+	Label::new(pair.into_inner().next().unwrap().as_str())
+}
+
+// record the type of the temp defined by `pair`, with the same rules as the
+// constructors of the instructions
+fn collect_def(pair: Pair<Rule>, defs: &mut HashMap<String, VarType>) {
+	let rule = pair.as_rule();
+	let mut pairs = pair.into_inner();
+	let Some(target) = pairs.next() else {
+		return;
+	};
+	if target.as_rule() != Rule::Temp {
+		return;
+	}
+	let var_type = match rule {
+		Rule::CompInstr => VarType::I32,
+		Rule::ArithInstr => parse_type(pairs.nth(1).unwrap()),
+		Rule::ConvertInstr
+		| Rule::PhiInstr
+		| Rule::AllocInstr
+		| Rule::LoadInstr
+		| Rule::GEPInstr
+		| Rule::CallInstr => parse_type(pairs.next().unwrap()),
+		_ => return,
+	};
+	defs.insert(target.as_str()[1..].to_string(), var_type);
+}
+
+fn parse_instr(pair: Pair<Rule>, types: &TempTypes) -> Result<LlvmInstr> {
+	let rule = pair.as_rule();
+	let mut pairs = pair.into_inner();
+	let mut next = || pairs.next().unwrap();
+	let instr: LlvmInstr = match rule {
+		Rule::ArithInstr => {
+			let target = types.parse_target(next())?;
+			let op = parse_arith_op(&next());
+			let var_type = parse_type(next());
+			Box::new(ArithInstr {
+				target,
+				op,
+				var_type,
+				lhs: types.parse_value(next(), var_type)?,
+				rhs: types.parse_value(next(), var_type)?,
+			})
+		}
+		Rule::CompInstr => {
+			let target = types.parse_target(next())?;
+			let kind = match next().as_str() {
+				"icmp" => CompKind::Icmp,
+				_ => CompKind::Fcmp,
+			};
+			let op = parse_comp_op(&next());
+			let var_type = parse_type(next());
+			let hint = match is_float_comp(op) {
+				true => VarType::F32,
+				false => VarType::I32,
+			};
+			Box::new(CompInstr {
+				kind,
+				target,
+				op,
+				var_type,
+				lhs: types.parse_value(next(), hint)?,
+				rhs: types.parse_value(next(), hint)?,
+			})
+		}
+		Rule::ConvertInstr => {
+			let target = types.parse_target(next())?;
+			let var_type = parse_type(next());
+			let op = match next().as_str() {
+				"sitofp" => ConvertOp::Int2Float,
+				_ => ConvertOp::Float2Int,
+			};
+			Box::new(ConvertInstr {
+				var_type,
+				target,
+				op,
+				lhs: types.parse_value(next(), op.type_from())?,
+			})
+		}
+		Rule::JumpInstr => JumpInstr::new(block_ref(next())),
+		Rule::JumpCondInstr => {
+			let var_type = parse_type(next());
+			Box::new(JumpCondInstr {
+				var_type,
+				cond: types.parse_value(next(), var_type)?,
+				target_true: block_ref(next()),
+				target_false: block_ref(next()),
+			})
+		}
+		Rule::RetInstr => {
+			let var_type = parse_type(next());
+			let value = match var_type {
+				VarType::Void => None,
+				_ => Some(types.parse_value(next(), var_type)?),
+			};
+			Box::new(RetInstr { value })
+		}
+		Rule::AllocInstr => {
+			let target = types.parse_target(next())?;
+			let var_type = parse_type(next());
+			let length_type = parse_type(next());
+			Box::new(AllocInstr {
+				target,
+				var_type,
+				length: types.parse_value(next(), length_type)?,
+			})
+		}
+		Rule::StoreInstr => {
+			let value_type = parse_type(next());
+			let value = types.parse_value(next(), value_type)?;
+			let addr_type = parse_type(next());
+			Box::new(StoreInstr {
+				value,
+				addr: types.parse_value(next(), addr_type)?,
+			})
+		}
+		Rule::LoadInstr => {
+			let target = types.parse_target(next())?;
+			let var_type = parse_type(next());
+			let addr_type = parse_type(next());
+			Box::new(LoadInstr {
+				target,
+				var_type,
+				addr: types.parse_value(next(), addr_type)?,
+			})
+		}
+		Rule::GEPInstr => {
+			let target = types.parse_target(next())?;
+			let var_type = parse_type(next());
+			let addr = types.parse_value(next(), var_type)?;
+			let offset_type = parse_type(next());
+			Box::new(GEPInstr {
+				target,
+				var_type,
+				addr,
+				offset: types.parse_value(next(), offset_type)?,
+			})
+		}
+		Rule::CallInstr => {
+			let target = types.parse_target(next())?;
+			let var_type = parse_type(next());
+			let func = block_ref(next());
+			let params =
+				pairs.map(|v| types.parse_typed_value(v)).collect::<Result<_>>()?;
+			Box::new(CallInstr {
+				target,
+				var_type,
+				func,
+				params,
+			})
+		}
+		_ => unreachable!(),
+	};
+	Ok(instr)
+}
+
+fn parse_phi(pair: Pair<Rule>, types: &TempTypes) -> Result<PhiInstr> {
+	let mut pairs = pair.into_inner();
+	let target = types.parse_target(pairs.next().unwrap())?;
+	let var_type = parse_type(pairs.next().unwrap());
+	let source = pairs
+		.map(|item| {
+			let mut pairs = item.into_inner();
+			let value = types.parse_value(pairs.next().unwrap(), var_type)?;
+			Ok((value, block_ref(pairs.next().unwrap())))
+		})
+		.collect::<Result<_>>()?;
+	Ok(PhiInstr {
+		target,
+		var_type,
+		source,
+	})
+}
+
+fn parse_block(pair: Pair<Rule>, types: &TempTypes) -> Result<ParsedBlock> {
+	let mut pairs = pair.into_inner();
+	let mut block = ParsedBlock {
+		label: Label::new(pairs.next().unwrap().as_str()),
+		phi_instrs: Vec::new(),
+		instrs: Vec::new(),
+		jump_instr: None,
+	};
+	for instr in pairs {
+		let instr = instr.into_inner().next().unwrap();
+		if block.jump_instr.is_some() {
+			return Err(syntax_error(&instr, "instruction after terminator"));
+		}
+		match instr.as_rule() {
+			Rule::PhiInstr if block.instrs.is_empty() => {
+				block.phi_instrs.push(parse_phi(instr, types)?)
+			}
+			Rule::PhiInstr => {
+				return Err(syntax_error(&instr, "phi after other instructions"))
+			}
+			Rule::JumpInstr | Rule::JumpCondInstr | Rule::RetInstr => {
+				block.jump_instr = Some(parse_instr(instr, types)?)
+			}
+			_ => block.instrs.push(parse_instr(instr, types)?),
+		}
+	}
+	Ok(block)
+}
+
+fn parse_func(
+	pair: Pair<Rule>,
+	temp_mgr: &mut LlvmTempManager,
+) -> Result<ParsedFunc> {
+	let mut pairs = pair.into_inner();
+	let ret_type = parse_type(pairs.next().unwrap());
+	let name = block_ref(pairs.next().unwrap()).name;
+	let mut defs = HashMap::new();
+	let mut params = Vec::new();
+	let mut blocks = Vec::new();
+	for pair in pairs {
+		match pair.as_rule() {
+			Rule::Param => {
+				let mut pairs = pair.into_inner();
+				let var_type = parse_type(pairs.next().unwrap());
+				let temp = pairs.next().unwrap();
+				defs.insert(temp.as_str()[1..].to_string(), var_type);
+				params.push(temp);
+			}
+			Rule::Block => blocks.push(pair),
+			_ => unreachable!(),
+		}
+	}
+	for block in blocks.iter() {
+		for instr in block.clone().into_inner().skip(1) {
+			collect_def(instr.into_inner().next().unwrap(), &mut defs);
+		}
+	}
+	for name in defs.keys() {
+		if let Ok(id) = name.parse::<u32>() {
+			temp_mgr.total = temp_mgr.total.max(id);
+		}
+	}
+	let types = TempTypes { defs };
+	Ok(ParsedFunc {
+		name,
+		ret_type,
+		params: params
+			.into_iter()
+			.map(|v| types.parse_value(v, VarType::Void))
+			.collect::<Result<_>>()?,
+		blocks: blocks
+			.into_iter()
+			.map(|v| parse_block(v, &types))
+			.collect::<Result<_>>()?,
+	})
+}
+
+fn parse_global(pair: Pair<Rule>) -> Result<GlobalVar> {
+	let mut pairs = pair.into_inner();
+	let ident = pairs.next().unwrap().as_str();
+	let mut data = Vec::new();
+	let mut flags = Vec::new();
+	for pair in pairs {
+		let rule = pair.as_rule();
+		let value = pair.clone().into_inner().next().map(|v| v.as_str());
+		match (rule, value) {
+			(Rule::Byte, Some(v)) => data.push(ValueItem::Byte(
+				v.parse().map_err(|e| syntax_error(&pair, e))?,
+			)),
+			(Rule::Word, Some(v)) => data.push(ValueItem::Word(
+				v.parse().map_err(|e| syntax_error(&pair, e))?,
+			)),
+			(Rule::Zero, Some(v)) => data.push(ValueItem::Zero(
+				v.parse().map_err(|e| syntax_error(&pair, e))?,
+			)),
+			(Rule::Bool, _) => flags.push(pair.as_str() == "true"),
+			_ => unreachable!(),
+		}
+	}
+	Ok(GlobalVar::new(ident, data, flags[0], flags[1]))
+}
+
+fn parse_extern(pair: Pair<Rule>) -> LlvmExtern {
+	let rule = pair.as_rule();
+	let mut pairs = pair.into_inner();
+	if rule == Rule::ExternVar {
+		let var_type = parse_type(pairs.next().unwrap());
+		let name = pairs.next().unwrap().as_str()[1..].to_string();
+		return LlvmExtern::Var { name, var_type };
+	}
+	LlvmExtern::Func {
+		ret_type: parse_type(pairs.next().unwrap()),
+		name: block_ref(pairs.next().unwrap()).name,
+		params: pairs.map(parse_type).collect(),
+	}
+}
+
+pub fn parse_llvm(str: &str) -> Result<ParsedProgram> {
+	let program = LlvmParser::parse(Rule::Program, str)
+		.map_err(|e| LlvmSyntexError(e.to_string()))?
+		.next()
+		.unwrap();
+	let mut parsed = ParsedProgram {
+		global_vars: Vec::new(),
+		externs: Vec::new(),
+		funcs: Vec::new(),
+		temp_mgr: LlvmTempManager::new(),
+	};
+	for pair in program.into_inner() {
+		match pair.as_rule() {
+			Rule::GlobalDecl => parsed.global_vars.push(parse_global(pair)?),
+			Rule::ExternFunc | Rule::ExternVar => {
+				parsed.externs.push(parse_extern(pair))
+			}
+			Rule::FuncDecl => {
+				parsed.funcs.push(parse_func(pair, &mut parsed.temp_mgr)?)
+			}
+			Rule::EOI => (),
+			_ => unreachable!(),
+		}
+	}
+	Ok(parsed)
+}
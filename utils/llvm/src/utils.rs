@@ -2,7 +2,7 @@ use crate::{
 	llvmop::Value,
 	ArithInstr,
 	ArithOp::{self},
-	GEPInstr, LlvmInstr, LlvmTemp, LlvmTempManager, VarType,
+	CompOp, ConvertOp, GEPInstr, LlvmInstr, LlvmTemp, LlvmTempManager, VarType,
 };
 
 pub fn unwrap_values(arr: Vec<&Value>) -> Vec<LlvmTemp> {
@@ -19,17 +19,21 @@ pub fn compute_two_value(
 	// 只考虑 int
 	match (v1.clone(), v2.clone()) {
 		(Value::Int(i1), Value::Int(i2)) => {
+			// 与 RV64 的 `*w` 指令一样只保留低 32 位
 			let i = match op {
-				Add => i1 + i2,
-				AddD => i1 + i2,
-				Mul => i1 * i2,
-				MulD => i1 * i2,
-				Sub => i1 - i2,
-				SubD => i1 - i2,
-				Div => i1 / i2,
-				DivD => i1 / i2,
-				Rem => i1 % i2,
-				RemD => i1 % i2,
+				Add | AddD => i1.wrapping_add(i2),
+				Mul | MulD => i1.wrapping_mul(i2),
+				Sub | SubD => i1.wrapping_sub(i2),
+				Div | DivD => i1.wrapping_div(i2),
+				Rem | RemD => i1.wrapping_rem(i2),
+				Shl => i1.wrapping_shl(i2 as u32),
+				Lshr => (i1 as u32).wrapping_shr(i2 as u32) as i32,
+				Ashr => i1.wrapping_shr(i2 as u32),
+				And => i1 & i2,
+				Or => i1 | i2,
+				Xor => i1 ^ i2,
+				Min => i1.min(i2),
+				Max => i1.max(i2),
 				_ => unreachable!(),
 			};
 			(Value::Int(i), None)
@@ -40,6 +44,8 @@ pub fn compute_two_value(
 				ArithOp::Fmul => f1 * f2,
 				ArithOp::Fsub => f1 - f2,
 				ArithOp::Fdiv => f1 / f2,
+				ArithOp::Fmin => f1.min(f2),
+				ArithOp::Fmax => f1.max(f2),
 				_ => unreachable!(),
 			};
 			(Value::Float(f), None)
@@ -51,9 +57,7 @@ pub fn compute_two_value(
 				| (1, ArithOp::Mul | ArithOp::MulD) => (v2, None),
 				(0, Mul | MulD) => (Value::Int(0), None),
 				_ => {
-					assert!(
-						t2.var_type != VarType::I32Ptr && t2.var_type != VarType::F32Ptr
-					);
+					assert!(!t2.var_type.is_ptr());
 					let target = temp_mgr.new_temp(t2.var_type, false);
 					let instr: LlvmInstr = Box::new(ArithInstr {
 						target: target.clone(),
@@ -92,9 +96,7 @@ pub fn compute_two_value(
 				(0, ArithOp::Mul) => (Value::Int(0), None),
 				_ => {
 					let target = temp_mgr.new_temp(t1.var_type, false);
-					let instr: LlvmInstr = if t1.var_type == VarType::I32Ptr
-						|| t1.var_type == VarType::F32Ptr
-					{
+					let instr: LlvmInstr = if t1.var_type.is_ptr() {
 						Box::new(GEPInstr {
 							target: target.clone(),
 							var_type: t1.var_type,
@@ -140,8 +142,8 @@ pub fn compute_two_value(
 					|| t1.var_type == VarType::F32
 					|| t2.var_type == VarType::F32
 			);
-			assert!(t2.var_type != VarType::I32Ptr && t2.var_type != VarType::F32Ptr);
-			if t1.var_type == VarType::I32Ptr || t1.var_type == VarType::F32Ptr {
+			assert!(!t2.var_type.is_ptr());
+			if t1.var_type.is_ptr() {
 				let target = temp_mgr.new_temp(t1.var_type, false);
 				let instr = Box::new(GEPInstr {
 					target: target.clone(),
@@ -167,3 +169,36 @@ pub fn compute_two_value(
 		}
 	}
 }
+
+/// 两个常量比较的结果，不是常量时返回 `None`
+pub fn compare_two_value(v1: &Value, v2: &Value, op: CompOp) -> Option<Value> {
+	use std::cmp::Ordering::*;
+	use CompOp::*;
+	let order = match (v1, v2) {
+		(Value::Int(i1), Value::Int(i2)) => Some(i1.cmp(i2)),
+		(Value::Float(f1), Value::Float(f2)) => f1.partial_cmp(f2),
+		_ => return None,
+	};
+	let result = match op {
+		EQ | OEQ => order == Some(Equal),
+		NE | ONE => order != Some(Equal),
+		SGT | OGT => order == Some(Greater),
+		SGE | OGE => matches!(order, Some(Greater | Equal)),
+		SLT | OLT => order == Some(Less),
+		SLE | OLE => matches!(order, Some(Less | Equal)),
+	};
+	Some(Value::Int(result as i32))
+}
+
+/// 常量类型转换的结果，不是常量时返回 `None`
+pub fn convert_value(value: &Value, op: ConvertOp) -> Option<Value> {
+	match (value, op) {
+		(Value::Int(i), ConvertOp::Int2Float) => Some(Value::Float(*i as f32)),
+		// `fcvt.w.s` 会饱和，NaN 得到最大的整数
+		(Value::Float(f), ConvertOp::Float2Int) if f.is_nan() => {
+			Some(Value::Int(i32::MAX))
+		}
+		(Value::Float(f), ConvertOp::Float2Int) => Some(Value::Int(*f as i32)),
+		_ => None,
+	}
+}
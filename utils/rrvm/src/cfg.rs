@@ -1,4 +1,7 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+	collections::{HashMap, HashSet},
+	rc::Rc,
+};
 
 use llvm::JumpInstr;
 use utils::{InstrTrait, Label, TempTrait};
@@ -126,8 +129,9 @@ pub fn unlink_node<T: InstrTrait<U>, U: TempTrait>(
 	from: &Node<T, U>,
 	to: &Node<T, U>,
 ) {
-	from.borrow_mut().succ.retain(|v| v != to);
-	to.borrow_mut().prev.retain(|v| v != from);
+	// comparing the blocks borrows them, and `from` may be `to`
+	from.borrow_mut().succ.retain(|v| !Rc::ptr_eq(v, to));
+	to.borrow_mut().prev.retain(|v| !Rc::ptr_eq(v, from));
 	from.borrow_mut().jump_instr = None;
 }
 
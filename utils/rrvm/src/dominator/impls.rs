@@ -43,4 +43,15 @@ impl<T: InstrTrait<U>, U: TempTrait> DomTree<T, U> {
 	pub fn get_dominator(&mut self, id: i32) -> Option<Node<T, U>> {
 		self.dominator.get(&id).cloned()
 	}
+	/// Whether block `x` dominates block `y`, or post-dominates it if the tree
+	/// is built in reverse. A block dominates itself
+	pub fn dominates(&self, x: i32, mut y: i32) -> bool {
+		while y != x {
+			match self.dominator.get(&y) {
+				Some(v) => y = v.borrow().id,
+				None => return false,
+			}
+		}
+		true
+	}
 }
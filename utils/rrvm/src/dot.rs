@@ -0,0 +1,238 @@
+// Graphviz views of a program, one cluster per function, for reading the
+// result of a pass with `dot -Tsvg`
+
+use std::collections::HashMap;
+
+use instruction::riscv::prelude::*;
+use llvm::{LlvmInstrVariant, LlvmTemp};
+use utils::{InstrTrait, Label, TempTrait};
+
+use crate::{
+	basicblock::BasicBlock,
+	dominator::DomTree,
+	func::RrvmFunc,
+	prelude::{LlvmProgram, RiscvProgram},
+	rrvm_loop::LoopPtr,
+};
+
+// the condition under which a block goes on to the one labelled so
+type EdgeLabel<T, U> = fn(&BasicBlock<T, U>, &Label) -> Option<String>;
+
+fn escape(text: &str) -> String {
+	text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn node_id<T: InstrTrait<U>, U: TempTrait>(
+	func: &RrvmFunc<T, U>,
+	block: &BasicBlock<T, U>,
+) -> String {
+	format!("\"{}.{}\"", escape(&func.name), block.label())
+}
+
+fn graph<T: InstrTrait<U>, U: TempTrait>(
+	funcs: &[RrvmFunc<T, U>],
+	body: impl Fn(&RrvmFunc<T, U>, &mut String),
+) -> String {
+	let mut out =
+		String::from("digraph {\n\tnode [shape=box fontname=monospace];\n");
+	for func in funcs.iter() {
+		let name = escape(&func.name);
+		out +=
+			&format!("\tsubgraph \"cluster_{name}\" {{\n\t\tlabel=\"{name}\";\n");
+		body(func, &mut out);
+		out += "\t}\n";
+	}
+	out += "}\n";
+	out
+}
+
+// the block with its weight and instructions, left aligned
+fn block_node<T: InstrTrait<U>, U: TempTrait>(
+	func: &RrvmFunc<T, U>,
+	block: &BasicBlock<T, U>,
+) -> String {
+	let mut text = format!("{} (weight {})\\l", block.label(), block.weight);
+	let instrs = block.phi_instrs.iter().map(|v| v.to_string());
+	let instrs = instrs
+		.chain(block.instrs.iter().map(|v| v.to_string()))
+		.chain(block.jump_instr.iter().map(|v| v.to_string()));
+	for instr in instrs {
+		text += &format!("{}\\l", escape(instr.trim()));
+	}
+	format!("{} [label=\"{text}\"]", node_id(func, block))
+}
+
+fn cfg<T: InstrTrait<U>, U: TempTrait>(
+	funcs: &[RrvmFunc<T, U>],
+	edge_label: EdgeLabel<T, U>,
+) -> String {
+	graph(funcs, |func, out| {
+		for block in func.cfg.blocks.iter() {
+			let block = &block.borrow();
+			*out += &format!("\t\t{};\n", block_node(func, block));
+			for succ in block.succ.iter() {
+				let succ = &succ.borrow();
+				let attrs = edge_label(block, &succ.label())
+					.map(|v| format!(" [label=\"{}\"]", escape(&v)))
+					.unwrap_or_default();
+				let (from, to) = (node_id(func, block), node_id(func, succ));
+				*out += &format!("\t\t{from} -> {to}{attrs};\n");
+			}
+		}
+	})
+}
+
+fn domtree<T: InstrTrait<U>, U: TempTrait>(funcs: &[RrvmFunc<T, U>]) -> String {
+	graph(funcs, |func, out| {
+		let tree = DomTree::new(&func.cfg, false);
+		for block in func.cfg.blocks.iter() {
+			let block = &block.borrow();
+			*out += &format!(
+				"\t\t{} [label=\"{}\"];\n",
+				node_id(func, block),
+				block.label()
+			);
+			for child in tree.dom_direct.get(&block.id).into_iter().flatten() {
+				let (from, to) = (node_id(func, block), node_id(func, &child.borrow()));
+				*out += &format!("\t\t{from} -> {to};\n");
+			}
+		}
+	})
+}
+
+// a cluster for each loop, holding the blocks in no deeper loop
+fn loop_cluster<T: InstrTrait<U>, U: TempTrait>(
+	func: &RrvmFunc<T, U>,
+	node: &LoopPtr<T, U>,
+	blocks: &HashMap<i32, Vec<i32>>,
+	out: &mut String,
+) {
+	let node = node.borrow();
+	let indent = "\t".repeat(node.level as usize + 1);
+	// the root loop stands for the whole function, which has its cluster
+	if node.level > 0 {
+		*out += &format!(
+			"{indent}subgraph \"cluster_{}.loop{}\" {{\n",
+			escape(&func.name),
+			node.id
+		);
+		*out += &format!(
+			"{indent}\tlabel=\"loop at {}, level {}\";\n",
+			node.header.borrow().label(),
+			node.level
+		);
+	}
+	for block in func.cfg.blocks.iter() {
+		let block = &block.borrow();
+		if blocks.get(&node.id).is_some_and(|v| v.contains(&block.id)) {
+			*out += &format!("{indent}\t{};\n", block_node(func, block));
+		}
+	}
+	for sub in node.subloops.iter() {
+		loop_cluster(func, sub, blocks, out);
+	}
+	if node.level > 0 {
+		*out += &format!("{indent}}}\n");
+	}
+}
+
+fn loops<T: InstrTrait<U>, U: TempTrait>(funcs: &[RrvmFunc<T, U>]) -> String {
+	graph(funcs, |func, out| {
+		let (root, loop_map) = func.cfg.loop_analysis();
+		let mut blocks: HashMap<i32, Vec<i32>> = HashMap::new();
+		for (block, node) in loop_map.iter() {
+			blocks.entry(node.borrow().id).or_default().push(*block);
+		}
+		loop_cluster(func, &root, &blocks, out);
+		// edges back to the header of a loop holding the block are bold
+		for block in func.cfg.blocks.iter() {
+			let block = &block.borrow();
+			for succ in block.succ.iter() {
+				let succ = &succ.borrow();
+				let node = loop_map[&succ.id].borrow();
+				let back = node.level > 0
+					&& node.header.borrow().id == succ.id
+					&& node.is_super_loop_of(&loop_map[&block.id]);
+				let attrs = if back { " [style=bold]" } else { "" };
+				let (from, to) = (node_id(func, block), node_id(func, succ));
+				*out += &format!("\t\t{from} -> {to}{attrs};\n");
+			}
+		}
+	})
+}
+
+fn llvm_edge(
+	block: &BasicBlock<llvm::LlvmInstr, LlvmTemp>,
+	target: &Label,
+) -> Option<String> {
+	match block.jump_instr.as_ref()?.get_variant() {
+		LlvmInstrVariant::JumpCondInstr(v) if v.target_true == *target => {
+			Some(v.cond.to_string())
+		}
+		LlvmInstrVariant::JumpCondInstr(v) if v.target_false == *target => {
+			Some(format!("!{}", v.cond))
+		}
+		_ => None,
+	}
+}
+
+// the branch taken is labelled with its comparison, the other edge is what
+// is left when it is not taken
+fn riscv_edge(
+	block: &BasicBlock<RiscvInstr, instruction::temp::Temp>,
+	target: &Label,
+) -> Option<String> {
+	let branch =
+		block.instrs.iter().chain(block.jump_instr.iter()).find_map(|v| match v
+			.get_variant()
+		{
+			RiscvInstrVariant::BranInstr(v)
+				if !(v.op == BranInstrOp::Beq
+					&& v.rs1.is_zero()
+					&& v.rs2.is_zero()) =>
+			{
+				Some(v)
+			}
+			_ => None,
+		})?;
+	match &branch.to {
+		RiscvImm::Label(to) if to == target => {
+			Some(format!("{} {}, {}", branch.op, branch.rs1, branch.rs2))
+		}
+		_ => Some("else".to_string()),
+	}
+}
+
+impl LlvmProgram {
+	/// The control flow graph of each function, edges labelled with the
+	/// condition they are taken on
+	pub fn dot_cfg(&self) -> String {
+		cfg(&self.funcs, llvm_edge)
+	}
+	/// The dominator tree of each function
+	pub fn dot_domtree(&self) -> String {
+		domtree(&self.funcs)
+	}
+	/// The control flow graph of each function with its loops as nested
+	/// clusters, back edges in bold
+	pub fn dot_loops(&self) -> String {
+		loops(&self.funcs)
+	}
+}
+
+impl RiscvProgram {
+	/// The control flow graph of each function, edges labelled with the
+	/// condition they are taken on
+	pub fn dot_cfg(&self) -> String {
+		cfg(&self.funcs, riscv_edge)
+	}
+	/// The dominator tree of each function
+	pub fn dot_domtree(&self) -> String {
+		domtree(&self.funcs)
+	}
+	/// The control flow graph of each function with its loops as nested
+	/// clusters, back edges in bold
+	pub fn dot_loops(&self) -> String {
+		loops(&self.funcs)
+	}
+}
@@ -1,11 +1,14 @@
-use std::{any::Any, fmt::Display};
+use std::{any::Any, collections::HashMap, fmt::Display};
 
-use utils::{InstrTrait, TempTrait};
+use llvm::{parse_llvm, LlvmInstrVariant, ParsedFunc};
+use utils::{
+	errors::Result, InstrTrait, Label, SysycError::LlvmSyntexError, TempTrait,
+};
 
 use crate::{
-	cfg::CFG,
+	cfg::{force_link_node, BasicBlock, CFG},
 	func::RrvmFunc,
-	program::{LlvmProgram, RrvmProgram},
+	program::{LlvmFunc, LlvmProgram, RrvmProgram},
 };
 
 impl<T: InstrTrait<U>, U: TempTrait> Display for CFG<T, U> {
@@ -44,6 +47,9 @@ impl<T: InstrTrait<U>, U: TempTrait, M: Any> Display for RrvmProgram<T, U, M> {
 		for item in &self.global_vars {
 			writeln!(f, "{:?}", &item)?;
 		}
+		for item in &self.externs {
+			writeln!(f, "{}", item)?;
+		}
 
 		write!(f, "{}", funcs)
 	}
@@ -53,6 +59,7 @@ impl<T: InstrTrait<U>, U: TempTrait, M: Any> RrvmProgram<T, U, M> {
 	pub fn new(temp_mgr: M) -> Self {
 		Self {
 			global_vars: Vec::new(),
+			externs: Vec::new(),
 			funcs: Vec::new(),
 			temp_mgr,
 		}
@@ -68,3 +75,89 @@ impl LlvmProgram {
 		}
 	}
 }
+
+fn parse_block_id(label: &Label) -> Result<i32> {
+	match label.name.as_str() {
+		"entry" => Ok(0),
+		name => name
+			.strip_prefix('B')
+			.and_then(|v| v.parse().ok())
+			.ok_or_else(|| LlvmSyntexError(format!("invalid block label `{name}`"))),
+	}
+}
+
+impl LlvmFunc {
+	// weights of blocks are not printed, so every block gets weight 1
+	fn from_parsed(func: ParsedFunc) -> Result<Self> {
+		let mut nodes = HashMap::new();
+		let mut cfg = CFG { blocks: Vec::new() };
+		for block in func.blocks.iter() {
+			let id = parse_block_id(&block.label)?;
+			let node = BasicBlock::new_node(id, 1.0);
+			if nodes.insert(block.label.clone(), node.clone()).is_some() {
+				return Err(LlvmSyntexError(format!(
+					"block `{}` is defined twice in function `{}`",
+					block.label, func.name
+				)));
+			}
+			cfg.blocks.push(node);
+		}
+		let get_node = |label: &Label| {
+			nodes.get(label).cloned().ok_or_else(|| {
+				LlvmSyntexError(format!(
+					"undefined block `{}` in function `{}`",
+					label, func.name
+				))
+			})
+		};
+		for (block, node) in func.blocks.into_iter().zip(cfg.blocks.iter()) {
+			let Some(jump_instr) = block.jump_instr else {
+				return Err(LlvmSyntexError(format!(
+					"block `{}` in function `{}` has no terminator",
+					block.label, func.name
+				)));
+			};
+			let mut targets = match jump_instr.get_variant() {
+				LlvmInstrVariant::JumpInstr(v) => vec![v.target.clone()],
+				LlvmInstrVariant::JumpCondInstr(v) => {
+					vec![v.target_true.clone(), v.target_false.clone()]
+				}
+				_ => Vec::new(),
+			};
+			targets.dedup();
+			for target in targets.iter() {
+				force_link_node(node, &get_node(target)?);
+			}
+			let mut node = node.borrow_mut();
+			node.phi_instrs = block.phi_instrs;
+			node.instrs = block.instrs;
+			node.jump_instr = Some(jump_instr);
+		}
+		Ok(Self {
+			total: cfg.blocks.iter().map(|v| v.borrow().id).max().unwrap_or(0),
+			spills: 0,
+			cfg,
+			name: func.name,
+			ret_type: func.ret_type,
+			params: func.params,
+		})
+	}
+}
+
+impl LlvmProgram {
+	/// Read back the text printed by `--llvm`, rebuilding the CFG of each
+	/// function
+	pub fn parse(code: &str) -> Result<Self> {
+		let parsed = parse_llvm(code)?;
+		Ok(Self {
+			global_vars: parsed.global_vars,
+			externs: parsed.externs,
+			funcs: parsed
+				.funcs
+				.into_iter()
+				.map(LlvmFunc::from_parsed)
+				.collect::<Result<_>>()?,
+			temp_mgr: parsed.temp_mgr,
+		})
+	}
+}
@@ -1,11 +1,14 @@
 pub mod basicblock;
 pub mod cfg;
 pub mod dominator;
+pub mod dot;
 pub mod func;
 pub mod impls;
+pub mod link;
 pub mod prelude;
 pub mod program;
 pub mod rrvm_loop;
+pub mod verifier;
 
 use basicblock::Node;
 use cfg::CFG;
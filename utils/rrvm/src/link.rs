@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use llvm::{LlvmExtern, LlvmTemp, LlvmTempManager, VarType};
+use utils::{
+	errors::{check_errors, Result},
+	SysycError::{self, LinkError},
+	UseTemp,
+};
+
+use crate::program::{LlvmFunc, LlvmProgram};
+
+fn signature(ret_type: VarType, params: &[VarType]) -> String {
+	let params =
+		params.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ");
+	format!("{} ({})", ret_type, params)
+}
+
+fn multiple_definition(name: &str) -> SysycError {
+	LinkError(format!("multiple definition of `{}`", name))
+}
+
+// strings are named by their index in the program they come from, so they
+// are named again by their index in the linked one
+fn rename_strings(func: &LlvmFunc, names: &HashMap<String, String>) {
+	if names.is_empty() {
+		return;
+	}
+	for block in func.cfg.blocks.iter() {
+		for instr in block.borrow_mut().instrs.iter_mut() {
+			let map = instr
+				.get_read()
+				.into_iter()
+				.filter(|v| v.is_global)
+				.filter_map(|v| {
+					let name = names.get(&v.name)?;
+					Some((v.clone(), LlvmTemp::new(name, v.var_type, true)))
+				})
+				.collect();
+			instr.map_all_temp(&map);
+		}
+	}
+}
+
+impl LlvmProgram {
+	/// Merge the programs of several files into one, each symbol declared
+	/// `extern` must be defined by exactly one of them with the same type
+	pub fn link(programs: Vec<LlvmProgram>) -> Result<LlvmProgram> {
+		let mut linked = LlvmProgram::new(LlvmTempManager::new());
+		let mut errors = Vec::new();
+		for program in programs {
+			linked.temp_mgr.total = linked.temp_mgr.total.max(program.temp_mgr.total);
+			let mut names = HashMap::new();
+			for mut var in program.global_vars {
+				if var.ident.starts_with(".str.") {
+					let ident = format!(".str.{}", linked.global_vars.len());
+					names.insert(std::mem::replace(&mut var.ident, ident.clone()), ident);
+				} else if linked.defines(&var.ident) {
+					errors.push(multiple_definition(&var.ident));
+					continue;
+				}
+				linked.global_vars.push(var);
+			}
+			for func in program.funcs {
+				if linked.defines(&func.name) {
+					errors.push(multiple_definition(&func.name));
+					continue;
+				}
+				rename_strings(&func, &names);
+				linked.funcs.push(func);
+			}
+			linked.externs.extend(program.externs);
+		}
+		for symbol in std::mem::take(&mut linked.externs) {
+			if let Err(err) = linked.resolve(&symbol) {
+				errors.push(err);
+			}
+		}
+		check_errors(errors)?;
+		Ok(linked)
+	}
+	fn defines(&self, name: &str) -> bool {
+		self.funcs.iter().any(|v| v.name == name)
+			|| self.global_vars.iter().any(|v| v.ident == name)
+	}
+	// the definition of a symbol declared `extern`
+	fn resolve(&self, symbol: &LlvmExtern) -> Result<()> {
+		let name = symbol.name();
+		let func = self.funcs.iter().find(|v| v.name == name);
+		let var = self.global_vars.iter().find(|v| v.ident == name);
+		match (symbol, func, var) {
+			(
+				LlvmExtern::Func {
+					ret_type, params, ..
+				},
+				Some(func),
+				_,
+			) => {
+				let types: Vec<_> = func.params.iter().map(|v| v.get_type()).collect();
+				if func.ret_type != *ret_type || types != *params {
+					return Err(LinkError(format!(
+						"conflicting types for `{}`, declared as `{}` but defined as `{}`",
+						name,
+						signature(*ret_type, params),
+						signature(func.ret_type, &types)
+					)));
+				}
+				Ok(())
+			}
+			// only a `float` is told apart by the data of a variable
+			(LlvmExtern::Var { var_type, .. }, _, Some(var)) => {
+				if var_type.is_float() != var.is_float {
+					return Err(LinkError(format!(
+						"conflicting types for `{}`, declared as `{}`",
+						name, var_type
+					)));
+				}
+				Ok(())
+			}
+			(_, None, None) => {
+				Err(LinkError(format!("undefined reference to `{}`", name)))
+			}
+			_ => Err(LinkError(format!(
+				"`{}` is declared as a different kind of symbol",
+				name
+			))),
+		}
+	}
+}
@@ -13,6 +13,8 @@ pub type RiscvProgram = RrvmProgram<RiscvInstr, temp::Temp, temp::TempManager>;
 
 pub struct RrvmProgram<T: InstrTrait<U>, U: TempTrait, M: Any> {
 	pub global_vars: Vec<GlobalVar>,
+	/// symbols declared `extern`, only kept by the IR
+	pub externs: Vec<LlvmExtern>,
 	pub funcs: Vec<RrvmFunc<T, U>>,
 	pub temp_mgr: M,
 }
@@ -1,17 +1,23 @@
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use utils::math::{increment, Range};
+use utils::{
+	math::{increment, Range},
+	InstrTrait, TempTrait,
+};
 
-use super::super::{dominator::compute_dominator, LlvmCFG, LlvmNode};
+use super::super::{
+	cfg::{Node, CFG},
+	dominator::compute_dominator,
+};
 
 use super::{Loop, LoopPtr};
 
-impl LlvmCFG {
-	pub fn loop_analysis(&self) -> (LoopPtr, HashMap<i32, LoopPtr>) {
+impl<T: InstrTrait<U>, U: TempTrait> CFG<T, U> {
+	pub fn loop_analysis(&self) -> (LoopPtr<T, U>, HashMap<i32, LoopPtr<T, U>>) {
 		let mut loop_map = HashMap::new();
-		let mut dominates: HashMap<i32, Vec<LlvmNode>> = HashMap::new();
-		let mut dominates_directly: HashMap<i32, Vec<LlvmNode>> = HashMap::new();
-		let mut dominator: HashMap<i32, LlvmNode> = HashMap::new();
+		let mut dominates: HashMap<i32, Vec<Node<T, U>>> = HashMap::new();
+		let mut dominates_directly: HashMap<i32, Vec<Node<T, U>>> = HashMap::new();
+		let mut dominator: HashMap<i32, Node<T, U>> = HashMap::new();
 		compute_dominator(
 			self,
 			false,
@@ -33,21 +39,23 @@ impl LlvmCFG {
 			if let Some(l) = loop_map.get(&bb.borrow().id).cloned() {
 				if l.borrow().outer.is_none() {
 					root_loop.borrow_mut().subloops.push(l.clone());
-					l.borrow_mut()
-						.outer
-						.replace(Rc::<RefCell<Loop>>::downgrade(&root_loop));
+					l.borrow_mut().outer.replace(Rc::downgrade(&root_loop));
 				}
 			} else {
 				loop_map.insert(bb.borrow().id, root_loop.clone());
 			}
 		}
 
-		fn dfs(node: &LoopPtr, depth: i32, mut cnt: i32) -> i32 {
+		fn dfs<T: InstrTrait<U>, U: TempTrait>(
+			node: &LoopPtr<T, U>,
+			depth: i32,
+			mut cnt: i32,
+		) -> i32 {
 			let node = &mut node.borrow_mut();
 			node.id = increment(&mut cnt);
 			node.level = depth;
 			for sub in node.subloops.iter() {
-				cnt = dfs(sub, depth, cnt)
+				cnt = dfs(sub, depth + 1, cnt)
 			}
 			node.loop_range = Range::new(node.id, cnt);
 			cnt
@@ -60,11 +68,11 @@ impl LlvmCFG {
 
 // 这里本来想实现成 LlvmNode 的一个成员函数的，但这样做，参数中就会有一个 &mut self,
 // 而它常常是一个 borrow_mut 的结果，这导致在函数体内无法再对自己 borrow。
-pub fn loop_dfs(
-	cur_bb: LlvmNode,
-	loop_map: &mut HashMap<i32, LoopPtr>,
-	dominates: &HashMap<i32, Vec<LlvmNode>>,
-	dominates_directly: &HashMap<i32, Vec<LlvmNode>>,
+pub fn loop_dfs<T: InstrTrait<U>, U: TempTrait>(
+	cur_bb: Node<T, U>,
+	loop_map: &mut HashMap<i32, LoopPtr<T, U>>,
+	dominates: &HashMap<i32, Vec<Node<T, U>>>,
+	dominates_directly: &HashMap<i32, Vec<Node<T, U>>>,
 ) {
 	// dfs on dom tree
 	// 换成 hashmap 存储后，就不用标记成 None 了
@@ -105,8 +113,7 @@ pub fn loop_dfs(
 					continue;
 				}
 				new_loop.borrow_mut().subloops.push(inner_loop.clone());
-				inner_loop.borrow_mut().outer =
-					Some(Rc::<RefCell<Loop>>::downgrade(&new_loop));
+				inner_loop.borrow_mut().outer = Some(Rc::downgrade(&new_loop));
 				bbs.append(inner_loop.borrow().header.borrow().prev.clone().as_mut());
 			}
 		}
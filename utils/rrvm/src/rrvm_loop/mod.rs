@@ -6,48 +6,49 @@ use std::{
 	rc::{Rc, Weak},
 };
 
-use utils::{math::Range, CALL_INSTR_CNT};
+use instruction::{riscv::RiscvInstr, temp::Temp};
+use llvm::{LlvmInstr, LlvmTemp};
+use utils::{math::Range, InstrTrait, TempTrait, CALL_INSTR_CNT};
 
-use crate::LlvmCFG;
+use crate::cfg::{Node, CFG};
 
-use super::LlvmNode;
-
-pub type LoopPtr = Rc<RefCell<Loop>>;
-pub type LoopWeakPtr = Weak<RefCell<Loop>>;
+pub type LoopPtr<T = LlvmInstr, U = LlvmTemp> = Rc<RefCell<Loop<T, U>>>;
+pub type LoopWeakPtr<T = LlvmInstr, U = LlvmTemp> = Weak<RefCell<Loop<T, U>>>;
+pub type RiscvLoopPtr = LoopPtr<RiscvInstr, Temp>;
 
 pub mod loop_analysis;
 
 // Instances of this class are used to represent loops that are detected in the flow graph.
 #[derive(Clone)]
-pub struct Loop {
+pub struct Loop<T: InstrTrait<U> = LlvmInstr, U: TempTrait = LlvmTemp> {
 	pub id: i32,
 
 	/// To determine whether a loop is a super loop of another loop
 	pub loop_range: Range,
 	// 外层 loop
 	// 防止内存泄漏！
-	pub outer: Option<LoopWeakPtr>,
+	pub outer: Option<LoopWeakPtr<T, U>>,
 	// 循环头，即 loop 的入口
-	pub header: LlvmNode,
+	pub header: Node<T, U>,
 	// 循环的嵌套层数, 一层循环为 1, 二层循环为 2, 被视为一个只执行一次的循环的整个控制流为 0
 	pub level: i32,
 	// 子 loop
-	pub subloops: Vec<LoopPtr>,
+	pub subloops: Vec<LoopPtr<T, U>>,
 	// loop 中的所有 block，不包括子 loop 中的 block
 }
 
-impl Loop {
-	pub fn is_strict_super_loop_of(&self, other: &LoopPtr) -> bool {
+impl<T: InstrTrait<U>, U: TempTrait> Loop<T, U> {
+	pub fn is_strict_super_loop_of(&self, other: &LoopPtr<T, U>) -> bool {
 		let other = other.borrow();
 		self.loop_range.contains(&other.loop_range) && self.id != other.id
 	}
 
-	pub fn is_super_loop_of(&self, other: &LoopPtr) -> bool {
+	pub fn is_super_loop_of(&self, other: &LoopPtr<T, U>) -> bool {
 		let other = other.borrow();
 		self.loop_range.contains(&other.loop_range)
 	}
 
-	fn new(header: LlvmNode) -> Self {
+	fn new(header: Node<T, U>) -> Self {
 		Self {
 			id: 0,
 			loop_range: Range::default(),
@@ -68,8 +69,8 @@ impl Loop {
 	/// @param blocks - The set of blocks in the loop, not containing blocks in subloops.
 	pub fn get_loop_preheader(
 		&self,
-		loop_map: &HashMap<i32, LoopPtr>,
-	) -> Option<LlvmNode> {
+		loop_map: &HashMap<i32, LoopPtr<T, U>>,
+	) -> Option<Node<T, U>> {
 		let pred = self.get_loop_predecessor(loop_map)?;
 		if pred.borrow().succ.len() != 1 {
 			eprintln!("Preheader is not legal to hoist into");
@@ -91,8 +92,8 @@ impl Loop {
 	/// $\sum $
 	pub fn get_loop_predecessor(
 		&self,
-		loop_map: &HashMap<i32, LoopPtr>,
-	) -> Option<LlvmNode> {
+		loop_map: &HashMap<i32, LoopPtr<T, U>>,
+	) -> Option<Node<T, U>> {
 		let header = self.header.borrow();
 		let mut pred = None;
 		for pred_ in header.prev.iter() {
@@ -114,8 +115,8 @@ impl Loop {
 	/// @param blocks - The set of blocks in the loop, not containing blocks in subloops.
 	pub fn get_loop_latches(
 		&self,
-		loop_map: &HashMap<i32, LoopPtr>,
-	) -> Vec<LlvmNode> {
+		loop_map: &HashMap<i32, LoopPtr<T, U>>,
+	) -> Vec<Node<T, U>> {
 		let header = self.header.borrow();
 		let mut latches = Vec::new();
 		for pred in header.prev.iter() {
@@ -130,9 +131,9 @@ impl Loop {
 	/// @param blocks - The set of blocks in the loop, not containing blocks in subloops.
 	pub fn get_single_exit(
 		&self,
-		blocks: &[LlvmNode],
-		loop_map: &HashMap<i32, LoopPtr>,
-	) -> Option<LlvmNode> {
+		blocks: &[Node<T, U>],
+		loop_map: &HashMap<i32, LoopPtr<T, U>>,
+	) -> Option<Node<T, U>> {
 		let mut exit = None;
 		for block in blocks.iter() {
 			for succ in block.borrow().succ.iter() {
@@ -150,7 +151,10 @@ impl Loop {
 		self.subloops.is_empty()
 	}
 	// 临时计算 loop 内有哪些 block, 包括子循环的 block
-	pub fn blocks(&self, loop_map: &HashMap<i32, LoopPtr>) -> Vec<LlvmNode> {
+	pub fn blocks(
+		&self,
+		loop_map: &HashMap<i32, LoopPtr<T, U>>,
+	) -> Vec<Node<T, U>> {
 		// 从 header 开始，遍历在同一循环内的后继，直到回到 header
 		let mut deduplicate = HashSet::new();
 		let mut visited = Vec::new();
@@ -173,9 +177,9 @@ impl Loop {
 	// 临时计算 loop 内有哪些 block, 不包括子循环的 block
 	pub fn blocks_without_subloops(
 		&self,
-		_cfg: &LlvmCFG,
-		loop_map: &HashMap<i32, LoopPtr>,
-	) -> Vec<LlvmNode> {
+		_cfg: &CFG<T, U>,
+		loop_map: &HashMap<i32, LoopPtr<T, U>>,
+	) -> Vec<Node<T, U>> {
 		// 从 header 开始，遍历在同一循环内的后继，直到回到 header
 		let mut deduplicate = HashSet::new();
 		let mut visited = Vec::new();
@@ -196,7 +200,7 @@ impl Loop {
 		}
 		visited
 	}
-	pub fn instr_cnt(&self, loop_map: &HashMap<i32, LoopPtr>) -> usize {
+	pub fn instr_cnt(&self, loop_map: &HashMap<i32, LoopPtr<T, U>>) -> usize {
 		self
 			.blocks(loop_map)
 			.iter()
@@ -211,13 +215,13 @@ impl Loop {
 	}
 }
 
-impl Hash for Loop {
+impl<T: InstrTrait<U>, U: TempTrait> Hash for Loop<T, U> {
 	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
 		self.id.hash(state);
 	}
 }
 
-impl Display for Loop {
+impl<T: InstrTrait<U>, U: TempTrait> Display for Loop<T, U> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		let outer = if let Some(outer) = &self.outer {
 			if let Some(outer) = outer.upgrade() {
@@ -0,0 +1,264 @@
+// Checks the structural invariants that optimizer passes rely on, so that a
+// broken pass is reported right after it runs instead of much later as a
+// panic in the backend.
+
+use std::{
+	collections::{HashMap, HashSet},
+	rc::Rc,
+};
+
+use llvm::{LlvmInstrVariant, LlvmTemp, Value};
+use utils::{errors::Result, Label, UseTemp};
+
+use crate::{dominator::LlvmDomTree, program::LlvmFunc, LlvmNode};
+
+// where a temp is defined, phis of a block are at position 0, normal
+// instructions start from 1
+#[derive(Clone, Copy)]
+enum DefPos {
+	Param,
+	Instr(i32, usize),
+}
+
+struct FuncVerifier<'a> {
+	func: &'a LlvmFunc,
+	nodes: HashMap<Label, LlvmNode>,
+	defs: HashMap<String, DefPos>,
+	reachable: HashSet<i32>,
+	dom_tree: LlvmDomTree,
+}
+
+fn unique_labels(
+	labels: impl Iterator<Item = Label>,
+) -> Option<HashSet<Label>> {
+	let mut set = HashSet::new();
+	for label in labels {
+		if !set.insert(label) {
+			return None;
+		}
+	}
+	Some(set)
+}
+
+impl<'a> FuncVerifier<'a> {
+	fn new(func: &'a LlvmFunc) -> Self {
+		Self {
+			func,
+			nodes: HashMap::new(),
+			defs: HashMap::new(),
+			reachable: HashSet::new(),
+			dom_tree: LlvmDomTree::default(),
+		}
+	}
+
+	fn check_blocks(&mut self) -> Result<(), String> {
+		for block in self.func.cfg.blocks.iter() {
+			let label = block.borrow().label();
+			if self.nodes.insert(label.clone(), block.clone()).is_some() {
+				return Err(format!("block `{label}` appears twice"));
+			}
+		}
+		let contains = |list: &[LlvmNode], node: &LlvmNode| {
+			list.iter().any(|v| Rc::ptr_eq(v, node))
+		};
+		for node in self.func.cfg.blocks.iter() {
+			let block = node.borrow();
+			let label = block.label();
+			let Some(jump) = block.jump_instr.as_ref() else {
+				return Err(format!("block `{label}` has no terminator"));
+			};
+			let targets: HashSet<_> = match jump.get_variant() {
+				LlvmInstrVariant::JumpInstr(v) => [v.target.clone()].into(),
+				LlvmInstrVariant::JumpCondInstr(v) => {
+					[v.target_true.clone(), v.target_false.clone()].into()
+				}
+				LlvmInstrVariant::RetInstr(_) => HashSet::new(),
+				_ => return Err(format!("block `{label}` ends with `{jump}`")),
+			};
+			let Some(succ) =
+				unique_labels(block.succ.iter().map(|v| v.borrow().label()))
+			else {
+				return Err(format!("block `{label}` has duplicated successors"));
+			};
+			if succ != targets {
+				return Err(format!(
+					"successors of block `{label}` do not match its terminator `{jump}`"
+				));
+			}
+			for v in block.succ.iter() {
+				let v_label = v.borrow().label();
+				if !self.nodes.get(&v_label).is_some_and(|u| Rc::ptr_eq(u, v)) {
+					return Err(format!(
+						"block `{label}` jumps to `{v_label}` which is not in the function"
+					));
+				}
+				if !contains(&v.borrow().prev, node) {
+					return Err(format!(
+						"`{label}` is a predecessor of `{v_label}` but not recorded in its prev"
+					));
+				}
+			}
+			for u in block.prev.iter() {
+				let u_label = u.borrow().label();
+				if !self.nodes.get(&u_label).is_some_and(|v| Rc::ptr_eq(u, v)) {
+					return Err(format!(
+						"block `{label}` has predecessor `{u_label}` which is not in the function"
+					));
+				}
+				if !contains(&u.borrow().succ, node) {
+					return Err(format!(
+						"`{u_label}` is in prev of `{label}` but does not jump to it"
+					));
+				}
+			}
+			let Some(prev) =
+				unique_labels(block.prev.iter().map(|v| v.borrow().label()))
+			else {
+				return Err(format!("block `{label}` has duplicated predecessors"));
+			};
+			for phi in block.phi_instrs.iter() {
+				let incoming = unique_labels(phi.source.iter().map(|(_, l)| l.clone()));
+				if incoming.as_ref() != Some(&prev) {
+					return Err(format!(
+						"incoming labels of `{}` do not match predecessors of block `{label}`",
+						phi.to_string().trim()
+					));
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn check_defs(&mut self) -> Result<(), String> {
+		let mut define = |temp: LlvmTemp, pos: DefPos| {
+			if self.defs.insert(temp.name.clone(), pos).is_some() {
+				return Err(format!("`{temp}` is defined more than once"));
+			}
+			Ok(())
+		};
+		for param in self.func.params.iter() {
+			if let Some(temp) = param.unwrap_temp() {
+				define(temp, DefPos::Param)?;
+			}
+		}
+		for node in self.func.cfg.blocks.iter() {
+			let block = node.borrow();
+			for phi in block.phi_instrs.iter() {
+				define(phi.target.clone(), DefPos::Instr(block.id, 0))?;
+			}
+			for (index, instr) in block.instrs.iter().enumerate() {
+				if let Some(temp) = instr.get_write() {
+					define(temp, DefPos::Instr(block.id, index + 1))?;
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn compute_dominance(&mut self) {
+		let entry = self.func.cfg.get_entry();
+		self.reachable.insert(entry.borrow().id);
+		let mut stack = vec![entry];
+		while let Some(node) = stack.pop() {
+			for succ in node.borrow().succ.iter() {
+				if self.reachable.insert(succ.borrow().id) {
+					stack.push(succ.clone());
+				}
+			}
+		}
+		self.dom_tree = LlvmDomTree::new(&self.func.cfg, false);
+	}
+
+	// whether the definition of `temp` is available at position `pos` of
+	// block `id`, `pos` is `None` for the end of the block
+	fn check_use(
+		&self,
+		temp: &LlvmTemp,
+		id: i32,
+		pos: Option<usize>,
+	) -> Result<(), String> {
+		if temp.is_global {
+			return Ok(());
+		}
+		let label = utils::to_label(id);
+		match self.defs.get(&temp.name) {
+			None => Err(format!("`{temp}` used in block `{label}` is never defined")),
+			Some(DefPos::Param) => Ok(()),
+			Some(DefPos::Instr(def_id, def_pos)) => {
+				let available = match (*def_id == id, pos) {
+					(true, Some(pos)) => *def_pos < pos,
+					(true, None) => true,
+					(false, _) => self.dom_tree.dominates(*def_id, id),
+				};
+				if available {
+					Ok(())
+				} else {
+					Err(format!(
+						"use of `{temp}` in block `{label}` is not dominated by its definition"
+					))
+				}
+			}
+		}
+	}
+
+	fn check_uses(&self) -> Result<(), String> {
+		for node in self.func.cfg.blocks.iter() {
+			let block = node.borrow();
+			if !self.reachable.contains(&block.id) {
+				continue;
+			}
+			for phi in block.phi_instrs.iter() {
+				for (value, label) in phi.source.iter() {
+					if let (Some(temp), Some(prev)) =
+						(value.unwrap_temp(), self.nodes.get(label))
+					{
+						self.check_use(&temp, prev.borrow().id, None)?;
+					}
+				}
+			}
+			let instrs = block.instrs.iter().chain(block.jump_instr.iter());
+			for (index, instr) in instrs.enumerate() {
+				for temp in instr.get_read() {
+					self.check_use(&temp, block.id, Some(index + 1))?;
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn check_types(&self) -> Result<(), String> {
+		fn same_type(lhs: &Value, rhs: &Value) -> bool {
+			lhs.get_type() == rhs.get_type()
+		}
+		for node in self.func.cfg.blocks.iter() {
+			for instr in node.borrow().instrs.iter() {
+				let ok = match instr.get_variant() {
+					LlvmInstrVariant::ArithInstr(v) => same_type(&v.lhs, &v.rhs),
+					LlvmInstrVariant::CompInstr(v) => same_type(&v.lhs, &v.rhs),
+					_ => true,
+				};
+				if !ok {
+					return Err(format!(
+						"operand types of `{}` do not agree",
+						instr.to_string().trim()
+					));
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn verify(mut self) -> Result<(), String> {
+		self.check_blocks()?;
+		self.check_defs()?;
+		self.compute_dominance();
+		self.check_uses()?;
+		self.check_types()
+	}
+}
+
+impl LlvmFunc {
+	pub fn verify(&self) -> Result<(), String> {
+		FuncVerifier::new(self).verify()
+	}
+}
@@ -24,6 +24,9 @@ pub static VEC_EXTERN: [&str; 17] = [
 	"_sysy_stoptime",
 ];
 
+/// functions whose arguments after the declared ones are passed as in C
+pub static VEC_VARIADIC: [&str; 1] = ["putf"];
+
 pub static VEC_MACRO: [&str; 2] = ["starttime", "stoptime"];
 pub const MAX_PHI_NUM: usize = 10;
 
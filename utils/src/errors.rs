@@ -1,5 +1,10 @@
 use thiserror::Error;
 
+use crate::{
+	source_map::SourceMap,
+	span::{render_diagnostic, Span},
+};
+
 pub use SysycError::*;
 
 #[derive(Error, Debug)]
@@ -22,6 +27,55 @@ pub enum SysycError {
 	RiscvGenError(String),
 	#[error("Llvm generating error: {0}")]
 	LlvmvGenError(String),
+	#[error("Llvm verify error: {0}")]
+	LlvmVerifyError(String),
+	#[error("Runtime error: {0}")]
+	RuntimeError(String),
+	/// the program runs more steps than it is allowed to
+	#[error("Runtime error: still running after {0} steps")]
+	StepLimitError(u64),
+	#[error("Link error: {0}")]
+	LinkError(String),
+	#[error("Preprocess error: {0}")]
+	PreprocessError(String),
+	#[error("{1}")]
+	Located(Span, Box<SysycError>),
+	#[error("{}", .0.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("\n"))]
+	Errors(Vec<SysycError>),
+}
+
+impl SysycError {
+	/// Attach the location of the error, an error keeps the innermost one
+	pub fn at(self, span: Span) -> Self {
+		match self {
+			Located(..) | Errors(_) => self,
+			_ => Located(span, Box::new(self)),
+		}
+	}
+	/// Render the error with the line of source code it comes from
+	pub fn render(&self, file_name: &str, code: &str) -> String {
+		self.render_in(&SourceMap::single(file_name, code))
+	}
+	/// Render the error with the line of the file it comes from
+	pub fn render_in(&self, sources: &SourceMap) -> String {
+		match self {
+			Located(span, err) => {
+				let (file_name, code, span) = sources.locate(*span);
+				render_diagnostic(err, Some(span), file_name, code)
+			}
+			Errors(errors) => errors.iter().map(|v| v.render_in(sources)).collect(),
+			_ => render_diagnostic(self, None, "", ""),
+		}
+	}
+}
+
+/// `Ok` if nothing goes wrong, otherwise all the errors
+pub fn check_errors(mut errors: Vec<SysycError>) -> Result<()> {
+	match errors.len() {
+		0 => Ok(()),
+		1 => Err(errors.pop().unwrap()),
+		_ => Err(Errors(errors)),
+	}
 }
 
 pub type Result<T, E = SysycError> = core::result::Result<T, E>;
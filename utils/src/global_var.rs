@@ -4,6 +4,7 @@ pub use ValueItem::*;
 
 #[derive(Debug)]
 pub enum ValueItem {
+	Byte(u8),
 	Word(u32),
 	Zero(usize),
 }
@@ -13,11 +14,14 @@ pub struct GlobalVar {
 	pub ident: String,
 	pub data: Vec<ValueItem>,
 	pub is_float: bool,
+	/// a string literal, which is never written, so it is put into `.rodata`
+	pub is_const: bool,
 }
 
 impl Display for ValueItem {
 	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
 		match self {
+			Byte(v) => write!(f, "  .byte {}", v),
 			Word(v) => write!(f, "  .word {}", v),
 			Zero(v) => write!(f, "  .zero {}", v),
 		}
@@ -27,6 +31,7 @@ impl Display for ValueItem {
 impl ValueItem {
 	fn size(&self) -> usize {
 		match self {
+			Byte(_) => 1,
 			Word(_) => 4,
 			Zero(v) => *v,
 		}
@@ -46,11 +51,13 @@ impl GlobalVar {
 		ident: impl Display,
 		data: Vec<ValueItem>,
 		is_float: bool,
+		is_const: bool,
 	) -> Self {
 		Self {
 			ident: ident.to_string(),
 			data,
 			is_float,
+			is_const,
 		}
 	}
 	pub fn size(&self) -> usize {
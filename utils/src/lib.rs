@@ -4,6 +4,8 @@ pub mod global_var;
 pub mod label;
 pub mod mapper;
 pub mod math;
+pub mod source_map;
+pub mod span;
 pub mod union_find;
 use std::{fmt::Display, hash::Hash};
 
@@ -11,6 +13,8 @@ pub use constants::*;
 pub use errors::*;
 pub use global_var::*;
 pub use label::*;
+pub use source_map::*;
+pub use span::*;
 
 pub fn fatal_error(str: impl Display) {
 	eprintln!("{}: {}", console::style("fatal error").bold().red(), str);
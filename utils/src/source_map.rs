@@ -0,0 +1,87 @@
+use crate::Span;
+
+// the output from `out` on comes from `orig` in `file`, as is, or expanded
+// from the `len` bytes there
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Segment {
+	out: usize,
+	file: usize,
+	orig: usize,
+	len: Option<usize>,
+}
+
+/// The files that the code given to the parser comes from, so that a
+/// diagnostic points at the line it is about
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+	files: Vec<(String, String)>,
+	segments: Vec<Segment>,
+}
+
+impl SourceMap {
+	/// The code of a single file, as is
+	pub fn single(file_name: &str, code: &str) -> Self {
+		let mut map = Self::default();
+		let file = map.add_file(file_name.to_string(), code.to_string());
+		map.map(0, file, 0, None);
+		map
+	}
+	/// Add a file, returning its index
+	pub fn add_file(&mut self, name: String, code: String) -> usize {
+		self.files.push((name, code));
+		self.files.len() - 1
+	}
+	pub fn name(&self, file: usize) -> &str {
+		&self.files[file].0
+	}
+	pub fn code(&self, file: usize) -> &str {
+		&self.files[file].1
+	}
+	/// Map the output from `out` on to `orig` in `file`, as is, or as
+	/// expanded from the `len` bytes there
+	pub fn map(
+		&mut self,
+		out: usize,
+		file: usize,
+		orig: usize,
+		len: Option<usize>,
+	) {
+		let segment = Segment {
+			out,
+			file,
+			orig,
+			len,
+		};
+		if let Some(last) = self.segments.last_mut() {
+			let follows = match len {
+				None => last.len.is_none() && last.orig + (out - last.out) == orig,
+				Some(_) => last.len == len && last.orig == orig,
+			};
+			if last.file == file && follows {
+				return;
+			}
+			if last.out == out {
+				*last = segment;
+				return;
+			}
+		}
+		self.segments.push(segment);
+	}
+	/// The name and code of the file that `span` of the output comes from,
+	/// with the span in that file
+	pub fn locate(&self, span: Span) -> (&str, &str, Span) {
+		let index = self.segments.partition_point(|v| v.out <= span.start);
+		let Some(segment) = index.checked_sub(1).map(|v| self.segments[v]) else {
+			return ("", "", span);
+		};
+		let (name, code) = &self.files[segment.file];
+		let span = match segment.len {
+			None => Span::new(
+				segment.orig + (span.start - segment.out),
+				segment.orig + (span.end.max(span.start) - segment.out),
+			),
+			Some(len) => Span::new(segment.orig, segment.orig + len),
+		};
+		(name, code, span)
+	}
+}
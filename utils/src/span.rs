@@ -0,0 +1,101 @@
+use std::fmt::Debug;
+
+/// Byte range `[start, end)` of the source code
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+	pub start: usize,
+	pub end: usize,
+}
+
+impl Debug for Span {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}..{}", self.start, self.end)
+	}
+}
+
+impl Span {
+	pub fn new(start: usize, end: usize) -> Self {
+		Self { start, end }
+	}
+	/// The smallest span covering both
+	pub fn merge(self, other: Span) -> Self {
+		Self {
+			start: self.start.min(other.start),
+			end: self.end.max(other.end),
+		}
+	}
+}
+
+/// Line and column (both from 1) of the byte offset `pos` in `code`
+pub fn line_col(code: &str, pos: usize) -> (usize, usize) {
+	let pos = pos.min(code.len());
+	let before = &code[..pos];
+	let line = before.matches('\n').count() + 1;
+	let line_start = before.rfind('\n').map_or(0, |v| v + 1);
+	(line, before[line_start..].chars().count() + 1)
+}
+
+/// Render `message` at `span` of the file, like
+///
+/// ```text
+/// error: Type Error: ...
+///  --> a.sy:3:9
+///   |
+/// 3 |     a = !1.5;
+///   |         ^^^^
+/// ```
+pub fn render_diagnostic(
+	message: impl std::fmt::Display,
+	span: Option<Span>,
+	file_name: &str,
+	code: &str,
+) -> String {
+	let style = console::Style::new().bold().red();
+	render("error", style, message, span, file_name, code)
+}
+
+/// Render `message` at `span` of the file like [`render_diagnostic`], as a
+/// warning
+pub fn render_warning(
+	message: impl std::fmt::Display,
+	span: Option<Span>,
+	file_name: &str,
+	code: &str,
+) -> String {
+	let style = console::Style::new().bold().magenta();
+	render("warning", style, message, span, file_name, code)
+}
+
+fn render(
+	level: &str,
+	style: console::Style,
+	message: impl std::fmt::Display,
+	span: Option<Span>,
+	file_name: &str,
+	code: &str,
+) -> String {
+	let head = format!("{}: {}\n", style.apply_to(level), message);
+	let Some(span) = span else {
+		return head;
+	};
+	let start = span.start.min(code.len());
+	let end = span.end.clamp(start, code.len());
+	let (line, col) = line_col(code, start);
+	let line_start = code[..start].rfind('\n').map_or(0, |v| v + 1);
+	let line_str = code[line_start..].lines().next().unwrap_or("");
+	// only mark the first line of a multi-line span
+	let width =
+		code[start..end].lines().next().map_or(0, |v| v.chars().count()).max(1);
+	let number = line.to_string();
+	let pad = " ".repeat(number.len());
+	let indent: String = line_str
+		.chars()
+		.take(col - 1)
+		.map(|v| if v == '\t' { '\t' } else { ' ' })
+		.collect();
+	format!(
+		"{head}{pad}{} {file_name}:{line}:{col}\n{pad} |\n{number} | {line_str}\n{pad} | {indent}{}\n",
+		console::style("-->").bold().blue(),
+		style.apply_to("^".repeat(width)),
+	)
+}